@@ -5,25 +5,31 @@
 /// Run this example with the command `cargo run --example generate_typescript_types --features "ts-gen"`
 extern crate meshtastic;
 
-use meshtastic::ts::specta::{
-    export::ts_with_cfg,
-    ts::{BigIntExportBehavior, ExportConfiguration, ModuleExportBehavior, TsExportError},
+use meshtastic::ts::{
+    export_ts_types,
+    specta::ts::{BigIntExportBehavior, ExportConfiguration, ModuleExportBehavior, TsExportError},
+    EnumExportBehavior,
 };
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Exports relative to the root workspace directory
-    export_ts_types("./examples/bindings.ts")?;
+    export_bindings("./examples/bindings.ts")?;
 
     Ok(())
 }
 
-fn export_ts_types(file_path: &str) -> Result<(), TsExportError> {
+fn export_bindings(file_path: &str) -> Result<(), TsExportError> {
     // Sets up a default configuration for exporting typescript types
     let ts_export_config = ExportConfiguration::default()
         .bigint(BigIntExportBehavior::String)
         .modules(ModuleExportBehavior::Enabled);
 
-    // Use Specta helper function to export typescript types
-    ts_with_cfg(file_path, &ts_export_config)
+    // Render protobuf enums as string unions matching their `as_str_name()` values,
+    // which is the form expected by the Meshtastic web/JS clients
+    export_ts_types(
+        file_path,
+        &ts_export_config,
+        EnumExportBehavior::StringUnion,
+    )
 }
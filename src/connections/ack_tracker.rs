@@ -0,0 +1,435 @@
+use std::{collections::HashMap, time::Duration};
+
+use prost::Message;
+
+use crate::protobufs;
+
+use super::wrappers::NodeId;
+
+/// The node id used by the firmware to indicate a broadcast destination.
+const BROADCAST_NODE_ID: u32 = u32::MAX;
+
+/// An enum that represents the acknowledgement status of a packet sent with `want_ack` set.
+///
+/// # Variants
+///
+/// * `ImplicitAck` - The connected radio overheard another node rebroadcasting the packet. This indicates
+///     that the packet will likely arrive, but delivery cannot be guaranteed. Broadcast packets only ever
+///     receive implicit acknowledgements.
+/// * `ExplicitAck` - The destination node sent a routing acknowledgement for the packet, confirming delivery.
+/// * `Nak(protobufs::routing::Error)` - A routing error was reported for the packet, specified by the
+///     passed `protobufs::routing::Error` reason. Unrecognized error codes are reported as `GotNak`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AckStatus {
+    ImplicitAck,
+    ExplicitAck,
+    Nak(protobufs::routing::Error),
+}
+
+impl AckStatus {
+    /// Returns whether the status represents a successful (implicit or explicit) acknowledgement.
+    pub fn is_ack(&self) -> bool {
+        matches!(self, AckStatus::ImplicitAck | AckStatus::ExplicitAck)
+    }
+}
+
+/// The maximum number of reliable sends that may await an acknowledgement at once, if no limit is set
+/// and the radio has not yet reported the length of its outgoing packet queue.
+pub const DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS: usize = 8;
+
+/// A struct that configures the retransmission behavior of reliable sends.
+///
+/// If no final acknowledgement is received within the current timeout, the packet is
+/// retransmitted with the same packet id, mirroring the resend logic of the firmware.
+/// The timeout doubles after every retransmission, starting from `base_timeout`.
+///
+/// # Default
+///
+/// The default configuration retransmits up to 3 times, with a base timeout of 15 seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReliableSendConfig {
+    /// The maximum number of times the packet is retransmitted after the initial transmission.
+    pub max_retransmits: u32,
+
+    /// The time to wait for an acknowledgement after the initial transmission.
+    pub base_timeout: Duration,
+}
+
+impl Default for ReliableSendConfig {
+    fn default() -> Self {
+        Self {
+            max_retransmits: 3,
+            base_timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+impl ReliableSendConfig {
+    /// Creates a new `ReliableSendConfig` with the specified retransmission count and base timeout.
+    pub fn new(max_retransmits: u32, base_timeout: Duration) -> Self {
+        Self {
+            max_retransmits,
+            base_timeout,
+        }
+    }
+
+    /// Returns the acknowledgement timeout for the specified attempt, where attempt `0` is the initial transmission.
+    pub fn timeout_for_attempt(&self, attempt: u32) -> Duration {
+        self.base_timeout
+            .saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// A struct that represents an acknowledgement event for a tracked packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AckEvent {
+    /// The id of the packet that was acknowledged.
+    pub packet_id: u32,
+
+    /// The node that generated the acknowledgement.
+    pub from: NodeId,
+
+    /// The acknowledgement status of the packet.
+    pub status: AckStatus,
+}
+
+/// A struct that tracks outgoing packets that requested an acknowledgement, and classifies the
+/// acknowledgements received from the radio.
+///
+/// Broadcast packets receive implicit acknowledgements: the connected radio overhears a neighbor
+/// rebroadcasting the packet, and reports a routing message with no error originating from the local
+/// node. Direct packets receive explicit routing acknowledgements from the destination node, and may
+/// receive an implicit acknowledgement beforehand. Routing messages containing an error are reported
+/// as a `Nak`. The tracker also treats seeing one of our own tracked packet ids being rebroadcast
+/// as an implicit acknowledgement.
+///
+/// A tracked packet stops being pending once it has received its final acknowledgement. For broadcast
+/// packets this is the first implicit acknowledgement, and for direct packets this is either an explicit
+/// acknowledgement or a `Nak`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AckTracker {
+    local_node_id: NodeId,
+    pending: HashMap<u32, NodeId>,
+}
+
+impl AckTracker {
+    /// Creates a new `AckTracker` for the radio with the specified node id.
+    ///
+    /// # Arguments
+    ///
+    /// * `local_node_id` - The node id of the connected radio, used to detect implicit acknowledgements.
+    ///
+    /// # Returns
+    ///
+    /// A new `AckTracker` instance with no tracked packets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut ack_tracker = AckTracker::new(my_node_info.my_node_num.into());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn new(local_node_id: NodeId) -> Self {
+        Self {
+            local_node_id,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Returns the node id of the connected radio.
+    pub fn local_node_id(&self) -> NodeId {
+        self.local_node_id
+    }
+
+    /// Updates the node id of the connected radio, e.g. once the `MyInfo` packet has been received.
+    pub fn set_local_node_id(&mut self, local_node_id: NodeId) {
+        self.local_node_id = local_node_id;
+    }
+
+    /// Starts tracking the acknowledgement status of an outgoing mesh packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - The outgoing `MeshPacket`. The packet's `id` and `to` fields are recorded.
+    ///
+    /// # Returns
+    ///
+    /// None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// ack_tracker.track(&mesh_packet);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn track(&mut self, packet: &protobufs::MeshPacket) {
+        self.track_id(packet.id, packet.to.into());
+    }
+
+    /// Starts tracking the acknowledgement status of an outgoing packet by id and destination.
+    pub fn track_id(&mut self, packet_id: u32, destination: NodeId) {
+        self.pending.insert(packet_id, destination);
+    }
+
+    /// Stops tracking the packet with the specified id, returning whether it was being tracked.
+    pub fn forget(&mut self, packet_id: u32) -> bool {
+        self.pending.remove(&packet_id).is_some()
+    }
+
+    /// Returns whether the packet with the specified id is still awaiting its final acknowledgement.
+    pub fn is_pending(&self, packet_id: u32) -> bool {
+        self.pending.contains_key(&packet_id)
+    }
+
+    /// Returns the ids of all packets that are still awaiting their final acknowledgement.
+    pub fn pending_packet_ids(&self) -> Vec<u32> {
+        self.pending.keys().copied().collect()
+    }
+
+    /// Classifies a `FromRadio` packet received from the radio, returning an acknowledgement
+    /// event if the packet acknowledges one of the tracked packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `FromRadio` packet received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// An `AckEvent` if the packet acknowledges a tracked packet, otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     if let Some(ack_event) = ack_tracker.handle_packet(&packet) {
+    ///         println!("Packet {} status: {:?}", ack_event.packet_id, ack_event.status);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn handle_packet(&mut self, packet: &protobufs::FromRadio) -> Option<AckEvent> {
+        match packet.payload_variant.as_ref()? {
+            protobufs::from_radio::PayloadVariant::Packet(mesh_packet) => {
+                self.handle_mesh_packet(mesh_packet)
+            }
+            _ => None,
+        }
+    }
+
+    /// Classifies a `MeshPacket` received from the radio, returning an acknowledgement
+    /// event if the packet acknowledges one of the tracked packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `MeshPacket` received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// An `AckEvent` if the packet acknowledges a tracked packet, otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(ack_event) = ack_tracker.handle_mesh_packet(&mesh_packet) {
+    ///     println!("Packet {} status: {:?}", ack_event.packet_id, ack_event.status);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn handle_mesh_packet(&mut self, packet: &protobufs::MeshPacket) -> Option<AckEvent> {
+        let data = match packet.payload_variant.as_ref() {
+            Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) => Some(data),
+            _ => None,
+        };
+
+        let is_routing_packet =
+            data.is_some_and(|d| d.portnum == protobufs::PortNum::RoutingApp as i32);
+
+        // Seeing one of our own packets being rebroadcast indicates that a neighbor received it
+
+        if !is_routing_packet && self.local_node_id == packet.from && self.is_pending(packet.id) {
+            return Some(self.resolve(packet.id, packet.from.into(), AckStatus::ImplicitAck));
+        }
+
+        let data = data.filter(|_| is_routing_packet)?;
+        let destination = *self.pending.get(&data.request_id)?;
+
+        let routing = protobufs::Routing::decode(data.payload.as_slice()).ok()?;
+
+        let status = match routing.variant? {
+            protobufs::routing::Variant::ErrorReason(0) => {
+                if self.local_node_id == packet.from && destination != self.local_node_id {
+                    AckStatus::ImplicitAck
+                } else {
+                    AckStatus::ExplicitAck
+                }
+            }
+            protobufs::routing::Variant::ErrorReason(code) => AckStatus::Nak(
+                protobufs::routing::Error::try_from(code)
+                    .unwrap_or(protobufs::routing::Error::GotNak),
+            ),
+            _ => return None,
+        };
+
+        Some(self.resolve(data.request_id, packet.from.into(), status))
+    }
+
+    fn resolve(&mut self, packet_id: u32, from: NodeId, status: AckStatus) -> AckEvent {
+        let is_broadcast = self
+            .pending
+            .get(&packet_id)
+            .is_some_and(|destination| *destination == BROADCAST_NODE_ID);
+
+        // Direct packets may still receive an explicit acknowledgement after an implicit one
+
+        if status != AckStatus::ImplicitAck || is_broadcast {
+            self.pending.remove(&packet_id);
+        }
+
+        AckEvent {
+            packet_id,
+            from,
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL_NODE_ID: u32 = 0x1234;
+    const REMOTE_NODE_ID: u32 = 0x5678;
+
+    fn routing_packet(
+        from: u32,
+        request_id: u32,
+        error: protobufs::routing::Error,
+    ) -> protobufs::MeshPacket {
+        let routing = protobufs::Routing {
+            variant: Some(protobufs::routing::Variant::ErrorReason(error as i32)),
+        };
+
+        protobufs::MeshPacket {
+            from,
+            to: LOCAL_NODE_ID,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: protobufs::PortNum::RoutingApp as i32,
+                    payload: routing.encode_to_vec(),
+                    request_id,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn broadcast_receives_implicit_ack() {
+        let mut ack_tracker = AckTracker::new(LOCAL_NODE_ID.into());
+        ack_tracker.track_id(1, BROADCAST_NODE_ID.into());
+
+        let event = ack_tracker
+            .handle_mesh_packet(&routing_packet(
+                LOCAL_NODE_ID,
+                1,
+                protobufs::routing::Error::None,
+            ))
+            .unwrap();
+
+        assert_eq!(event.status, AckStatus::ImplicitAck);
+        assert!(!ack_tracker.is_pending(1));
+    }
+
+    #[test]
+    fn direct_packet_upgrades_implicit_to_explicit_ack() {
+        let mut ack_tracker = AckTracker::new(LOCAL_NODE_ID.into());
+        ack_tracker.track_id(2, REMOTE_NODE_ID.into());
+
+        let implicit = ack_tracker
+            .handle_mesh_packet(&routing_packet(
+                LOCAL_NODE_ID,
+                2,
+                protobufs::routing::Error::None,
+            ))
+            .unwrap();
+        assert_eq!(implicit.status, AckStatus::ImplicitAck);
+        assert!(ack_tracker.is_pending(2));
+
+        let explicit = ack_tracker
+            .handle_mesh_packet(&routing_packet(
+                REMOTE_NODE_ID,
+                2,
+                protobufs::routing::Error::None,
+            ))
+            .unwrap();
+        assert_eq!(explicit.status, AckStatus::ExplicitAck);
+        assert_eq!(explicit.from, REMOTE_NODE_ID);
+        assert!(!ack_tracker.is_pending(2));
+    }
+
+    #[test]
+    fn routing_error_is_reported_as_nak() {
+        let mut ack_tracker = AckTracker::new(LOCAL_NODE_ID.into());
+        ack_tracker.track_id(3, REMOTE_NODE_ID.into());
+
+        let event = ack_tracker
+            .handle_mesh_packet(&routing_packet(
+                LOCAL_NODE_ID,
+                3,
+                protobufs::routing::Error::MaxRetransmit,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            event.status,
+            AckStatus::Nak(protobufs::routing::Error::MaxRetransmit)
+        );
+        assert!(ack_tracker.pending_packet_ids().is_empty());
+    }
+
+    #[test]
+    fn untracked_packets_are_ignored() {
+        let mut ack_tracker = AckTracker::new(LOCAL_NODE_ID.into());
+
+        assert!(ack_tracker
+            .handle_mesh_packet(&routing_packet(
+                REMOTE_NODE_ID,
+                4,
+                protobufs::routing::Error::None
+            ))
+            .is_none());
+    }
+}
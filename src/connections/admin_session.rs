@@ -0,0 +1,569 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+};
+
+use log::warn;
+use prost::Message;
+use tokio::sync::oneshot;
+
+use crate::errors_internal::Error;
+use crate::helpers_internal::admin::{decode_admin_response, AdminResponse};
+use crate::protobufs;
+
+#[derive(Debug)]
+struct PendingAdminRequest {
+    registration: u64,
+    request: protobufs::AdminMessage,
+    response_tx: oneshot::Sender<Result<AdminResponse, Error>>,
+}
+
+/// A struct that tracks outstanding admin requests, and correlates the admin responses received
+/// from the mesh with the requests they answer.
+///
+/// Admin responses carry the id of the request packet within their `request_id` field. A request is
+/// resolved once a response of the requested kind is received (see `AdminResponse::answers`), or once
+/// a routing error is reported for it, e.g. `NotAuthorized` if the destination node does not accept
+/// admin messages from the local node, or `BadRequest` if the request was malformed. Successful routing
+/// acknowledgements are ignored, as the admin response follows separately.
+///
+/// Cloning an `AdminSession` returns a handle to the same set of outstanding requests, which allows
+/// several admin requests to be awaited concurrently while packets are fed to the session elsewhere.
+///
+/// Each admin request is answered by a single response packet. Most requests retrieve a complete
+/// logical result, e.g. a `GetConfigRequest` is answered by one `GetConfigResponse` containing the
+/// whole requested configuration section. Other results span several packets, e.g. the full channel
+/// table is retrieved with one `GetChannelRequest` per channel index, each answered by its own
+/// `GetChannelResponse`. The `register_all` method tracks such a sequence of requests as one operation,
+/// which only resolves once every request has been answered.
+///
+/// A request stops being tracked once its `PendingAdminResponse` is dropped, e.g. because it was awaited
+/// within a timeout that elapsed, so requests that are never answered do not accumulate in the session.
+#[derive(Clone, Debug, Default)]
+pub struct AdminSession {
+    inner: Arc<Mutex<SessionInner>>,
+}
+
+#[derive(Debug, Default)]
+struct SessionInner {
+    next_registration: u64,
+    pending: HashMap<u32, PendingAdminRequest>,
+}
+
+impl AdminSession {
+    /// Creates a new `AdminSession` with no outstanding requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking an admin request that was sent within the packet with the specified id.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_id` - The id of the mesh packet containing the admin request.
+    /// * `request` - The admin request, used to check that responses are of the requested kind.
+    ///
+    /// # Returns
+    ///
+    /// A `PendingAdminResponse` future that resolves once the request is answered or fails. The request
+    /// stops being tracked once the future is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pending_response = admin_session.register(mesh_packet.id, request);
+    /// let response = tokio::time::timeout(Duration::from_secs(30), pending_response).await??;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn register(
+        &self,
+        packet_id: u32,
+        request: protobufs::AdminMessage,
+    ) -> PendingAdminResponse {
+        let (response_tx, response_rx) = oneshot::channel();
+
+        let mut inner = self.lock();
+        let registration = inner.next_registration;
+        inner.next_registration += 1;
+
+        let replaced = inner.pending.insert(
+            packet_id,
+            PendingAdminRequest {
+                registration,
+                request,
+                response_tx,
+            },
+        );
+
+        if replaced.is_some() {
+            warn!("Replaced outstanding admin request with duplicate packet id {packet_id}");
+        }
+
+        PendingAdminResponse {
+            session: self.clone(),
+            packet_id,
+            registration,
+            response_rx,
+        }
+    }
+
+    /// Starts tracking a sequence of admin requests that together form one logical operation, e.g. the
+    /// `GetChannelRequest`s for every index of a channel table.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The ids of the mesh packets containing the admin requests, along with the requests.
+    ///
+    /// # Returns
+    ///
+    /// A `PendingAdminResponses` future that resolves once all requests are answered, or once any of
+    /// the requests fails. The requests stop being tracked once the future is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pending_responses = admin_session.register_all(channel_requests);
+    /// let channels = tokio::time::timeout(Duration::from_secs(60), pending_responses).await??;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn register_all(
+        &self,
+        requests: impl IntoIterator<Item = (u32, protobufs::AdminMessage)>,
+    ) -> PendingAdminResponses {
+        let pending: Vec<PendingAdminResponse> = requests
+            .into_iter()
+            .map(|(packet_id, request)| self.register(packet_id, request))
+            .collect();
+
+        PendingAdminResponses {
+            session: self.clone(),
+            responses: vec![None; pending.len()],
+            pending,
+        }
+    }
+
+    /// Stops tracking the admin request with the specified id, returning whether it was outstanding.
+    /// The `PendingAdminResponse` of the request resolves to `Error::RequestCancelled`.
+    pub fn cancel(&self, packet_id: u32) -> bool {
+        self.lock().pending.remove(&packet_id).is_some()
+    }
+
+    /// Returns whether the admin request with the specified id is still awaiting a response.
+    pub fn is_pending(&self, packet_id: u32) -> bool {
+        self.lock().pending.contains_key(&packet_id)
+    }
+
+    /// Returns the packet ids of all admin requests that are still awaiting a response.
+    pub fn pending_request_ids(&self) -> Vec<u32> {
+        self.lock().pending.keys().copied().collect()
+    }
+
+    /// Resolves the outstanding admin request answered by a `FromRadio` packet, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `FromRadio` packet received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the packet resolved an outstanding admin request, otherwise `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     admin_session.handle_packet(&packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn handle_packet(&self, packet: &protobufs::FromRadio) -> bool {
+        match packet.payload_variant.as_ref() {
+            Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) => {
+                self.handle_mesh_packet(mesh_packet)
+            }
+            _ => false,
+        }
+    }
+
+    /// Resolves the outstanding admin request answered by a `MeshPacket`, if any. See the
+    /// `handle_packet` method for details.
+    pub fn handle_mesh_packet(&self, packet: &protobufs::MeshPacket) -> bool {
+        let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+            packet.payload_variant.as_ref()
+        else {
+            return false;
+        };
+
+        let mut inner = self.lock();
+
+        let Some(pending_request) = inner.pending.get(&data.request_id) else {
+            return false;
+        };
+
+        let result = match protobufs::PortNum::try_from(data.portnum) {
+            Ok(protobufs::PortNum::RoutingApp) => {
+                let Some(reason) = routing_error(data) else {
+                    return false;
+                };
+
+                Err(Error::RequestFailed {
+                    packet_id: data.request_id,
+                    reason,
+                })
+            }
+            Ok(protobufs::PortNum::AdminApp) => match decode_admin_response(data) {
+                Ok(admin_message) => match AdminResponse::from_admin_message(admin_message) {
+                    Some(response) if response.answers(&pending_request.request) => Ok(response),
+                    _ => return false,
+                },
+                Err(e) => Err(e),
+            },
+            _ => return false,
+        };
+
+        let Some(pending_request) = inner.pending.remove(&data.request_id) else {
+            return false;
+        };
+
+        // The receiver may have been dropped if the caller is no longer interested in the response
+        let _ = pending_request.response_tx.send(result);
+
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionInner> {
+        // The map is never left in an inconsistent state, so a poisoned lock can be recovered
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Returns the routing error reported within a routing packet, if the packet reports an error.
+fn routing_error(data: &protobufs::Data) -> Option<protobufs::routing::Error> {
+    let routing = protobufs::Routing::decode(data.payload.as_slice()).ok()?;
+
+    match routing.variant? {
+        protobufs::routing::Variant::ErrorReason(0) => None,
+        protobufs::routing::Variant::ErrorReason(code) => Some(
+            protobufs::routing::Error::try_from(code).unwrap_or(protobufs::routing::Error::GotNak),
+        ),
+        _ => None,
+    }
+}
+
+/// A future that resolves to the response to an admin request registered with an `AdminSession`.
+///
+/// The future resolves to `Error::RequestFailed` if a routing error is reported for the request,
+/// and to `Error::RequestCancelled` if the request is cancelled or the session is dropped. The
+/// future does not time out on its own, and is usually awaited within `tokio::time::timeout`. Dropping
+/// the future stops tracking the request within the session.
+#[derive(Debug)]
+pub struct PendingAdminResponse {
+    session: AdminSession,
+    packet_id: u32,
+    registration: u64,
+    response_rx: oneshot::Receiver<Result<AdminResponse, Error>>,
+}
+
+impl PendingAdminResponse {
+    /// Returns the id of the mesh packet containing the admin request.
+    pub fn packet_id(&self) -> u32 {
+        self.packet_id
+    }
+}
+
+impl Drop for PendingAdminResponse {
+    fn drop(&mut self) {
+        let mut inner = self.session.lock();
+
+        // The packet id may have been registered again by another request in the meantime
+        let is_own_entry = inner
+            .pending
+            .get(&self.packet_id)
+            .is_some_and(|pending_request| pending_request.registration == self.registration);
+
+        if is_own_entry {
+            inner.pending.remove(&self.packet_id);
+        }
+    }
+}
+
+impl Future for PendingAdminResponse {
+    type Output = Result<AdminResponse, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let packet_id = self.packet_id;
+
+        Pin::new(&mut self.response_rx)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(Error::RequestCancelled { packet_id })))
+    }
+}
+
+/// A future that resolves to the responses to a sequence of admin requests registered with the
+/// `register_all` method of an `AdminSession`.
+///
+/// The future resolves to the responses in the order in which the requests were registered, regardless
+/// of the order in which the responses are received. If any request fails, the future resolves to the
+/// error of that request, and the remaining requests are cancelled. Like `PendingAdminResponse`, the
+/// future does not time out on its own, and dropping it stops tracking all requests of the sequence.
+#[derive(Debug)]
+pub struct PendingAdminResponses {
+    session: AdminSession,
+
+    // Each request stops being tracked once its `PendingAdminResponse` is dropped along with this struct
+    pending: Vec<PendingAdminResponse>,
+    responses: Vec<Option<AdminResponse>>,
+}
+
+impl PendingAdminResponses {
+    /// Returns the ids of the mesh packets containing the admin requests, in order of registration.
+    pub fn packet_ids(&self) -> Vec<u32> {
+        self.pending
+            .iter()
+            .map(PendingAdminResponse::packet_id)
+            .collect()
+    }
+
+    /// Returns the number of requests that have been answered so far.
+    pub fn num_answered(&self) -> usize {
+        self.responses
+            .iter()
+            .filter(|response| response.is_some())
+            .count()
+    }
+}
+
+impl Future for PendingAdminResponses {
+    type Output = Result<Vec<AdminResponse>, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        for (pending_response, response) in this.pending.iter_mut().zip(&mut this.responses) {
+            if response.is_some() {
+                continue;
+            }
+
+            match Pin::new(pending_response).poll(cx) {
+                Poll::Ready(Ok(admin_response)) => *response = Some(admin_response),
+                Poll::Ready(Err(e)) => {
+                    for packet_id in this.pending.iter().map(PendingAdminResponse::packet_id) {
+                        this.session.cancel(packet_id);
+                    }
+
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => (),
+            }
+        }
+
+        if this.responses.iter().any(Option::is_none) {
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Ok(this
+            .responses
+            .iter_mut()
+            .filter_map(Option::take)
+            .collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protobufs::admin_message::PayloadVariant;
+
+    fn response_packet(
+        request_id: u32,
+        portnum: protobufs::PortNum,
+        payload: Vec<u8>,
+    ) -> protobufs::MeshPacket {
+        protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: portnum as i32,
+                    payload,
+                    request_id,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    fn admin_message(payload_variant: PayloadVariant) -> protobufs::AdminMessage {
+        protobufs::AdminMessage {
+            payload_variant: Some(payload_variant),
+        }
+    }
+
+    #[tokio::test]
+    async fn correlates_concurrent_requests() {
+        let session = AdminSession::new();
+
+        let owner_response =
+            session.register(1, admin_message(PayloadVariant::GetOwnerRequest(true)));
+        let metadata_response = session.register(
+            2,
+            admin_message(PayloadVariant::GetDeviceMetadataRequest(true)),
+        );
+
+        // A response of the wrong kind does not resolve the request
+        let metadata = admin_message(PayloadVariant::GetDeviceMetadataResponse(Default::default()));
+        assert!(!session.handle_mesh_packet(&response_packet(
+            1,
+            protobufs::PortNum::AdminApp,
+            metadata.encode_to_vec()
+        )));
+
+        let not_authorized = protobufs::Routing {
+            variant: Some(protobufs::routing::Variant::ErrorReason(
+                protobufs::routing::Error::NotAuthorized as i32,
+            )),
+        };
+        assert!(session.handle_mesh_packet(&response_packet(
+            2,
+            protobufs::PortNum::RoutingApp,
+            not_authorized.encode_to_vec()
+        )));
+
+        let owner = admin_message(PayloadVariant::GetOwnerResponse(Default::default()));
+        assert!(session.handle_mesh_packet(&response_packet(
+            1,
+            protobufs::PortNum::AdminApp,
+            owner.encode_to_vec()
+        )));
+
+        assert!(matches!(owner_response.await, Ok(AdminResponse::Owner(_))));
+        assert!(matches!(
+            metadata_response.await,
+            Err(Error::RequestFailed {
+                packet_id: 2,
+                reason: protobufs::routing::Error::NotAuthorized
+            })
+        ));
+        assert!(session.pending_request_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stops_tracking_dropped_requests() {
+        let session = AdminSession::new();
+
+        let owner_response =
+            session.register(1, admin_message(PayloadVariant::GetOwnerRequest(true)));
+        let pending_responses = session.register_all((0..2).map(|index| {
+            (
+                10 + index,
+                admin_message(PayloadVariant::GetChannelRequest(index + 1)),
+            )
+        }));
+
+        // A request that times out is dropped without ever being answered
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), owner_response)
+                .await
+                .is_err()
+        );
+        let mut pending_request_ids = session.pending_request_ids();
+        pending_request_ids.sort();
+        assert_eq!(pending_request_ids, [10, 11]);
+
+        drop(pending_responses);
+        assert!(session.pending_request_ids().is_empty());
+
+        // Dropping a response only stops tracking its own registration of a reused packet id
+        let stale_response =
+            session.register(2, admin_message(PayloadVariant::GetOwnerRequest(true)));
+        let _owner_response =
+            session.register(2, admin_message(PayloadVariant::GetOwnerRequest(true)));
+        drop(stale_response);
+        assert_eq!(session.pending_request_ids(), [2]);
+    }
+
+    #[tokio::test]
+    async fn resolves_request_sequences_once_complete() {
+        let session = AdminSession::new();
+
+        let channel_response = |request_id, index| {
+            let channel = admin_message(PayloadVariant::GetChannelResponse(protobufs::Channel {
+                index,
+                ..Default::default()
+            }));
+
+            response_packet(
+                request_id,
+                protobufs::PortNum::AdminApp,
+                channel.encode_to_vec(),
+            )
+        };
+
+        let mut pending_responses = session.register_all((1..=3).map(|index| {
+            (
+                10 + index,
+                admin_message(PayloadVariant::GetChannelRequest(index)),
+            )
+        }));
+
+        // Responses arrive out of order, and the operation is incomplete until all have arrived
+        assert!(session.handle_mesh_packet(&channel_response(13, 2)));
+        assert!(session.handle_mesh_packet(&channel_response(11, 0)));
+        assert!(futures_util::poll!(&mut pending_responses).is_pending());
+        assert_eq!(pending_responses.num_answered(), 2);
+
+        assert!(session.handle_mesh_packet(&channel_response(12, 1)));
+
+        let indices: Vec<i32> = pending_responses
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|response| match response {
+                AdminResponse::Channel(channel) => channel.index,
+                _ => panic!("Unexpected response {response:?}"),
+            })
+            .collect();
+        assert_eq!(indices, [0, 1, 2]);
+
+        // A failed request cancels the remaining requests of the sequence
+        let pending_responses = session.register_all((1..=2).map(|index| {
+            (
+                20 + index,
+                admin_message(PayloadVariant::GetChannelRequest(index)),
+            )
+        }));
+        session.cancel(21);
+
+        assert!(matches!(
+            pending_responses.await,
+            Err(Error::RequestCancelled { packet_id: 21 })
+        ));
+        assert!(session.pending_request_ids().is_empty());
+    }
+}
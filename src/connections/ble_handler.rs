@@ -1,35 +1,483 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant as StdInstant};
+
 use btleplug::api::{
-    Central, CentralEvent, Characteristic, Manager as _, Peripheral as _, ScanFilter,
+    BDAddr, Central, CentralEvent, Characteristic, Manager as _, Peripheral as _, ScanFilter,
     ValueNotification, WriteType,
 };
-use btleplug::platform::{Adapter, Manager, Peripheral};
-use futures_util::stream::BoxStream;
-use log::error;
+use btleplug::platform::{Adapter, Manager, Peripheral, PeripheralId};
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use log::{debug, error, warn};
+use prost::Message;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
 use uuid::Uuid;
 
+use crate::connections::health::{ConnectionEvent, ConnectionState, HealthMonitor};
+use crate::connections::stream_api::StreamHandle;
 use crate::errors_internal::{BleConnectionError, Error, InternalStreamError};
+use crate::protobufs;
+use crate::utils_internal::{connect_within, format_data_packet};
 
 const MSH_SERVICE: Uuid = Uuid::from_u128(0x6ba1b218_15a8_461f_9fa8_5dcae273eafd);
 const FROMRADIO: Uuid = Uuid::from_u128(0x2c55e69e_4993_11ed_b878_0242ac120002);
 const TORADIO: Uuid = Uuid::from_u128(0xf75c76d2_129e_4dad_a1dd_7866124401e7);
 const FROMNUM: Uuid = Uuid::from_u128(0xed9da18c_a800_4f66_a670_aa7547e34453);
 
+/// The time after which a discovered BLE radio that has stopped advertising is reported as lost by
+/// the stream returned from the `ble_discovery_stream` function.
+pub const BLE_DEVICE_LOST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The time allowed for connecting to a BLE radio in the `build_ble_stream` function, after which it fails
+/// with `Error::ConnectTimeout`. Connecting includes the pairing handshake, so it takes longer than opening
+/// a TCP connection.
+pub const BLE_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The time without data from the radio after which the stream built by the `build_ble_stream` function
+/// restores the connection. BLE notifications can stop silently, e.g. when the radio sleeps or
+/// moves out of range, so a quiet connection is not necessarily a closed one.
+pub const BLE_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The interval at which the stream built by the `build_ble_stream` function checks for a stalled connection.
+const BLE_STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A struct that describes a BLE radio running Meshtastic, as found by the `ble_discovery_stream` function.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BleDevice {
+    /// The advertised name of the radio, which can be passed to `BleHandler::new`.
+    pub name: Option<String>,
+
+    /// The MAC address of the radio. Some platforms (e.g. macOS) do not expose MAC addresses, in
+    /// which case this address is zeroed.
+    pub mac_address: BDAddr,
+}
+
+/// An enum that represents a change in the set of BLE radios within range of the host.
+///
+/// # Variants
+///
+/// * `Found` - A radio started advertising the Meshtastic service.
+/// * `Lost` - A radio has not advertised for `BLE_DEVICE_LOST_TIMEOUT`, e.g. because it moved out of range.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BleDiscoveryEvent {
+    Found(BleDevice),
+    Lost(BleDevice),
+}
+
+/// Returns a stream of `BleDiscoveryEvent`s, which report BLE radios running Meshtastic as they come
+/// into and go out of range of the host.
+///
+/// The stream is backed by a single scan on the first BLE adapter of the host, which runs within a
+/// background task. The scan is stopped and the task finishes once the stream is dropped, so the
+/// stream can be dropped at any time without leaking the task.
+///
+/// # Arguments
+///
+/// None
+///
+/// # Returns
+///
+/// A stream of `BleDiscoveryEvent`s, which ends if the adapter stops reporting events.
+///
+/// # Examples
+///
+/// ```
+/// let mut discovery_stream = std::pin::pin!(utils::stream::ble_discovery_stream().await?);
+///
+/// while let Some(event) = discovery_stream.next().await {
+///     match event {
+///         BleDiscoveryEvent::Found(device) => println!("Found {:?}", device.name),
+///         BleDiscoveryEvent::Lost(device) => println!("Lost {:?}", device.name),
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::StreamBuildError` if the host has no BLE adapter, or if the scan fails to start.
+///
+/// # Panics
+///
+/// None
+///
+pub async fn ble_discovery_stream() -> Result<impl Stream<Item = BleDiscoveryEvent> + Send, Error> {
+    let scan_error_fn = |e: btleplug::Error| Error::StreamBuildError {
+        source: Box::new(e),
+        description: "Failed to scan for BLE devices".to_owned(),
+    };
+
+    let manager = Manager::new().await.map_err(scan_error_fn)?;
+    let adapter = manager
+        .adapters()
+        .await
+        .map_err(scan_error_fn)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::StreamBuildError {
+            source: Box::new(BleConnectionError()),
+            description: "No BLE adapter is available".to_owned(),
+        })?;
+
+    // Subscribe before scanning to avoid missing devices that are discovered immediately
+    let events = adapter.events().await.map_err(scan_error_fn)?;
+    adapter
+        .start_scan(ScanFilter {
+            services: vec![MSH_SERVICE],
+        })
+        .await
+        .map_err(scan_error_fn)?;
+
+    let (event_tx, event_rx) = mpsc::channel(32);
+    tokio::spawn(run_ble_discovery(adapter, events, event_tx));
+
+    Ok(futures_util::stream::unfold(
+        event_rx,
+        |mut event_rx| async move { event_rx.recv().await.map(|event| (event, event_rx)) },
+    ))
+}
+
+/// Forwards the radios found by a running scan as `BleDiscoveryEvent`s, until the receiver is dropped.
+async fn run_ble_discovery(
+    adapter: Adapter,
+    mut events: BoxStream<'static, CentralEvent>,
+    event_tx: mpsc::Sender<BleDiscoveryEvent>,
+) {
+    let mut devices: HashMap<PeripheralId, (BleDevice, Instant)> = HashMap::new();
+    let mut lost_check = tokio::time::interval(BLE_DEVICE_LOST_TIMEOUT / 3);
+
+    'discovery: loop {
+        tokio::select! {
+            _ = event_tx.closed() => break,
+            event = events.next() => {
+                let id = match event {
+                    Some(CentralEvent::DeviceDiscovered(id))
+                    | Some(CentralEvent::DeviceUpdated(id))
+                    | Some(CentralEvent::ServicesAdvertisement { id, .. })
+                    | Some(CentralEvent::ServiceDataAdvertisement { id, .. })
+                    | Some(CentralEvent::ManufacturerDataAdvertisement { id, .. }) => id,
+                    Some(_) => continue,
+                    None => break,
+                };
+
+                if let Some((_, last_seen)) = devices.get_mut(&id) {
+                    *last_seen = Instant::now();
+                    continue;
+                }
+
+                let Some(device) = meshtastic_device(&adapter, &id).await else {
+                    continue;
+                };
+
+                devices.insert(id, (device.clone(), Instant::now()));
+
+                if event_tx.send(BleDiscoveryEvent::Found(device)).await.is_err() {
+                    break;
+                }
+            }
+            _ = lost_check.tick() => {
+                let lost_ids: Vec<PeripheralId> = devices
+                    .iter()
+                    .filter(|(_, (_, last_seen))| last_seen.elapsed() > BLE_DEVICE_LOST_TIMEOUT)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+
+                for id in lost_ids {
+                    let Some((device, _)) = devices.remove(&id) else {
+                        continue;
+                    };
+
+                    if event_tx.send(BleDiscoveryEvent::Lost(device)).await.is_err() {
+                        break 'discovery;
+                    }
+                }
+            }
+        }
+    }
+
+    if let Err(e) = adapter.stop_scan().await {
+        warn!("Failed to stop BLE discovery scan: {e:?}");
+    }
+
+    debug!("BLE discovery task finished");
+}
+
+/// Returns the `BleDevice` describing a peripheral, if the peripheral advertises the Meshtastic service.
+/// Some platforms ignore the service filter of a scan, so the advertised services are checked here.
+async fn meshtastic_device(adapter: &Adapter, id: &PeripheralId) -> Option<BleDevice> {
+    let properties = adapter
+        .peripheral(id)
+        .await
+        .ok()?
+        .properties()
+        .await
+        .ok()??;
+
+    if !properties.services.contains(&MSH_SERVICE) {
+        return None;
+    }
+
+    Some(BleDevice {
+        name: properties.local_name,
+        mac_address: properties.address,
+    })
+}
+
+/// Builds a stream to the BLE radio with the specified name, which is compatible with the `StreamApi` API.
+///
+/// Packets are exchanged with the radio by a background task, which finishes once the stream is closed.
+/// The task also restores the connection if no data has been received from the radio for `BLE_STALL_TIMEOUT`,
+/// as BLE notifications can stop without the connection being reported as closed. If the radio still answers
+/// a heartbeat, only the notification subscription is renewed, otherwise the connection is re-established.
+/// The recovery is reported on the `connection_events` of the `ConnectedStreamApi` struct: `Reconnecting` once
+/// the stall is detected, followed by `Connected` and `Reconnected` once the connection has been restored.
+///
+/// # Arguments
+///
+/// * `name` - The advertised name of the radio, e.g. as reported by the `ble_discovery_stream` function.
+///
+/// # Returns
+///
+/// Returns a result that resolves to a `StreamHandle` of the client side of the stream, or an error if the
+/// radio could not be connected.
+///
+/// # Examples
+///
+/// ```
+/// let ble_stream = utils::stream::build_ble_stream("Meshtastic_1234".to_string()).await?;
+/// let (decoded_listener, stream_api) = stream_api.connect(ble_stream).await;
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::StreamBuildError` if the radio cannot be found or connected, and with
+/// `Error::ConnectTimeout` if the radio could not be connected within `BLE_CONNECT_TIMEOUT`.
+///
+/// # Panics
+///
+/// None
+///
+pub async fn build_ble_stream(name: String) -> Result<StreamHandle<DuplexStream>, Error> {
+    build_ble_stream_with_timeout(name, BLE_CONNECT_TIMEOUT).await
+}
+
+/// Builds a stream to the BLE radio with the specified name, failing if the radio cannot be connected
+/// within the specified timeout. The stream behaves as described for the `build_ble_stream` function.
+///
+/// The timeout applies to the initial connection, and to every reconnection made when the connection
+/// stalls. It is independent of the time taken by the radio to respond to the `configure` handshake.
+///
+/// # Arguments
+///
+/// * `name` - The advertised name of the radio, e.g. as reported by the `ble_discovery_stream` function.
+/// * `timeout` - The maximum time allowed for connecting to the radio.
+///
+/// # Returns
+///
+/// Returns a result that resolves to a `StreamHandle` of the client side of the stream, or an error if the
+/// radio could not be connected.
+///
+/// # Examples
+///
+/// ```
+/// let ble_stream = utils::stream::build_ble_stream_with_timeout(
+///     "Meshtastic_1234".to_string(),
+///     Duration::from_secs(30),
+/// )
+/// .await?;
+/// let (decoded_listener, stream_api) = stream_api.connect(ble_stream).await;
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::StreamBuildError` if the radio cannot be found or connected, and with
+/// `Error::ConnectTimeout` if the radio could not be connected within the specified timeout.
+///
+/// # Panics
+///
+/// None
+///
+pub async fn build_ble_stream_with_timeout(
+    name: String,
+    timeout: Duration,
+) -> Result<StreamHandle<DuplexStream>, Error> {
+    let ble_handler = BleHandler::with_connect_timeout(name, timeout).await?;
+    let health_monitor = Arc::new(HealthMonitor::new());
+
+    let (client, server) = tokio::io::duplex(BLE_STREAM_BUFFER_SIZE);
+    let join_handle = tokio::spawn(run_ble_stream(ble_handler, server, health_monitor.clone()));
+
+    Ok(StreamHandle {
+        join_handle: Some(join_handle),
+        health_monitor: Some(health_monitor),
+        ..StreamHandle::from_stream(client)
+    })
+}
+
+/// The size of the buffer of the in-memory stream between the `StreamApi` struct and the BLE task.
+const BLE_STREAM_BUFFER_SIZE: usize = 4096;
+
+/// Exchanges packets between the client side of a BLE stream and the radio, until the client side is
+/// closed, restoring the connection whenever it stalls.
+async fn run_ble_stream(
+    mut ble_handler: BleHandler,
+    server: DuplexStream,
+    health_monitor: Arc<HealthMonitor>,
+) -> Result<(), Error> {
+    let (mut server_read, mut server_write) = tokio::io::split(server);
+    let mut stall_detector = StallDetector::new(health_monitor);
+    let mut stall_check = tokio::time::interval(BLE_STALL_CHECK_INTERVAL);
+
+    let mut notifications = ble_handler.notifications().await?;
+
+    // Packets queued before subscribing are not announced by a notification
+    ble_handler.forward_from_radio(&mut server_write).await?;
+
+    let mut read_buffer = [0; 1024];
+    let mut to_radio = Vec::new();
+
+    loop {
+        tokio::select! {
+            read = server_read.read(&mut read_buffer) => {
+                let read = read.map_err(|e| {
+                    Error::InternalStreamError(InternalStreamError::StreamReadError {
+                        source: Box::new(e),
+                    })
+                })?;
+
+                if read == 0 {
+                    debug!("BLE stream closed by the client");
+                    return Ok(());
+                }
+
+                to_radio.extend_from_slice(&read_buffer[..read]);
+
+                while let Some(packet) = take_framed_packet(&mut to_radio) {
+                    ble_handler.write_to_radio(&packet).await?;
+                }
+            }
+            notification = notifications.next() => {
+                let Some(notification) = notification else {
+                    // The radio stopped notifying the host, which is detected as a stall
+                    warn!("BLE notification stream ended");
+                    notifications = Box::pin(futures_util::stream::pending());
+                    continue;
+                };
+
+                if BleHandler::filter_map(notification).await.is_some() {
+                    ble_handler.forward_from_radio(&mut server_write).await?;
+                }
+            }
+            _ = stall_check.tick() => {
+                if !stall_detector.is_stalled_at(StdInstant::now()) {
+                    continue;
+                }
+
+                stall_detector.start_recovery();
+
+                let restored = match ble_handler.restore_connection().await {
+                    Ok(()) => ble_handler.notifications().await,
+                    Err(e) => Err(e),
+                };
+
+                // The recovery is retried on the next check, as the connection is still stalled
+                match restored {
+                    Ok(restored_notifications) => {
+                        notifications = restored_notifications;
+                        stall_detector.finish_recovery(StdInstant::now());
+                        ble_handler.forward_from_radio(&mut server_write).await?;
+                    }
+                    Err(e) => warn!("Failed to restore stalled BLE connection: {e}"),
+                }
+            }
+        }
+    }
+}
+
+/// Removes the first complete packet, including its 4-byte packet header, from data written by the
+/// `StreamApi` struct. Returns `None` if the data does not yet contain a complete packet.
+fn take_framed_packet(data: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let [_, _, msb, lsb, ..] = data[..] else {
+        return None;
+    };
+
+    let packet_len = 4 + usize::from(u16::from_be_bytes([msb, lsb]));
+    if data.len() < packet_len {
+        return None;
+    }
+
+    Some(data.drain(..packet_len).collect())
+}
+
+/// Detects stalls of a BLE connection based on the times recorded by the `HealthMonitor` of the
+/// connection, and reports their recovery as connection events.
+#[derive(Debug)]
+struct StallDetector {
+    health_monitor: Arc<HealthMonitor>,
+    last_recovery: Option<StdInstant>,
+}
+
+impl StallDetector {
+    fn new(health_monitor: Arc<HealthMonitor>) -> Self {
+        Self {
+            health_monitor,
+            last_recovery: None,
+        }
+    }
+
+    /// Returns whether no data has been received from the radio within `BLE_STALL_TIMEOUT` before the
+    /// specified time, since the connection was opened or last restored.
+    fn is_stalled_at(&self, now: StdInstant) -> bool {
+        let health = *self.health_monitor.subscribe().borrow();
+
+        let last_activity = [health.last_byte_received, self.last_recovery]
+            .into_iter()
+            .flatten()
+            .fold(health.connected_at, StdInstant::max);
+
+        now.saturating_duration_since(last_activity) >= BLE_STALL_TIMEOUT
+    }
+
+    fn start_recovery(&self) {
+        self.health_monitor.set_state(ConnectionState::Reconnecting);
+    }
+
+    fn finish_recovery(&mut self, now: StdInstant) {
+        self.last_recovery = Some(now);
+        self.health_monitor.set_state(ConnectionState::Connected);
+        self.health_monitor.emit(ConnectionEvent::Reconnected);
+    }
+}
+
+/// A struct that handles the connection to a BLE radio running Meshtastic, and reads from and writes to
+/// the Meshtastic GATT characteristics of the radio.
+///
+/// **Note:** The GATT MTU of the connection is negotiated by the operating system (e.g. BlueZ and
+/// CoreBluetooth request the largest MTU supported by both sides when connecting), and is neither
+/// exposed nor adjustable through `btleplug`. Writes to the radio use write requests with response,
+/// which the operating system splits into long writes if a packet exceeds the negotiated MTU.
 pub struct BleHandler {
     radio: Peripheral,
     adapter: Adapter,
     toradio_char: Characteristic,
     fromradio_char: Characteristic,
     fromnum_char: Characteristic,
+    name: String,
+    connect_timeout: Duration,
 }
 
 #[allow(dead_code)]
 impl BleHandler {
     pub async fn new(name: String) -> Result<Self, Error> {
+        Self::with_connect_timeout(name, BLE_CONNECT_TIMEOUT).await
+    }
+
+    /// Connects to the BLE radio with the specified name, failing with `Error::ConnectTimeout` if the
+    /// radio cannot be connected within the specified timeout.
+    pub async fn with_connect_timeout(name: String, timeout: Duration) -> Result<Self, Error> {
         let (radio, adapter) = Self::find_ble_radio(&name).await?;
-        radio.connect().await.map_err(|e| Error::StreamBuildError {
-            source: Box::new(e),
-            description: format!("Failed to connect to the device {name}"),
-        })?;
+        Self::connect_radio(&radio, &name, timeout).await?;
         let [toradio_char, fromnum_char, fromradio_char] =
             Self::find_characteristics(&radio).await?;
         Ok(BleHandler {
@@ -38,9 +486,78 @@ impl BleHandler {
             toradio_char,
             fromradio_char,
             fromnum_char,
+            name,
+            connect_timeout: timeout,
         })
     }
 
+    /// Connects to the radio, as `Peripheral::connect` can wait indefinitely for a radio that has gone
+    /// out of range after it was found.
+    async fn connect_radio(radio: &Peripheral, name: &str, timeout: Duration) -> Result<(), Error> {
+        connect_within(name, timeout, radio.connect())
+            .await?
+            .map_err(|e| Error::StreamBuildError {
+                source: Box::new(e),
+                description: format!("Failed to connect to the device {name}"),
+            })
+    }
+
+    /// Restores a stalled connection to the radio. A heartbeat is written to the radio to check whether
+    /// the link is still alive. If it is, the radio has only stopped notifying the host, and the stale
+    /// `fromnum` subscription is dropped. Otherwise the connection is re-established, and the
+    /// characteristics of the radio are rediscovered.
+    ///
+    /// In both cases the `notifications` method must be called again to resubscribe.
+    async fn restore_connection(&mut self) -> Result<(), Error> {
+        let is_connected = self.radio.is_connected().await.unwrap_or(false);
+
+        if is_connected && self.write_heartbeat().await.is_ok() {
+            warn!("No data received from BLE radio, renewing notification subscription");
+
+            // The subscription may be stale, so a failure to unsubscribe is expected
+            let _ = self.radio.unsubscribe(&self.fromnum_char).await;
+            return Ok(());
+        }
+
+        warn!("BLE radio did not answer heartbeat, reconnecting");
+
+        // The previous connection may already be gone, in which case there is nothing to close
+        let _ = self.radio.disconnect().await;
+
+        Self::connect_radio(&self.radio, &self.name, self.connect_timeout).await?;
+
+        let [toradio_char, fromnum_char, fromradio_char] =
+            Self::find_characteristics(&self.radio).await?;
+
+        self.toradio_char = toradio_char;
+        self.fromnum_char = fromnum_char;
+        self.fromradio_char = fromradio_char;
+
+        Ok(())
+    }
+
+    /// Writes a heartbeat to the radio, which fails if the radio does not acknowledge the write.
+    async fn write_heartbeat(&self) -> Result<(), Error> {
+        let heartbeat = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::Heartbeat(
+                protobufs::Heartbeat::default(),
+            )),
+        };
+
+        self.radio
+            .write(
+                &self.toradio_char,
+                &heartbeat.encode_to_vec(),
+                WriteType::WithResponse,
+            )
+            .await
+            .map_err(|e: btleplug::Error| {
+                Error::InternalStreamError(InternalStreamError::StreamWriteError {
+                    source: Box::new(e),
+                })
+            })
+    }
+
     async fn scan_peripherals(adapter: &Adapter) -> Result<Vec<Peripheral>, btleplug::Error> {
         adapter
             .start_scan(ScanFilter {
@@ -144,6 +661,27 @@ impl BleHandler {
             .map_err(Self::ble_read_error_fn)
     }
 
+    /// Reads every packet queued by the radio, and writes them to the client side of the stream with
+    /// a packet header, as expected by the `StreamApi` struct.
+    async fn forward_from_radio<W>(&self, client: &mut W) -> Result<(), Error>
+    where
+        W: AsyncWriteExt + Unpin,
+    {
+        loop {
+            let packet = self.read_from_radio().await?;
+            if packet.is_empty() {
+                return Ok(());
+            }
+
+            let packet = format_data_packet(packet.into())?;
+            client.write_all(packet.data()).await.map_err(|e| {
+                Error::InternalStreamError(InternalStreamError::StreamWriteError {
+                    source: Box::new(e),
+                })
+            })?;
+        }
+    }
+
     fn parse_u32(data: Vec<u8>) -> Result<u32, Error> {
         let parsed_value = u32::from_le_bytes(data.as_slice().try_into().map_err(|e| {
             Error::InternalStreamError(InternalStreamError::StreamReadError {
@@ -162,7 +700,7 @@ impl BleHandler {
         Self::parse_u32(data)
     }
 
-    pub async fn notifications(&self) -> Result<BoxStream<ValueNotification>, Error> {
+    pub async fn notifications(&self) -> Result<BoxStream<'static, ValueNotification>, Error> {
         self.radio
             .subscribe(&self.fromnum_char)
             .await
@@ -200,3 +738,73 @@ impl BleHandler {
         return false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_complete_framed_packets() {
+        let mut data = vec![0x94, 0xc3, 0x00, 0x02, 0x08, 0x01, 0x94, 0xc3, 0x00];
+
+        assert_eq!(
+            take_framed_packet(&mut data),
+            Some(vec![0x94, 0xc3, 0x00, 0x02, 0x08, 0x01])
+        );
+        assert_eq!(take_framed_packet(&mut data), None);
+
+        data.extend_from_slice(&[0x01, 0x18]);
+        assert_eq!(
+            take_framed_packet(&mut data),
+            Some(vec![0x94, 0xc3, 0x00, 0x01, 0x18])
+        );
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn detects_and_recovers_stalled_connections() {
+        let health_monitor = Arc::new(HealthMonitor::new());
+        let mut events = health_monitor.subscribe_events();
+        let mut stall_detector = StallDetector::new(health_monitor.clone());
+
+        let connected_at = health_monitor.subscribe().borrow().connected_at;
+        let second = Duration::from_secs(1);
+
+        assert!(!stall_detector.is_stalled_at(connected_at + BLE_STALL_TIMEOUT - second));
+        assert!(stall_detector.is_stalled_at(connected_at + BLE_STALL_TIMEOUT));
+
+        // Data received from the radio postpones the stall
+        health_monitor.record_bytes_received();
+        let received_at = health_monitor
+            .subscribe()
+            .borrow()
+            .last_byte_received
+            .unwrap();
+
+        assert!(!stall_detector.is_stalled_at(received_at + BLE_STALL_TIMEOUT - second));
+        assert!(stall_detector.is_stalled_at(received_at + BLE_STALL_TIMEOUT));
+
+        // A restored connection is given a full timeout to deliver data
+        let recovered_at = received_at + BLE_STALL_TIMEOUT;
+        stall_detector.start_recovery();
+        stall_detector.finish_recovery(recovered_at);
+
+        assert!(!stall_detector.is_stalled_at(recovered_at + BLE_STALL_TIMEOUT - second));
+        assert!(stall_detector.is_stalled_at(recovered_at + BLE_STALL_TIMEOUT));
+
+        let mut received_events = vec![];
+        while let Ok(event) = events.try_recv() {
+            received_events.push(event);
+        }
+
+        assert_eq!(
+            received_events,
+            [
+                ConnectionEvent::Connected,
+                ConnectionEvent::Reconnecting,
+                ConnectionEvent::Connected,
+                ConnectionEvent::Reconnected,
+            ]
+        );
+    }
+}
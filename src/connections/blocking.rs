@@ -0,0 +1,281 @@
+use std::{convert::Infallible, future::Future};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    runtime::Runtime,
+};
+
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::utils_internal::generate_rand_id;
+
+use super::{
+    stream_api::{state, ConnectedStreamApi, StreamApi, StreamHandle},
+    wrappers::{mesh_channel::MeshChannel, NodeId, PacketId},
+    PacketDestination, PacketRouter,
+};
+
+/// A packet router used by the `BlockingStreamApi` struct, which discards echoed packets and reports
+/// the node id of the connected radio once it is known.
+#[derive(Clone, Copy, Debug, Default)]
+struct BlockingRouter {
+    node_id: NodeId,
+}
+
+impl PacketRouter<(), Infallible> for BlockingRouter {
+    fn handle_packet_from_radio(
+        &mut self,
+        _packet: protobufs::FromRadio,
+    ) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn handle_mesh_packet(&mut self, _packet: protobufs::MeshPacket) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn source_node_id(&self) -> NodeId {
+        self.node_id
+    }
+}
+
+/// A blocking facade over the `StreamApi` struct, for scripts that do not want to set up an async runtime.
+///
+/// The `BlockingStreamApi` struct owns a private tokio runtime, on which the worker threads of the
+/// underlying `ConnectedStreamApi` run. Each method blocks the calling thread until the corresponding
+/// async operation completes. This struct is intended as a convenience for simple scripts, and only
+/// exposes a small subset of the async API; applications that need more control should use the
+/// `StreamApi` struct directly.
+///
+/// **Note:** The methods of this struct must not be called from within an async context, as blocking
+/// within an async runtime will panic.
+///
+/// This struct is only available if the `blocking` feature is enabled.
+pub struct BlockingStreamApi {
+    runtime: Runtime,
+    stream_api: ConnectedStreamApi<state::Configured>,
+    packet_receiver: crate::packet::PacketReceiver,
+    router: BlockingRouter,
+}
+
+impl BlockingStreamApi {
+    /// Connects to a radio and requests its configuration, blocking until the connection is open.
+    ///
+    /// The stream to the radio is built by the passed closure, which is run on the private runtime of
+    /// the `BlockingStreamApi` struct. This allows the async stream builder utility functions to be used.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_stream` - A closure that returns a future resolving to a `StreamHandle` of the stream to the radio.
+    ///
+    /// # Returns
+    ///
+    /// A `BlockingStreamApi` instance connected to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stream_api =
+    ///     BlockingStreamApi::connect(|| utils::stream::build_tcp_stream("localhost:4403".to_string()))?;
+    ///
+    /// while let Some(packet) = stream_api.recv() {
+    ///     println!("Received packet: {:?}", packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the runtime cannot be created, if the stream fails to build, or if the configuration
+    /// request fails to send.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an async context.
+    ///
+    pub fn connect<S, F, Fut>(build_stream: F) -> Result<Self, Error>
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<StreamHandle<S>, Error>>,
+    {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| Error::StreamBuildError {
+                source: Box::new(e),
+                description: "Failed to build runtime for blocking API".to_string(),
+            })?;
+
+        let (packet_receiver, stream_api) = runtime.block_on(async {
+            let stream_handle = build_stream().await?;
+            let (packet_receiver, stream_api) = StreamApi::new().connect(stream_handle).await;
+            let stream_api = stream_api.configure(generate_rand_id()).await?;
+
+            Ok::<_, Error>((packet_receiver, stream_api))
+        })?;
+
+        Ok(Self {
+            runtime,
+            stream_api,
+            packet_receiver,
+            router: BlockingRouter::default(),
+        })
+    }
+
+    /// Blocks until the next packet is received from the radio.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The next `FromRadio` packet, or `None` once the connection to the radio has closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(packet) = stream_api.recv() {
+    ///     println!("Received packet: {:?}", packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an async context.
+    ///
+    pub fn recv(&mut self) -> Option<protobufs::FromRadio> {
+        self.runtime.block_on(self.packet_receiver.recv())
+    }
+
+    /// Returns an iterator over the packets received from the radio, which blocks until each next
+    /// packet is received. The iterator ends once the connection to the radio has closed.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// An iterator of `FromRadio` packets, which calls the `recv` method for each item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for packet in stream_api.iter() {
+    ///     println!("Received packet: {:?}", packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an async context.
+    ///
+    pub fn iter(&mut self) -> impl Iterator<Item = protobufs::FromRadio> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+
+    /// Sends the specified text content over the mesh, blocking until the packets have been queued.
+    /// See the `send_text` method of the `ConnectedStreamApi` struct for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A `String` containing the text to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the packet on.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the ids of the sent packets, in the order of the chunks of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.send_text(
+    ///     "Hello world!".to_string(),
+    ///     PacketDestination::Broadcast,
+    ///     true,
+    ///     MeshChannel::default(),
+    /// )?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the packets fails to send.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an async context.
+    ///
+    pub fn send_text(
+        &mut self,
+        text: String,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel: MeshChannel,
+    ) -> Result<Vec<PacketId>, Error> {
+        self.router.node_id = self.stream_api.my_node_id().unwrap_or_default();
+
+        self.runtime.block_on(self.stream_api.send_text(
+            &mut self.router,
+            text,
+            destination,
+            want_ack,
+            channel,
+        ))
+    }
+
+    /// Disconnects from the radio, blocking until all worker threads have shut down.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the worker threads fail to join.
+    pub fn disconnect(self) -> Result<(), Error> {
+        self.runtime.block_on(self.stream_api.disconnect())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::utils_internal::format_data_packet;
+
+    #[test]
+    fn iterator_ends_when_connection_closes() {
+        let packet = protobufs::FromRadio {
+            id: 1,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(42)),
+        };
+
+        let framed_packet = format_data_packet(packet.encode_to_vec().into()).unwrap();
+
+        let mut stream_api = BlockingStreamApi::connect(|| async move {
+            let (client, mut server) = tokio::io::duplex(1024);
+
+            server.write_all(framed_packet.data()).await.unwrap();
+            drop(server);
+
+            Ok(StreamHandle::from_stream(client))
+        })
+        .unwrap();
+
+        let packets: Vec<protobufs::FromRadio> = stream_api.iter().collect();
+        assert_eq!(packets, [packet]);
+    }
+}
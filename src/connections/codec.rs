@@ -0,0 +1,269 @@
+use log::warn;
+use prost::Message;
+use tokio_util::bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::errors_internal::Error;
+use crate::protobufs;
+
+use super::stream_buffer::{FramingConfig, StreamBuffer, DEFAULT_MAX_BUFFER_SIZE};
+
+/// A `tokio_util` codec that decodes `FromRadio` packets from the framed byte stream sent by a radio,
+/// and encodes `ToRadio` packets into the framed byte stream expected by a radio.
+///
+/// The codec reuses the framing and resynchronization logic of the `StreamApi` struct, so bytes that
+/// are not part of a valid packet (e.g. debug log output of the radio) are skipped, and malformed
+/// packets are discarded without ending the stream. This allows a transport to be wrapped in a
+/// `tokio_util::codec::FramedRead`, yielding decoded packets through the `StreamExt` trait, as an
+/// alternative to the channel-based API of the `StreamApi` struct. Wrapping a transport in a
+/// `tokio_util::codec::Framed` allows packets to be both sent and received through the codec.
+///
+/// Each encoded `ToRadio` packet is prefixed with the `START1` and `START2` magic bytes and the big-endian
+/// length of the encoded packet. Packets larger than `MAX_PACKET_SIZE`, the maximum packet size accepted by
+/// the firmware, fail to encode, as the radio would be unable to parse them. The `with_framing` method
+/// overrides these framing constants.
+///
+/// **Note:** The codec only frames packets. Unlike the `StreamApi` struct, it does not send heartbeats
+/// or request the configuration of the radio.
+#[derive(Debug)]
+pub struct MeshtasticCodec {
+    buffer: StreamBuffer,
+    framing: FramingConfig,
+}
+
+impl MeshtasticCodec {
+    /// Creates a new `MeshtasticCodec` that buffers at most `DEFAULT_MAX_BUFFER_SIZE` bytes of
+    /// undecoded data.
+    pub fn new() -> Self {
+        Self::with_max_buffer_size(DEFAULT_MAX_BUFFER_SIZE)
+    }
+
+    /// Creates a new `MeshtasticCodec` that buffers at most `max_buffer_size` bytes of undecoded data.
+    /// If this limit is exceeded, the buffered data is discarded and the codec resynchronizes on the
+    /// next packet header.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_buffer_size` - The maximum number of bytes to hold while waiting for the remainder of a packet.
+    ///
+    /// # Returns
+    ///
+    /// A new `MeshtasticCodec` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut packets = FramedRead::new(tcp_stream, MeshtasticCodec::with_max_buffer_size(8192));
+    ///
+    /// while let Some(packet) = packets.next().await {
+    ///     println!("Received packet: {:?}", packet?);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_max_buffer_size(max_buffer_size: usize) -> Self {
+        Self::with_framing(max_buffer_size, FramingConfig::default())
+    }
+
+    /// Creates a new `MeshtasticCodec` that frames packets with the specified magic bytes and maximum
+    /// packet size, rather than the framing used by the firmware. This is intended for experimenting
+    /// with protocol variants.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_buffer_size` - The maximum number of bytes to hold while waiting for the remainder of a packet.
+    /// * `framing` - The magic bytes and maximum packet size to frame packets with.
+    ///
+    /// # Returns
+    ///
+    /// A new `MeshtasticCodec` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let framing = FramingConfig {
+    ///     max_packet_size: 1024,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let framed = Framed::new(stream, MeshtasticCodec::with_framing(DEFAULT_MAX_BUFFER_SIZE, framing));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_framing(max_buffer_size: usize, framing: FramingConfig) -> Self {
+        Self {
+            buffer: StreamBuffer::with_framing(max_buffer_size, framing),
+            framing,
+        }
+    }
+}
+
+impl Default for MeshtasticCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for MeshtasticCodec {
+    type Item = protobufs::FromRadio;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // Incomplete packets are left in the read buffer of the framed transport
+        match self.buffer.next_packet(src) {
+            Ok(decoded_packet) => Ok(decoded_packet.map(|p| p.packet)),
+            Err(e) => {
+                // The buffer discards the overflowing data and recovers on the next packet header
+                warn!("{e}");
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Encoder<protobufs::ToRadio> for MeshtasticCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: protobufs::ToRadio, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let data = item.encode_to_vec();
+
+        // The packet length must also fit within the two length bytes of the header
+        let data_length = match u16::try_from(data.len()) {
+            Ok(length) if data.len() <= self.framing.max_packet_size => length,
+            _ => {
+                return Err(Error::InvalidaDataSize {
+                    data_length: data.len(),
+                })
+            }
+        };
+
+        dst.reserve(4 + data.len());
+        dst.extend_from_slice(&[self.framing.start1, self.framing.start2]);
+        dst.extend_from_slice(&data_length.to_be_bytes());
+        dst.extend_from_slice(&data);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncReadExt;
+    use tokio_util::codec::{Framed, FramedRead};
+
+    use super::*;
+    use crate::connections::stream_buffer::MAX_PACKET_SIZE;
+    use crate::utils_internal::format_data_packet;
+
+    #[tokio::test]
+    async fn decodes_packets_from_framed_stream() {
+        let packets: Vec<protobufs::FromRadio> = (1..=3)
+            .map(|id| protobufs::FromRadio {
+                id,
+                payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(id)),
+            })
+            .collect();
+
+        // Debug log output may be interleaved with the packets sent by the radio
+        let mut data = b"DEBUG | boot\n".to_vec();
+
+        for packet in &packets {
+            let framed_packet = format_data_packet(packet.encode_to_vec().into()).unwrap();
+            data.extend_from_slice(framed_packet.data());
+        }
+
+        let decoded: Vec<protobufs::FromRadio> =
+            FramedRead::with_capacity(data.as_slice(), MeshtasticCodec::new(), 5)
+                .map(|packet| packet.unwrap())
+                .collect()
+                .await;
+
+        assert_eq!(decoded, packets);
+    }
+
+    #[tokio::test]
+    async fn encodes_framed_packets() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut client = Framed::new(client, MeshtasticCodec::new());
+
+        let packet = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::WantConfigId(42)),
+        };
+        client.send(packet.clone()).await.unwrap();
+
+        let oversized_packet = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::Packet(
+                protobufs::MeshPacket {
+                    payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(
+                        vec![0; MAX_PACKET_SIZE],
+                    )),
+                    ..Default::default()
+                },
+            )),
+        };
+        assert!(matches!(
+            client.send(oversized_packet).await,
+            Err(Error::InvalidaDataSize { .. })
+        ));
+
+        drop(client);
+
+        // The radio receives the same bytes that the `StreamApi` struct would write
+        let mut written = vec![];
+        server.read_to_end(&mut written).await.unwrap();
+
+        assert_eq!(
+            written,
+            format_data_packet(packet.encode_to_vec().into())
+                .unwrap()
+                .data()
+        );
+    }
+
+    #[tokio::test]
+    async fn supports_custom_framing() {
+        let framing = FramingConfig {
+            start1: 0xaa,
+            start2: 0x55,
+            max_packet_size: 1024,
+        };
+
+        let packet = protobufs::FromRadio {
+            id: 7,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(7)),
+        };
+
+        let data = packet.encode_to_vec();
+        let mut framed_packet = vec![0xaa, 0x55, 0x00, data.len() as u8];
+        framed_packet.extend_from_slice(&data);
+
+        // Packets framed with the default magic bytes are skipped
+        let mut stream = format_data_packet(data.into()).unwrap().data_vec();
+        stream.extend_from_slice(&framed_packet);
+
+        let decoded: Vec<protobufs::FromRadio> = FramedRead::new(
+            stream.as_slice(),
+            MeshtasticCodec::with_framing(DEFAULT_MAX_BUFFER_SIZE, framing),
+        )
+        .map(|packet| packet.unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(decoded, [packet]);
+    }
+}
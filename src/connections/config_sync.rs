@@ -0,0 +1,544 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use crate::errors_internal::Error;
+use crate::helpers_internal::firmware::{supported_config_types, supported_module_config_types};
+use crate::protobufs;
+use crate::protobufs::admin_message::{ConfigType, ModuleConfigType};
+
+use super::radio_state::{ConfigHandshake, RadioState};
+
+/// The maximum number of message channels supported by the firmware.
+pub const MAX_NUM_CHANNELS: u32 = 8;
+
+/// A struct that tracks the progress of the initial configuration sync with a radio.
+///
+/// After a `WantConfigId` packet has been sent to the radio (see the `configure` method),
+/// the radio responds with its current configuration, module configuration, and channel
+/// configuration, followed by a `ConfigCompleteId` packet. This struct is fed the resulting
+/// `FromRadio` packets, and reports which configuration sections have been received and
+/// which are still pending. This is intended to allow a UI to show sync progress.
+///
+/// The sync is considered complete once all expected responses have been received, and a
+/// `ConfigCompleteId` packet matching the configuration id has arrived.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigSync {
+    config_id: u32,
+
+    expected_config_types: BTreeSet<ConfigType>,
+    expected_module_config_types: BTreeSet<ModuleConfigType>,
+    expected_channels: BTreeSet<u32>,
+
+    received_config_types: BTreeSet<ConfigType>,
+    received_module_config_types: BTreeSet<ModuleConfigType>,
+    received_channels: BTreeSet<u32>,
+
+    config_complete: bool,
+}
+
+impl ConfigSync {
+    /// Creates a new `ConfigSync` tracker that expects every `ConfigType`, every `ModuleConfigType`,
+    /// and all `MAX_NUM_CHANNELS` channels to be sent by the radio.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - The configuration id that was sent to the radio within the `WantConfigId` packet.
+    ///
+    /// # Returns
+    ///
+    /// A new `ConfigSync` instance with no received responses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config_id = utils::generate_rand_id();
+    /// let mut config_sync = ConfigSync::new(config_id);
+    /// let stream_api = stream_api.configure(config_id).await?;
+    ///
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     config_sync.handle_packet(&packet);
+    ///     println!("Sync progress: {:?}", config_sync.progress());
+    ///
+    ///     if config_sync.is_complete() {
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn new(config_id: u32) -> Self {
+        let config_types = (0..)
+            .map_while(|v| ConfigType::try_from(v).ok())
+            .collect::<Vec<_>>();
+
+        let module_config_types = (0..)
+            .map_while(|v| ModuleConfigType::try_from(v).ok())
+            .collect::<Vec<_>>();
+
+        Self::with_expected(
+            config_id,
+            config_types,
+            module_config_types,
+            0..MAX_NUM_CHANNELS,
+        )
+    }
+
+    /// Creates a new `ConfigSync` tracker that only expects the specified configuration sections.
+    /// This is intended to be used with radios that don't report every configuration section,
+    /// for example due to running older firmware.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - The configuration id that was sent to the radio within the `WantConfigId` packet.
+    /// * `config_types` - The `ConfigType` sections the radio is expected to send.
+    /// * `module_config_types` - The `ModuleConfigType` sections the radio is expected to send.
+    /// * `channels` - The channel indices the radio is expected to send.
+    ///
+    /// # Returns
+    ///
+    /// A new `ConfigSync` instance with no received responses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config_sync = ConfigSync::with_expected(
+    ///     config_id,
+    ///     [ConfigType::DeviceConfig, ConfigType::LoraConfig],
+    ///     [],
+    ///     0..8,
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_expected(
+        config_id: u32,
+        config_types: impl IntoIterator<Item = ConfigType>,
+        module_config_types: impl IntoIterator<Item = ModuleConfigType>,
+        channels: impl IntoIterator<Item = u32>,
+    ) -> Self {
+        Self {
+            config_id,
+            expected_config_types: config_types.into_iter().collect(),
+            expected_module_config_types: module_config_types.into_iter().collect(),
+            expected_channels: channels.into_iter().collect(),
+            received_config_types: BTreeSet::new(),
+            received_module_config_types: BTreeSet::new(),
+            received_channels: BTreeSet::new(),
+            config_complete: false,
+        }
+    }
+
+    /// Updates the sync progress based on a `FromRadio` packet received from the radio.
+    /// Packets that are not part of the configuration sync are ignored.
+    ///
+    /// The `DeviceMetadata` the radio sends before its configuration narrows the expected sections down
+    /// to those returned by the `supported_config_types` and `supported_module_config_types` functions,
+    /// so that the sync can complete on firmware that doesn't report every section.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `FromRadio` packet received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// A `bool` indicating whether the packet was part of the configuration sync.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     config_sync.handle_packet(&packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn handle_packet(&mut self, packet: &protobufs::FromRadio) -> bool {
+        let Some(payload_variant) = packet.payload_variant.as_ref() else {
+            return false;
+        };
+
+        match payload_variant {
+            protobufs::from_radio::PayloadVariant::Metadata(metadata) => {
+                let config_types = supported_config_types(metadata);
+                let module_config_types = supported_module_config_types(metadata);
+
+                self.expected_config_types
+                    .retain(|config_type| config_types.contains(config_type));
+                self.expected_module_config_types
+                    .retain(|module_config_type| module_config_types.contains(module_config_type));
+            }
+            protobufs::from_radio::PayloadVariant::Config(config) => {
+                match config.config_type() {
+                    Some(config_type) => self.received_config_types.insert(config_type),
+                    None => return false,
+                };
+            }
+            protobufs::from_radio::PayloadVariant::ModuleConfig(module_config) => {
+                match module_config.module_config_type() {
+                    Some(module_config_type) => {
+                        self.received_module_config_types.insert(module_config_type)
+                    }
+                    None => return false,
+                };
+            }
+            protobufs::from_radio::PayloadVariant::Channel(channel) => {
+                let Ok(index) = u32::try_from(channel.index) else {
+                    return false;
+                };
+
+                self.received_channels.insert(index);
+            }
+            protobufs::from_radio::PayloadVariant::ConfigCompleteId(id) => {
+                if *id != self.config_id {
+                    return false;
+                }
+
+                self.config_complete = true;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Returns the configuration id this tracker is waiting on.
+    pub fn config_id(&self) -> u32 {
+        self.config_id
+    }
+
+    /// Returns the `ConfigType` sections that have been received, in ascending order.
+    pub fn received_config_types(&self) -> Vec<ConfigType> {
+        self.received_config_types.iter().copied().collect()
+    }
+
+    /// Returns the expected `ConfigType` sections that have not yet been received, in ascending order.
+    pub fn pending_config_types(&self) -> Vec<ConfigType> {
+        self.expected_config_types
+            .difference(&self.received_config_types)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the `ModuleConfigType` sections that have been received, in ascending order.
+    pub fn received_module_config_types(&self) -> Vec<ModuleConfigType> {
+        self.received_module_config_types.iter().copied().collect()
+    }
+
+    /// Returns the expected `ModuleConfigType` sections that have not yet been received, in ascending order.
+    pub fn pending_module_config_types(&self) -> Vec<ModuleConfigType> {
+        self.expected_module_config_types
+            .difference(&self.received_module_config_types)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the channel indices that have been received, in ascending order.
+    pub fn received_channels(&self) -> Vec<u32> {
+        self.received_channels.iter().copied().collect()
+    }
+
+    /// Returns the expected channel indices that have not yet been received, in ascending order.
+    pub fn pending_channels(&self) -> Vec<u32> {
+        self.expected_channels
+            .difference(&self.received_channels)
+            .copied()
+            .collect()
+    }
+
+    /// Returns whether the radio has sent a `ConfigCompleteId` packet matching the configuration id.
+    pub fn config_complete_received(&self) -> bool {
+        self.config_complete
+    }
+
+    /// Returns the progress of the sync as a `(received, expected)` tuple. The `ConfigCompleteId`
+    /// packet is counted as one of the expected responses. This is intended to drive a progress bar.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the number of received expected responses, and the total number of expected responses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (received, expected) = config_sync.progress();
+    /// println!("Synced {}/{} sections", received, expected);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn progress(&self) -> (usize, usize) {
+        let expected = self.expected_config_types.len()
+            + self.expected_module_config_types.len()
+            + self.expected_channels.len()
+            + 1;
+
+        let received = self
+            .expected_config_types
+            .intersection(&self.received_config_types)
+            .count()
+            + self
+                .expected_module_config_types
+                .intersection(&self.received_module_config_types)
+                .count()
+            + self
+                .expected_channels
+                .intersection(&self.received_channels)
+                .count()
+            + usize::from(self.config_complete);
+
+        (received, expected)
+    }
+
+    /// Returns whether all expected responses and the `ConfigCompleteId` packet have been received.
+    pub fn is_complete(&self) -> bool {
+        let (received, expected) = self.progress();
+        received == expected
+    }
+}
+
+/// A handle to a `configure` handshake, which can be used to wait for the handshake to complete, or to
+/// cancel a handshake that has stalled without dropping the connection to the radio.
+///
+/// Cancelling a handshake only stops the library from waiting for it, as the firmware does not support
+/// aborting a config sync. Any configuration packets the radio is still sending are received as usual.
+/// A cancelled handshake can be retried by calling the `reconfigure` method of the `ConnectedStreamApi`
+/// struct with a new config id, which makes the radio restart the sync from the beginning.
+///
+/// Cloning a `ConfigHandle` returns a handle to the same handshake.
+#[derive(Clone, Debug)]
+pub struct ConfigHandle {
+    config_id: u32,
+    radio_state: Arc<RadioState>,
+}
+
+impl ConfigHandle {
+    pub(crate) fn new(config_id: u32, radio_state: Arc<RadioState>) -> Self {
+        Self {
+            config_id,
+            radio_state,
+        }
+    }
+
+    /// Returns the config id that was sent to the radio within the `WantConfigId` packet.
+    pub fn config_id(&self) -> u32 {
+        self.config_id
+    }
+
+    /// Returns whether the radio has not yet completed the handshake, and the handshake has not been
+    /// cancelled or superseded by a newer handshake.
+    pub fn is_pending(&self) -> bool {
+        self.radio_state.config_handshake() == Some(ConfigHandshake::Pending(self.config_id))
+    }
+
+    /// Cancels the handshake, returning whether it was still pending. Any task waiting on the `wait`
+    /// method of this handshake resolves to `Error::ConfigCancelled`.
+    pub fn cancel(&self) -> bool {
+        self.radio_state.cancel_config_handshake(self.config_id)
+    }
+
+    /// Waits until the radio has sent the `ConfigCompleteId` packet matching the config id of this handshake.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the handshake completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config_handle = stream_api.config_handle().unwrap();
+    ///
+    /// if tokio::time::timeout(Duration::from_secs(30), config_handle.wait()).await.is_err() {
+    ///     config_handle.cancel();
+    ///     stream_api.reconfigure(utils::generate_rand_id()).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::ConfigCancelled` if the handshake is cancelled, or superseded by a newer handshake.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn wait(&self) -> Result<(), Error> {
+        match self
+            .radio_state
+            .wait_for_config_handshake(self.config_id)
+            .await
+        {
+            Some(ConfigHandshake::Complete(config_id)) if config_id == self.config_id => Ok(()),
+            _ => Err(Error::ConfigCancelled {
+                config_id: self.config_id,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_packet(payload_variant: protobufs::config::PayloadVariant) -> protobufs::FromRadio {
+        protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Config(
+                protobufs::Config {
+                    payload_variant: Some(payload_variant),
+                },
+            )),
+        }
+    }
+
+    fn channel_packet(index: i32) -> protobufs::FromRadio {
+        protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Channel(
+                protobufs::Channel {
+                    index,
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+
+    fn config_complete_packet(config_id: u32) -> protobufs::FromRadio {
+        protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(
+                config_id,
+            )),
+        }
+    }
+
+    #[test]
+    fn new_expects_all_sections() {
+        let config_sync = ConfigSync::new(1);
+
+        assert_eq!(config_sync.pending_config_types().len(), 7);
+        assert_eq!(config_sync.pending_module_config_types().len(), 13);
+        assert_eq!(config_sync.pending_channels(), (0..8).collect::<Vec<_>>());
+        assert_eq!(config_sync.progress(), (0, 7 + 13 + 8 + 1));
+    }
+
+    #[test]
+    fn tracks_received_and_pending_sections() {
+        let mut config_sync = ConfigSync::with_expected(
+            42,
+            [ConfigType::DeviceConfig, ConfigType::LoraConfig],
+            [],
+            0..2,
+        );
+
+        assert!(config_sync.handle_packet(&config_packet(
+            protobufs::config::PayloadVariant::Lora(Default::default())
+        )));
+        assert!(config_sync.handle_packet(&channel_packet(1)));
+
+        assert_eq!(
+            config_sync.received_config_types(),
+            vec![ConfigType::LoraConfig]
+        );
+        assert_eq!(
+            config_sync.pending_config_types(),
+            vec![ConfigType::DeviceConfig]
+        );
+        assert_eq!(config_sync.pending_channels(), vec![0]);
+        assert_eq!(config_sync.progress(), (2, 5));
+        assert!(!config_sync.is_complete());
+    }
+
+    #[test]
+    fn expects_only_sections_supported_by_firmware() {
+        let mut config_sync = ConfigSync::new(42);
+
+        assert!(config_sync.handle_packet(&protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Metadata(
+                protobufs::DeviceMetadata {
+                    firmware_version: "2.1.5.abcdef0".to_string(),
+                    ..Default::default()
+                },
+            )),
+        }));
+
+        let pending_module_config_types = config_sync.pending_module_config_types();
+        assert_eq!(config_sync.pending_config_types().len(), 7);
+        assert!(pending_module_config_types.contains(&ModuleConfigType::RemotehardwareConfig));
+        assert!(!pending_module_config_types.contains(&ModuleConfigType::NeighborinfoConfig));
+        assert!(!pending_module_config_types.contains(&ModuleConfigType::PaxcounterConfig));
+        assert_eq!(config_sync.progress(), (0, 7 + 9 + 8 + 1));
+    }
+
+    #[test]
+    fn completes_only_on_matching_config_id() {
+        let mut config_sync = ConfigSync::with_expected(42, [], [], 0..1);
+
+        assert!(config_sync.handle_packet(&channel_packet(0)));
+        assert!(!config_sync.handle_packet(&config_complete_packet(41)));
+        assert!(!config_sync.is_complete());
+
+        assert!(config_sync.handle_packet(&config_complete_packet(42)));
+        assert!(config_sync.is_complete());
+    }
+
+    #[tokio::test]
+    async fn config_handle_resolves_on_completion_or_cancellation() {
+        let radio_state = Arc::new(RadioState::new());
+
+        radio_state.start_config_handshake(1);
+        let cancelled = ConfigHandle::new(1, radio_state.clone());
+        assert!(cancelled.is_pending());
+        assert!(cancelled.cancel());
+        assert!(!cancelled.cancel());
+        assert!(matches!(
+            cancelled.wait().await,
+            Err(Error::ConfigCancelled { config_id: 1 })
+        ));
+
+        radio_state.start_config_handshake(2);
+        let retried = ConfigHandle::new(2, radio_state.clone());
+
+        // A late completion of the cancelled handshake does not complete the retried handshake
+        radio_state.handle_packet(&config_complete_packet(1));
+        assert!(retried.is_pending());
+
+        radio_state.handle_packet(&config_complete_packet(2));
+        assert!(!retried.is_pending());
+        assert!(retried.wait().await.is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+use prost::Message;
+
+use crate::protobufs;
+
+use super::events::{DetectionEvent, TextMessage};
+
+/// An enum that represents the decoded payload of a `MeshPacket`, as returned by the `decoded_payload`
+/// function. Each variant corresponds to the `PortNum` the payload was sent on.
+///
+/// # Variants
+///
+/// * `Text` - A text message, sent on the `TextMessageApp` or `TextMessageCompressedApp` port.
+/// * `Position` - A position update, sent on the `PositionApp` port.
+/// * `Telemetry` - Telemetry, sent on the `TelemetryApp` port.
+/// * `NodeInfo` - The `User` information of a node, sent on the `NodeinfoApp` port.
+/// * `Routing` - A routing packet, e.g. an acknowledgement, sent on the `RoutingApp` port.
+/// * `Waypoint` - A waypoint, sent on the `WaypointApp` port.
+/// * `Admin` - An admin message, sent on the `AdminApp` port.
+/// * `Paxcount` - A crowd count of the paxcounter module, sent on the `PaxcounterApp` port.
+/// * `MapReport` - A map report, sent on the `MapReportApp` port.
+/// * `NeighborInfo` - The neighbors of a node, sent on the `NeighborinfoApp` port.
+/// * `Traceroute` - The route of a traceroute, sent on the `TracerouteApp` port.
+/// * `StoreForward` - A store and forward message, sent on the `StoreForwardApp` port.
+/// * `Detection` - An alert of the detection sensor module, sent on the `DetectionSensorApp` port.
+/// * `Raw` - A payload on any other port, or a payload that failed to decode, containing the port
+///     number and the undecoded bytes of the payload.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodedPayload {
+    Text(String),
+    Position(protobufs::Position),
+    Telemetry(protobufs::Telemetry),
+    NodeInfo(protobufs::User),
+    Routing(protobufs::Routing),
+    Waypoint(protobufs::Waypoint),
+    Admin(protobufs::AdminMessage),
+    Paxcount(protobufs::Paxcount),
+    MapReport(protobufs::MapReport),
+    NeighborInfo(protobufs::NeighborInfo),
+    Traceroute(protobufs::RouteDiscovery),
+    StoreForward(protobufs::StoreAndForward),
+    Detection(DetectionEvent),
+    Raw { portnum: i32, bytes: Vec<u8> },
+}
+
+/// Decodes the payload of a `MeshPacket` into the protobuf message corresponding to its `PortNum`,
+/// which avoids matching on the payload variant and port number of every received packet by hand.
+///
+/// Payloads on ports without a corresponding variant, e.g. the private application ports, are returned
+/// as `DecodedPayload::Raw` rather than being dropped. Payloads that fail to decode into the message of
+/// their port are returned as `DecodedPayload::Raw` as well, so that they can be inspected.
+///
+/// # Arguments
+///
+/// * `mesh_packet` - A `MeshPacket` received from the radio.
+///
+/// # Returns
+///
+/// The `DecodedPayload` of the packet, or `None` if the packet has no decoded payload, e.g. because
+/// it is encrypted with the key of a channel that is not within the channel table of the radio.
+///
+/// # Examples
+///
+/// ```
+/// match decoded_payload(&mesh_packet) {
+///     Some(DecodedPayload::Text(text)) => println!("Text: {text}"),
+///     Some(DecodedPayload::Position(position)) => println!("Position: {position:?}"),
+///     Some(DecodedPayload::Raw { portnum, bytes }) => println!("{} bytes on port {portnum}", bytes.len()),
+///     _ => {}
+/// }
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn decoded_payload(mesh_packet: &protobufs::MeshPacket) -> Option<DecodedPayload> {
+    use protobufs::PortNum;
+
+    let data = match mesh_packet.payload_variant.as_ref()? {
+        protobufs::mesh_packet::PayloadVariant::Decoded(data) => data,
+        _ => return None,
+    };
+
+    let payload = data.payload.as_slice();
+
+    let decoded = match PortNum::try_from(data.portnum) {
+        Ok(PortNum::TextMessageApp | PortNum::TextMessageCompressedApp) => {
+            TextMessage::from_mesh_packet(mesh_packet)
+                .map(|message| DecodedPayload::Text(message.text))
+        }
+        Ok(PortNum::PositionApp) => protobufs::Position::decode(payload)
+            .ok()
+            .map(DecodedPayload::Position),
+        Ok(PortNum::TelemetryApp) => protobufs::Telemetry::decode(payload)
+            .ok()
+            .map(DecodedPayload::Telemetry),
+        Ok(PortNum::NodeinfoApp) => protobufs::User::decode(payload)
+            .ok()
+            .map(DecodedPayload::NodeInfo),
+        Ok(PortNum::RoutingApp) => protobufs::Routing::decode(payload)
+            .ok()
+            .map(DecodedPayload::Routing),
+        Ok(PortNum::WaypointApp) => protobufs::Waypoint::decode(payload)
+            .ok()
+            .map(DecodedPayload::Waypoint),
+        Ok(PortNum::AdminApp) => protobufs::AdminMessage::decode(payload)
+            .ok()
+            .map(DecodedPayload::Admin),
+        Ok(PortNum::PaxcounterApp) => protobufs::Paxcount::decode(payload)
+            .ok()
+            .map(DecodedPayload::Paxcount),
+        Ok(PortNum::MapReportApp) => protobufs::MapReport::decode(payload)
+            .ok()
+            .map(DecodedPayload::MapReport),
+        Ok(PortNum::NeighborinfoApp) => protobufs::NeighborInfo::decode(payload)
+            .ok()
+            .map(DecodedPayload::NeighborInfo),
+        Ok(PortNum::TracerouteApp) => protobufs::RouteDiscovery::decode(payload)
+            .ok()
+            .map(DecodedPayload::Traceroute),
+        Ok(PortNum::StoreForwardApp) => protobufs::StoreAndForward::decode(payload)
+            .ok()
+            .map(DecodedPayload::StoreForward),
+        Ok(PortNum::DetectionSensorApp) => Some(DecodedPayload::Detection(
+            DetectionEvent::from_payload(payload),
+        )),
+        _ => None,
+    };
+
+    Some(decoded.unwrap_or_else(|| DecodedPayload::Raw {
+        portnum: data.portnum,
+        bytes: data.payload.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(portnum: i32, payload: Vec<u8>) -> protobufs::MeshPacket {
+        protobufs::MeshPacket {
+            from: 1,
+            to: u32::MAX,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum,
+                    payload,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decodes_payloads_by_port_number() {
+        assert_eq!(
+            decoded_payload(&packet(
+                protobufs::PortNum::TextMessageApp as i32,
+                "Hello mesh".into()
+            )),
+            Some(DecodedPayload::Text("Hello mesh".to_string()))
+        );
+
+        let position = protobufs::Position {
+            latitude_i: 473_977_000,
+            longitude_i: 85_456_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            decoded_payload(&packet(
+                protobufs::PortNum::PositionApp as i32,
+                position.encode_to_vec()
+            )),
+            Some(DecodedPayload::Position(position))
+        );
+
+        // Private application ports and payloads that fail to decode are passed through
+        assert_eq!(
+            decoded_payload(&packet(300, vec![1, 2, 3])),
+            Some(DecodedPayload::Raw {
+                portnum: 300,
+                bytes: vec![1, 2, 3]
+            })
+        );
+        assert_eq!(
+            decoded_payload(&packet(protobufs::PortNum::PositionApp as i32, vec![0xff])),
+            Some(DecodedPayload::Raw {
+                portnum: protobufs::PortNum::PositionApp as i32,
+                bytes: vec![0xff]
+            })
+        );
+
+        let encrypted = protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![1])),
+            ..Default::default()
+        };
+        assert_eq!(decoded_payload(&encrypted), None);
+    }
+}
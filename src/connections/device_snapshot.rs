@@ -0,0 +1,62 @@
+use crate::protobufs;
+
+/// A struct that bundles the state a radio reports during the `configure` handshake, i.e. the
+/// information about the radio itself, its node database, its channel table, and its configuration.
+///
+/// The snapshot is a copy of the state cached by the `ConnectedStreamApi` struct at the time it was
+/// taken, and is not updated as further packets are received from the radio. If the `serde` feature
+/// is enabled, the snapshot can be serialized, e.g. to persist it and restore the state of a user
+/// interface on the next launch, before the connection to the radio has been configured.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct DeviceSnapshot {
+    /// The information about the connected radio, if the radio has reported it.
+    pub my_node_info: Option<protobufs::MyNodeInfo>,
+
+    /// The metadata of the connected radio, e.g. its firmware version, if the radio has reported it.
+    pub metadata: Option<protobufs::DeviceMetadata>,
+
+    /// The nodes within the node database of the radio, ordered by node number.
+    pub nodes: Vec<protobufs::NodeInfo>,
+
+    /// The channel table of the radio, ordered by channel index.
+    pub channels: Vec<protobufs::Channel>,
+
+    /// The device configuration of the radio. Sections that have not been reported are `None`.
+    pub local_config: protobufs::LocalConfig,
+
+    /// The module configuration of the radio. Sections that have not been reported are `None`.
+    pub local_module_config: protobufs::LocalModuleConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_through_json() {
+        let snapshot = DeviceSnapshot {
+            my_node_info: Some(protobufs::MyNodeInfo {
+                my_node_num: 0xa1b2_c3d4,
+                ..Default::default()
+            }),
+            nodes: vec![protobufs::NodeInfo {
+                num: 1,
+                ..Default::default()
+            }],
+            channels: vec![protobufs::Channel {
+                index: 0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<DeviceSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+}
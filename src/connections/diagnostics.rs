@@ -0,0 +1,107 @@
+use std::time::SystemTime;
+
+use tokio::sync::broadcast;
+
+/// The number of dropped packets that are buffered for each diagnostics subscriber before the
+/// oldest reports are discarded for subscribers that are lagging behind.
+pub const DROPPED_PACKET_CAPACITY: usize = 64;
+
+/// An enum that describes why the receive pipeline, or a packet stream of the `ConnectedStreamApi`
+/// struct, discarded data received from the radio.
+///
+/// **Note:** The receive pipeline does not deduplicate packets or filter out the packets sent by the
+/// local node, as the firmware already does so, so these are not reported as drop reasons.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// A complete packet was framed, but could not be decoded as a `FromRadio` protobuf.
+    DecodeFailure,
+
+    /// A packet header was found within the data of the previous packet, so the previous packet
+    /// was truncated and discarded.
+    MalformedPacket,
+
+    /// The buffered, undecoded data exceeded the maximum buffer size and was discarded.
+    BufferOverflow,
+
+    /// A decoded packet could not be forwarded because the `PacketReceiver` returned by the
+    /// `connect` method of the `StreamApi` struct has been dropped.
+    ReceiverClosed,
+
+    /// A typed packet stream, e.g. the `position_stream` method, skipped a packet on the port it
+    /// filters for, as the payload of the packet could not be decoded. The raw bytes are the payload
+    /// of the packet.
+    PayloadDecodeFailure,
+
+    /// An event stream created with `DecodePolicy::Skip` skipped a packet that failed to decode.
+    SkippedByDecodePolicy,
+
+    /// A packet stream fell behind the received packets, and its queue of unread packets was full,
+    /// so its oldest `skipped_packets` packets were discarded. The raw bytes are empty, as the
+    /// discarded packets are no longer available.
+    QueueFull { skipped_packets: u64 },
+}
+
+/// A struct that describes data discarded by the receive pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DroppedPacket {
+    /// The reason the data was discarded.
+    pub reason: DropReason,
+
+    /// The discarded bytes, excluding the packet header for framed packets.
+    pub raw: Vec<u8>,
+
+    /// The host time at which the data was discarded.
+    pub dropped_at: SystemTime,
+}
+
+/// A struct that reports the data discarded by the receive pipeline to diagnostics subscribers.
+///
+/// Diagnostics are opt-in, as reporting requires copying the discarded bytes. A disabled instance
+/// reports nothing, and can be cloned and passed to the worker threads at no cost.
+#[derive(Clone, Debug, Default)]
+pub struct DropDiagnostics {
+    dropped_packet_tx: Option<broadcast::Sender<DroppedPacket>>,
+}
+
+impl DropDiagnostics {
+    /// Creates a `DropDiagnostics` instance that doesn't report dropped packets.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `DropDiagnostics` instance that reports dropped packets to its subscribers.
+    pub fn enabled() -> Self {
+        let (dropped_packet_tx, _) = broadcast::channel(DROPPED_PACKET_CAPACITY);
+
+        Self {
+            dropped_packet_tx: Some(dropped_packet_tx),
+        }
+    }
+
+    /// Returns whether dropped packets are reported.
+    pub fn is_enabled(&self) -> bool {
+        self.dropped_packet_tx.is_some()
+    }
+
+    /// Returns a receiver of the packets dropped after this call, or `None` if diagnostics are disabled.
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<DroppedPacket>> {
+        self.dropped_packet_tx
+            .as_ref()
+            .map(broadcast::Sender::subscribe)
+    }
+
+    /// Reports that the passed bytes were discarded for the specified reason. The bytes are only
+    /// copied if diagnostics are enabled.
+    pub fn report(&self, reason: DropReason, raw: &[u8]) {
+        let Some(dropped_packet_tx) = &self.dropped_packet_tx else {
+            return;
+        };
+
+        // A send error only means that nobody is currently subscribed
+        let _ = dropped_packet_tx.send(DroppedPacket {
+            reason,
+            raw: raw.to_vec(),
+            dropped_at: SystemTime::now(),
+        });
+    }
+}
@@ -0,0 +1,380 @@
+use std::fmt::Display;
+
+use log::warn;
+use prost::Message;
+
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::protobufs::admin_message::PayloadVariant as AdminVariant;
+
+use super::{
+    stream_api::{state, ConnectedStreamApi},
+    PacketDestination, PacketRouter,
+};
+
+/// A guard that accumulates settings edits for the connected radio, and applies them within a
+/// single edit transaction when committed. This struct is created by the `begin_edit` method of
+/// the `ConnectedStreamApi` struct.
+///
+/// Edits are only buffered by this struct until the `commit` method is called, which sends the
+/// `BeginEditSettings` admin message, followed by the buffered edits in the order they were made,
+/// followed by the `CommitEditSettings` admin message. The radio saves the edited settings and
+/// restarts once it receives the commit message.
+///
+/// Dropping the guard without committing rolls the transaction back: the buffered edits are
+/// discarded, and as nothing was sent, the settings of the radio are left unchanged. A warning is
+/// logged if edits are discarded this way, as this is usually a bug.
+#[derive(Debug)]
+pub struct EditTransaction<'a> {
+    stream_api: &'a mut ConnectedStreamApi<state::Configured>,
+    edits: Vec<protobufs::AdminMessage>,
+    finished: bool,
+}
+
+impl<'a> EditTransaction<'a> {
+    fn new(stream_api: &'a mut ConnectedStreamApi<state::Configured>) -> Self {
+        Self {
+            stream_api,
+            edits: vec![],
+            finished: false,
+        }
+    }
+
+    /// Buffers an update of a section of the device configuration.
+    pub fn set_config(&mut self, config: protobufs::Config) -> &mut Self {
+        self.push(AdminVariant::SetConfig(config))
+    }
+
+    /// Buffers an update of a section of the module configuration.
+    pub fn set_module_config(&mut self, module_config: protobufs::ModuleConfig) -> &mut Self {
+        self.push(AdminVariant::SetModuleConfig(module_config))
+    }
+
+    /// Buffers an update of a channel within the channel table.
+    pub fn set_channel(&mut self, channel: protobufs::Channel) -> &mut Self {
+        self.push(AdminVariant::SetChannel(channel))
+    }
+
+    /// Buffers an update of the `User` information of the radio, e.g. its names.
+    pub fn set_owner(&mut self, user: protobufs::User) -> &mut Self {
+        self.push(AdminVariant::SetOwner(user))
+    }
+
+    /// Returns the edits buffered so far, in the order they will be sent.
+    pub fn edits(&self) -> &[protobufs::AdminMessage] {
+        &self.edits
+    }
+
+    fn push(&mut self, payload_variant: AdminVariant) -> &mut Self {
+        self.edits.push(protobufs::AdminMessage {
+            payload_variant: Some(payload_variant),
+        });
+
+        self
+    }
+
+    /// Applies the buffered edits to the radio within a single edit transaction.
+    ///
+    /// All admin messages are sent to the connected radio on the channel returned by the `admin_channel`
+    /// method of the `ConnectedStreamApi` struct, as it was before the transaction. Channels edited within
+    /// the transaction are reflected by the `channels` method of the `ConnectedStreamApi` struct immediately.
+    /// The method returns once all packets are queued to be sent to the radio.
+    ///
+    /// **Note:** The radio will restart after receiving the commit message, which will disconnect
+    /// the `StreamApi` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether all packets of the transaction were queued to be sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut transaction = stream_api.begin_edit();
+    /// transaction.set_config(lora_config).set_channel(primary_channel);
+    /// transaction.commit(&mut packet_router).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the packets fails to send. If the transaction was already begun on the radio,
+    /// the radio keeps the edits that were received in memory without saving them, so they are lost
+    /// once the radio restarts.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn commit<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        mut self,
+        packet_router: &mut R,
+    ) -> Result<(), Error> {
+        self.finished = true;
+
+        let begin = protobufs::AdminMessage {
+            payload_variant: Some(AdminVariant::BeginEditSettings(true)),
+        };
+        let commit = protobufs::AdminMessage {
+            payload_variant: Some(AdminVariant::CommitEditSettings(true)),
+        };
+
+        let edits = std::mem::take(&mut self.edits);
+        let admin_channel = self.stream_api.admin_channel();
+
+        for admin_message in std::iter::once(begin).chain(edits).chain([commit]) {
+            if let Some(AdminVariant::SetChannel(channel)) = &admin_message.payload_variant {
+                self.stream_api
+                    .radio_state()
+                    .update_channel(channel.clone());
+            }
+
+            self.stream_api
+                .send_mesh_packet(
+                    packet_router,
+                    admin_message.encode_to_vec().into(),
+                    protobufs::PortNum::AdminApp,
+                    PacketDestination::Local,
+                    admin_channel,
+                    true,
+                    true,
+                    false,
+                    None,
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Discards the buffered edits without sending anything to the radio. This is equivalent to
+    /// dropping the guard, but doesn't log a warning.
+    pub fn rollback(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for EditTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished && !self.edits.is_empty() {
+            warn!(
+                "Discarding {} settings edits of a transaction that was never committed",
+                self.edits.len()
+            );
+        }
+    }
+}
+
+impl ConnectedStreamApi<state::Configured> {
+    /// Begins a transaction of settings edits, returning an `EditTransaction` guard that buffers
+    /// the edits until it is committed. This avoids restarting the radio once for each edit, and
+    /// prevents forgetting to commit the transaction, as the edits are only sent on commit.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// An `EditTransaction` guard that borrows this `ConnectedStreamApi` until it is committed or dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut transaction = stream_api.begin_edit();
+    ///
+    /// for section in edited_sections {
+    ///     transaction.set_config(section);
+    /// }
+    ///
+    /// transaction.commit(&mut packet_router).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn begin_edit(&mut self) -> EditTransaction<'_> {
+        EditTransaction::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::DuplexStream;
+
+    use crate::connections::{
+        stream_api::{StreamApi, StreamHandle},
+        test_utils::{read_mesh_packet, read_to_radio, TestRouter},
+        wrappers::mesh_channel::MeshChannel,
+    };
+
+    use super::*;
+
+    /// Reads the admin messages written to the radio, skipping all other packets.
+    async fn read_admin_messages(server: &mut DuplexStream, count: usize) -> Vec<AdminVariant> {
+        let mut admin_messages = vec![];
+
+        while admin_messages.len() < count {
+            let mesh_packet = read_mesh_packet(server).await;
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant
+            else {
+                continue;
+            };
+
+            assert_eq!(data.portnum(), protobufs::PortNum::AdminApp);
+            assert_eq!(mesh_packet.to, 1);
+
+            let admin_message = protobufs::AdminMessage::decode(data.payload.as_slice()).unwrap();
+            admin_messages.push(admin_message.payload_variant.unwrap());
+        }
+
+        admin_messages
+    }
+
+    #[tokio::test]
+    async fn commits_buffered_edits_within_a_transaction() {
+        let (client, mut server) = tokio::io::duplex(4096);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let channel = protobufs::Channel {
+            index: 1,
+            role: protobufs::channel::Role::Secondary as i32,
+            ..Default::default()
+        };
+
+        let mut transaction = stream_api.begin_edit();
+        transaction
+            .set_config(protobufs::Config::default())
+            .set_channel(channel.clone());
+        transaction.commit(&mut TestRouter).await.unwrap();
+
+        let admin_messages =
+            tokio::time::timeout(Duration::from_secs(1), read_admin_messages(&mut server, 4))
+                .await
+                .unwrap();
+
+        assert_eq!(
+            admin_messages,
+            [
+                AdminVariant::BeginEditSettings(true),
+                AdminVariant::SetConfig(protobufs::Config::default()),
+                AdminVariant::SetChannel(channel.clone()),
+                AdminVariant::CommitEditSettings(true),
+            ]
+        );
+        assert!(stream_api.channels().contains(&channel));
+    }
+
+    #[tokio::test]
+    async fn sends_transactions_to_the_local_node_on_the_admin_channel() {
+        let (client, mut server) = tokio::io::duplex(4096);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        assert_eq!(
+            read_to_radio(&mut server).await.payload_variant,
+            Some(protobufs::to_radio::PayloadVariant::WantConfigId(1))
+        );
+
+        stream_api.radio_state().update_channel(protobufs::Channel {
+            index: 2,
+            role: protobufs::channel::Role::Secondary as i32,
+            settings: Some(protobufs::ChannelSettings {
+                name: "admin".to_string(),
+                ..Default::default()
+            }),
+        });
+        assert_eq!(stream_api.admin_channel(), MeshChannel::new(2).unwrap());
+
+        let module_config = protobufs::ModuleConfig {
+            payload_variant: Some(protobufs::module_config::PayloadVariant::Telemetry(
+                Default::default(),
+            )),
+        };
+
+        let mut transaction = stream_api.begin_edit();
+        transaction
+            .set_module_config(module_config.clone())
+            .set_config(protobufs::Config::default());
+        transaction.commit(&mut TestRouter).await.unwrap();
+
+        // Every packet written after the handshake belongs to the transaction, in order
+        let mut admin_messages = vec![];
+        for _ in 0..4 {
+            let to_radio = read_to_radio(&mut server).await;
+
+            let Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)) =
+                to_radio.payload_variant
+            else {
+                panic!("expected a mesh packet");
+            };
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant
+            else {
+                panic!("expected a decoded payload");
+            };
+
+            assert_eq!(mesh_packet.to, 1);
+            assert_eq!(mesh_packet.channel, 2);
+            assert_eq!(data.portnum(), protobufs::PortNum::AdminApp);
+
+            let admin_message = protobufs::AdminMessage::decode(data.payload.as_slice()).unwrap();
+            admin_messages.push(admin_message.payload_variant.unwrap());
+        }
+
+        assert_eq!(
+            admin_messages,
+            [
+                AdminVariant::BeginEditSettings(true),
+                AdminVariant::SetModuleConfig(module_config),
+                AdminVariant::SetConfig(protobufs::Config::default()),
+                AdminVariant::CommitEditSettings(true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_transactions_send_nothing() {
+        let (client, mut server) = tokio::io::duplex(4096);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        stream_api
+            .begin_edit()
+            .set_config(protobufs::Config::default());
+
+        assert!(tokio::time::timeout(
+            Duration::from_millis(50),
+            read_admin_messages(&mut server, 1)
+        )
+        .await
+        .is_err());
+    }
+}
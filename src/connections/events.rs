@@ -0,0 +1,932 @@
+use log::warn;
+use prost::Message;
+
+use crate::errors_internal::Error;
+use crate::helpers_internal::text_compression::decompress_bytes;
+use crate::protobufs;
+
+use super::{
+    wrappers::{mesh_channel::MeshChannel, NodeId, PacketId},
+    PacketDestination,
+};
+
+/// A struct that represents a text message received from the mesh.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextMessage {
+    /// The node that sent the message.
+    pub from: NodeId,
+
+    /// The destination of the message. Broadcast messages have a destination of
+    /// `PacketDestination::Broadcast`, and direct messages have a destination of
+    /// `PacketDestination::Node`.
+    pub to: PacketDestination,
+
+    /// The channel the message was received on.
+    pub channel: MeshChannel,
+
+    /// The content of the message. Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub text: String,
+
+    /// Whether the message is a direct message, rather than a broadcast message. Broadcasts replayed
+    /// by a store and forward server are reported as broadcast messages, see `MeshPacket::is_broadcast`.
+    pub is_dm: bool,
+
+    /// The id of the mesh packet that contained the message.
+    pub packet_id: PacketId,
+}
+
+impl TextMessage {
+    /// Creates a `TextMessage` from a received `MeshPacket`, if the packet contains a text message.
+    ///
+    /// Text messages sent on the `TextMessageCompressedApp` port are decompressed, and are otherwise handled
+    /// identically to those sent on the `TextMessageApp` port. Compression is an optimization of the firmware
+    /// that is invisible to app developers, so compressed messages are rarely seen outside of MQTT.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesh_packet` - A `MeshPacket` received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// A `TextMessage` if the packet is a decoded `TextMessageApp` or `TextMessageCompressedApp` packet,
+    /// otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(text_message) = TextMessage::from_mesh_packet(&mesh_packet) {
+    ///     println!("{}: {}", text_message.from, text_message.text);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_mesh_packet(mesh_packet: &protobufs::MeshPacket) -> Option<Self> {
+        match Self::decode_mesh_packet(mesh_packet) {
+            Ok(text_message) => text_message,
+            Err(e) => {
+                warn!("Failed to decompress text message {}: {e}", mesh_packet.id);
+                None
+            }
+        }
+    }
+
+    /// Extracts a `TextMessage` from a mesh packet as described for the `from_mesh_packet` method,
+    /// but fails with `Error::InvalidCompressedText` if the payload of a `TextMessageCompressedApp`
+    /// packet cannot be decompressed, instead of returning `None`.
+    pub(crate) fn decode_mesh_packet(
+        mesh_packet: &protobufs::MeshPacket,
+    ) -> Result<Option<Self>, Error> {
+        let data = match mesh_packet.payload_variant.as_ref() {
+            Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) => data,
+            _ => return Ok(None),
+        };
+
+        let text = match protobufs::PortNum::try_from(data.portnum) {
+            Ok(protobufs::PortNum::TextMessageApp) => {
+                String::from_utf8_lossy(&data.payload).into_owned()
+            }
+            Ok(protobufs::PortNum::TextMessageCompressedApp) => {
+                String::from_utf8_lossy(&decompress_bytes(&data.payload)?).into_owned()
+            }
+            _ => return Ok(None),
+        };
+
+        // Replayed broadcasts are addressed to the requesting node, so the destination alone is not reliable
+        let is_dm = !mesh_packet.is_broadcast();
+
+        let to = match is_dm {
+            true => PacketDestination::Node(mesh_packet.to.into()),
+            false => PacketDestination::Broadcast,
+        };
+
+        Ok(Some(TextMessage {
+            from: mesh_packet.from.into(),
+            to,
+            channel: mesh_packet.channel.into(),
+            text,
+            is_dm,
+            packet_id: mesh_packet.id.into(),
+        }))
+    }
+}
+
+/// An enum that represents the channel a mesh packet was received on, resolved against the channel
+/// table of the radio.
+///
+/// The radio reports the index of the channel for packets it was able to decrypt, whereas packets it
+/// could not decrypt carry the hash of the channel they were sent on. Packets on channels that are not
+/// within the channel table are reported as `Unknown`, rather than being attributed to the primary channel.
+///
+/// # Variants
+///
+/// * `Known` - The packet was received on the specified channel of the channel table.
+/// * `Unknown` - The packet was received on a channel that is not within the channel table, identified
+///     by the `channel` field of the `MeshPacket`, i.e. the channel hash for packets that were not decrypted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReceivedChannel {
+    Known(MeshChannel),
+    Unknown(u32),
+}
+
+impl ReceivedChannel {
+    /// Resolves the channel a `MeshPacket` was received on against the passed channel table, e.g. as
+    /// returned by the `channels` method of the `ConnectedStreamApi` struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesh_packet` - A `MeshPacket` received from the radio.
+    /// * `channels` - The channel table of the radio.
+    ///
+    /// # Returns
+    ///
+    /// `ReceivedChannel::Known` if the packet was decrypted and its channel index is an enabled channel
+    /// of the channel table, and `ReceivedChannel::Unknown` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match ReceivedChannel::resolve(&mesh_packet, &stream_api.channels()) {
+    ///     ReceivedChannel::Known(channel) => println!("Received on channel {}", channel),
+    ///     ReceivedChannel::Unknown(hash) => println!("Received on unknown channel {:#x}", hash),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn resolve(mesh_packet: &protobufs::MeshPacket, channels: &[protobufs::Channel]) -> Self {
+        Self::resolve_with(mesh_packet, |index| {
+            channels.iter().any(|channel| {
+                channel.index as u32 == index
+                    && channel.role() != protobufs::channel::Role::Disabled
+            })
+        })
+    }
+
+    /// Resolves the channel a `MeshPacket` was received on using the passed lookup, which returns
+    /// whether the channel with the specified index is an enabled channel of the channel table.
+    pub(crate) fn resolve_with(
+        mesh_packet: &protobufs::MeshPacket,
+        is_channel_active: impl FnOnce(u32) -> bool,
+    ) -> Self {
+        let is_decoded = matches!(
+            mesh_packet.payload_variant,
+            Some(protobufs::mesh_packet::PayloadVariant::Decoded(_))
+        );
+
+        if is_decoded && is_channel_active(mesh_packet.channel) {
+            ReceivedChannel::Known(mesh_packet.channel.into())
+        } else {
+            ReceivedChannel::Unknown(mesh_packet.channel)
+        }
+    }
+}
+
+/// An enum that represents a change to a waypoint, as received from the mesh.
+///
+/// Meshtastic clients delete a waypoint by re-sending it with an expiry time in the past,
+/// or with an empty name. This enum encapsulates that convention, so that consumers can
+/// distinguish new or updated waypoints from deleted waypoints.
+///
+/// # Variants
+///
+/// * `Updated` - A waypoint was created or updated by the specified node.
+/// * `Removed` - The waypoint with the specified id was deleted by the specified node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WaypointEvent {
+    Updated {
+        from: NodeId,
+        waypoint: protobufs::Waypoint,
+    },
+    Removed {
+        from: NodeId,
+        waypoint_id: u32,
+    },
+}
+
+impl WaypointEvent {
+    /// Classifies a waypoint received from the specified node as either an update or a deletion.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The node that sent the waypoint.
+    /// * `waypoint` - The received `Waypoint`.
+    /// * `now_epoch_secs` - The current time, in seconds since the unix epoch. Waypoints with a
+    ///     non-zero expiry time at or before this time are treated as deleted.
+    ///
+    /// # Returns
+    ///
+    /// A `WaypointEvent` describing the change to the waypoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let event = WaypointEvent::from_waypoint(from, waypoint, utils::current_epoch_secs_u32());
+    ///
+    /// match event {
+    ///     WaypointEvent::Updated { waypoint, .. } => map.insert(waypoint.id, waypoint),
+    ///     WaypointEvent::Removed { waypoint_id, .. } => map.remove(&waypoint_id),
+    /// };
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_waypoint(from: NodeId, waypoint: protobufs::Waypoint, now_epoch_secs: u32) -> Self {
+        let is_expired = waypoint.expire != 0 && waypoint.expire <= now_epoch_secs;
+
+        if is_expired || waypoint.name.is_empty() {
+            return WaypointEvent::Removed {
+                from,
+                waypoint_id: waypoint.id,
+            };
+        }
+
+        WaypointEvent::Updated { from, waypoint }
+    }
+
+    /// Returns the id of the waypoint this event refers to.
+    pub fn waypoint_id(&self) -> u32 {
+        match self {
+            WaypointEvent::Updated { waypoint, .. } => waypoint.id,
+            WaypointEvent::Removed { waypoint_id, .. } => *waypoint_id,
+        }
+    }
+}
+
+/// The ASCII bell character, which the detection sensor module includes in its alerts if configured
+/// to do so, to trigger an audible notification on receiving devices.
+const BELL_CHARACTER: char = '\u{7}';
+
+/// A struct that represents an alert sent by the detection sensor module of a node, e.g. when a
+/// motion or door sensor connected to the node is triggered.
+///
+/// The detection sensor module sends its alerts as text on the `DetectionSensorApp` port, in the form
+/// `"<name> detected"` when the sensor is triggered, or `"<name> state: <0|1>"` when reporting the current
+/// state of the sensor. The alert may contain a bell character (`0x07`), which is removed from the name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DetectionEvent {
+    /// The name of the sensor, as configured within the detection sensor module config.
+    pub name: String,
+
+    /// Whether the sensor is triggered. Alerts that do not report a state are treated as triggered.
+    pub triggered: bool,
+
+    /// Whether the alert contained a bell character, requesting an audible notification.
+    pub bell: bool,
+}
+
+impl DetectionEvent {
+    /// Parses the payload of a `DetectionSensorApp` packet into a `DetectionEvent`.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload of a `DetectionSensorApp` packet. Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `DetectionEvent`. Payloads in an unrecognized format are returned as a triggered event,
+    /// with the whole payload as the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let event = DetectionEvent::from_payload(b"Front door detected\x07");
+    /// assert_eq!(event.name, "Front door");
+    /// assert!(event.triggered && event.bell);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_payload(payload: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(payload);
+
+        let bell = text.contains(BELL_CHARACTER);
+        let text = text.replace(BELL_CHARACTER, "");
+        let text = text.trim();
+
+        let (name, triggered) = if let Some(name) = text.strip_suffix(" detected") {
+            (name, true)
+        } else if let Some((name, state)) = text
+            .rsplit_once(" state: ")
+            .and_then(|(name, state)| Some((name, state.trim().parse::<i32>().ok()?)))
+        {
+            (name, state != 0)
+        } else {
+            (text, true)
+        };
+
+        DetectionEvent {
+            name: name.trim().to_string(),
+            triggered,
+            bell,
+        }
+    }
+
+    /// Creates a `DetectionEvent` from a received `MeshPacket`, if the packet was sent on the
+    /// `DetectionSensorApp` port.
+    pub fn from_mesh_packet(mesh_packet: &protobufs::MeshPacket) -> Option<Self> {
+        match mesh_packet.payload_variant.as_ref()? {
+            protobufs::mesh_packet::PayloadVariant::Decoded(data)
+                if data.portnum == protobufs::PortNum::DetectionSensorApp as i32 =>
+            {
+                Some(Self::from_payload(&data.payload))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An enum that represents a high-level event received from the radio, as returned by the
+/// `events` method of the `ConnectedStreamApi` struct.
+///
+/// Each `FromRadio` packet received from the radio is converted into a single `MeshEvent`. Packets
+/// with a payload that is recognized by the library are decoded into the corresponding typed variant,
+/// and all other packets (including packets that fail to decode) are passed through as `Raw`, so that
+/// no packets are lost.
+///
+/// # Variants
+///
+/// * `Text` - A text message was received from the mesh.
+/// * `Position` - A position update was received from the specified node.
+/// * `Telemetry` - Telemetry was received from the specified node.
+/// * `Paxcount` - A crowd count was received from the paxcounter module of the specified node.
+/// * `MapReport` - The specified node reported its information for display on a map.
+/// * `NodeInfo` - Information about a node was received, either from the radio's node database or
+///     from a `NodeinfoApp` packet broadcast by the node.
+/// * `Waypoint` - A waypoint was created, updated, or deleted.
+/// * `Detection` - The detection sensor module of the specified node sent an alert.
+/// * `Ack` - The specified node acknowledged the packet with the specified id.
+/// * `Routing` - A routing packet other than a successful acknowledgement was received, e.g. a
+///     negative acknowledgement or a route discovery packet.
+/// * `Log` - A log record was emitted by the radio.
+/// * `Rebooted` - The radio has rebooted.
+/// * `ConfigComplete` - The radio has finished sending its configuration for the specified config id.
+/// * `Raw` - A packet that does not correspond to any of the other variants.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshEvent {
+    Text(TextMessage),
+    Position {
+        from: NodeId,
+        position: protobufs::Position,
+    },
+    Telemetry {
+        from: NodeId,
+        telemetry: protobufs::Telemetry,
+    },
+    Paxcount {
+        from: NodeId,
+        paxcount: protobufs::Paxcount,
+    },
+    MapReport {
+        from: NodeId,
+        map_report: protobufs::MapReport,
+    },
+    NodeInfo(protobufs::NodeInfo),
+    Waypoint(WaypointEvent),
+    Detection {
+        from: NodeId,
+        event: DetectionEvent,
+    },
+    Ack {
+        from: NodeId,
+        request_id: PacketId,
+    },
+    Routing {
+        from: NodeId,
+        request_id: PacketId,
+        routing: protobufs::Routing,
+    },
+    Log(protobufs::LogRecord),
+    Rebooted,
+    ConfigComplete(u32),
+    Raw(protobufs::FromRadio),
+}
+
+impl MeshEvent {
+    /// Converts a `FromRadio` packet received from the radio into a `MeshEvent`.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `FromRadio` packet received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// The typed `MeshEvent` corresponding to the packet, or `MeshEvent::Raw` if the packet
+    /// could not be decoded into a typed variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     if let MeshEvent::Text(message) = MeshEvent::from_packet(packet) {
+    ///         println!("{}: {}", message.from, message.text);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_packet(packet: protobufs::FromRadio) -> Self {
+        match decode_event(&packet, crate::utils_internal::current_epoch_secs_u32()) {
+            Ok(Some(event)) => event,
+            Ok(None) | Err(_) => MeshEvent::Raw(packet),
+        }
+    }
+
+    /// Converts a `FromRadio` packet received from the radio into a `MeshEvent`, handling packets
+    /// with a payload that fails to decode as specified by the passed `DecodePolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A `FromRadio` packet received from the radio.
+    /// * `policy` - Whether packets that fail to decode are passed through as `MeshEvent::Raw`,
+    ///     or logged and skipped.
+    ///
+    /// # Returns
+    ///
+    /// The `MeshEvent` corresponding to the packet, or `None` if the packet failed to decode
+    /// and the policy is `DecodePolicy::Skip`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(event) = MeshEvent::from_packet_with_policy(packet, DecodePolicy::Skip) {
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_packet_with_policy(
+        packet: protobufs::FromRadio,
+        policy: DecodePolicy,
+    ) -> Option<Self> {
+        match decode_event(&packet, crate::utils_internal::current_epoch_secs_u32()) {
+            Ok(Some(event)) => Some(event),
+            Ok(None) => Some(MeshEvent::Raw(packet)),
+            Err(e) => match policy {
+                DecodePolicy::Raw => Some(MeshEvent::Raw(packet)),
+                DecodePolicy::Skip => {
+                    warn!("Skipping packet that failed to decode: {e}");
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// A struct that represents a high-level event received from the radio, along with the channel the
+/// packet it was decoded from was received on, as returned by the `events_with_channels` method of the
+/// `ConnectedStreamApi` struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelEvent {
+    /// The received event.
+    pub event: MeshEvent,
+
+    /// The channel the event was received on, or `None` if the event was not decoded from a mesh
+    /// packet, e.g. a log record.
+    pub channel: Option<ReceivedChannel>,
+}
+
+/// An enum that defines how the event stream handles packets with a payload that fails to decode,
+/// e.g. packets sent by a node running incompatible firmware.
+///
+/// # Variants
+///
+/// * `Raw` - Packets that fail to decode are passed through as `MeshEvent::Raw`, so no packets are lost.
+/// * `Skip` - Packets that fail to decode are logged and skipped.
+///
+/// # Default
+///
+/// The default value for this enum is `Raw`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DecodePolicy {
+    #[default]
+    Raw,
+    Skip,
+}
+
+/// Decodes a `FromRadio` packet into a typed `MeshEvent`. Returns `Ok(None)` if the packet does not
+/// correspond to a typed variant, and an error if the payload of a recognized packet fails to decode
+/// or decompress.
+pub(crate) fn decode_event(
+    packet: &protobufs::FromRadio,
+    now_epoch_secs: u32,
+) -> Result<Option<MeshEvent>, Error> {
+    use protobufs::from_radio::PayloadVariant;
+
+    let event = match packet.payload_variant.as_ref() {
+        Some(PayloadVariant::Packet(mesh_packet)) => {
+            return decode_mesh_packet_event(mesh_packet, now_epoch_secs)
+        }
+        Some(PayloadVariant::NodeInfo(node_info)) => MeshEvent::NodeInfo(node_info.clone()),
+        Some(PayloadVariant::LogRecord(log_record)) => MeshEvent::Log(log_record.clone()),
+        Some(PayloadVariant::Rebooted(true)) => MeshEvent::Rebooted,
+        Some(PayloadVariant::ConfigCompleteId(config_id)) => MeshEvent::ConfigComplete(*config_id),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(event))
+}
+
+fn decode_mesh_packet_event(
+    mesh_packet: &protobufs::MeshPacket,
+    now_epoch_secs: u32,
+) -> Result<Option<MeshEvent>, Error> {
+    use protobufs::PortNum;
+
+    let data = match mesh_packet.payload_variant.as_ref() {
+        Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) => data,
+        _ => return Ok(None),
+    };
+
+    let from = NodeId::from(mesh_packet.from);
+
+    let event = match PortNum::try_from(data.portnum) {
+        Ok(PortNum::TextMessageApp | PortNum::TextMessageCompressedApp) => {
+            match TextMessage::decode_mesh_packet(mesh_packet)? {
+                Some(message) => MeshEvent::Text(message),
+                None => return Ok(None),
+            }
+        }
+        Ok(PortNum::PositionApp) => MeshEvent::Position {
+            from,
+            position: protobufs::Position::decode(data.payload.as_slice())?,
+        },
+        Ok(PortNum::TelemetryApp) => MeshEvent::Telemetry {
+            from,
+            telemetry: protobufs::Telemetry::decode(data.payload.as_slice())?,
+        },
+        Ok(PortNum::PaxcounterApp) => MeshEvent::Paxcount {
+            from,
+            paxcount: protobufs::Paxcount::decode(data.payload.as_slice())?,
+        },
+        Ok(PortNum::MapReportApp) => MeshEvent::MapReport {
+            from,
+            map_report: protobufs::MapReport::decode(data.payload.as_slice())?,
+        },
+        Ok(PortNum::NodeinfoApp) => MeshEvent::NodeInfo(protobufs::NodeInfo {
+            num: mesh_packet.from,
+            user: Some(protobufs::User::decode(data.payload.as_slice())?),
+            snr: mesh_packet.rx_snr,
+            last_heard: mesh_packet.rx_time,
+            channel: mesh_packet.channel,
+            ..Default::default()
+        }),
+        Ok(PortNum::WaypointApp) => MeshEvent::Waypoint(WaypointEvent::from_waypoint(
+            from,
+            protobufs::Waypoint::decode(data.payload.as_slice())?,
+            now_epoch_secs,
+        )),
+        Ok(PortNum::DetectionSensorApp) => MeshEvent::Detection {
+            from,
+            event: DetectionEvent::from_payload(&data.payload),
+        },
+        Ok(PortNum::RoutingApp) => {
+            let routing = protobufs::Routing::decode(data.payload.as_slice())?;
+            let request_id = PacketId::from(data.request_id);
+
+            match routing.variant {
+                Some(protobufs::routing::Variant::ErrorReason(error_reason))
+                    if error_reason == protobufs::routing::Error::None as i32 =>
+                {
+                    MeshEvent::Ack { from, request_id }
+                }
+                _ => MeshEvent::Routing {
+                    from,
+                    request_id,
+                    routing,
+                },
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The node id used by the firmware to indicate a broadcast destination.
+    const BROADCAST_NODE_ID: u32 = u32::MAX;
+
+    fn text_packet(to: u32, port_num: protobufs::PortNum) -> protobufs::MeshPacket {
+        protobufs::MeshPacket {
+            from: 1,
+            to,
+            channel: 2,
+            id: 3,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: port_num as i32,
+                    payload: b"Hello".to_vec(),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn distinguishes_direct_and_broadcast_messages() {
+        let broadcast = TextMessage::from_mesh_packet(&text_packet(
+            BROADCAST_NODE_ID,
+            protobufs::PortNum::TextMessageApp,
+        ))
+        .unwrap();
+
+        assert!(!broadcast.is_dm);
+        assert_eq!(broadcast.to, PacketDestination::Broadcast);
+        assert_eq!(broadcast.text, "Hello");
+        assert_eq!(broadcast.channel, MeshChannel::from(2));
+        assert_eq!(broadcast.packet_id, 3);
+
+        let direct =
+            TextMessage::from_mesh_packet(&text_packet(7, protobufs::PortNum::TextMessageApp))
+                .unwrap();
+
+        assert!(direct.is_dm);
+        assert_eq!(direct.to, PacketDestination::Node(7.into()));
+
+        // Store and forward servers replay broadcasts as packets addressed to the requesting node
+        let mut replayed = text_packet(7, protobufs::PortNum::TextMessageApp);
+        #[allow(deprecated)]
+        {
+            replayed.delayed = protobufs::mesh_packet::Delayed::Broadcast as i32;
+        }
+
+        let replayed = TextMessage::from_mesh_packet(&replayed).unwrap();
+        assert!(!replayed.is_dm);
+        assert_eq!(replayed.to, PacketDestination::Broadcast);
+    }
+
+    #[test]
+    fn decompresses_compressed_text_messages() {
+        let mut packet = text_packet(
+            BROADCAST_NODE_ID,
+            protobufs::PortNum::TextMessageCompressedApp,
+        );
+
+        if let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+            packet.payload_variant.as_mut()
+        {
+            data.payload = vec![0xec, 0xf8, 0xe2, 0x8b, 0xff];
+        }
+
+        let message = TextMessage::from_mesh_packet(&packet).unwrap();
+        assert_eq!(message.text, "hello");
+
+        let event = decode_mesh_packet_event(&packet, 0).unwrap();
+        assert_eq!(event, Some(MeshEvent::Text(message)));
+    }
+
+    #[test]
+    fn resolves_received_channels() {
+        let channels = [
+            protobufs::Channel {
+                index: 0,
+                role: protobufs::channel::Role::Primary as i32,
+                ..Default::default()
+            },
+            protobufs::Channel {
+                index: 1,
+                role: protobufs::channel::Role::Disabled as i32,
+                ..Default::default()
+            },
+        ];
+
+        let mut mesh_packet = text_packet(BROADCAST_NODE_ID, protobufs::PortNum::TextMessageApp);
+        mesh_packet.channel = 0;
+        assert_eq!(
+            ReceivedChannel::resolve(&mesh_packet, &channels),
+            ReceivedChannel::Known(MeshChannel::from(0))
+        );
+
+        mesh_packet.channel = 1;
+        assert_eq!(
+            ReceivedChannel::resolve(&mesh_packet, &channels),
+            ReceivedChannel::Unknown(1)
+        );
+
+        // Packets that were not decrypted carry the channel hash
+        mesh_packet.channel = 0;
+        mesh_packet.payload_variant =
+            Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![
+                0;
+                8
+            ]));
+        assert_eq!(
+            ReceivedChannel::resolve(&mesh_packet, &channels),
+            ReceivedChannel::Unknown(0)
+        );
+    }
+
+    #[test]
+    fn classifies_waypoint_deletions() {
+        let waypoint = protobufs::Waypoint {
+            id: 5,
+            name: "Camp".to_string(),
+            expire: 1_000,
+            ..Default::default()
+        };
+
+        let updated = WaypointEvent::from_waypoint(1.into(), waypoint.clone(), 999);
+        assert!(matches!(updated, WaypointEvent::Updated { .. }));
+
+        let expired = WaypointEvent::from_waypoint(1.into(), waypoint.clone(), 1_000);
+        assert!(matches!(
+            expired,
+            WaypointEvent::Removed { waypoint_id: 5, .. }
+        ));
+
+        let unnamed = protobufs::Waypoint {
+            name: String::new(),
+            expire: 0,
+            ..waypoint
+        };
+        assert_eq!(
+            WaypointEvent::from_waypoint(1.into(), unnamed, 0).waypoint_id(),
+            5
+        );
+    }
+
+    #[test]
+    fn skips_text_messages_that_fail_to_decompress() {
+        let mut mesh_packet = text_packet(
+            BROADCAST_NODE_ID,
+            protobufs::PortNum::TextMessageCompressedApp,
+        );
+        if let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+            mesh_packet.payload_variant.as_mut()
+        {
+            // Refers to a code point before the start of the unicode range
+            data.payload = vec![0x04, 0x82];
+        }
+
+        let corrupt = protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Packet(
+                mesh_packet.clone(),
+            )),
+        };
+
+        assert!(TextMessage::from_mesh_packet(&mesh_packet).is_none());
+        assert!(matches!(
+            TextMessage::decode_mesh_packet(&mesh_packet),
+            Err(Error::InvalidCompressedText { .. })
+        ));
+        assert_eq!(
+            MeshEvent::from_packet_with_policy(corrupt.clone(), DecodePolicy::Raw),
+            Some(MeshEvent::Raw(corrupt.clone()))
+        );
+        assert_eq!(
+            MeshEvent::from_packet_with_policy(corrupt, DecodePolicy::Skip),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_other_ports() {
+        assert!(TextMessage::from_mesh_packet(&text_packet(
+            BROADCAST_NODE_ID,
+            protobufs::PortNum::PositionApp
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn converts_packets_into_events() {
+        let routing = protobufs::Routing {
+            variant: Some(protobufs::routing::Variant::ErrorReason(
+                protobufs::routing::Error::None as i32,
+            )),
+        };
+
+        let ack = protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Packet(
+                protobufs::MeshPacket {
+                    from: 4,
+                    payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                        protobufs::Data {
+                            portnum: protobufs::PortNum::RoutingApp as i32,
+                            payload: routing.encode_to_vec(),
+                            request_id: 9,
+                            ..Default::default()
+                        },
+                    )),
+                    ..Default::default()
+                },
+            )),
+        };
+
+        assert_eq!(
+            MeshEvent::from_packet(ack),
+            MeshEvent::Ack {
+                from: 4.into(),
+                request_id: 9.into()
+            }
+        );
+
+        let malformed = protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Packet(
+                protobufs::MeshPacket {
+                    payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                        protobufs::Data {
+                            portnum: protobufs::PortNum::PositionApp as i32,
+                            payload: vec![0xff],
+                            ..Default::default()
+                        },
+                    )),
+                    ..Default::default()
+                },
+            )),
+        };
+
+        assert_eq!(
+            MeshEvent::from_packet(malformed.clone()),
+            MeshEvent::Raw(malformed.clone())
+        );
+        assert_eq!(
+            MeshEvent::from_packet_with_policy(malformed, DecodePolicy::Skip),
+            None
+        );
+
+        let config_complete = protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(7)),
+        };
+
+        assert_eq!(
+            MeshEvent::from_packet(config_complete),
+            MeshEvent::ConfigComplete(7)
+        );
+    }
+
+    #[test]
+    fn parses_detection_sensor_alerts() {
+        assert_eq!(
+            DetectionEvent::from_payload(b"\x07Front door detected"),
+            DetectionEvent {
+                name: "Front door".to_string(),
+                triggered: true,
+                bell: true,
+            }
+        );
+        assert_eq!(
+            DetectionEvent::from_payload(b"Garage state: 0"),
+            DetectionEvent {
+                name: "Garage".to_string(),
+                triggered: false,
+                bell: false,
+            }
+        );
+        assert_eq!(
+            DetectionEvent::from_payload(b"Motion"),
+            DetectionEvent {
+                name: "Motion".to_string(),
+                triggered: true,
+                bell: false,
+            }
+        );
+    }
+}
@@ -1,16 +1,26 @@
 use crate::errors_internal::{Error, InternalChannelError, InternalStreamError};
 use crate::protobufs;
 use crate::types::EncodedToRadioPacketWithHeader;
-use crate::utils::format_data_packet;
+use crate::utils::{current_epoch_secs_u32, format_data_packet};
 use log::{debug, error, trace, warn};
 use prost::Message;
+use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::spawn;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::{broadcast, oneshot};
 use tokio::task::JoinHandle;
+use tokio_util::bytes::BytesMut;
 use tokio_util::sync::CancellationToken;
 
-use crate::connections::stream_buffer::StreamBuffer;
+use crate::connections::admin_session::AdminSession;
+use crate::connections::diagnostics::{DropDiagnostics, DropReason};
+use crate::connections::health::{
+    ConnectionEvent, ConnectionState, HealthMonitor, MAX_CLOCK_SKEW_SECS,
+};
+use crate::connections::radio_state::{ConfigHandshake, RadioState};
+use crate::connections::stream_buffer::{ReceivedPacket, StreamBuffer};
+use crate::connections::write_queue::WriteQueue;
 
 use super::wrappers::encoded_data::IncomingStreamData;
 
@@ -18,19 +28,28 @@ use super::wrappers::encoded_data::IncomingStreamData;
 /// Needs to be less than this: https://github.com/meshtastic/firmware/blob/eb372c190ec82366998c867acc609a418130d842/src/SerialConsole.cpp#L8
 pub const CLIENT_HEARTBEAT_INTERVAL: u64 = 5 * 60; // 5 minutes
 
+/// A request for the write handler to flush the underlying stream once all queued packets have been written.
+/// The result of the flush is sent back through the contained channel.
+pub type FlushRequest = oneshot::Sender<Result<(), Error>>;
+
+/// The number of decoded packets that are buffered for internal packet subscribers (e.g. reliable sends)
+/// before the oldest packets are dropped for subscribers that are lagging behind.
+pub const PACKET_BROADCAST_CAPACITY: usize = 256;
+
 pub fn spawn_read_handler<R>(
     cancellation_token: CancellationToken,
     read_stream: R,
     read_output_tx: UnboundedSender<IncomingStreamData>,
+    health_monitor: Arc<HealthMonitor>,
 ) -> JoinHandle<Result<(), Error>>
 where
     R: AsyncReadExt + Send + Unpin + 'static,
 {
-    let handle = start_read_handler(read_stream, read_output_tx.clone());
+    let handle = start_read_handler(read_stream, read_output_tx.clone(), health_monitor.clone());
 
     spawn(async move {
         // Check for cancellation signal or handle termination
-        tokio::select! {
+        let result = tokio::select! {
             _ = cancellation_token.cancelled() => {
                 debug!("Read handler cancelled");
                 Ok(())
@@ -39,13 +58,18 @@ where
                 error!("Read handler unexpectedly terminated: {:#?}", e);
                 e
             }
-        }
+        };
+
+        health_monitor.set_state(ConnectionState::Disconnected);
+
+        result
     })
 }
 
 async fn start_read_handler<R>(
     read_stream: R,
     read_output_tx: UnboundedSender<IncomingStreamData>,
+    health_monitor: Arc<HealthMonitor>,
 ) -> Result<(), Error>
 where
     R: AsyncReadExt + Send + Unpin + 'static,
@@ -63,6 +87,8 @@ where
             }
             Ok(n) => {
                 trace!("Read {} bytes from stream", n);
+                health_monitor.record_bytes_received();
+
                 let data: IncomingStreamData = buffer[..n].to_vec().into();
                 trace!("Read data: {:?}", data);
 
@@ -93,11 +119,19 @@ pub fn spawn_write_handler<W>(
     cancellation_token: CancellationToken,
     write_stream: W,
     write_input_rx: tokio::sync::mpsc::UnboundedReceiver<EncodedToRadioPacketWithHeader>,
+    write_queue: Arc<WriteQueue>,
+    flush_request_rx: UnboundedReceiver<FlushRequest>,
 ) -> JoinHandle<Result<(), Error>>
 where
     W: AsyncWriteExt + Send + Unpin + 'static,
 {
-    let handle = start_write_handler(cancellation_token.clone(), write_stream, write_input_rx);
+    let handle = start_write_handler(
+        cancellation_token.clone(),
+        write_stream,
+        write_input_rx,
+        write_queue,
+        flush_request_rx,
+    );
 
     spawn(async move {
         tokio::select! {
@@ -119,22 +153,95 @@ async fn start_write_handler<W>(
     _cancellation_token: CancellationToken,
     mut write_stream: W,
     mut write_input_rx: tokio::sync::mpsc::UnboundedReceiver<EncodedToRadioPacketWithHeader>,
+    write_queue: Arc<WriteQueue>,
+    mut flush_request_rx: UnboundedReceiver<FlushRequest>,
 ) -> Result<(), Error>
 where
     W: AsyncWriteExt + Send + Unpin + 'static,
 {
     debug!("Started write handler");
 
-    while let Some(message) = write_input_rx.recv().await {
-        trace!("Writing packet data: {:?}", message);
+    let mut pending_flushes: Vec<FlushRequest> = Vec::new();
 
-        if let Err(e) = write_stream.write(message.data()).await {
-            error!("Error writing to stream: {:?}", e);
-            return Err(Error::InternalStreamError(
-                InternalStreamError::StreamWriteError {
-                    source: Box::new(e),
+    // The first recoverable write error since the last flush, which is reported to the next flush requests
+    let mut write_error: Option<std::io::Error> = None;
+
+    loop {
+        // Packets sent through the raw write channel carry no priority information,
+        // so they are queued with the default priority.
+        while let Ok(message) = write_input_rx.try_recv() {
+            write_queue.push(message, protobufs::mesh_packet::Priority::Default);
+        }
+
+        let Some(message) = write_queue.pop() else {
+            // Flushes are only performed once every previously queued packet has been written
+            if !pending_flushes.is_empty() {
+                let flush_result = match write_error.take() {
+                    Some(e) => Err(e),
+                    None => write_stream.flush().await,
+                };
+
+                for flush_request in pending_flushes.drain(..) {
+                    let response = match &flush_result {
+                        Ok(()) => Ok(()),
+                        Err(e) => Err(stream_write_error(std::io::Error::new(
+                            e.kind(),
+                            e.to_string(),
+                        ))),
+                    };
+
+                    // The requester may have stopped waiting for the flush to complete
+                    let _ = flush_request.send(response);
+                }
+
+                if let Err(e) = flush_result {
+                    if is_fatal_write_error(&e) {
+                        error!("Fatal error flushing stream: {:?}", e);
+                        return Err(stream_write_error(e));
+                    }
+
+                    warn!("Error flushing stream: {:?}", e);
+                }
+
+                continue;
+            }
+
+            tokio::select! {
+                message = write_input_rx.recv() => match message {
+                    Some(message) => write_queue.push(message, protobufs::mesh_packet::Priority::Default),
+                    None => break,
                 },
-            ));
+                Some(flush_request) = flush_request_rx.recv() => pending_flushes.push(flush_request),
+                _ = write_queue.notified() => (),
+            }
+
+            continue;
+        };
+
+        trace!("Writing packet data: {:?}", message);
+
+        if let Err((e, written)) = write_frame(&mut write_stream, message.data()).await {
+            if is_fatal_write_error(&e) {
+                // The stream can no longer be written to. The read handler keeps draining any data
+                // that is still buffered, and terminates once the stream reports its own closure.
+                error!("Fatal error writing to stream: {:?}", e);
+                return Err(stream_write_error(e));
+            }
+
+            if written > 0 {
+                // The radio would read the start of the next packet as the rest of this one, so the
+                // framing of the stream cannot be recovered
+                error!(
+                    "Error writing to stream after {written} of {} bytes: {:?}",
+                    message.data().len(),
+                    e
+                );
+                return Err(stream_write_error(e));
+            }
+
+            // The packet is dropped, but the connection stays open so that incoming packets still flow
+            warn!("Error writing to stream, dropping packet: {:?}", e);
+            write_error.get_or_insert(e);
         }
     }
 
@@ -143,12 +250,75 @@ where
     Ok(())
 }
 
+/// Writes a complete framed packet to the stream. On failure, returns the error along with the number
+/// of bytes of the packet that were written before the error occurred.
+async fn write_frame<W>(write_stream: &mut W, frame: &[u8]) -> Result<(), (std::io::Error, usize)>
+where
+    W: AsyncWriteExt + Unpin,
+{
+    let mut written = 0;
+
+    while written < frame.len() {
+        match write_stream.write(&frame[written..]).await {
+            Ok(0) => return Err((std::io::ErrorKind::WriteZero.into(), written)),
+            Ok(n) => written += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(e) => return Err((e, written)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether a write error indicates that the stream to the radio is no longer usable.
+/// Other write errors, e.g. timeouts, only affect the packet being written, as long as none of
+/// the packet has been written yet.
+fn is_fatal_write_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    // Serial ports report a detached device as `EIO`, or as an uncategorized error
+    #[cfg(unix)]
+    if e.raw_os_error() == Some(EIO) {
+        return true;
+    }
+
+    matches!(
+        e.kind(),
+        ErrorKind::BrokenPipe
+            | ErrorKind::NotConnected
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::WriteZero
+            | ErrorKind::Other
+    )
+}
+
+/// The `errno` value of an I/O error, which is the same on all supported Unix platforms.
+#[cfg(unix)]
+const EIO: i32 = 5;
+
+fn stream_write_error(e: std::io::Error) -> Error {
+    Error::InternalStreamError(InternalStreamError::StreamWriteError {
+        source: Box::new(e),
+    })
+}
+
 pub fn spawn_processing_handler(
     cancellation_token: CancellationToken,
     read_output_rx: UnboundedReceiver<IncomingStreamData>,
-    decoded_packet_tx: UnboundedSender<protobufs::FromRadio>,
+    decoded_packet_tx: UnboundedSender<ReceivedPacket>,
+    max_buffer_size: usize,
+    health_monitor: Arc<HealthMonitor>,
+    drop_diagnostics: DropDiagnostics,
 ) -> JoinHandle<Result<(), Error>> {
-    let handle = start_processing_handler(read_output_rx, decoded_packet_tx);
+    let handle = start_processing_handler(
+        read_output_rx,
+        decoded_packet_tx,
+        max_buffer_size,
+        health_monitor,
+        drop_diagnostics,
+    );
 
     spawn(async move {
         tokio::select! {
@@ -166,24 +336,162 @@ pub fn spawn_processing_handler(
 
 async fn start_processing_handler(
     mut read_output_rx: tokio::sync::mpsc::UnboundedReceiver<IncomingStreamData>,
-    decoded_packet_tx: UnboundedSender<protobufs::FromRadio>,
+    decoded_packet_tx: UnboundedSender<ReceivedPacket>,
+    max_buffer_size: usize,
+    health_monitor: Arc<HealthMonitor>,
+    drop_diagnostics: DropDiagnostics,
 ) {
     debug!("Started message processing handler");
 
-    let mut buffer = StreamBuffer::new(decoded_packet_tx);
+    let mut stream_buffer =
+        StreamBuffer::with_max_buffer_size(max_buffer_size).with_drop_diagnostics(drop_diagnostics);
+    let mut buffer = BytesMut::new();
 
     while let Some(message) = read_output_rx.recv().await {
-        buffer.process_incoming_bytes(message);
+        buffer.extend_from_slice(message.data());
+
+        loop {
+            match stream_buffer.next_packet(&mut buffer) {
+                Ok(Some(decoded_packet)) => {
+                    if let Err(e) = decoded_packet_tx.send(decoded_packet) {
+                        error!("Failed to send decoded packet: {e}");
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(Error::BufferOverflow { buffer_size, .. }) => {
+                    warn!("Discarded {buffer_size} bytes exceeding the maximum buffer size");
+                    health_monitor.emit(ConnectionEvent::BufferOverflow {
+                        discarded_bytes: buffer_size,
+                    });
+                    break;
+                }
+                Err(e) => {
+                    warn!("{e}");
+                    break;
+                }
+            }
+        }
     }
 
     debug!("Processing read_output_rx channel closed");
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_dispatch_handler(
+    cancellation_token: CancellationToken,
+    dispatch_input_rx: UnboundedReceiver<ReceivedPacket>,
+    decoded_packet_tx: UnboundedSender<protobufs::FromRadio>,
+    packet_broadcast_tx: broadcast::Sender<ReceivedPacket>,
+    radio_state: Arc<RadioState>,
+    admin_session: AdminSession,
+    health_monitor: Arc<HealthMonitor>,
+    drop_diagnostics: DropDiagnostics,
+) -> JoinHandle<Result<(), Error>> {
+    let handle = start_dispatch_handler(
+        dispatch_input_rx,
+        decoded_packet_tx,
+        packet_broadcast_tx,
+        radio_state,
+        admin_session,
+        health_monitor,
+        drop_diagnostics,
+    );
+
+    spawn(async move {
+        tokio::select! {
+            _ = cancellation_token.cancelled() => {
+                debug!("Dispatch handler cancelled");
+                Ok(())
+            }
+            _ = handle => {
+                error!("Dispatch handler unexpectedly terminated");
+                Err(Error::InternalChannelError(InternalChannelError::ChannelClosedEarly {}))
+            }
+        }
+    })
+}
+
+async fn start_dispatch_handler(
+    mut dispatch_input_rx: UnboundedReceiver<ReceivedPacket>,
+    decoded_packet_tx: UnboundedSender<protobufs::FromRadio>,
+    packet_broadcast_tx: broadcast::Sender<ReceivedPacket>,
+    radio_state: Arc<RadioState>,
+    admin_session: AdminSession,
+    health_monitor: Arc<HealthMonitor>,
+    drop_diagnostics: DropDiagnostics,
+) {
+    debug!("Started dispatch handler");
+
+    let mut clock_skewed = false;
+
+    while let Some(received_packet) = dispatch_input_rx.recv().await {
+        health_monitor.record_packet_received();
+
+        match &received_packet.packet.payload_variant {
+            Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(_)) => {
+                health_monitor.emit(ConnectionEvent::ConfigComplete)
+            }
+            Some(protobufs::from_radio::PayloadVariant::Rebooted(true)) => {
+                health_monitor.emit(ConnectionEvent::DeviceRebooted)
+            }
+            Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) => {
+                if let Some(skew_secs) = mesh_packet.clock_skew(current_epoch_secs_u32()) {
+                    let is_skewed = skew_secs.abs() > MAX_CLOCK_SKEW_SECS;
+
+                    if is_skewed && !clock_skewed {
+                        warn!("Radio clock is skewed by {skew_secs} seconds");
+                        health_monitor.emit(ConnectionEvent::ClockSkewDetected { skew_secs });
+                    }
+
+                    clock_skewed = is_skewed;
+                }
+            }
+            _ => (),
+        }
+
+        let config_changed = radio_state.handle_packet(&received_packet.packet);
+
+        // Configuration received during a handshake is part of the sync, rather than a change
+        if config_changed
+            && matches!(
+                radio_state.config_handshake(),
+                Some(ConfigHandshake::Complete(_))
+            )
+        {
+            health_monitor.emit(ConnectionEvent::ConfigChanged);
+        }
+
+        admin_session.handle_packet(&received_packet.packet);
+
+        let packet = received_packet.packet.clone();
+        let raw = drop_diagnostics
+            .is_enabled()
+            .then(|| received_packet.raw.clone());
+
+        // Internal subscribers only exist while e.g. a reliable send is awaiting an ACK,
+        // so a send error here only means that nobody is currently listening.
+        let _ = packet_broadcast_tx.send(received_packet);
+
+        if decoded_packet_tx.send(packet).is_err() {
+            trace!("Decoded packet receiver dropped, packet not forwarded");
+
+            if let Some(raw) = raw {
+                drop_diagnostics.report(DropReason::ReceiverClosed, &raw);
+            }
+        }
+    }
+
+    debug!("Dispatch input channel closed");
+}
+
 pub fn spawn_heartbeat_handler(
     cancellation_token: CancellationToken,
     write_input_tx: UnboundedSender<EncodedToRadioPacketWithHeader>,
+    health_monitor: Arc<HealthMonitor>,
 ) -> JoinHandle<Result<(), Error>> {
-    let handle = start_heartbeat_handler(cancellation_token.clone(), write_input_tx);
+    let handle =
+        start_heartbeat_handler(cancellation_token.clone(), write_input_tx, health_monitor);
 
     spawn(async move {
         tokio::select! {
@@ -204,6 +512,7 @@ pub fn spawn_heartbeat_handler(
 async fn start_heartbeat_handler(
     _cancellation_token: CancellationToken,
     write_input_tx: UnboundedSender<EncodedToRadioPacketWithHeader>,
+    health_monitor: Arc<HealthMonitor>,
 ) -> Result<(), Error> {
     debug!("Started heartbeat handler");
 
@@ -244,6 +553,7 @@ async fn start_heartbeat_handler(
             ));
         }
 
+        health_monitor.record_heartbeat_sent();
         log::info!("Sent heartbeat packet");
     }
 
@@ -251,3 +561,144 @@ async fn start_heartbeat_handler(
 
     // Return type should be never (!)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tokio::io::AsyncWrite;
+
+    use super::*;
+
+    /// A writer that accepts at most `accepted` bytes per write, fails the write after the first
+    /// `failing_after` bytes with a recoverable error, and records all written bytes.
+    struct FlakyWriter {
+        accepted: usize,
+        failing_after: Option<usize>,
+        written: Arc<std::sync::Mutex<Vec<u8>>>,
+    }
+
+    impl FlakyWriter {
+        fn new(accepted: usize, failing_after: usize) -> Self {
+            Self {
+                accepted,
+                failing_after: Some(failing_after),
+                written: Arc::default(),
+            }
+        }
+    }
+
+    impl AsyncWrite for FlakyWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            if self.failing_after == Some(self.written.lock().unwrap().len()) {
+                self.failing_after = None;
+                return Poll::Ready(Err(std::io::ErrorKind::TimedOut.into()));
+            }
+
+            let accepted = buf.len().min(self.accepted);
+            self.written
+                .lock()
+                .unwrap()
+                .extend_from_slice(&buf[..accepted]);
+            Poll::Ready(Ok(accepted))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn recovers_from_non_fatal_write_errors() {
+        let writer = FlakyWriter::new(usize::MAX, 0);
+        let written = writer.written.clone();
+
+        let cancellation_token = CancellationToken::new();
+        let write_queue = Arc::new(WriteQueue::new());
+        let (_write_input_tx, write_input_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (flush_request_tx, flush_request_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let handle = spawn_write_handler(
+            cancellation_token.clone(),
+            writer,
+            write_input_rx,
+            write_queue.clone(),
+            flush_request_rx,
+        );
+
+        let flush = || {
+            let (response_tx, response_rx) = oneshot::channel();
+            flush_request_tx.send(response_tx).unwrap();
+            response_rx
+        };
+
+        write_queue.push(vec![1].into(), protobufs::mesh_packet::Priority::Default);
+        assert!(flush().await.unwrap().is_err());
+
+        write_queue.push(vec![2].into(), protobufs::mesh_packet::Priority::Default);
+        assert!(flush().await.unwrap().is_ok());
+
+        assert_eq!(*written.lock().unwrap(), [2]);
+        assert!(!handle.is_finished());
+        assert!(!cancellation_token.is_cancelled());
+
+        cancellation_token.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn closes_streams_after_partially_written_packets() {
+        let writer = FlakyWriter::new(2, 2);
+        let written = writer.written.clone();
+
+        let write_queue = Arc::new(WriteQueue::new());
+        let (_write_input_tx, write_input_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (_flush_request_tx, flush_request_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        write_queue.push(
+            vec![1, 2, 3, 4].into(),
+            protobufs::mesh_packet::Priority::Default,
+        );
+        write_queue.push(vec![5].into(), protobufs::mesh_packet::Priority::Default);
+
+        let handle = spawn_write_handler(
+            CancellationToken::new(),
+            writer,
+            write_input_rx,
+            write_queue,
+            flush_request_rx,
+        );
+
+        // The rest of the packet must not be followed by the next packet
+        assert!(handle.await.unwrap().is_err());
+        assert_eq!(*written.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn treats_detached_devices_as_fatal() {
+        assert!(is_fatal_write_error(&std::io::ErrorKind::BrokenPipe.into()));
+        assert!(is_fatal_write_error(&std::io::Error::other(
+            "No such device"
+        )));
+        #[cfg(unix)]
+        assert!(is_fatal_write_error(&std::io::Error::from_raw_os_error(
+            EIO
+        )));
+
+        assert!(!is_fatal_write_error(&std::io::ErrorKind::TimedOut.into()));
+        assert!(!is_fatal_write_error(
+            &std::io::ErrorKind::WouldBlock.into()
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    watch,
+};
+
+/// The number of lifecycle events buffered for each receiver returned by the `connection_events`
+/// method of the `ConnectedStreamApi` struct. Further events are dropped until the receiver catches up.
+pub const CONNECTION_EVENT_CAPACITY: usize = 32;
+
+/// An enum that represents the state of the connection to the radio.
+///
+/// # Variants
+///
+/// * `Connected` - The connection to the radio is open.
+/// * `Reconnecting` - The connection to the radio was lost, and is being re-established.
+/// * `Disconnected` - The connection to the radio was closed, either by the `disconnect` method
+///     or because the underlying stream failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting,
+    Disconnected,
+}
+
+/// The maximum difference between the receive timestamps reported by the radio and the host time, in
+/// seconds, before a `ConnectionEvent::ClockSkewDetected` event is emitted.
+pub const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// An enum that represents a lifecycle event of the connection to the radio, as returned by the
+/// `connection_events` method of the `ConnectedStreamApi` struct.
+///
+/// # Variants
+///
+/// * `Connected` - The connection to the radio is open.
+/// * `ConfigStarted` - The configuration of the radio was requested by the `configure` method.
+/// * `ConfigComplete` - The radio has finished sending its configuration.
+/// * `Reconnecting` - The connection to the radio was lost, and is being re-established.
+/// * `Reconnected` - The connection to the radio was re-established by a `ReconnectingStreamApi`, and
+///     the `configure` handshake of the new connection has completed, or a stalled BLE connection built
+///     by the `build_ble_stream` function was restored.
+/// * `Disconnected` - The connection to the radio was closed, either by the `disconnect` method
+///     or because the underlying stream failed.
+/// * `DeviceRebooted` - The radio has reported that it rebooted.
+/// * `ConfigChanged` - The radio has reported a changed configuration, module configuration, or channel
+///     after the `configure` handshake completed, e.g. because it was changed by a remote admin.
+/// * `ClockSkewDetected` - The receive timestamps of the packets reported by the radio differ from the host
+///     time by more than `MAX_CLOCK_SKEW_SECS` seconds, so timestamps reported by the radio should not be
+///     trusted. The event is emitted once when the skew is first detected, and again only after the clock
+///     of the radio has been within the threshold in the meantime.
+/// * `BufferOverflow` - The data received from the radio exceeded the maximum buffer size set by the
+///     `StreamApi::with_max_buffer_size` method without forming a complete packet, so `discarded_bytes`
+///     bytes were discarded. Any packets contained in the discarded data are lost.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectionEvent {
+    Connected,
+    ConfigStarted,
+    ConfigComplete,
+    Reconnecting,
+    Reconnected,
+    Disconnected,
+    DeviceRebooted,
+    ConfigChanged,
+    ClockSkewDetected { skew_secs: i64 },
+    BufferOverflow { discarded_bytes: usize },
+}
+
+impl From<ConnectionState> for ConnectionEvent {
+    fn from(state: ConnectionState) -> Self {
+        match state {
+            ConnectionState::Connected => ConnectionEvent::Connected,
+            ConnectionState::Reconnecting => ConnectionEvent::Reconnecting,
+            ConnectionState::Disconnected => ConnectionEvent::Disconnected,
+        }
+    }
+}
+
+/// A struct that summarizes the health of the connection to the radio, as returned by the
+/// `health` method of the `ConnectedStreamApi` struct.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionHealth {
+    /// The state of the connection to the radio.
+    pub state: ConnectionState,
+
+    /// The time at which the connection to the radio was opened.
+    pub connected_at: Instant,
+
+    /// The time at which data was last received from the radio, if any.
+    pub last_byte_received: Option<Instant>,
+
+    /// The time at which a packet was last decoded from the data received from the radio, if any.
+    pub last_packet_received: Option<Instant>,
+
+    /// The time at which a heartbeat was last sent to the radio, if any.
+    pub last_heartbeat_sent: Option<Instant>,
+}
+
+impl ConnectionHealth {
+    fn new(connected_at: Instant) -> Self {
+        Self {
+            state: ConnectionState::Connected,
+            connected_at,
+            last_byte_received: None,
+            last_packet_received: None,
+            last_heartbeat_sent: None,
+        }
+    }
+
+    /// Returns whether the radio has sent any data since the last heartbeat was sent to it.
+    /// Returns `true` if no heartbeat has been sent yet.
+    pub fn heartbeat_acknowledged(&self) -> bool {
+        match (self.last_heartbeat_sent, self.last_byte_received) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(heartbeat_sent), Some(byte_received)) => byte_received >= heartbeat_sent,
+        }
+    }
+
+    /// Returns the time elapsed since data was last received from the radio, or since the
+    /// connection was opened if no data has been received yet.
+    pub fn silence(&self) -> Duration {
+        self.last_byte_received
+            .unwrap_or(self.connected_at)
+            .elapsed()
+    }
+
+    /// Returns whether the connection should be considered unhealthy, i.e. whether the connection is
+    /// not open, or the radio has not sent any data within the specified duration.
+    pub fn is_stale(&self, max_silence: Duration) -> bool {
+        self.state != ConnectionState::Connected || self.silence() > max_silence
+    }
+}
+
+/// A struct that records the health of the connection to the radio, which is updated by the
+/// worker threads as data is sent to and received from the radio. Changes to the state of the
+/// connection, along with other lifecycle events, are delivered to all event subscribers.
+#[derive(Debug)]
+pub struct HealthMonitor {
+    health: watch::Sender<ConnectionHealth>,
+    event_txs: Mutex<Vec<mpsc::Sender<ConnectionEvent>>>,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthMonitor {
+    /// Creates a new `HealthMonitor` for a connection opened at the current time.
+    pub fn new() -> Self {
+        let (health, _) = watch::channel(ConnectionHealth::new(Instant::now()));
+
+        Self {
+            health,
+            event_txs: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns a receiver that is notified whenever the health of the connection changes.
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionHealth> {
+        self.health.subscribe()
+    }
+
+    /// Records that data was received from the radio.
+    pub fn record_bytes_received(&self) {
+        self.health
+            .send_modify(|health| health.last_byte_received = Some(Instant::now()));
+    }
+
+    /// Records that a packet was decoded from the data received from the radio.
+    pub fn record_packet_received(&self) {
+        self.health
+            .send_modify(|health| health.last_packet_received = Some(Instant::now()));
+    }
+
+    /// Records that a heartbeat was sent to the radio.
+    pub fn record_heartbeat_sent(&self) {
+        self.health
+            .send_modify(|health| health.last_heartbeat_sent = Some(Instant::now()));
+    }
+
+    /// Returns a receiver of the lifecycle events of the connection. The current state of the
+    /// connection is delivered as the first event, so that subscribers don't miss e.g. the
+    /// `Connected` event that was emitted before they subscribed.
+    pub fn subscribe_events(&self) -> mpsc::Receiver<ConnectionEvent> {
+        let (event_tx, event_rx) = mpsc::channel(CONNECTION_EVENT_CAPACITY);
+
+        // The channel was just created, so the initial event always fits
+        let _ = event_tx.try_send(self.health.borrow().state.into());
+        self.lock_event_txs().push(event_tx);
+
+        event_rx
+    }
+
+    /// Delivers a lifecycle event to all event subscribers, and stops delivering events to
+    /// subscribers that have dropped their receiver.
+    pub fn emit(&self, event: ConnectionEvent) {
+        self.lock_event_txs()
+            .retain(|event_tx| match event_tx.try_send(event) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!("Connection event receiver is full, dropping {event:?} event");
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
+    }
+
+    /// Updates the state of the connection, emitting the corresponding event if the state changed.
+    pub fn set_state(&self, state: ConnectionState) {
+        let modified = self.health.send_if_modified(|health| {
+            let modified = health.state != state;
+            health.state = state;
+            modified
+        });
+
+        if modified {
+            self.emit(state.into());
+        }
+    }
+
+    fn lock_event_txs(&self) -> MutexGuard<'_, Vec<mpsc::Sender<ConnectionEvent>>> {
+        // The list is never left in an inconsistent state, so a poisoned lock can be recovered
+        self.event_txs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_heartbeat_acknowledgement() {
+        let health_monitor = HealthMonitor::new();
+        let health = health_monitor.subscribe();
+        assert!(health.borrow().heartbeat_acknowledged());
+
+        health_monitor.record_heartbeat_sent();
+        assert!(!health.borrow().heartbeat_acknowledged());
+
+        health_monitor.record_bytes_received();
+        assert!(health.borrow().heartbeat_acknowledged());
+        assert!(!health.borrow().is_stale(Duration::from_secs(60)));
+
+        health_monitor.set_state(ConnectionState::Disconnected);
+        assert!(health.borrow().is_stale(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn delivers_lifecycle_events() {
+        let health_monitor = HealthMonitor::new();
+        let mut events = health_monitor.subscribe_events();
+
+        health_monitor.emit(ConnectionEvent::ConfigStarted);
+        health_monitor.set_state(ConnectionState::Connected);
+        health_monitor.set_state(ConnectionState::Disconnected);
+
+        let mut late_events = health_monitor.subscribe_events();
+
+        assert_eq!(events.try_recv(), Ok(ConnectionEvent::Connected));
+        assert_eq!(events.try_recv(), Ok(ConnectionEvent::ConfigStarted));
+        assert_eq!(events.try_recv(), Ok(ConnectionEvent::Disconnected));
+        assert!(events.try_recv().is_err());
+        assert_eq!(late_events.try_recv(), Ok(ConnectionEvent::Disconnected));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::crypto_internal::encrypt_data;
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::utils_internal::generate_rand_id;
+
+use super::{
+    wrappers::{encoded_data::EncodedMeshPacketData, mesh_channel::MeshChannel, NodeId},
+    PacketDestination,
+};
+
+/// A builder for outgoing `MeshPacket` structs containing a decoded payload.
+///
+/// The builder defaults to a broadcast packet on the primary channel, without requesting an
+/// acknowledgement or a response, and with an `Unset` priority. Packets with an `Unset` priority
+/// are queued with a priority derived from their contents in the same way as the firmware: routing
+/// packets use `Ack` priority, packets with `want_ack` set use `Reliable` priority, position packets
+/// use `Background` priority, and all other packets are treated as `Default` priority.
+///
+/// # Examples
+///
+/// ```
+/// let mesh_packet = MeshPacketBuilder::new(protobufs::PortNum::PrivateApp, byte_data.into())
+///     .destination(PacketDestination::Node(node_id))
+///     .want_ack(true)
+///     .priority(protobufs::mesh_packet::Priority::Background)
+///     .build(packet_router.source_node_id());
+///
+/// stream_api
+///     .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)))
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct MeshPacketBuilder {
+    port_num: i32,
+    packet_data: EncodedMeshPacketData,
+    destination: PacketDestination,
+    channel: MeshChannel,
+    want_ack: bool,
+    want_response: bool,
+    reply_id: Option<u32>,
+    emoji: Option<u32>,
+    priority: protobufs::mesh_packet::Priority,
+}
+
+impl MeshPacketBuilder {
+    /// Creates a new builder for a packet containing the specified payload on the specified port.
+    pub fn new(port_num: protobufs::PortNum, packet_data: EncodedMeshPacketData) -> Self {
+        Self::with_port_num_value(port_num as i32, packet_data)
+    }
+
+    /// Creates a new builder for a packet containing the specified payload on a private application
+    /// port, i.e. a port number in the range reserved for custom applications (`PrivateApp` to `Max`,
+    /// or 256 to 511). The generated `PortNum` enum has no variants for most of these port numbers.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidPrivatePortNum` if the port number is outside of the private range.
+    pub fn new_private(port_num: u32, packet_data: EncodedMeshPacketData) -> Result<Self, Error> {
+        let private_range = protobufs::PortNum::PrivateApp as u32..=protobufs::PortNum::Max as u32;
+
+        if !private_range.contains(&port_num) {
+            return Err(Error::InvalidPrivatePortNum { port_num });
+        }
+
+        Ok(Self::with_port_num_value(port_num as i32, packet_data))
+    }
+
+    fn with_port_num_value(port_num: i32, packet_data: EncodedMeshPacketData) -> Self {
+        Self {
+            port_num,
+            packet_data,
+            destination: PacketDestination::Broadcast,
+            channel: MeshChannel::default(),
+            want_ack: false,
+            want_response: false,
+            reply_id: None,
+            emoji: None,
+            priority: protobufs::mesh_packet::Priority::Unset,
+        }
+    }
+
+    /// Sets the destination of the packet. Defaults to `PacketDestination::Broadcast`.
+    pub fn destination(mut self, destination: PacketDestination) -> Self {
+        self.destination = destination;
+        self
+    }
+
+    /// Sets the channel the packet is sent on. Defaults to the primary channel.
+    pub fn channel(mut self, channel: MeshChannel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Sets whether the radio should wait for an acknowledgement from other nodes on the mesh.
+    pub fn want_ack(mut self, want_ack: bool) -> Self {
+        self.want_ack = want_ack;
+        self
+    }
+
+    /// Sets whether the destination node should respond to the packet.
+    pub fn want_response(mut self, want_response: bool) -> Self {
+        self.want_response = want_response;
+        self
+    }
+
+    /// Sets the id of the packet this packet is a reply to.
+    pub fn reply_id(mut self, reply_id: Option<u32>) -> Self {
+        self.reply_id = reply_id;
+        self
+    }
+
+    /// Sets the unicode emoji data sent with the packet.
+    pub fn emoji(mut self, emoji: Option<u32>) -> Self {
+        self.emoji = emoji;
+        self
+    }
+
+    /// Sets the priority of the packet within the client-side write queue and the transmit queue
+    /// of the radio. If the priority is `Unset`, the priority is derived from the contents of the
+    /// packet, and is treated as `Default` for packets without a specific mapping.
+    pub fn priority(mut self, priority: protobufs::mesh_packet::Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Builds the `MeshPacket` with a newly generated packet id.
+    ///
+    /// # Arguments
+    ///
+    /// * `own_node_id` - The id of the local node, which is used as the sender of the packet and
+    ///     as the destination of packets sent to `PacketDestination::Local`.
+    ///
+    /// # Returns
+    ///
+    /// The built `MeshPacket`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mesh_packet = MeshPacketBuilder::new(protobufs::PortNum::TextMessageApp, text.into())
+    ///     .build(packet_router.source_node_id());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn build(self, own_node_id: NodeId) -> protobufs::MeshPacket {
+        let packet_destination: NodeId = match self.destination {
+            PacketDestination::Local => own_node_id,
+            PacketDestination::Broadcast => u32::MAX.into(),
+            PacketDestination::Node(id) => id,
+        };
+
+        // NOTE(canardleteer): We don't warn on deprecation here, because it
+        //                     remains valid for many active nodes, and
+        //                     remains a part of the generated interface.
+        #[allow(deprecated)]
+        protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: self.port_num,
+                    payload: self.packet_data.data_vec(),
+                    want_response: self.want_response,
+                    reply_id: self.reply_id.unwrap_or(0),
+                    emoji: self.emoji.unwrap_or(0),
+                    dest: 0,       // TODO change this
+                    request_id: 0, // TODO change this
+                    source: 0,     // TODO change this
+                },
+            )),
+            rx_time: 0,                     // * not transmitted
+            rx_snr: 0.0,                    // * not transmitted
+            hop_limit: 0,                   // * not transmitted
+            priority: self.priority as i32, // * derived on the client and device if unset
+            rx_rssi: 0,                     // * not transmitted
+            delayed: 0,   // * not transmitted [deprecated since protobufs v2.2.19]
+            hop_start: 0, // * set on device
+            via_mqtt: false,
+            from: own_node_id.id(),
+            to: packet_destination.id(),
+            id: generate_rand_id(),
+            want_ack: self.want_ack,
+            channel: self.channel.channel(),
+        }
+    }
+
+    /// Builds the `MeshPacket` with a newly generated packet id, and with its payload encrypted with the
+    /// PSK of the specified channel, as if it was encrypted by the radio. The radio sends packets with an encrypted payload
+    /// without re-encrypting them, which allows bridges to forward packets between radios without exposing
+    /// their contents as plaintext.
+    ///
+    /// The firmware expects the `channel` field of packets with an encrypted payload to contain the hash of the
+    /// channel rather than its index, so the channel set on the builder is replaced by the hash of the passed
+    /// channel, as returned by `ChannelSettings::channel_hash`.
+    ///
+    /// # Arguments
+    ///
+    /// * `own_node_id` - The id of the local node, which is used as the sender of the packet and
+    ///     as the destination of packets sent to `PacketDestination::Local`.
+    /// * `channel_settings` - The settings of the channel the packet is sent on, which provide both the
+    ///     PSK and the hash of the channel.
+    ///
+    /// # Returns
+    ///
+    /// The built `MeshPacket`, containing an encrypted payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mesh_packet = MeshPacketBuilder::new(protobufs::PortNum::TextMessageApp, text.into())
+    ///     .build_encrypted(packet_router.source_node_id(), &channel_settings)?;
+    ///
+    /// stream_api
+    ///     .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)))
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidPskLength` if the PSK is not 0, 1, 16, or 32 bytes long.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn build_encrypted(
+        self,
+        own_node_id: NodeId,
+        channel_settings: &protobufs::ChannelSettings,
+    ) -> Result<protobufs::MeshPacket, Error> {
+        let channel_hash = channel_settings.channel_hash()?;
+        let mut mesh_packet = self.build(own_node_id);
+
+        if let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+            mesh_packet.payload_variant.take()
+        {
+            let payload = encrypt_data(
+                &data,
+                &channel_settings.psk,
+                mesh_packet.from,
+                mesh_packet.id,
+            )?;
+            mesh_packet.payload_variant =
+                Some(protobufs::mesh_packet::PayloadVariant::Encrypted(payload));
+        }
+
+        mesh_packet.channel = channel_hash as u32;
+
+        Ok(mesh_packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_private_port_numbers() {
+        let mesh_packet = MeshPacketBuilder::new_private(300, vec![1, 2, 3].into())
+            .unwrap()
+            .build(NodeId::new(1));
+
+        let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+            mesh_packet.payload_variant
+        else {
+            panic!("expected a decoded payload");
+        };
+
+        assert_eq!(data.portnum, 300);
+        assert_eq!(data.payload, vec![1, 2, 3]);
+
+        for port_num in [1, 255, 512] {
+            assert!(matches!(
+                MeshPacketBuilder::new_private(port_num, vec![].into()),
+                Err(Error::InvalidPrivatePortNum { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn builds_encrypted_packets() {
+        let channel_settings = protobufs::ChannelSettings {
+            name: "LongFast".to_string(),
+            ..protobufs::ChannelSettings::default_primary()
+        };
+
+        let mesh_packet = MeshPacketBuilder::new(
+            protobufs::PortNum::TextMessageApp,
+            "Hello mesh".as_bytes().to_vec().into(),
+        )
+        .destination(PacketDestination::Node(NodeId::new(2)))
+        .build_encrypted(NodeId::new(1), &channel_settings)
+        .unwrap();
+
+        assert_eq!(mesh_packet.to, 2);
+        assert_eq!(mesh_packet.channel, 8);
+        assert!(matches!(
+            mesh_packet.payload_variant,
+            Some(protobufs::mesh_packet::PayloadVariant::Encrypted(_))
+        ));
+
+        let data = crate::crypto_internal::decrypt_mesh_packet(&mesh_packet, &channel_settings.psk)
+            .unwrap();
+        assert_eq!(data.portnum(), protobufs::PortNum::TextMessageApp);
+        assert_eq!(data.payload, b"Hello mesh");
+    }
+}
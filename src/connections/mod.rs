@@ -1,15 +1,40 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use crate::errors_internal::Error;
 use crate::protobufs;
 
 use self::wrappers::NodeId;
 
+pub mod ack_tracker;
+pub mod admin_session;
 #[cfg(feature = "bluetooth-le")]
 pub mod ble_handler;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod codec;
+pub mod config_sync;
+pub mod decoded_payload;
+pub mod device_snapshot;
+pub mod diagnostics;
+pub mod edit_transaction;
+pub mod events;
 pub mod handlers;
+pub mod health;
+pub mod mesh_packet_builder;
+pub mod packet_sender;
+pub mod packet_streams;
+pub mod radio_state;
+pub mod rate_limiter;
+pub mod readonly;
+pub mod reconnecting;
+pub mod reliable_sender;
+pub mod request_registry;
 pub mod stream_api;
 pub mod stream_buffer;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod wrappers;
+pub mod write_queue;
 
 /// An enum that defines the possible destinations for a mesh packet.
 /// This enum is used to specify the destination of a packet when sending
@@ -25,7 +50,7 @@ pub mod wrappers;
 /// # Default
 ///
 /// The default value for this enum is `Broadcast`.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum PacketDestination {
     Local,
     #[default]
@@ -33,6 +58,36 @@ pub enum PacketDestination {
     Node(NodeId),
 }
 
+impl FromStr for PacketDestination {
+    type Err = Error;
+
+    /// Parses a destination as specified by users of other Meshtastic tooling, e.g. on the command line.
+    ///
+    /// The aliases `^local` and `^all` parse to `Local` and `Broadcast` respectively. A node can be specified
+    /// either by its user id, i.e. its hexadecimal node number prefixed with `!` (e.g. `!a1b2c3d4`), or by
+    /// its decimal node number.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidDestination` if the string is neither an alias nor a valid node id.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let node_num = match s {
+            "^local" => return Ok(PacketDestination::Local),
+            "^all" => return Ok(PacketDestination::Broadcast),
+            _ => match s.strip_prefix('!') {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => s.parse(),
+            },
+        };
+
+        node_num
+            .map(|node_num| PacketDestination::Node(node_num.into()))
+            .map_err(|_| Error::InvalidDestination {
+                destination: s.to_string(),
+            })
+    }
+}
+
 /// This trait defines the behavior of a struct that is able to route mesh packets.
 /// More generally, this trait defines the behavior of a struct that is able to send
 /// and receive mesh packets.
@@ -158,3 +213,28 @@ pub trait PacketRouter<M: Sized, E: Display + std::error::Error + 'static> {
     ///
     fn source_node_id(&self) -> NodeId;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_destination_aliases_and_node_ids() {
+        let parse = |s: &str| s.parse::<PacketDestination>();
+
+        assert_eq!(parse("^local").unwrap(), PacketDestination::Local);
+        assert_eq!(parse("^all").unwrap(), PacketDestination::Broadcast);
+        assert_eq!(
+            parse("!a1b2c3d4").unwrap(),
+            PacketDestination::Node(0xa1b2_c3d4.into())
+        );
+        assert_eq!(parse("1234").unwrap(), PacketDestination::Node(1234.into()));
+
+        for invalid in ["^everyone", "!xyz", "", "!"] {
+            assert!(matches!(
+                parse(invalid),
+                Err(Error::InvalidDestination { .. })
+            ));
+        }
+    }
+}
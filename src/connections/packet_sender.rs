@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use log::debug;
+use prost::Message;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::errors_internal::{Error, InternalChannelError};
+use crate::protobufs;
+use crate::types::EncodedToRadioPacketWithHeader;
+use crate::utils;
+
+use super::{
+    radio_state::{QueueFullBehavior, RadioState},
+    rate_limiter::RateLimiter,
+    wrappers::encoded_data::EncodedToRadioPacket,
+    write_queue::{effective_priority, WriteQueue},
+};
+
+/// The state needed to queue packets to be written to the radio, which is shared by the
+/// `ConnectedStreamApi` struct and the `ReliableSender` handles created from it. Cloning a
+/// `PacketSender` returns a handle to the same write queue, rate limiter and settings.
+#[derive(Clone, Debug)]
+pub(crate) struct PacketSender {
+    write_input_tx: UnboundedSender<EncodedToRadioPacketWithHeader>,
+    write_queue: Arc<WriteQueue>,
+    radio_state: Arc<RadioState>,
+    settings: Arc<Mutex<SendSettings>>,
+}
+
+#[derive(Debug, Default)]
+struct SendSettings {
+    queue_full_behavior: QueueFullBehavior,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl PacketSender {
+    pub(crate) fn new(
+        write_input_tx: UnboundedSender<EncodedToRadioPacketWithHeader>,
+        write_queue: Arc<WriteQueue>,
+        radio_state: Arc<RadioState>,
+    ) -> Self {
+        Self {
+            write_input_tx,
+            write_queue,
+            radio_state,
+            settings: Arc::default(),
+        }
+    }
+
+    /// Encodes and queues a `ToRadio` packet. Mesh packets are subject to the rate limit and the
+    /// `QueueFullBehavior`, and are queued with their effective priority. See the
+    /// `send_to_radio_packet` method of the `ConnectedStreamApi` struct for details.
+    pub(crate) async fn send_to_radio_packet(
+        &self,
+        payload_variant: Option<protobufs::to_radio::PayloadVariant>,
+    ) -> Result<(), Error> {
+        let mesh_packet_priority = match payload_variant.as_ref() {
+            Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)) => {
+                Some(effective_priority(mesh_packet))
+            }
+            _ => None,
+        };
+
+        if let Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)) =
+            payload_variant.as_ref()
+        {
+            // The channel field of packets with an encrypted payload contains the channel hash, not an index
+            let is_decoded = matches!(
+                mesh_packet.payload_variant,
+                Some(protobufs::mesh_packet::PayloadVariant::Decoded(_))
+            );
+
+            if is_decoded && self.radio_state.is_channel_disabled(mesh_packet.channel) {
+                return Err(Error::ChannelDisabled {
+                    channel: mesh_packet.channel,
+                });
+            }
+        }
+
+        if mesh_packet_priority.is_some() {
+            self.acquire_rate_limit().await;
+            self.check_radio_queue().await?;
+        }
+
+        let packet = protobufs::ToRadio { payload_variant };
+
+        let mut packet_buf = vec![];
+        packet.encode(&mut packet_buf)?;
+        self.enqueue_raw(packet_buf.into(), mesh_packet_priority)?;
+
+        if mesh_packet_priority.is_some() {
+            self.radio_state.reserve_queue_slot();
+        }
+
+        Ok(())
+    }
+
+    /// Waits until the rate limiter, if any, allows another mesh packet to be sent.
+    async fn acquire_rate_limit(&self) {
+        loop {
+            // The lock is not held while waiting, so the limit can be changed by another handle
+            let wait = match self.lock().rate_limiter.as_mut() {
+                Some(rate_limiter) => rate_limiter.try_acquire_at(tokio::time::Instant::now()),
+                None => Ok(()),
+            };
+
+            match wait {
+                Ok(()) => return,
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Applies the configured `QueueFullBehavior` based on the last queue status reported by the radio.
+    async fn check_radio_queue(&self) -> Result<(), Error> {
+        if self.radio_state.has_free_queue_slot() {
+            return Ok(());
+        }
+
+        let queue_full_behavior = self.lock().queue_full_behavior;
+
+        match queue_full_behavior {
+            QueueFullBehavior::SendAnyway => Ok(()),
+            QueueFullBehavior::Await => {
+                debug!("Radio queue is full, waiting for a free slot");
+                self.radio_state.wait_for_free_queue_slot().await;
+                Ok(())
+            }
+            QueueFullBehavior::Error => Err(Error::QueueFull {
+                maxlen: self
+                    .radio_state
+                    .queue_status()
+                    .map_or(0, |status| status.maxlen),
+            }),
+        }
+    }
+
+    /// Adds the packet header to an encoded `ToRadio` packet, and queues it with the specified priority.
+    /// Packets without a priority are written in the order they were queued, see `enqueue_framed`.
+    pub(crate) fn enqueue_raw(
+        &self,
+        data: EncodedToRadioPacket,
+        priority: Option<protobufs::mesh_packet::Priority>,
+    ) -> Result<(), Error> {
+        let data_with_header = utils::format_data_packet(data)?;
+        self.enqueue_framed(data_with_header, priority)
+    }
+
+    /// Queues packet data that already carries the packet header with the specified priority.
+    ///
+    /// Packets without a priority, i.e. control packets and raw packets, are never reordered with
+    /// respect to other packets, so e.g. a `CommitEditSettings` packet cannot overtake the edits that
+    /// were queued before it.
+    pub(crate) fn enqueue_framed(
+        &self,
+        data_with_header: EncodedToRadioPacketWithHeader,
+        priority: Option<protobufs::mesh_packet::Priority>,
+    ) -> Result<(), Error> {
+        // The write handler owns the receiving half of this channel, so a closed channel
+        // indicates that queued packets will never be written.
+        if self.write_input_tx.is_closed() {
+            return Err(Error::InternalChannelError(
+                InternalChannelError::ChannelClosedEarly,
+            ));
+        }
+
+        match priority {
+            Some(priority) => self.write_queue.push(data_with_header, priority),
+            None => self.write_queue.push_in_order(data_with_header),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn write_queue_depth(&self) -> usize {
+        self.write_queue.len()
+    }
+
+    pub(crate) fn write_input_sender(&self) -> UnboundedSender<EncodedToRadioPacketWithHeader> {
+        self.write_input_tx.clone()
+    }
+
+    pub(crate) fn set_queue_full_behavior(&self, queue_full_behavior: QueueFullBehavior) {
+        self.lock().queue_full_behavior = queue_full_behavior;
+    }
+
+    pub(crate) fn set_rate_limit(&self, packets_per_minute: Option<u32>) {
+        self.lock().rate_limiter = packets_per_minute.map(RateLimiter::new);
+    }
+
+    pub(crate) fn rate_limit(&self) -> Option<u32> {
+        self.lock()
+            .rate_limiter
+            .as_ref()
+            .map(RateLimiter::packets_per_minute)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SendSettings> {
+        // The settings are never left in an inconsistent state, so a poisoned lock can be recovered
+        self.settings.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,1098 @@
+use std::time::SystemTime;
+
+use futures_util::{stream, Stream};
+use log::warn;
+use prost::Message;
+use tokio::sync::broadcast;
+use tokio_util::bytes::Bytes;
+
+use crate::protobufs;
+
+use crate::utils_internal::current_epoch_secs_u32;
+
+use super::{
+    diagnostics::{DropDiagnostics, DropReason},
+    events::{
+        ChannelEvent, DecodePolicy, DetectionEvent, MeshEvent, ReceivedChannel, TextMessage,
+        WaypointEvent,
+    },
+    stream_api::ConnectedStreamApi,
+    stream_buffer::ReceivedPacket,
+    wrappers::NodeId,
+};
+
+/// A subscription to the packets received from the radio, along with the diagnostics that the
+/// packets skipped by the streams created from it are reported to.
+#[derive(Debug)]
+pub(crate) struct PacketSubscription {
+    packet_rx: broadcast::Receiver<ReceivedPacket>,
+    drop_diagnostics: DropDiagnostics,
+}
+
+impl PacketSubscription {
+    pub(crate) fn new(
+        packet_rx: broadcast::Receiver<ReceivedPacket>,
+        drop_diagnostics: DropDiagnostics,
+    ) -> Self {
+        Self {
+            packet_rx,
+            drop_diagnostics,
+        }
+    }
+}
+
+/// Decodes the payload of a decoded `MeshPacket` as the specified protobuf message type,
+/// if the packet was sent on the specified port. Payloads that fail to decode are reported
+/// to the passed diagnostics.
+pub(crate) fn decode_mesh_packet_payload<T: Message + Default>(
+    mesh_packet: &protobufs::MeshPacket,
+    port_num: protobufs::PortNum,
+    drop_diagnostics: &DropDiagnostics,
+) -> Option<T> {
+    let data = match mesh_packet.payload_variant.as_ref()? {
+        protobufs::mesh_packet::PayloadVariant::Decoded(data) => data,
+        _ => return None,
+    };
+
+    if data.portnum != port_num as i32 {
+        return None;
+    }
+
+    match T::decode(data.payload.as_slice()) {
+        Ok(message) => Some(message),
+        Err(e) => {
+            warn!(
+                "Failed to decode {} payload of packet {}: {}",
+                port_num.as_str_name(),
+                mesh_packet.id,
+                e
+            );
+            drop_diagnostics.report(DropReason::PayloadDecodeFailure, &data.payload);
+            None
+        }
+    }
+}
+
+/// Converts a decoded `FromRadio` packet into a `MeshEvent` as specified by the passed `DecodePolicy`,
+/// reporting packets that are skipped by the policy to the passed diagnostics.
+fn decode_event(
+    packet: protobufs::FromRadio,
+    raw: &[u8],
+    policy: DecodePolicy,
+    drop_diagnostics: &DropDiagnostics,
+) -> Option<MeshEvent> {
+    let event = MeshEvent::from_packet_with_policy(packet, policy);
+
+    if event.is_none() {
+        drop_diagnostics.report(DropReason::SkippedByDecodePolicy, raw);
+    }
+
+    event
+}
+
+/// Converts a subscription to received packets into a stream of the items returned by the passed
+/// `filter_map` function, which is also passed the diagnostics of the subscription. Packets for which
+/// the function returns `None` are skipped. The stream ends once the connection to the radio is closed.
+pub(crate) fn filter_map_received_packets<T, F>(
+    subscription: PacketSubscription,
+    filter_map: F,
+) -> impl Stream<Item = T> + Send + 'static
+where
+    T: Send + 'static,
+    F: FnMut(ReceivedPacket, &DropDiagnostics) -> Option<T> + Send + 'static,
+{
+    stream::unfold(
+        (subscription, filter_map),
+        |(mut subscription, mut filter_map)| async move {
+            loop {
+                match subscription.packet_rx.recv().await {
+                    Ok(packet) => {
+                        if let Some(item) = filter_map(packet, &subscription.drop_diagnostics) {
+                            return Some((item, (subscription, filter_map)));
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Packet stream lagged behind, skipped {skipped} packets");
+
+                        // The skipped packets are no longer available, so no bytes are reported
+                        subscription.drop_diagnostics.report(
+                            DropReason::QueueFull {
+                                skipped_packets: skipped,
+                            },
+                            &[],
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        },
+    )
+}
+
+/// Converts a subscription to received packets into a stream of the items returned by the passed
+/// `filter_map` function, which is called with the decoded `FromRadio` packets.
+pub(crate) fn filter_map_packets<T, F>(
+    subscription: PacketSubscription,
+    mut filter_map: F,
+) -> impl Stream<Item = T> + Send + 'static
+where
+    T: Send + 'static,
+    F: FnMut(protobufs::FromRadio, &DropDiagnostics) -> Option<T> + Send + 'static,
+{
+    filter_map_received_packets(subscription, move |received_packet, drop_diagnostics| {
+        filter_map(received_packet.packet, drop_diagnostics)
+    })
+}
+
+/// Converts a subscription to decoded `FromRadio` packets into a stream of the items returned
+/// by the passed `filter_map` function, which is only called for `MeshPacket` packets.
+pub(crate) fn filter_map_mesh_packets<T, F>(
+    subscription: PacketSubscription,
+    mut filter_map: F,
+) -> impl Stream<Item = T> + Send + 'static
+where
+    T: Send + 'static,
+    F: FnMut(protobufs::MeshPacket, &DropDiagnostics) -> Option<T> + Send + 'static,
+{
+    filter_map_packets(subscription, move |packet, drop_diagnostics| {
+        match packet.payload_variant? {
+            protobufs::from_radio::PayloadVariant::Packet(mesh_packet) => {
+                filter_map(mesh_packet, drop_diagnostics)
+            }
+            _ => None,
+        }
+    })
+}
+
+// Typed packet streams
+
+impl<State> ConnectedStreamApi<State> {
+    /// Returns a stream of position updates received from the mesh, paired with the id of the
+    /// node that sent them. The stream decodes the payload of all `PositionApp` packets received
+    /// after this method is called. Packets that fail to decode are logged and skipped.
+    ///
+    /// This stream is independent of the decoded packet channel returned by the `connect` method,
+    /// which continues to receive all packets. Multiple streams can be created at the same time.
+    /// The stream ends once the connection to the radio is closed.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, Position)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut positions = Box::pin(stream_api.position_stream());
+    ///
+    /// while let Some((node_id, position)) = positions.next().await {
+    ///     println!("Node {} is at {}, {}", node_id, position.latitude_i, position.longitude_i);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn position_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::Position)> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| {
+                let position = decode_mesh_packet_payload::<protobufs::Position>(
+                    &mesh_packet,
+                    protobufs::PortNum::PositionApp,
+                    drop_diagnostics,
+                )?;
+
+                Some((mesh_packet.from.into(), position))
+            },
+        )
+    }
+
+    /// Returns a stream of the crowd counts reported by paxcounter nodes on the mesh, paired with the id
+    /// of the node that sent them. The stream decodes the payload of all `PaxcounterApp` packets received
+    /// after this method is called. Packets that fail to decode are logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, Paxcount)` tuples, containing the number of WiFi and BLE devices seen
+    /// by the node and the uptime of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut paxcounts = Box::pin(stream_api.paxcount_stream());
+    ///
+    /// while let Some((node_id, paxcount)) = paxcounts.next().await {
+    ///     println!("Node {} sees {} WiFi and {} BLE devices", node_id, paxcount.wifi, paxcount.ble);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn paxcount_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::Paxcount)> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| {
+                let paxcount = decode_mesh_packet_payload::<protobufs::Paxcount>(
+                    &mesh_packet,
+                    protobufs::PortNum::PaxcounterApp,
+                    drop_diagnostics,
+                )?;
+
+                Some((mesh_packet.from.into(), paxcount))
+            },
+        )
+    }
+
+    /// Returns a stream of the map reports received from the mesh, paired with the id of the node
+    /// that sent them. The stream decodes the payload of all `MapReportApp` packets received after
+    /// this method is called. Packets that fail to decode are logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, MapReport)` tuples. The `MapReport::position` method returns the
+    /// reported position as a `Position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut map_reports = Box::pin(stream_api.map_report_stream());
+    ///
+    /// while let Some((node_id, map_report)) = map_reports.next().await {
+    ///     println!("Node {} ({}) runs firmware {}", node_id, map_report.long_name, map_report.firmware_version);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn map_report_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::MapReport)> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| {
+                let map_report = decode_mesh_packet_payload::<protobufs::MapReport>(
+                    &mesh_packet,
+                    protobufs::PortNum::MapReportApp,
+                    drop_diagnostics,
+                )?;
+
+                Some((mesh_packet.from.into(), map_report))
+            },
+        )
+    }
+
+    /// Returns a stream of telemetry received from the mesh, paired with the id of the node
+    /// that sent it. The stream decodes the payload of all `TelemetryApp` packets received after
+    /// this method is called. Packets that fail to decode are logged and skipped.
+    ///
+    /// See the `device_metrics_stream` and `environment_metrics_stream` methods for streams
+    /// that only contain a single telemetry variant.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, Telemetry)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut telemetry = Box::pin(stream_api.telemetry_stream());
+    ///
+    /// while let Some((node_id, telemetry)) = telemetry.next().await {
+    ///     println!("Node {} sent telemetry {:?}", node_id, telemetry.variant);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn telemetry_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::Telemetry)> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| {
+                let telemetry = decode_mesh_packet_payload::<protobufs::Telemetry>(
+                    &mesh_packet,
+                    protobufs::PortNum::TelemetryApp,
+                    drop_diagnostics,
+                )?;
+
+                Some((mesh_packet.from.into(), telemetry))
+            },
+        )
+    }
+
+    /// Returns a stream of device metrics (e.g. battery level and channel utilization) received
+    /// from the mesh, paired with the id of the node that sent them. This stream only contains
+    /// the `DeviceMetrics` variant of the telemetry received by the `telemetry_stream` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, DeviceMetrics)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut device_metrics = Box::pin(stream_api.device_metrics_stream());
+    ///
+    /// while let Some((node_id, metrics)) = device_metrics.next().await {
+    ///     println!("Node {} battery level: {}", node_id, metrics.battery_level);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn device_metrics_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::DeviceMetrics)> + Send + 'static {
+        self.telemetry_variant_stream(|variant| match variant {
+            protobufs::telemetry::Variant::DeviceMetrics(metrics) => Some(metrics),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of environment metrics (e.g. temperature and humidity) received from
+    /// the mesh, paired with the id of the node that sent them. This stream only contains the
+    /// `EnvironmentMetrics` variant of the telemetry received by the `telemetry_stream` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, EnvironmentMetrics)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut environment_metrics = Box::pin(stream_api.environment_metrics_stream());
+    ///
+    /// while let Some((node_id, metrics)) = environment_metrics.next().await {
+    ///     println!("Node {} temperature: {}", node_id, metrics.temperature);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn environment_metrics_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, protobufs::EnvironmentMetrics)> + Send + 'static {
+        self.telemetry_variant_stream(|variant| match variant {
+            protobufs::telemetry::Variant::EnvironmentMetrics(metrics) => Some(metrics),
+            _ => None,
+        })
+    }
+
+    /// Returns a stream of text messages received from the mesh. The stream contains all
+    /// `TextMessageApp` packets received after this method is called, including both broadcast
+    /// and direct messages. Compressed `TextMessageCompressedApp` packets are decompressed and
+    /// included in the stream, so that consumers do not need to handle both ports.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `TextMessage` structs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut text_messages = Box::pin(stream_api.text_message_stream());
+    ///
+    /// while let Some(message) = text_messages.next().await {
+    ///     if message.is_dm {
+    ///         println!("DM from {}: {}", message.from, message.text);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn text_message_stream(&self) -> impl Stream<Item = TextMessage> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| match TextMessage::decode_mesh_packet(&mesh_packet) {
+                Ok(text_message) => text_message,
+                Err(e) => {
+                    warn!("Failed to decompress text message {}: {e}", mesh_packet.id);
+                    if let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                        &mesh_packet.payload_variant
+                    {
+                        drop_diagnostics.report(DropReason::PayloadDecodeFailure, &data.payload);
+                    }
+                    None
+                }
+            },
+        )
+    }
+
+    /// Returns a stream of waypoint changes received from the mesh. The stream decodes the payload
+    /// of all `WaypointApp` packets received after this method is called, and classifies each
+    /// waypoint as either a new or updated waypoint, or a deleted waypoint. See the `WaypointEvent`
+    /// enum for details on how deletions are detected.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `WaypointEvent` enums.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut waypoints = Box::pin(stream_api.waypoint_stream());
+    ///
+    /// while let Some(event) = waypoints.next().await {
+    ///     match event {
+    ///         WaypointEvent::Updated { waypoint, .. } => println!("Waypoint {} updated", waypoint.id),
+    ///         WaypointEvent::Removed { waypoint_id, .. } => println!("Waypoint {} removed", waypoint_id),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn waypoint_stream(&self) -> impl Stream<Item = WaypointEvent> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            |mesh_packet, drop_diagnostics| {
+                let waypoint = decode_mesh_packet_payload::<protobufs::Waypoint>(
+                    &mesh_packet,
+                    protobufs::PortNum::WaypointApp,
+                    drop_diagnostics,
+                )?;
+
+                Some(WaypointEvent::from_waypoint(
+                    mesh_packet.from.into(),
+                    waypoint,
+                    current_epoch_secs_u32(),
+                ))
+            },
+        )
+    }
+
+    /// Returns a stream of the alerts sent by the detection sensor modules of nodes on the mesh,
+    /// e.g. when a motion or door sensor is triggered. See the `DetectionEvent` struct for details
+    /// on how alerts are parsed.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(NodeId, DetectionEvent)` tuples, containing the sending node and the parsed alert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut detections = Box::pin(stream_api.detection_stream());
+    ///
+    /// while let Some((from, event)) = detections.next().await {
+    ///     if event.triggered {
+    ///         println!("{} triggered on node {}", event.name, from);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn detection_stream(
+        &self,
+    ) -> impl Stream<Item = (NodeId, DetectionEvent)> + Send + 'static {
+        filter_map_mesh_packets(self.packet_subscription(), |mesh_packet, _| {
+            let event = DetectionEvent::from_mesh_packet(&mesh_packet)?;
+            Some((NodeId::from(mesh_packet.from), event))
+        })
+    }
+
+    /// Returns a stream of high-level events received from the radio. Every packet received after
+    /// this method is called is converted into a `MeshEvent`, with recognized payloads (e.g. text
+    /// messages, positions, and telemetry) decoded into typed variants. Packets that do not map to
+    /// a typed variant are passed through as `MeshEvent::Raw`, so no packets are lost. This includes
+    /// packets that fail to decode; use the `events_with_policy` method to skip these packets instead.
+    ///
+    /// This is the simplest way to consume decoded data from the radio, as an alternative to the
+    /// individual typed streams and the decoded packet channel returned by the `connect` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `MeshEvent` enums.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut events = Box::pin(stream_api.events());
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     match event {
+    ///         MeshEvent::Text(message) => println!("{}: {}", message.from, message.text),
+    ///         MeshEvent::Position { from, position } => println!("{} moved to {:?}", from, position),
+    ///         _ => (),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn events(&self) -> impl Stream<Item = MeshEvent> + Send + 'static {
+        self.events_with_policy(DecodePolicy::default())
+    }
+
+    /// Returns a stream of high-level events received from the radio, as with the `events` method,
+    /// handling packets that fail to decode as specified by the passed `DecodePolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Whether packets that fail to decode are passed through as `MeshEvent::Raw`,
+    ///     or logged and skipped.
+    ///
+    /// # Returns
+    ///
+    /// A stream of `MeshEvent` enums.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut events = Box::pin(stream_api.events_with_policy(DecodePolicy::Skip));
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn events_with_policy(
+        &self,
+        policy: DecodePolicy,
+    ) -> impl Stream<Item = MeshEvent> + Send + 'static {
+        filter_map_received_packets(
+            self.packet_subscription(),
+            move |received_packet, drop_diagnostics| {
+                decode_event(
+                    received_packet.packet,
+                    &received_packet.raw,
+                    policy,
+                    drop_diagnostics,
+                )
+            },
+        )
+    }
+
+    /// Returns a stream of high-level events received from the radio, as with the `events_with_policy`
+    /// method, where each event is paired with the raw bytes of the `FromRadio` packet it was decoded from.
+    ///
+    /// The raw bytes are exactly as received from the radio, excluding the 4-byte packet header. This
+    /// allows auditing and logging tools to store received packets for later re-decoding, e.g. once
+    /// the library is updated to support fields added by newer firmware.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Whether packets that fail to decode are passed through as `MeshEvent::Raw`,
+    ///     or logged and skipped.
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(MeshEvent, Bytes)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut events = Box::pin(stream_api.events_with_raw(DecodePolicy::Raw));
+    ///
+    /// while let Some((event, raw)) = events.next().await {
+    ///     audit_log.write_all(&raw)?;
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn events_with_raw(
+        &self,
+        policy: DecodePolicy,
+    ) -> impl Stream<Item = (MeshEvent, Bytes)> + Send + 'static {
+        filter_map_received_packets(
+            self.packet_subscription(),
+            move |received_packet, drop_diagnostics| {
+                let event = decode_event(
+                    received_packet.packet,
+                    &received_packet.raw,
+                    policy,
+                    drop_diagnostics,
+                )?;
+                Some((event, received_packet.raw))
+            },
+        )
+    }
+
+    /// Returns a stream of high-level events received from the radio, as with the `events_with_policy`
+    /// method, where each event is wrapped in a `ChannelEvent` along with the channel the packet it was
+    /// decoded from was received on.
+    ///
+    /// The channel is resolved against the channel table of the radio when the packet is received, so that
+    /// multi-channel applications can route messages to the right conversation. Packets on channels that
+    /// are not within the channel table are attributed to `ReceivedChannel::Unknown`, rather than to the
+    /// primary channel. Events that were not decoded from a mesh packet, e.g. log records, have no channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Whether packets that fail to decode are passed through as `MeshEvent::Raw`,
+    ///     or logged and skipped.
+    ///
+    /// # Returns
+    ///
+    /// A stream of `ChannelEvent` structs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut events = Box::pin(stream_api.events_with_channels(DecodePolicy::Raw));
+    ///
+    /// while let Some(ChannelEvent { event, channel }) = events.next().await {
+    ///     if let (MeshEvent::Text(message), Some(ReceivedChannel::Known(channel))) = (event, channel) {
+    ///         conversations[channel.channel() as usize].push(message);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn events_with_channels(
+        &self,
+        policy: DecodePolicy,
+    ) -> impl Stream<Item = ChannelEvent> + Send + 'static {
+        let radio_state = self.radio_state();
+
+        filter_map_received_packets(
+            self.packet_subscription(),
+            move |received_packet, drop_diagnostics| {
+                let channel = match &received_packet.packet.payload_variant {
+                    Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) => {
+                        Some(ReceivedChannel::resolve_with(mesh_packet, |index| {
+                            radio_state.is_channel_active(index)
+                        }))
+                    }
+                    _ => None,
+                };
+
+                let event = decode_event(
+                    received_packet.packet,
+                    &received_packet.raw,
+                    policy,
+                    drop_diagnostics,
+                )?;
+                Some(ChannelEvent { event, channel })
+            },
+        )
+    }
+
+    /// Returns a stream of all packets received from the radio, where each packet is paired with the
+    /// host time at which the library received it.
+    ///
+    /// The `rx_time` reported by the radio for mesh packets may be zero or inaccurate, e.g. if the radio
+    /// has no GPS fix or time source. The host time allows received packets to be accurately correlated
+    /// with other local events, e.g. within data loggers.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of `(FromRadio, SystemTime)` tuples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut packets = Box::pin(stream_api.packets_with_timestamps());
+    ///
+    /// while let Some((packet, received_at)) = packets.next().await {
+    ///     println!("{:?}: {:?}", received_at, packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn packets_with_timestamps(
+        &self,
+    ) -> impl Stream<Item = (protobufs::FromRadio, SystemTime)> + Send + 'static {
+        filter_map_received_packets(self.packet_subscription(), |received_packet, _| {
+            Some((received_packet.packet, received_packet.received_at))
+        })
+    }
+
+    /// Returns a stream of high-level events received from the radio, as with the `events_with_policy`
+    /// method, where each event is paired with the host time at which the library received the packet
+    /// the event was decoded from. See the `packets_with_timestamps` method for details.
+    pub fn events_with_timestamps(
+        &self,
+        policy: DecodePolicy,
+    ) -> impl Stream<Item = (MeshEvent, SystemTime)> + Send + 'static {
+        filter_map_received_packets(
+            self.packet_subscription(),
+            move |received_packet, drop_diagnostics| {
+                let event = decode_event(
+                    received_packet.packet,
+                    &received_packet.raw,
+                    policy,
+                    drop_diagnostics,
+                )?;
+                Some((event, received_packet.received_at))
+            },
+        )
+    }
+
+    /// Returns a stream of the packets transmitted by a simulated node, e.g. one started by the
+    /// Meshtasticator simulator. Simulated nodes report the packets they transmit as `SimulatorApp`
+    /// packets, which this stream unwraps using the `MeshPacket::from_simulator_packet` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A stream of the `MeshPacket` structs transmitted by the simulated node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut transmitted_packets = Box::pin(node_a_api.simulator_packet_stream());
+    ///
+    /// while let Some(mesh_packet) = transmitted_packets.next().await {
+    ///     node_b_api.send_simulated_packet(mesh_packet).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn simulator_packet_stream(
+        &self,
+    ) -> impl Stream<Item = protobufs::MeshPacket> + Send + 'static {
+        filter_map_mesh_packets(self.packet_subscription(), |mesh_packet, _| {
+            mesh_packet.from_simulator_packet()
+        })
+    }
+
+    fn telemetry_variant_stream<T: Send + 'static>(
+        &self,
+        select_variant: fn(protobufs::telemetry::Variant) -> Option<T>,
+    ) -> impl Stream<Item = (NodeId, T)> + Send + 'static {
+        filter_map_mesh_packets(
+            self.packet_subscription(),
+            move |mesh_packet, drop_diagnostics| {
+                let telemetry = decode_mesh_packet_payload::<protobufs::Telemetry>(
+                    &mesh_packet,
+                    protobufs::PortNum::TelemetryApp,
+                    drop_diagnostics,
+                )?;
+
+                Some((mesh_packet.from.into(), select_variant(telemetry.variant?)?))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use crate::connections::diagnostics::DroppedPacket;
+
+    use super::*;
+
+    fn mesh_packet(from: u32, port_num: protobufs::PortNum, payload: Vec<u8>) -> ReceivedPacket {
+        let packet = protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Packet(
+                protobufs::MeshPacket {
+                    from,
+                    payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                        protobufs::Data {
+                            portnum: port_num as i32,
+                            payload,
+                            ..Default::default()
+                        },
+                    )),
+                    ..Default::default()
+                },
+            )),
+        };
+
+        ReceivedPacket {
+            raw: packet.encode_to_vec().into(),
+            packet,
+            received_at: std::time::SystemTime::now(),
+        }
+    }
+
+    fn subscription(
+        packet_rx: broadcast::Receiver<ReceivedPacket>,
+    ) -> (PacketSubscription, broadcast::Receiver<DroppedPacket>) {
+        let drop_diagnostics = DropDiagnostics::enabled();
+        let dropped_packets = drop_diagnostics.subscribe().unwrap();
+
+        (
+            PacketSubscription::new(packet_rx, drop_diagnostics),
+            dropped_packets,
+        )
+    }
+
+    #[tokio::test]
+    async fn decodes_matching_packets_only() {
+        let (packet_tx, packet_rx) = broadcast::channel(16);
+        let (subscription, mut dropped_packets) = subscription(packet_rx);
+
+        let positions = filter_map_mesh_packets(subscription, |mesh_packet, drop_diagnostics| {
+            let position = decode_mesh_packet_payload::<protobufs::Position>(
+                &mesh_packet,
+                protobufs::PortNum::PositionApp,
+                drop_diagnostics,
+            )?;
+
+            Some((NodeId::from(mesh_packet.from), position))
+        });
+
+        let position = protobufs::Position {
+            latitude_i: 1,
+            longitude_i: 2,
+            ..Default::default()
+        };
+
+        packet_tx
+            .send(mesh_packet(
+                1,
+                protobufs::PortNum::TextMessageApp,
+                b"hi".to_vec(),
+            ))
+            .unwrap();
+        packet_tx
+            .send(mesh_packet(2, protobufs::PortNum::PositionApp, vec![0xff]))
+            .unwrap();
+        packet_tx
+            .send(mesh_packet(
+                3,
+                protobufs::PortNum::PositionApp,
+                position.encode_to_vec(),
+            ))
+            .unwrap();
+        drop(packet_tx);
+
+        let positions = positions.collect::<Vec<_>>().await;
+        assert_eq!(positions, vec![(NodeId::from(3), position)]);
+
+        // Only the position that failed to decode is reported, not the text message
+        let dropped_packet = dropped_packets.try_recv().unwrap();
+        assert_eq!(dropped_packet.reason, DropReason::PayloadDecodeFailure);
+        assert_eq!(dropped_packet.raw, vec![0xff]);
+        assert!(dropped_packets.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_packets_skipped_by_decode_policy() {
+        let (packet_tx, packet_rx) = broadcast::channel(16);
+        let (subscription, mut dropped_packets) = subscription(packet_rx);
+
+        let events =
+            filter_map_received_packets(subscription, |received_packet, drop_diagnostics| {
+                decode_event(
+                    received_packet.packet,
+                    &received_packet.raw,
+                    DecodePolicy::Skip,
+                    drop_diagnostics,
+                )
+            });
+
+        let malformed = mesh_packet(1, protobufs::PortNum::PositionApp, vec![0xff]);
+        packet_tx.send(malformed.clone()).unwrap();
+        drop(packet_tx);
+
+        assert_eq!(events.collect::<Vec<_>>().await, vec![]);
+
+        let dropped_packet = dropped_packets.try_recv().unwrap();
+        assert_eq!(dropped_packet.reason, DropReason::SkippedByDecodePolicy);
+        assert_eq!(dropped_packet.raw, malformed.raw);
+    }
+
+    #[tokio::test]
+    async fn reports_packets_skipped_by_lagging_streams() {
+        let (packet_tx, packet_rx) = broadcast::channel(1);
+        let (subscription, mut dropped_packets) = subscription(packet_rx);
+
+        let packets = filter_map_packets(subscription, |packet, _| Some(packet));
+
+        // The stream is not polled until all packets are sent, so only the last one is kept
+        for from in 1..=3 {
+            packet_tx
+                .send(mesh_packet(
+                    from,
+                    protobufs::PortNum::TextMessageApp,
+                    vec![],
+                ))
+                .unwrap();
+        }
+        drop(packet_tx);
+
+        assert_eq!(packets.collect::<Vec<_>>().await.len(), 1);
+
+        let dropped_packet = dropped_packets.try_recv().unwrap();
+        assert_eq!(
+            dropped_packet.reason,
+            DropReason::QueueFull { skipped_packets: 2 }
+        );
+        assert!(dropped_packet.raw.is_empty());
+    }
+}
@@ -0,0 +1,788 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Instant,
+};
+
+use tokio::sync::watch;
+
+use crate::helpers_internal::firmware::DeviceCapabilities;
+use crate::protobufs;
+
+use super::{device_snapshot::DeviceSnapshot, wrappers::NodeId};
+
+/// The default number of log records retained by the `recent_logs` method of the `ConnectedStreamApi` struct.
+pub const DEFAULT_LOG_BUFFER_SIZE: usize = 100;
+
+/// An enum that defines how the "send" methods behave when the radio reports that its
+/// outgoing packet queue is full.
+///
+/// The radio reports the state of its outgoing queue via `QueueStatus` packets. If packets
+/// are sent faster than they can be transmitted over LoRa, the queue fills up and the radio
+/// silently drops further packets.
+///
+/// # Variants
+///
+/// * `SendAnyway` - Packets are sent to the radio regardless of the reported queue status.
+/// * `Await` - Sending a mesh packet waits until the radio reports a free queue slot.
+/// * `Error` - Sending a mesh packet fails with `Error::QueueFull` if the radio has no free queue slots.
+///
+/// # Default
+///
+/// The default value for this enum is `SendAnyway`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum QueueFullBehavior {
+    #[default]
+    SendAnyway,
+    Await,
+    Error,
+}
+
+/// An enum that represents the state of the most recent `configure` handshake with the radio.
+///
+/// # Variants
+///
+/// * `Pending` - A `WantConfigId` packet with the specified config id was sent, and the radio has not yet
+///     sent the matching `ConfigCompleteId` packet.
+/// * `Complete` - The radio has sent the `ConfigCompleteId` packet matching the specified config id.
+/// * `Cancelled` - The handshake with the specified config id was cancelled before it completed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigHandshake {
+    Pending(u32),
+    Complete(u32),
+    Cancelled(u32),
+}
+
+/// A struct that holds state reported by the connected radio, which is updated by the
+/// dispatch worker thread as packets are received from the radio.
+#[derive(Debug)]
+pub struct RadioState {
+    queue_status: watch::Sender<Option<protobufs::QueueStatus>>,
+    channels: Mutex<BTreeMap<i32, protobufs::Channel>>,
+    my_node_info: Mutex<Option<protobufs::MyNodeInfo>>,
+    nodes: Mutex<BTreeMap<u32, protobufs::NodeInfo>>,
+    refreshed_nodes: Mutex<Option<BTreeMap<u32, protobufs::NodeInfo>>>,
+    metadata: Mutex<Option<protobufs::DeviceMetadata>>,
+    device_time: Mutex<Option<(u32, Instant)>>,
+    local_config: Mutex<protobufs::LocalConfig>,
+    local_module_config: Mutex<protobufs::LocalModuleConfig>,
+    recent_logs: Mutex<VecDeque<protobufs::LogRecord>>,
+    log_capacity: usize,
+    config_handshake: watch::Sender<Option<ConfigHandshake>>,
+}
+
+impl Default for RadioState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RadioState {
+    /// Creates a new `RadioState` with no reported state, which retains `DEFAULT_LOG_BUFFER_SIZE` log records.
+    pub fn new() -> Self {
+        Self::with_log_capacity(DEFAULT_LOG_BUFFER_SIZE)
+    }
+
+    /// Creates a new `RadioState` with no reported state, which retains the specified number of log records.
+    pub fn with_log_capacity(log_capacity: usize) -> Self {
+        let (queue_status, _) = watch::channel(None);
+        let (config_handshake, _) = watch::channel(None);
+
+        Self {
+            queue_status,
+            channels: Mutex::new(BTreeMap::new()),
+            my_node_info: Mutex::new(None),
+            nodes: Mutex::new(BTreeMap::new()),
+            refreshed_nodes: Mutex::new(None),
+            metadata: Mutex::new(None),
+            device_time: Mutex::new(None),
+            local_config: Mutex::new(protobufs::LocalConfig::default()),
+            local_module_config: Mutex::new(protobufs::LocalModuleConfig::default()),
+            recent_logs: Mutex::new(VecDeque::with_capacity(log_capacity)),
+            log_capacity,
+            config_handshake,
+        }
+    }
+
+    /// Updates the radio state based on a `FromRadio` packet received from the radio.
+    ///
+    /// Configuration and channel packets update the cached configuration and channel table whenever
+    /// they are received, i.e. both during the `configure` handshake and afterwards, e.g. when the
+    /// configuration of the radio is changed remotely.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the packet changed the cached configuration, module configuration, or channel table.
+    pub fn handle_packet(&self, packet: &protobufs::FromRadio) -> bool {
+        match packet.payload_variant.as_ref() {
+            Some(protobufs::from_radio::PayloadVariant::QueueStatus(queue_status)) => {
+                self.queue_status.send_replace(Some(*queue_status));
+            }
+            Some(protobufs::from_radio::PayloadVariant::Channel(channel)) => {
+                return self.update_channel(channel.clone());
+            }
+            Some(protobufs::from_radio::PayloadVariant::Config(config)) => {
+                let mut local_config = lock(&self.local_config);
+                let previous = local_config.clone();
+                local_config.apply_config(config.clone());
+
+                return *local_config != previous;
+            }
+            Some(protobufs::from_radio::PayloadVariant::ModuleConfig(module_config)) => {
+                let mut local_module_config = lock(&self.local_module_config);
+                let previous = local_module_config.clone();
+                local_module_config.apply_module_config(module_config.clone());
+
+                return *local_module_config != previous;
+            }
+            Some(protobufs::from_radio::PayloadVariant::MyInfo(my_node_info)) => {
+                *lock(&self.my_node_info) = Some(*my_node_info);
+            }
+            Some(protobufs::from_radio::PayloadVariant::NodeInfo(node_info)) => {
+                lock(&self.nodes).insert(node_info.num, node_info.clone());
+
+                if let Some(refreshed_nodes) = lock(&self.refreshed_nodes).as_mut() {
+                    refreshed_nodes.insert(node_info.num, node_info.clone());
+                }
+            }
+            Some(protobufs::from_radio::PayloadVariant::Metadata(metadata)) => {
+                *lock(&self.metadata) = Some(metadata.clone());
+            }
+            Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) => {
+                self.record_device_time(mesh_packet.rx_time);
+            }
+            Some(protobufs::from_radio::PayloadVariant::LogRecord(log_record)) => {
+                self.record_device_time(log_record.time);
+                self.record_log(log_record.clone());
+            }
+            Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(config_id)) => {
+                self.config_handshake.send_if_modified(|handshake| {
+                    if *handshake != Some(ConfigHandshake::Pending(*config_id)) {
+                        return false;
+                    }
+
+                    *handshake = Some(ConfigHandshake::Complete(*config_id));
+                    true
+                });
+            }
+            _ => (),
+        }
+
+        false
+    }
+
+    /// Stores the specified channel in the channel table, replacing any channel with the same index.
+    /// Returns whether the channel table changed.
+    pub fn update_channel(&self, channel: protobufs::Channel) -> bool {
+        let mut channels = lock(&self.channels);
+
+        if channels.get(&channel.index) == Some(&channel) {
+            return false;
+        }
+
+        channels.insert(channel.index, channel);
+        true
+    }
+
+    /// Returns the device configuration reported by the radio. Sections that have not been
+    /// reported are `None`.
+    pub fn local_config(&self) -> protobufs::LocalConfig {
+        lock(&self.local_config).clone()
+    }
+
+    /// Returns whether the radio reported that it is managed by a mesh administrator, i.e. whether the
+    /// `is_managed` field of its device configuration is set. Radios that have not reported their device
+    /// configuration are not considered managed.
+    pub fn is_managed(&self) -> bool {
+        lock(&self.local_config)
+            .device
+            .as_ref()
+            .is_some_and(|device| device.is_managed)
+    }
+
+    /// Returns the module configuration reported by the radio. Sections that have not been
+    /// reported are `None`.
+    pub fn local_module_config(&self) -> protobufs::LocalModuleConfig {
+        lock(&self.local_module_config).clone()
+    }
+
+    /// Returns all channels reported by the radio, ordered by channel index. Every call clones all
+    /// channels, so lookups of single channels should use the `channel` method instead.
+    pub fn channels(&self) -> Vec<protobufs::Channel> {
+        lock(&self.channels).values().cloned().collect()
+    }
+
+    /// Returns the channel with the specified index, if it has been reported by the radio.
+    pub fn channel(&self, index: u32) -> Option<protobufs::Channel> {
+        let index = i32::try_from(index).ok()?;
+        lock(&self.channels).get(&index).cloned()
+    }
+
+    /// Returns whether the channel with the specified index has been reported by the radio and is
+    /// enabled, without cloning the channel table.
+    pub fn is_channel_active(&self, index: u32) -> bool {
+        let Ok(index) = i32::try_from(index) else {
+            return false;
+        };
+
+        lock(&self.channels)
+            .get(&index)
+            .is_some_and(|channel| channel.role != protobufs::channel::Role::Disabled as i32)
+    }
+
+    /// Returns the index of the enabled channel with the specified name, if any. Names are compared
+    /// case-insensitively, as by the firmware, so e.g. a channel named `Admin` matches `admin`.
+    pub fn channel_index_by_name(&self, name: &str) -> Option<u32> {
+        lock(&self.channels)
+            .values()
+            .filter(|channel| channel.role != protobufs::channel::Role::Disabled as i32)
+            .find(|channel| {
+                channel
+                    .settings
+                    .as_ref()
+                    .is_some_and(|settings| settings.name.eq_ignore_ascii_case(name))
+            })
+            .and_then(|channel| u32::try_from(channel.index).ok())
+    }
+
+    /// Returns whether the radio has reported the channel with the specified index as unusable, i.e.
+    /// as disabled or without settings. Channels that have not been reported are not considered
+    /// disabled, as the channel table is only populated during the `configure` handshake.
+    pub fn is_channel_disabled(&self, index: u32) -> bool {
+        self.channel(index).is_some_and(|channel| {
+            channel.role == protobufs::channel::Role::Disabled as i32 || channel.settings.is_none()
+        })
+    }
+
+    /// Stores a log record reported by the radio, discarding the oldest record if the log buffer is full.
+    pub fn record_log(&self, log_record: protobufs::LogRecord) {
+        if self.log_capacity == 0 {
+            return;
+        }
+
+        let mut recent_logs = lock(&self.recent_logs);
+
+        if recent_logs.len() == self.log_capacity {
+            recent_logs.pop_front();
+        }
+
+        recent_logs.push_back(log_record);
+    }
+
+    /// Returns the most recent log records reported by the radio, oldest first.
+    pub fn recent_logs(&self) -> Vec<protobufs::LogRecord> {
+        lock(&self.recent_logs).iter().cloned().collect()
+    }
+
+    /// Returns the maximum number of log records that are retained.
+    pub fn log_capacity(&self) -> usize {
+        self.log_capacity
+    }
+
+    /// Stores a timestamp reported by the clock of the radio, e.g. the `rx_time` of a received packet.
+    /// Timestamps of 0 indicate that the radio does not know the time, and are ignored.
+    pub fn record_device_time(&self, device_time: u32) {
+        if device_time != 0 {
+            *lock(&self.device_time) = Some((device_time, Instant::now()));
+        }
+    }
+
+    /// Returns an estimate of the current time of the radio's clock, in seconds since the Unix epoch,
+    /// by advancing the most recent timestamp reported by the radio by the time elapsed since.
+    pub fn device_time(&self) -> Option<u32> {
+        lock(&self.device_time).map(|(device_time, recorded_at)| {
+            let elapsed = u32::try_from(recorded_at.elapsed().as_secs()).unwrap_or(u32::MAX);
+            device_time.saturating_add(elapsed)
+        })
+    }
+
+    /// Returns the node id of the connected radio, if the radio has reported it.
+    pub fn my_node_id(&self) -> Option<NodeId> {
+        lock(&self.my_node_info)
+            .as_ref()
+            .map(|my_node_info| my_node_info.my_node_num.into())
+    }
+
+    /// Returns the nodes reported by the radio, ordered by node number.
+    pub fn nodes(&self) -> Vec<protobufs::NodeInfo> {
+        lock(&self.nodes).values().cloned().collect()
+    }
+
+    /// Returns the `NodeInfo` of the specified node, if it has been reported.
+    pub fn node(&self, node_id: NodeId) -> Option<protobufs::NodeInfo> {
+        lock(&self.nodes).get(&node_id.id()).cloned()
+    }
+
+    /// Returns the node number of the node whose `User` has the specified `id`, if it has been reported.
+    pub fn resolve_user_id(&self, user_id: &str) -> Option<NodeId> {
+        lock(&self.nodes)
+            .values()
+            .find(|node_info| {
+                node_info
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.id == user_id)
+            })
+            .map(|node_info| node_info.num.into())
+    }
+
+    /// Returns the `DeviceMetadata` reported by the radio during the `configure` handshake, if any.
+    pub fn metadata(&self) -> Option<protobufs::DeviceMetadata> {
+        lock(&self.metadata).clone()
+    }
+
+    /// Returns the capabilities of the radio, derived from the `DeviceMetadata` it reported.
+    pub fn device_capabilities(&self) -> Option<DeviceCapabilities> {
+        lock(&self.metadata)
+            .as_ref()
+            .map(DeviceCapabilities::from_metadata)
+    }
+
+    /// Starts collecting the nodes reported by the radio into a new node database, e.g. while the node
+    /// database is re-requested. The cached nodes are still updated as usual, and are only replaced by
+    /// the collected nodes once the returned `NodeRefresh` is finished, so that nodes the radio no longer
+    /// knows about are not retained. Dropping the `NodeRefresh` without finishing it keeps the cached nodes.
+    pub fn begin_node_refresh(self: &Arc<Self>) -> NodeRefresh {
+        *lock(&self.refreshed_nodes) = Some(BTreeMap::new());
+
+        NodeRefresh {
+            radio_state: self.clone(),
+        }
+    }
+
+    /// Returns a `DeviceSnapshot` of the state reported by the radio.
+    pub fn snapshot(&self) -> DeviceSnapshot {
+        DeviceSnapshot {
+            my_node_info: *lock(&self.my_node_info),
+            metadata: self.metadata(),
+            nodes: self.nodes(),
+            channels: self.channels(),
+            local_config: self.local_config(),
+            local_module_config: self.local_module_config(),
+        }
+    }
+
+    /// Seeds the state with a previously taken `DeviceSnapshot`, so that the state of a radio is known
+    /// before it has been reported again. Packets received from the radio update the restored state as usual.
+    pub fn restore_snapshot(&self, snapshot: DeviceSnapshot) {
+        *lock(&self.my_node_info) = snapshot.my_node_info;
+        *lock(&self.metadata) = snapshot.metadata;
+        *lock(&self.nodes) = snapshot
+            .nodes
+            .into_iter()
+            .map(|node_info| (node_info.num, node_info))
+            .collect();
+        *lock(&self.channels) = snapshot
+            .channels
+            .into_iter()
+            .map(|channel| (channel.index, channel))
+            .collect();
+        *lock(&self.local_config) = snapshot.local_config;
+        *lock(&self.local_module_config) = snapshot.local_module_config;
+    }
+
+    /// Returns the most recent `QueueStatus` reported by the radio, if any.
+    pub fn queue_status(&self) -> Option<protobufs::QueueStatus> {
+        *self.queue_status.borrow()
+    }
+
+    /// Returns whether the radio has a free slot in its outgoing queue. If the radio has
+    /// not yet reported its queue status, the queue is assumed to have free slots.
+    pub fn has_free_queue_slot(&self) -> bool {
+        self.queue_status().is_none_or(|status| status.free > 0)
+    }
+
+    /// Optimistically marks one slot of the radio's outgoing queue as used. This accounts for
+    /// packets that have been sent, but for which the radio has not yet reported a new queue status.
+    pub fn reserve_queue_slot(&self) {
+        self.queue_status.send_modify(|status| {
+            if let Some(status) = status {
+                status.free = status.free.saturating_sub(1);
+            }
+        });
+    }
+
+    /// Returns the state of the most recent `configure` handshake, if a handshake has been started.
+    pub fn config_handshake(&self) -> Option<ConfigHandshake> {
+        *self.config_handshake.borrow()
+    }
+
+    /// Marks a `configure` handshake with the specified config id as pending, superseding any
+    /// handshake that is still pending.
+    pub fn start_config_handshake(&self, config_id: u32) {
+        self.config_handshake
+            .send_replace(Some(ConfigHandshake::Pending(config_id)));
+    }
+
+    /// Cancels the `configure` handshake with the specified config id, returning whether it was pending.
+    pub fn cancel_config_handshake(&self, config_id: u32) -> bool {
+        self.config_handshake.send_if_modified(|handshake| {
+            if *handshake != Some(ConfigHandshake::Pending(config_id)) {
+                return false;
+            }
+
+            *handshake = Some(ConfigHandshake::Cancelled(config_id));
+            true
+        })
+    }
+
+    /// Waits until the `configure` handshake with the specified config id is no longer pending,
+    /// returning the state it ended in.
+    pub async fn wait_for_config_handshake(&self, config_id: u32) -> Option<ConfigHandshake> {
+        let mut config_handshake_rx = self.config_handshake.subscribe();
+
+        // The sender is owned by `self`, so the channel cannot close while waiting
+        let handshake = config_handshake_rx
+            .wait_for(|handshake| *handshake != Some(ConfigHandshake::Pending(config_id)))
+            .await
+            .ok()
+            .and_then(|handshake| *handshake);
+
+        handshake
+    }
+
+    /// Waits until the radio reports a free slot in its outgoing queue.
+    pub async fn wait_for_free_queue_slot(&self) {
+        let mut queue_status_rx = self.queue_status.subscribe();
+
+        // The sender is owned by `self`, so the channel cannot close while waiting
+        let _ = queue_status_rx
+            .wait_for(|status| status.is_none_or(|status| status.free > 0))
+            .await;
+    }
+}
+
+/// A node database being collected by the `RadioState::begin_node_refresh` method.
+#[derive(Debug)]
+pub struct NodeRefresh {
+    radio_state: Arc<RadioState>,
+}
+
+impl NodeRefresh {
+    /// Replaces the cached nodes with the nodes reported since the refresh was started.
+    pub fn finish(self) {
+        if let Some(refreshed_nodes) = lock(&self.radio_state.refreshed_nodes).take() {
+            *lock(&self.radio_state.nodes) = refreshed_nodes;
+        }
+    }
+}
+
+impl Drop for NodeRefresh {
+    fn drop(&mut self) {
+        // Does nothing if the refresh was finished
+        lock(&self.radio_state.refreshed_nodes).take();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // The radio state is never left in an inconsistent state, so a poisoned lock can be recovered
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_status_packet(free: u32) -> protobufs::FromRadio {
+        protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::QueueStatus(
+                protobufs::QueueStatus {
+                    res: 0,
+                    free,
+                    maxlen: 16,
+                    mesh_packet_id: 0,
+                },
+            )),
+        }
+    }
+
+    #[test]
+    fn tracks_reported_queue_status() {
+        let radio_state = RadioState::new();
+        assert!(radio_state.has_free_queue_slot());
+
+        radio_state.handle_packet(&queue_status_packet(1));
+        assert!(radio_state.has_free_queue_slot());
+
+        radio_state.reserve_queue_slot();
+        assert!(!radio_state.has_free_queue_slot());
+        assert_eq!(radio_state.queue_status().map(|s| s.free), Some(0));
+    }
+
+    #[test]
+    fn resolves_enabled_channels_by_name() {
+        let radio_state = RadioState::new();
+
+        for (index, name, role) in [
+            (0, "", protobufs::channel::Role::Primary),
+            (1, "admin", protobufs::channel::Role::Secondary),
+            (2, "old", protobufs::channel::Role::Disabled),
+        ] {
+            radio_state.update_channel(protobufs::Channel {
+                index,
+                settings: Some(protobufs::ChannelSettings {
+                    name: name.to_string(),
+                    ..Default::default()
+                }),
+                role: role as i32,
+            });
+        }
+
+        assert_eq!(radio_state.channels().len(), 3);
+        assert_eq!(radio_state.channel(2).map(|c| c.index), Some(2));
+        assert_eq!(radio_state.channel_index_by_name("admin"), Some(1));
+        assert_eq!(radio_state.channel_index_by_name("old"), None);
+        assert_eq!(radio_state.channel_index_by_name("missing"), None);
+        assert!(!radio_state.is_channel_disabled(1));
+        assert!(radio_state.is_channel_disabled(2));
+        assert!(!radio_state.is_channel_disabled(3));
+        assert!(radio_state.is_channel_active(1));
+        assert!(!radio_state.is_channel_active(2));
+        assert!(!radio_state.is_channel_active(3));
+    }
+
+    #[test]
+    fn resolves_channel_names_case_insensitively() {
+        let radio_state = RadioState::new();
+
+        radio_state.update_channel(protobufs::Channel {
+            index: 1,
+            settings: Some(protobufs::ChannelSettings {
+                name: "Admin".to_string(),
+                ..Default::default()
+            }),
+            role: protobufs::channel::Role::Secondary as i32,
+        });
+
+        assert_eq!(radio_state.channel_index_by_name("admin"), Some(1));
+        assert_eq!(radio_state.channel_index_by_name("ADMIN"), Some(1));
+    }
+
+    #[test]
+    fn tracks_reported_node_id() {
+        let radio_state = RadioState::new();
+        assert_eq!(radio_state.my_node_id(), None);
+
+        radio_state.handle_packet(&protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::MyInfo(
+                protobufs::MyNodeInfo {
+                    my_node_num: 0x1234,
+                    ..Default::default()
+                },
+            )),
+        });
+
+        assert_eq!(radio_state.my_node_id(), Some(NodeId::new(0x1234)));
+    }
+
+    #[tokio::test]
+    async fn waits_for_free_queue_slot() {
+        let radio_state = std::sync::Arc::new(RadioState::new());
+        radio_state.handle_packet(&queue_status_packet(0));
+
+        let waiter = {
+            let radio_state = radio_state.clone();
+            tokio::spawn(async move { radio_state.wait_for_free_queue_slot().await })
+        };
+
+        radio_state.handle_packet(&queue_status_packet(3));
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should complete once a slot is free")
+            .unwrap();
+    }
+
+    #[test]
+    fn reports_configuration_changes() {
+        let radio_state = RadioState::new();
+
+        let lora_packet = |hop_limit| protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Config(
+                protobufs::Config {
+                    payload_variant: Some(protobufs::config::PayloadVariant::Lora(
+                        protobufs::config::LoRaConfig {
+                            hop_limit,
+                            ..Default::default()
+                        },
+                    )),
+                },
+            )),
+        };
+
+        assert!(radio_state.handle_packet(&lora_packet(3)));
+        assert!(!radio_state.handle_packet(&lora_packet(3)));
+        assert!(radio_state.handle_packet(&lora_packet(5)));
+        assert_eq!(
+            radio_state.local_config().lora.map(|lora| lora.hop_limit),
+            Some(5)
+        );
+
+        assert!(!radio_state.handle_packet(&queue_status_packet(1)));
+    }
+
+    #[test]
+    fn snapshots_reported_nodes() {
+        let radio_state = RadioState::new();
+
+        for num in [3, 1] {
+            radio_state.handle_packet(&protobufs::FromRadio {
+                id: 0,
+                payload_variant: Some(protobufs::from_radio::PayloadVariant::NodeInfo(
+                    protobufs::NodeInfo {
+                        num,
+                        ..Default::default()
+                    },
+                )),
+            });
+        }
+
+        let nodes: Vec<u32> = radio_state.snapshot().nodes.iter().map(|n| n.num).collect();
+        assert_eq!(nodes, [1, 3]);
+    }
+
+    #[test]
+    fn replaces_nodes_once_refresh_finishes() {
+        let radio_state = Arc::new(RadioState::new());
+        let node_info = |num| protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::NodeInfo(
+                protobufs::NodeInfo {
+                    num,
+                    ..Default::default()
+                },
+            )),
+        };
+        let node_nums = |radio_state: &RadioState| -> Vec<u32> {
+            radio_state.nodes().iter().map(|n| n.num).collect()
+        };
+
+        radio_state.handle_packet(&node_info(1));
+        radio_state.handle_packet(&node_info(2));
+
+        // A refresh that does not finish keeps the cached nodes
+        let node_refresh = radio_state.begin_node_refresh();
+        radio_state.handle_packet(&node_info(3));
+        drop(node_refresh);
+        assert_eq!(node_nums(&radio_state), [1, 2, 3]);
+
+        let node_refresh = radio_state.begin_node_refresh();
+        radio_state.handle_packet(&node_info(2));
+        assert_eq!(node_nums(&radio_state), [1, 2, 3]);
+
+        node_refresh.finish();
+        assert_eq!(node_nums(&radio_state), [2]);
+
+        // Nodes reported after the refresh are added as usual
+        radio_state.handle_packet(&node_info(4));
+        assert_eq!(node_nums(&radio_state), [2, 4]);
+    }
+
+    #[test]
+    fn resolves_user_ids_via_node_database() {
+        let radio_state = RadioState::new();
+
+        radio_state.handle_packet(&protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::NodeInfo(
+                protobufs::NodeInfo {
+                    num: 0x1234,
+                    user: Some(protobufs::User {
+                        id: "!0000abcd".to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )),
+        });
+
+        assert_eq!(
+            radio_state.resolve_user_id("!0000abcd"),
+            Some(0x1234.into())
+        );
+        assert_eq!(radio_state.resolve_user_id("!00001234"), None);
+    }
+
+    #[test]
+    fn restores_snapshots() {
+        let snapshot = DeviceSnapshot {
+            nodes: [1, 2]
+                .map(|num| protobufs::NodeInfo {
+                    num,
+                    ..Default::default()
+                })
+                .to_vec(),
+            ..Default::default()
+        };
+
+        let radio_state = RadioState::new();
+        radio_state.restore_snapshot(snapshot.clone());
+        assert_eq!(radio_state.snapshot(), snapshot);
+
+        // Nodes reported by the radio update the restored node database
+        radio_state.handle_packet(&protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::NodeInfo(
+                protobufs::NodeInfo {
+                    num: 2,
+                    snr: 5.0,
+                    ..Default::default()
+                },
+            )),
+        });
+
+        let nodes = radio_state.nodes();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].snr, 5.0);
+    }
+
+    #[test]
+    fn retains_most_recent_log_records() {
+        let radio_state = RadioState::with_log_capacity(2);
+
+        for message in ["first", "second", "third"] {
+            radio_state.handle_packet(&protobufs::FromRadio {
+                id: 0,
+                payload_variant: Some(protobufs::from_radio::PayloadVariant::LogRecord(
+                    protobufs::LogRecord {
+                        message: message.to_string(),
+                        ..Default::default()
+                    },
+                )),
+            });
+        }
+
+        let messages: Vec<String> = radio_state
+            .recent_logs()
+            .into_iter()
+            .map(|log_record| log_record.message)
+            .collect();
+
+        assert_eq!(messages, ["second", "third"]);
+    }
+
+    #[test]
+    fn estimates_device_time_from_reported_timestamps() {
+        let radio_state = RadioState::new();
+        assert_eq!(radio_state.device_time(), None);
+
+        let mesh_packet = |rx_time| protobufs::FromRadio {
+            id: 0,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::Packet(
+                protobufs::MeshPacket {
+                    rx_time,
+                    ..Default::default()
+                },
+            )),
+        };
+
+        radio_state.handle_packet(&mesh_packet(1_700_000_000));
+        radio_state.handle_packet(&mesh_packet(0));
+
+        let device_time = radio_state.device_time().unwrap();
+        assert!((1_700_000_000..1_700_000_002).contains(&device_time));
+    }
+}
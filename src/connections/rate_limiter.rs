@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// A token bucket that limits the rate at which mesh packets are sent to the radio.
+///
+/// The bucket holds up to `packets_per_minute` tokens and starts full, allowing a burst of up to
+/// `packets_per_minute` packets to be sent at once. Tokens are refilled continuously at a rate of
+/// `packets_per_minute` tokens per minute, and each sent packet consumes a single token.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    packets_per_minute: u32,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new, full `RateLimiter` allowing the specified number of packets per minute.
+    /// A limit of zero packets per minute is treated as a limit of one packet per minute.
+    pub fn new(packets_per_minute: u32) -> Self {
+        let packets_per_minute = packets_per_minute.max(1);
+
+        Self {
+            packets_per_minute,
+            tokens: packets_per_minute as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Returns the number of packets per minute allowed by the rate limiter.
+    pub fn packets_per_minute(&self) -> u32 {
+        self.packets_per_minute
+    }
+
+    /// Attempts to consume a token at the specified time. Returns the time to wait until a token
+    /// is available if the bucket is empty.
+    pub fn try_acquire_at(&mut self, now: Instant) -> Result<(), Duration> {
+        let tokens_per_sec = self.packets_per_minute as f64 / 60.0;
+
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * tokens_per_sec)
+            .min(self.packets_per_minute as f64);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1.0 - self.tokens) / tokens_per_sec,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_burst_then_refills() {
+        let mut rate_limiter = RateLimiter::new(6);
+        let start = rate_limiter.last_refill;
+
+        for _ in 0..6 {
+            assert!(rate_limiter.try_acquire_at(start).is_ok());
+        }
+
+        let wait = rate_limiter.try_acquire_at(start).unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 10.0);
+
+        let refilled = start + Duration::from_secs(11);
+        assert!(rate_limiter.try_acquire_at(refilled).is_ok());
+        assert!(rate_limiter.try_acquire_at(refilled).is_err());
+    }
+}
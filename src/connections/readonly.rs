@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc, watch};
+
+use crate::errors_internal::Error;
+use crate::helpers_internal::firmware::DeviceCapabilities;
+use crate::protobufs;
+
+use super::{
+    config_sync::ConfigHandle,
+    device_snapshot::DeviceSnapshot,
+    diagnostics::DroppedPacket,
+    health::{ConnectionEvent, ConnectionHealth},
+    stream_api::{state, ConnectedStreamApi, StreamApi},
+    wrappers::NodeId,
+};
+
+/// A passive connection to a radio, which never writes to the radio. This struct is created by the
+/// `connect_readonly` method of the `StreamApi` struct, which documents the tradeoffs of this mode.
+///
+/// Packets received from the radio are delivered through the `PacketReceiver` returned alongside this
+/// struct. This struct only exposes the state reported by the radio and the health of the connection,
+/// and none of the methods of the `ConnectedStreamApi` struct that send packets to the radio.
+#[derive(Debug)]
+pub struct ReadOnlyStreamApi {
+    stream_api: ConnectedStreamApi<state::Configured>,
+}
+
+impl ReadOnlyStreamApi {
+    pub(crate) fn new(stream_api: ConnectedStreamApi<state::Configured>) -> Self {
+        Self { stream_api }
+    }
+
+    /// Returns a handle of the `configure` handshake, or `None` if no handshake was started.
+    /// See the `config_handle` method of the `ConnectedStreamApi` struct for details.
+    pub fn config_handle(&self) -> Option<ConfigHandle> {
+        self.stream_api.config_handle()
+    }
+
+    /// Waits until the radio has completed the `configure` handshake, returning immediately if no
+    /// handshake was started. See the `wait_for_config` method of the `ConnectedStreamApi` struct for details.
+    pub async fn wait_for_config(&self, timeout: Duration) -> Result<(), Error> {
+        self.stream_api.wait_for_config(timeout).await
+    }
+
+    /// Returns the node id of the observed radio, if the radio has reported it.
+    pub fn my_node_id(&self) -> Option<NodeId> {
+        self.stream_api.my_node_id()
+    }
+
+    /// Returns the channel table of the observed radio, ordered by channel index. Every call
+    /// clones all channels, see the `channels` method of the `ConnectedStreamApi` struct.
+    pub fn channels(&self) -> Vec<protobufs::Channel> {
+        self.stream_api.channels()
+    }
+
+    /// Returns the device configuration the observed radio has reported.
+    pub fn local_config(&self) -> protobufs::LocalConfig {
+        self.stream_api.local_config()
+    }
+
+    /// Returns the module configuration the observed radio has reported.
+    pub fn local_module_config(&self) -> protobufs::LocalModuleConfig {
+        self.stream_api.local_module_config()
+    }
+
+    /// Returns the capabilities of the observed radio, if the radio has reported its metadata.
+    pub fn device_capabilities(&self) -> Option<DeviceCapabilities> {
+        self.stream_api.device_capabilities()
+    }
+
+    /// Returns a `DeviceSnapshot` of the state the observed radio has reported.
+    pub fn snapshot(&self) -> DeviceSnapshot {
+        self.stream_api.snapshot()
+    }
+
+    /// Returns the most recent log records reported by the observed radio, oldest first.
+    pub fn recent_logs(&self) -> Vec<protobufs::LogRecord> {
+        self.stream_api.recent_logs()
+    }
+
+    /// Returns a watch channel of the health of the connection to the radio.
+    pub fn health(&self) -> watch::Receiver<ConnectionHealth> {
+        self.stream_api.health()
+    }
+
+    /// Returns a receiver of the lifecycle events of the connection to the radio.
+    pub fn connection_events(&self) -> mpsc::Receiver<ConnectionEvent> {
+        self.stream_api.connection_events()
+    }
+
+    /// Returns a receiver of the data discarded by the receive pipeline, or `None` if drop
+    /// diagnostics are disabled.
+    pub fn dropped_packets(&self) -> Option<broadcast::Receiver<DroppedPacket>> {
+        self.stream_api.dropped_packets()
+    }
+
+    /// Disconnects from the radio, joining all worker threads. Nothing is written to the radio.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The disconnected `StreamApi` instance, which can be used to connect again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will fail if any of the worker threads fail to join.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn disconnect(self) -> Result<StreamApi, Error> {
+        self.stream_api.disconnect().await
+    }
+}
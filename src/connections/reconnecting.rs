@@ -0,0 +1,732 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use log::{debug, warn};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::{mpsc, watch, MappedMutexGuard, Mutex, MutexGuard},
+    task::JoinHandle,
+};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::errors_internal::Error;
+use crate::packet::PacketReceiver;
+use crate::protobufs;
+use crate::utils_internal::generate_rand_id;
+
+use super::{
+    health::{ConnectionEvent, ConnectionHealth, ConnectionState, HealthMonitor},
+    stream_api::{state, ConnectedStreamApi, StreamApi, StreamHandle},
+};
+
+/// A struct that configures how a `ReconnectingStreamApi` re-establishes a lost connection.
+///
+/// The delay before each reconnection attempt doubles after every failed attempt, starting from
+/// `initial_backoff`, and is capped at `max_backoff`. The delay starts from `initial_backoff` again
+/// every time the connection is lost.
+///
+/// # Default
+///
+/// The default configuration retries indefinitely, with a delay of 1 second growing up to 60 seconds,
+/// and waits up to 30 seconds for the `configure` handshake of each new connection to complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReconnectConfig {
+    /// The delay before the first reconnection attempt after the connection was lost.
+    pub initial_backoff: Duration,
+
+    /// The maximum delay between two reconnection attempts.
+    pub max_backoff: Duration,
+
+    /// The maximum number of consecutive failed reconnection attempts before giving up, or `None`
+    /// to retry indefinitely.
+    pub max_attempts: Option<u32>,
+
+    /// The time to wait for the `configure` handshake of a new connection to complete.
+    pub config_timeout: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: None,
+            config_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Returns the delay before the specified reconnection attempt, where attempt `0` is the first attempt.
+    pub fn backoff_for_attempt(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Aborts the task that serves the other side of a stream, e.g. a BLE handler, once the connection
+/// using the stream is closed or dropped.
+struct AbortOnDrop(Option<JoinHandle<Result<(), Error>>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.0.take() {
+            join_handle.abort();
+        }
+    }
+}
+
+/// The receivers of a `Connection`, which are read by the `Supervisor` without locking the connection.
+struct ConnectionReceivers {
+    packet_receiver: PacketReceiver,
+    events: mpsc::Receiver<ConnectionEvent>,
+    health: watch::Receiver<ConnectionHealth>,
+}
+
+/// A single connection opened by a `ReconnectingStreamApi`. Dropping the connection shuts down its
+/// worker threads, so that no tasks are leaked if the connection is abandoned at any point.
+struct Connection {
+    stream_api: ConnectedStreamApi<state::Configured>,
+    _workers: DropGuard,
+    _stream_task: AbortOnDrop,
+}
+
+impl Connection {
+    /// Opens a new stream, connects to the radio, and waits for the `configure` handshake to complete.
+    async fn open<S, F, Fut>(
+        stream_api: StreamApi,
+        connect: &mut F,
+        config_timeout: Duration,
+    ) -> Result<(ConnectionReceivers, Connection), Error>
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<StreamHandle<S>, Error>>,
+    {
+        let mut stream_handle = connect().await?;
+        let stream_task = AbortOnDrop(stream_handle.join_handle.take());
+
+        let (packet_receiver, stream_api) = stream_api.connect(stream_handle).await;
+        let workers = stream_api.cancellation_token().drop_guard();
+
+        let stream_api = stream_api.configure(generate_rand_id()).await?;
+        stream_api.wait_for_config(config_timeout).await?;
+
+        // Events of the handshake are not forwarded, as it is reported by the `Reconnected` event
+        let receivers = ConnectionReceivers {
+            packet_receiver,
+            events: stream_api.connection_events(),
+            health: stream_api.health(),
+        };
+
+        Ok((
+            receivers,
+            Connection {
+                stream_api,
+                _workers: workers,
+                _stream_task: stream_task,
+            },
+        ))
+    }
+
+    /// Disconnects from the radio, joining the worker threads of the connection.
+    async fn close(self) -> Result<StreamApi, Error> {
+        self.stream_api.disconnect().await
+    }
+}
+
+impl std::fmt::Debug for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connection")
+            .field("stream_api", &self.stream_api)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A connection to a radio that is transparently re-established whenever it is lost, e.g. because a
+/// USB cable glitched or a TCP socket was reset. This struct is created by the `connect_with_retry`
+/// method of the `StreamApi` struct.
+///
+/// A background task forwards the packets of the current connection to the `PacketReceiver` returned
+/// alongside this struct, which stays open across reconnections. Once the stream of the connection
+/// reaches its end or fails, the connection is closed, and new streams are opened using the passed
+/// closure with an exponential backoff, as configured by a `ReconnectConfig`. Each new connection repeats
+/// the `configure` handshake, after which a `ConnectionEvent::Reconnected` event is emitted.
+///
+/// The current connection is accessed through the `stream_api` method, which fails with
+/// `Error::NotConnected` while the connection is being re-established. Packets sent while the connection
+/// is lost are not retried on the new connection, and packets that were still being received when the
+/// connection was lost may be dropped.
+///
+/// Dropping this struct shuts down the background task and the current connection.
+#[derive(Debug)]
+pub struct ReconnectingStreamApi {
+    stream_api: StreamApi,
+    connection: Arc<Mutex<Option<Connection>>>,
+    health_monitor: Arc<HealthMonitor>,
+    supervisor_handle: JoinHandle<()>,
+    cancellation_token: CancellationToken,
+}
+
+impl ReconnectingStreamApi {
+    /// Returns the current connection to the radio, which is locked until the returned guard is dropped.
+    /// The guard should not be held for longer than necessary, as reconnecting waits for it to be dropped.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A guard of the `ConnectedStreamApi` of the current connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match stream_api.stream_api().await {
+    ///     Ok(mut connection) => {
+    ///         connection.send_text(&mut packet_router, "Hello mesh".to_string(), PacketDestination::Broadcast, true, MeshChannel::default()).await?;
+    ///     }
+    ///     Err(Error::NotConnected) => println!("The radio is reconnecting, try again later"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::NotConnected` if the connection is being re-established, or if reconnecting
+    /// was given up after `ReconnectConfig::max_attempts` failed attempts.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn stream_api(
+        &self,
+    ) -> Result<MappedMutexGuard<'_, ConnectedStreamApi<state::Configured>>, Error> {
+        MutexGuard::try_map(self.connection.lock().await, |connection| {
+            connection
+                .as_mut()
+                .map(|connection| &mut connection.stream_api)
+        })
+        .map_err(|_| Error::NotConnected)
+    }
+
+    /// Returns the state of the connection to the radio. The state is `Reconnecting` while the
+    /// connection is being re-established, and `Disconnected` once reconnecting was given up.
+    pub fn state(&self) -> ConnectionState {
+        self.health_monitor.subscribe().borrow().state
+    }
+
+    /// Returns a receiver of the lifecycle events of the connection to the radio, which are emitted across
+    /// reconnections: `Reconnecting` when the connection is lost, followed by `Connected` and `Reconnected`
+    /// once it has been re-established, or `Disconnected` once reconnecting was given up.
+    ///
+    /// The events of the current connection that occur after its `configure` handshake, e.g. `DeviceRebooted`,
+    /// `ConfigChanged`, `ClockSkewDetected`, or `BufferOverflow`, are forwarded to the receiver as well. The
+    /// `Connected`, `Reconnecting`, and `Disconnected` events of the current connection are not forwarded, as
+    /// they are replaced by the events above.
+    pub fn connection_events(&self) -> mpsc::Receiver<ConnectionEvent> {
+        self.health_monitor.subscribe_events()
+    }
+
+    /// Stops reconnecting and disconnects from the radio, joining all worker threads.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The disconnected `StreamApi` instance, which can be used to connect again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will fail if any of the worker threads of the current connection fail to join.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn disconnect(mut self) -> Result<StreamApi, Error> {
+        self.cancellation_token.cancel();
+
+        if let Err(e) = (&mut self.supervisor_handle).await {
+            warn!("Reconnection task failed to join: {e}");
+        }
+
+        let connection = self.connection.lock().await.take();
+        self.health_monitor.set_state(ConnectionState::Disconnected);
+
+        match connection {
+            Some(connection) => connection.close().await,
+            None => Ok(self.stream_api.clone()),
+        }
+    }
+}
+
+impl Drop for ReconnectingStreamApi {
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+impl StreamApi {
+    /// Connects to a radio like the `connect` method, and transparently re-establishes the connection
+    /// whenever it is lost, as described for the `ReconnectingStreamApi` struct.
+    ///
+    /// The passed closure opens a new stream to the radio, and is called for the initial connection as well
+    /// as for every reconnection attempt. If the returned `StreamHandle` contains a join handle, e.g. of a
+    /// BLE handler, the task is aborted once the connection using the stream is closed, so that no tasks
+    /// are leaked by repeated reconnection attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect` - A closure that opens a new stream to the radio.
+    /// * `reconnect_config` - A `ReconnectConfig` that specifies the backoff between reconnection attempts.
+    ///
+    /// # Returns
+    ///
+    /// A `PacketReceiver` of the decoded `FromRadio` packets of all connections, along with the
+    /// `ReconnectingStreamApi` struct that manages the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (mut decoded_listener, stream_api) = StreamApi::new()
+    ///     .connect_with_retry(
+    ///         || build_tcp_stream("localhost:4403".to_string()),
+    ///         ReconnectConfig::default(),
+    ///     )
+    ///     .await?;
+    ///
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     println!("Received packet: {:?}", packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the initial connection cannot be opened, or if its `configure` handshake does not complete
+    /// within `ReconnectConfig::config_timeout`. The initial connection is not retried, so that e.g. a
+    /// wrong address is reported immediately.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn connect_with_retry<S, F, Fut>(
+        self,
+        mut connect: F,
+        reconnect_config: ReconnectConfig,
+    ) -> Result<(PacketReceiver, ReconnectingStreamApi), Error>
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<StreamHandle<S>, Error>> + Send + 'static,
+    {
+        let (receivers, connection) =
+            Connection::open(self.clone(), &mut connect, reconnect_config.config_timeout).await?;
+
+        let connection = Arc::new(Mutex::new(Some(connection)));
+        let health_monitor = Arc::new(HealthMonitor::new());
+        let cancellation_token = CancellationToken::new();
+        let (packet_tx, packet_rx) = mpsc::unbounded_channel();
+
+        let supervisor = Supervisor {
+            stream_api: self.clone(),
+            reconnect_config,
+            connection: connection.clone(),
+            health_monitor: health_monitor.clone(),
+            cancellation_token: cancellation_token.clone(),
+            packet_tx,
+        };
+
+        let supervisor_handle = tokio::spawn(supervisor.run(connect, receivers));
+
+        Ok((
+            packet_rx,
+            ReconnectingStreamApi {
+                stream_api: self,
+                connection,
+                health_monitor,
+                supervisor_handle,
+                cancellation_token,
+            },
+        ))
+    }
+}
+
+/// The background task of a `ReconnectingStreamApi`, which forwards the packets and events of the
+/// current connection, and re-establishes the connection once it is lost.
+struct Supervisor {
+    stream_api: StreamApi,
+    reconnect_config: ReconnectConfig,
+    connection: Arc<Mutex<Option<Connection>>>,
+    health_monitor: Arc<HealthMonitor>,
+    cancellation_token: CancellationToken,
+    packet_tx: mpsc::UnboundedSender<protobufs::FromRadio>,
+}
+
+impl Supervisor {
+    async fn run<S, F, Fut>(self, mut connect: F, mut receivers: ConnectionReceivers)
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<StreamHandle<S>, Error>>,
+    {
+        loop {
+            // Forward packets and events until the connection is lost
+            loop {
+                tokio::select! {
+                    _ = self.cancellation_token.cancelled() => return,
+                    packet = receivers.packet_receiver.recv() => match packet {
+                        // The receiver may have been dropped by the user, which doesn't stop reconnecting
+                        Some(packet) => { let _ = self.packet_tx.send(packet); }
+                        None => break,
+                    },
+                    event = receivers.events.recv() => match event {
+                        Some(event) => self.forward_event(event),
+                        None => break,
+                    },
+                    _ = receivers.health.wait_for(|health| health.state == ConnectionState::Disconnected) => break,
+                }
+            }
+
+            warn!("Connection to the radio was lost, reconnecting");
+            self.health_monitor.set_state(ConnectionState::Reconnecting);
+
+            let lost_connection = self.connection.lock().await.take();
+            if let Some(lost_connection) = lost_connection {
+                if let Err(e) = lost_connection.close().await {
+                    debug!("Lost connection closed with error: {e}");
+                }
+            }
+
+            while let Ok(packet) = receivers.packet_receiver.try_recv() {
+                let _ = self.packet_tx.send(packet);
+            }
+
+            while let Ok(event) = receivers.events.try_recv() {
+                self.forward_event(event);
+            }
+
+            let Some((new_receivers, new_connection)) = self.reconnect(&mut connect).await else {
+                return;
+            };
+
+            receivers = new_receivers;
+            *self.connection.lock().await = Some(new_connection);
+
+            self.health_monitor.set_state(ConnectionState::Connected);
+            self.health_monitor.emit(ConnectionEvent::Reconnected);
+        }
+    }
+
+    /// Forwards an event of the current connection, except for the changes of its state, which are
+    /// reported by the state of the `ReconnectingStreamApi` instead.
+    fn forward_event(&self, event: ConnectionEvent) {
+        match event {
+            ConnectionEvent::Connected
+            | ConnectionEvent::Reconnecting
+            | ConnectionEvent::Disconnected => (),
+            event => self.health_monitor.emit(event),
+        }
+    }
+
+    /// Attempts to open a new connection with an exponential backoff. Returns `None` if the task was
+    /// cancelled, or if reconnecting was given up.
+    async fn reconnect<S, F, Fut>(
+        &self,
+        connect: &mut F,
+    ) -> Option<(ConnectionReceivers, Connection)>
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<StreamHandle<S>, Error>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            if self
+                .reconnect_config
+                .max_attempts
+                .is_some_and(|max_attempts| attempt >= max_attempts)
+            {
+                warn!("Giving up reconnecting to the radio after {attempt} failed attempts");
+                self.health_monitor.set_state(ConnectionState::Disconnected);
+                return None;
+            }
+
+            let backoff = self.reconnect_config.backoff_for_attempt(attempt);
+            attempt += 1;
+
+            // Cancelling drops a partially opened connection, which shuts down its tasks
+            let result = tokio::select! {
+                _ = self.cancellation_token.cancelled() => return None,
+                result = async {
+                    tokio::time::sleep(backoff).await;
+                    Connection::open(
+                        self.stream_api.clone(),
+                        connect,
+                        self.reconnect_config.config_timeout,
+                    )
+                    .await
+                } => result,
+            };
+
+            match result {
+                Ok(connection) => return Some(connection),
+                Err(e) => warn!("Reconnection attempt {attempt} failed: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+
+    use crate::connections::test_utils::{read_to_radio, write_from_radio};
+
+    use super::*;
+
+    /// Answers the `WantConfigId` packet of a new connection, like a radio without any configuration.
+    async fn complete_handshake(server: &mut DuplexStream) {
+        loop {
+            if let Some(protobufs::to_radio::PayloadVariant::WantConfigId(config_id)) =
+                read_to_radio(server).await.payload_variant
+            {
+                write_from_radio(
+                    server,
+                    protobufs::from_radio::PayloadVariant::ConfigCompleteId(config_id),
+                )
+                .await;
+                return;
+            }
+        }
+    }
+
+    fn fast_reconnect_config() -> ReconnectConfig {
+        ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            max_attempts: Some(3),
+            config_timeout: Duration::from_millis(200),
+        }
+    }
+
+    #[test]
+    fn doubles_backoff_up_to_the_maximum() {
+        let reconnect_config = fast_reconnect_config();
+
+        assert_eq!(
+            reconnect_config.backoff_for_attempt(0),
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            reconnect_config.backoff_for_attempt(1),
+            Duration::from_millis(20)
+        );
+        assert_eq!(
+            reconnect_config.backoff_for_attempt(10),
+            Duration::from_millis(40)
+        );
+        assert_eq!(
+            ReconnectConfig::default().backoff_for_attempt(u32::MAX),
+            Duration::from_secs(60)
+        );
+    }
+
+    fn log_record(message: &str) -> protobufs::from_radio::PayloadVariant {
+        protobufs::from_radio::PayloadVariant::LogRecord(protobufs::LogRecord {
+            message: message.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_the_stream_ends() {
+        let (server_tx, mut server_rx) = mpsc::unbounded_channel();
+
+        let connect = move || {
+            let (client, server) = tokio::io::duplex(4096);
+            server_tx.send(server).unwrap();
+            async move { Ok(StreamHandle::from_stream(client)) }
+        };
+
+        let (glitch_tx, glitch_rx) = tokio::sync::oneshot::channel();
+
+        let radio = tokio::spawn(async move {
+            let mut server = server_rx.recv().await.unwrap();
+            complete_handshake(&mut server).await;
+            write_from_radio(&mut server, log_record("before")).await;
+            glitch_rx.await.unwrap();
+
+            // Closing the stream simulates a glitch of the cable
+            drop(server);
+
+            let mut server = server_rx.recv().await.unwrap();
+            complete_handshake(&mut server).await;
+            write_from_radio(&mut server, log_record("after")).await;
+            server
+        });
+
+        let (mut packet_receiver, stream_api) = StreamApi::new()
+            .connect_with_retry(connect, fast_reconnect_config())
+            .await
+            .unwrap();
+        let mut events = stream_api.connection_events();
+        let mut glitch_tx = Some(glitch_tx);
+
+        let mut messages = vec![];
+        while messages.len() < 2 {
+            let packet = tokio::time::timeout(Duration::from_secs(5), packet_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+
+            if let Some(protobufs::from_radio::PayloadVariant::LogRecord(log_record)) =
+                packet.payload_variant
+            {
+                messages.push(log_record.message);
+
+                // Only glitch once the first packet was received, as packets in flight are lost
+                if let Some(glitch_tx) = glitch_tx.take() {
+                    glitch_tx.send(()).unwrap();
+                }
+            }
+        }
+        assert_eq!(messages, ["before", "after"]);
+
+        let mut received_events = vec![];
+        while received_events.last() != Some(&ConnectionEvent::Reconnected) {
+            received_events.push(events.recv().await.unwrap());
+        }
+        assert_eq!(
+            received_events,
+            [
+                ConnectionEvent::Connected,
+                ConnectionEvent::Reconnecting,
+                ConnectionEvent::Connected,
+                ConnectionEvent::Reconnected,
+            ]
+        );
+        assert!(stream_api.stream_api().await.is_ok());
+
+        let _server = radio.await.unwrap();
+        stream_api.disconnect().await.ok();
+    }
+
+    #[tokio::test]
+    async fn forwards_events_of_the_current_connection() {
+        let (client, mut server) = tokio::io::duplex(4096);
+        let mut stream = Some(client);
+
+        let connect = move || {
+            let stream = stream.take().ok_or(Error::NotConnected);
+            async move { stream.map(StreamHandle::from_stream) }
+        };
+
+        let (reboot_tx, reboot_rx) = tokio::sync::oneshot::channel();
+
+        let radio = tokio::spawn(async move {
+            complete_handshake(&mut server).await;
+            reboot_rx.await.unwrap();
+            write_from_radio(
+                &mut server,
+                protobufs::from_radio::PayloadVariant::Rebooted(true),
+            )
+            .await;
+            server
+        });
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect_with_retry(connect, fast_reconnect_config())
+            .await
+            .unwrap();
+        let mut events = stream_api.connection_events();
+        reboot_tx.send(()).unwrap();
+
+        let received_events = [events.recv().await, events.recv().await];
+        assert_eq!(
+            received_events,
+            [
+                Some(ConnectionEvent::Connected),
+                Some(ConnectionEvent::DeviceRebooted)
+            ]
+        );
+
+        let _server = radio.await.unwrap();
+        stream_api.disconnect().await.ok();
+    }
+
+    #[tokio::test]
+    async fn aborts_stream_tasks_of_failed_attempts() {
+        let stream_task_marker = Arc::new(());
+        let (server_tx, mut server_rx) = mpsc::unbounded_channel();
+
+        let marker = stream_task_marker.clone();
+        let connect = move || {
+            let (client, server) = tokio::io::duplex(4096);
+            server_tx.send(server).unwrap();
+
+            // The task of each stream holds the marker until it is aborted
+            let marker = marker.clone();
+            let join_handle = tokio::spawn(async move {
+                let _marker = marker;
+                std::future::pending::<Result<(), Error>>().await
+            });
+
+            async move {
+                Ok(StreamHandle {
+                    join_handle: Some(join_handle),
+                    ..StreamHandle::from_stream(client)
+                })
+            }
+        };
+
+        let radio = tokio::spawn(async move {
+            let mut server = server_rx.recv().await.unwrap();
+            complete_handshake(&mut server).await;
+            drop(server);
+
+            // Reconnection attempts are never answered, so their handshakes time out
+            let mut unanswered = vec![];
+            while let Some(server) = server_rx.recv().await {
+                unanswered.push(server);
+            }
+        });
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect_with_retry(connect, fast_reconnect_config())
+            .await
+            .unwrap();
+        let mut events = stream_api.connection_events();
+
+        while events.recv().await != Some(ConnectionEvent::Disconnected) {}
+
+        assert_eq!(stream_api.state(), ConnectionState::Disconnected);
+        assert!(matches!(
+            stream_api.stream_api().await,
+            Err(Error::NotConnected)
+        ));
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while Arc::strong_count(&stream_task_marker) > 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("stream tasks of failed attempts were not aborted");
+
+        drop(stream_api);
+        radio.await.unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use log::{debug, warn};
+use tokio::sync::{broadcast, OwnedSemaphorePermit, Semaphore};
+
+use crate::errors_internal::{Error, InternalChannelError};
+use crate::protobufs;
+
+use super::{
+    ack_tracker::{
+        AckStatus, AckTracker, ReliableSendConfig, DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS,
+    },
+    mesh_packet_builder::MeshPacketBuilder,
+    packet_sender::PacketSender,
+    radio_state::RadioState,
+    request_registry::RequestRegistry,
+    stream_api::echo_mesh_packet,
+    stream_buffer::ReceivedPacket,
+    wrappers::{encoded_data::EncodedMeshPacketData, mesh_channel::MeshChannel},
+    PacketDestination, PacketRouter,
+};
+
+/// A cloneable handle for sending mesh packets with application-layer retransmission, created by the
+/// `reliable_sender` method of the `ConnectedStreamApi` struct.
+///
+/// Unlike the `ConnectedStreamApi::send_mesh_packet_reliable` method, which borrows the connection for
+/// the duration of the send, each clone of a `ReliableSender` can await an acknowledgement independently.
+/// This allows several reliable sends to be in flight at once, e.g. from separate tasks. All clones share
+/// the in-flight limit configured with the `ConnectedStreamApi::set_max_in_flight_reliable_sends` method.
+#[derive(Clone, Debug)]
+pub struct ReliableSender {
+    packet_sender: PacketSender,
+    packet_broadcast_tx: broadcast::Sender<ReceivedPacket>,
+    request_registry: RequestRegistry,
+    in_flight_limit: InFlightLimit,
+}
+
+impl ReliableSender {
+    pub(crate) fn new(
+        packet_sender: PacketSender,
+        packet_broadcast_tx: broadcast::Sender<ReceivedPacket>,
+        request_registry: RequestRegistry,
+        in_flight_limit: InFlightLimit,
+    ) -> Self {
+        Self {
+            packet_sender,
+            packet_broadcast_tx,
+            request_registry,
+            in_flight_limit,
+        }
+    }
+
+    /// Sends a mesh packet with `want_ack` set, and retransmits it until it is acknowledged. See the
+    /// `ConnectedStreamApi::send_mesh_packet_reliable` method for a description of the arguments and
+    /// the retransmission behavior.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send, with `Error::MaxRetransmit` if no acknowledgement
+    /// is received after all retransmissions, or with `Error::RequestCancelled` if the request
+    /// is cancelled while awaiting an acknowledgement.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_mesh_packet_reliable<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &self,
+        packet_router: &mut R,
+        packet_data: EncodedMeshPacketData,
+        port_num: protobufs::PortNum,
+        destination: PacketDestination,
+        channel: MeshChannel,
+        want_response: bool,
+        echo_response: bool,
+        send_config: ReliableSendConfig,
+    ) -> Result<AckStatus, Error> {
+        let own_node_id = packet_router.source_node_id();
+
+        let mut mesh_packet = MeshPacketBuilder::new(port_num, packet_data)
+            .destination(destination)
+            .channel(channel)
+            .want_ack(true)
+            .want_response(want_response)
+            .build(own_node_id);
+
+        if echo_response {
+            echo_mesh_packet(packet_router, &mut mesh_packet)?;
+        }
+
+        let packet_id = mesh_packet.id;
+        let request = self.request_registry.register(packet_id);
+
+        // Hold a permit until the packet is acknowledged, to bound the number of in-flight packets
+        let _permit = tokio::select! {
+            _ = request.cancelled() => return Err(Error::RequestCancelled { packet_id }),
+            permit = self.in_flight_limit.acquire() => permit?,
+        };
+
+        let mut ack_tracker = AckTracker::new(own_node_id);
+        ack_tracker.track(&mesh_packet);
+
+        // Subscribe before sending to avoid missing a fast acknowledgement
+        let mut packet_rx = self.packet_broadcast_tx.subscribe();
+
+        let mut implicit_ack_received = false;
+        let attempts = send_config.max_retransmits.saturating_add(1);
+
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                debug!(
+                    "Retransmitting packet {packet_id} (attempt {})",
+                    attempt + 1
+                );
+            }
+
+            let payload_variant = Some(protobufs::to_radio::PayloadVariant::Packet(
+                mesh_packet.clone(),
+            ));
+            self.packet_sender
+                .send_to_radio_packet(payload_variant)
+                .await?;
+
+            let deadline = tokio::time::Instant::now() + send_config.timeout_for_attempt(attempt);
+
+            loop {
+                let received = tokio::select! {
+                    _ = request.cancelled() => return Err(Error::RequestCancelled { packet_id }),
+                    received = tokio::time::timeout_at(deadline, packet_rx.recv()) => received,
+                };
+
+                let packet = match received {
+                    Err(_elapsed) => break,
+                    Ok(Ok(packet)) => packet,
+                    Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+                        warn!("Reliable send skipped {skipped} packets while awaiting ACK");
+                        continue;
+                    }
+                    Ok(Err(broadcast::error::RecvError::Closed)) => {
+                        return Err(Error::InternalChannelError(
+                            InternalChannelError::ChannelClosedEarly,
+                        ));
+                    }
+                };
+
+                let Some(ack_event) = ack_tracker.handle_packet(&packet.packet) else {
+                    continue;
+                };
+
+                if ack_event.status == AckStatus::ImplicitAck {
+                    implicit_ack_received = true;
+                }
+
+                if !ack_tracker.is_pending(packet_id) {
+                    return Ok(ack_event.status);
+                }
+            }
+        }
+
+        if implicit_ack_received {
+            return Ok(AckStatus::ImplicitAck);
+        }
+
+        Err(Error::MaxRetransmit {
+            packet_id,
+            attempts,
+        })
+    }
+}
+
+/// Bounds the number of reliable sends awaiting an acknowledgement at once, using a single semaphore
+/// that is resized whenever the limit changes.
+///
+/// When the limit shrinks below the number of permits currently held, the shortfall is recorded as
+/// excess, and permits are forgotten instead of being returned to the semaphore until it is paid off.
+#[derive(Clone, Debug)]
+pub(crate) struct InFlightLimit {
+    semaphore: Arc<Semaphore>,
+    state: Arc<Mutex<LimitState>>,
+    radio_state: Arc<RadioState>,
+}
+
+#[derive(Debug)]
+struct LimitState {
+    configured: Option<usize>,
+    permits: usize,
+    excess: usize,
+}
+
+impl InFlightLimit {
+    pub(crate) fn new(radio_state: Arc<RadioState>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS)),
+            state: Arc::new(Mutex::new(LimitState {
+                configured: None,
+                permits: DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS,
+                excess: 0,
+            })),
+            radio_state,
+        }
+    }
+
+    /// Sets the configured limit, or `None` to use the queue length reported by the radio.
+    pub(crate) fn set_configured(&self, limit: Option<usize>) {
+        self.lock().configured = limit.map(|limit| limit.max(1));
+        self.resize(self.limit());
+    }
+
+    /// Returns the configured limit, falling back to the queue length reported by the radio, or
+    /// `DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS` if the radio has not reported its queue status.
+    pub(crate) fn limit(&self) -> usize {
+        self.lock().configured.unwrap_or_else(|| {
+            self.radio_state
+                .queue_status()
+                .map(|status| status.maxlen as usize)
+                .filter(|maxlen| *maxlen > 0)
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS)
+        })
+    }
+
+    /// Waits for an in-flight slot. The slot is released when the returned permit is dropped.
+    pub(crate) async fn acquire(&self) -> Result<InFlightPermit, Error> {
+        // The queue length reported by the radio may have changed since the last send
+        self.resize(self.limit());
+
+        let permit =
+            self.semaphore.clone().acquire_owned().await.map_err(|_| {
+                Error::InternalChannelError(InternalChannelError::ChannelClosedEarly)
+            })?;
+
+        Ok(InFlightPermit {
+            permit: Some(permit),
+            state: self.state.clone(),
+        })
+    }
+
+    fn resize(&self, limit: usize) {
+        let mut state = self.lock();
+
+        if limit > state.permits {
+            // Cancel outstanding excess before handing out new permits
+            let added = limit - state.permits;
+            let cancelled = added.min(state.excess);
+            state.excess -= cancelled;
+            self.semaphore.add_permits(added - cancelled);
+        } else {
+            // Permits that are currently held are forgotten once they are released
+            let removed = state.permits - limit;
+            let forgotten = self.semaphore.forget_permits(removed);
+            state.excess += removed - forgotten;
+        }
+
+        state.permits = limit;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LimitState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An in-flight slot acquired from an `InFlightLimit`, which is released when dropped.
+#[derive(Debug)]
+pub(crate) struct InFlightPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    state: Arc<Mutex<LimitState>>,
+}
+
+impl Drop for InFlightPermit {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if state.excess > 0 {
+            state.excess -= 1;
+            permit.forget();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizing_reuses_one_semaphore() {
+        let limit = InFlightLimit::new(Arc::new(RadioState::new()));
+        let semaphore = limit.semaphore.clone();
+
+        limit.set_configured(Some(2));
+        assert_eq!(limit.semaphore.available_permits(), 2);
+
+        limit.set_configured(Some(5));
+        assert_eq!(limit.semaphore.available_permits(), 5);
+        assert!(Arc::ptr_eq(&semaphore, &limit.semaphore));
+    }
+
+    #[tokio::test]
+    async fn shrinking_below_held_permits_forgets_released_permits() {
+        let limit = InFlightLimit::new(Arc::new(RadioState::new()));
+        limit.set_configured(Some(3));
+
+        let permits = vec![
+            limit.acquire().await.unwrap(),
+            limit.acquire().await.unwrap(),
+            limit.acquire().await.unwrap(),
+        ];
+
+        limit.set_configured(Some(1));
+        assert_eq!(limit.lock().excess, 2);
+
+        drop(permits);
+        assert_eq!(limit.lock().excess, 0);
+        assert_eq!(limit.semaphore.available_permits(), 1);
+
+        limit.set_configured(Some(2));
+        assert_eq!(limit.semaphore.available_permits(), 2);
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio_util::sync::CancellationToken;
+
+/// A struct that tracks the packet ids of requests whose responses or acknowledgements are being
+/// awaited, e.g. by the `send_mesh_packet_reliable` and `request_position` methods of the
+/// `ConnectedStreamApi` struct, so that individual waiters can be listed and cancelled.
+///
+/// Cloning a `RequestRegistry` returns a handle to the same set of pending requests.
+#[derive(Clone, Debug, Default)]
+pub struct RequestRegistry {
+    inner: Arc<Mutex<RegistryInner>>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryInner {
+    next_registration: u64,
+    pending: HashMap<u32, (u64, CancellationToken)>,
+}
+
+impl RequestRegistry {
+    /// Creates a new `RequestRegistry` with no pending requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking the request with the specified packet id. The request stops being tracked
+    /// once the returned `PendingRequest` is dropped.
+    pub fn register(&self, packet_id: u32) -> PendingRequest {
+        let cancellation_token = CancellationToken::new();
+
+        let mut inner = self.lock();
+        let registration = inner.next_registration;
+        inner.next_registration += 1;
+        inner
+            .pending
+            .insert(packet_id, (registration, cancellation_token.clone()));
+
+        PendingRequest {
+            registry: self.clone(),
+            packet_id,
+            registration,
+            cancellation_token,
+        }
+    }
+
+    /// Cancels the request with the specified packet id, returning whether it was pending. The waiter
+    /// of the request fails with `Error::RequestCancelled`.
+    pub fn cancel(&self, packet_id: u32) -> bool {
+        match self.lock().pending.remove(&packet_id) {
+            Some((_, cancellation_token)) => {
+                cancellation_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the packet ids of all pending requests.
+    pub fn pending_request_ids(&self) -> Vec<u32> {
+        self.lock().pending.keys().copied().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RegistryInner> {
+        // The map is never left in an inconsistent state, so a poisoned lock can be recovered
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A guard representing a request tracked by a `RequestRegistry`, which stops tracking the request
+/// when dropped.
+#[derive(Debug)]
+pub struct PendingRequest {
+    registry: RequestRegistry,
+    packet_id: u32,
+    registration: u64,
+    cancellation_token: CancellationToken,
+}
+
+impl PendingRequest {
+    /// Returns the packet id of the request.
+    pub fn packet_id(&self) -> u32 {
+        self.packet_id
+    }
+
+    /// Resolves once the request has been cancelled via the `RequestRegistry`.
+    pub async fn cancelled(&self) {
+        self.cancellation_token.cancelled().await
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        let mut inner = self.registry.lock();
+
+        // The packet id may have been registered again by another request in the meantime
+        let is_own_entry = inner
+            .pending
+            .get(&self.packet_id)
+            .is_some_and(|(registration, _)| *registration == self.registration);
+
+        if is_own_entry {
+            inner.pending.remove(&self.packet_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancels_individual_requests() {
+        let registry = RequestRegistry::new();
+
+        let first = registry.register(1);
+        let second = registry.register(2);
+
+        let mut pending_ids = registry.pending_request_ids();
+        pending_ids.sort();
+        assert_eq!(pending_ids, [1, 2]);
+
+        assert!(registry.cancel(1));
+        assert!(!registry.cancel(1));
+        first.cancelled().await;
+
+        drop(second);
+        assert!(registry.pending_request_ids().is_empty());
+    }
+}
@@ -1,27 +1,48 @@
-use futures_util::future::join3;
-use log::trace;
+use futures_util::future::join4;
+use log::{debug, trace, warn};
 use prost::Message;
-use std::{fmt::Display, marker::PhantomData};
+use std::{fmt::Display, marker::PhantomData, sync::Arc, time::Duration};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    sync::mpsc::UnboundedSender,
+    sync::{broadcast, mpsc, mpsc::UnboundedSender, watch},
     task::JoinHandle,
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::{errors_internal::Error, protobufs, types::EncodedToRadioPacketWithHeader, utils};
 use crate::{
+    errors_internal::{Error, InternalChannelError},
+    protobufs,
+    types::EncodedToRadioPacketWithHeader,
+    utils,
+};
+use crate::{
+    helpers_internal::firmware::DeviceCapabilities,
     packet::PacketReceiver,
     utils_internal::{current_epoch_secs_u32, generate_rand_id},
 };
 
 use super::{
+    ack_tracker::{AckStatus, ReliableSendConfig},
+    admin_session::{AdminSession, PendingAdminResponse, PendingAdminResponses},
+    config_sync::ConfigHandle,
+    device_snapshot::DeviceSnapshot,
+    diagnostics::{DropDiagnostics, DroppedPacket},
     handlers,
+    health::{ConnectionEvent, ConnectionHealth, ConnectionState, HealthMonitor},
+    mesh_packet_builder::MeshPacketBuilder,
+    packet_sender::PacketSender,
+    packet_streams::PacketSubscription,
+    radio_state::{ConfigHandshake, QueueFullBehavior, RadioState, DEFAULT_LOG_BUFFER_SIZE},
+    readonly::ReadOnlyStreamApi,
+    reliable_sender::{InFlightLimit, ReliableSender},
+    request_registry::{PendingRequest, RequestRegistry},
+    stream_buffer::{ReceivedPacket, DEFAULT_MAX_BUFFER_SIZE, START1, START2},
     wrappers::{
         encoded_data::{EncodedMeshPacketData, EncodedToRadioPacket, IncomingStreamData},
         mesh_channel::MeshChannel,
-        NodeId,
+        NodeId, PacketId,
     },
+    write_queue::WriteQueue,
     PacketDestination, PacketRouter,
 };
 
@@ -52,8 +73,12 @@ pub mod state {
 /// This will return an instance of the `ConnectedStreamApi` struct, which then allows the
 /// developer to call the `configure` method. The developer will then be able to interact with
 /// the radio by calling the various "send" methods, which will send packets onto the mesh.
-#[derive(Debug)]
-pub struct StreamApi;
+#[derive(Clone, Debug)]
+pub struct StreamApi {
+    max_buffer_size: usize,
+    log_buffer_size: usize,
+    drop_diagnostics: bool,
+}
 
 /// A struct that provides a high-level API for communicating with a Meshtastic radio.
 ///
@@ -69,13 +94,24 @@ pub struct StreamApi;
 /// and that the device will respond to "send" methods.
 #[derive(Debug)]
 pub struct ConnectedStreamApi<State = state::Configured> {
-    write_input_tx: UnboundedSender<EncodedToRadioPacketWithHeader>,
+    packet_sender: PacketSender,
+    flush_request_tx: UnboundedSender<handlers::FlushRequest>,
 
     read_handle: JoinHandle<Result<(), Error>>,
     write_handle: JoinHandle<Result<(), Error>>,
     processing_handle: JoinHandle<Result<(), Error>>,
+    dispatch_handle: JoinHandle<Result<(), Error>>,
     heartbeat_handle: JoinHandle<Result<(), Error>>,
 
+    packet_broadcast_tx: broadcast::Sender<ReceivedPacket>,
+    radio_state: Arc<RadioState>,
+    admin_session: AdminSession,
+    request_registry: RequestRegistry,
+    health_monitor: Arc<HealthMonitor>,
+    in_flight_limit: InFlightLimit,
+    max_buffer_size: usize,
+    drop_diagnostics: DropDiagnostics,
+
     cancellation_token: CancellationToken,
 
     typestate: PhantomData<State>,
@@ -86,6 +122,10 @@ pub struct ConnectedStreamApi<State = state::Configured> {
 pub struct StreamHandle<T: AsyncReadExt + AsyncWriteExt + Send> {
     pub stream: T,
     pub join_handle: Option<JoinHandle<Result<(), Error>>>,
+
+    /// The health monitor of the connection, if the task on the other side of the stream tracks the
+    /// health of the connection, e.g. to restore a stalled BLE connection.
+    pub(crate) health_monitor: Option<Arc<HealthMonitor>>,
 }
 
 impl<T: AsyncReadExt + AsyncWriteExt + Send> StreamHandle<T> {
@@ -93,6 +133,7 @@ impl<T: AsyncReadExt + AsyncWriteExt + Send> StreamHandle<T> {
         Self {
             stream,
             join_handle: None,
+            health_monitor: None,
         }
     }
 }
@@ -171,53 +212,112 @@ impl<State> ConnectedStreamApi<State> {
         reply_id: Option<u32>,
         emoji: Option<u32>,
     ) -> Result<(), Error> {
-        let own_node_id = packet_router.source_node_id();
-
-        let packet_destination: NodeId = match destination {
-            PacketDestination::Local => own_node_id,
-            PacketDestination::Broadcast => u32::MAX.into(),
-            PacketDestination::Node(id) => id,
-        };
-
-        // NOTE(canardleteer): We don't warn on deprecation here, because it
-        //                     remains valid for many active nodes, and
-        //                     remains a part of the generated interface.
-        #[allow(deprecated)]
-        let mut mesh_packet = protobufs::MeshPacket {
-            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
-                protobufs::Data {
-                    portnum: port_num as i32,
-                    payload: packet_data.data_vec(),
-                    want_response,
-                    reply_id: reply_id.unwrap_or(0),
-                    emoji: emoji.unwrap_or(0),
-                    dest: 0,       // TODO change this
-                    request_id: 0, // TODO change this
-                    source: 0,     // TODO change this
-                },
-            )),
-            rx_time: 0,   // * not transmitted
-            rx_snr: 0.0,  // * not transmitted
-            hop_limit: 0, // * not transmitted
-            priority: 0,  // * not transmitted
-            rx_rssi: 0,   // * not transmitted
-            delayed: 0,   // * not transmitted [deprecated since protobufs v2.2.19]
-            hop_start: 0, // * set on device
-            via_mqtt: false,
-            from: own_node_id.id(),
-            to: packet_destination.id(),
-            id: generate_rand_id(),
+        self.send_mesh_packet_with_priority(
+            packet_router,
+            packet_data,
+            port_num,
+            destination,
+            channel,
             want_ack,
-            channel: channel.channel(),
-        };
+            want_response,
+            echo_response,
+            reply_id,
+            emoji,
+            protobufs::mesh_packet::Priority::Unset,
+        )
+        .await
+    }
+
+    /// A helper method to send encoded byte data to the radio within a MeshPacket wrapper, with
+    /// the specified packet priority. This method behaves identically to the `send_mesh_packet`
+    /// method, except that the `priority` field of the outgoing MeshPacket is set.
+    ///
+    /// The priority is used to order packets within the client-side write queue, as well as within
+    /// the transmit queue of the radio. If the priority is `Unset`, the priority is derived from the
+    /// packet in the same way as the firmware (routing packets are sent with `Ack` priority, packets
+    /// with `want_ack` set are sent with `Reliable` priority, position packets are sent with `Background`
+    /// priority, and all other packets are sent with `Default` priority).
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    /// * `packet_data` - An `EncodedMeshPacketData` containing the byte data to send.
+    /// * `port_num` - A `PortNum` enum that specifies the port number to send the packet on.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the packet on.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `want_response` - A `bool` that specifies whether or not the radio should wait for a response
+    ///     from other nodes on the mesh.
+    /// * `echo_response` - A `bool` that specifies whether or not the radio should echo the packet back
+    ///     to the client.
+    /// * `reply_id` - An optional `u32` that specifies the ID of the packet to reply to.
+    /// * `emoji` - An optional `u32` that specifies the unicode emoji data to send with the packet.
+    /// * `priority` - A `Priority` enum that specifies the priority of the packet.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully dispatched to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Example 1: Send a low priority sensor reading
+    /// self.send_mesh_packet_with_priority(
+    ///     packet_router,
+    ///     byte_data.into(),
+    ///     protobufs::PortNum::PrivateApp,
+    ///     PacketDestination::Broadcast,
+    ///     0.into(),
+    ///     false,
+    ///     false,
+    ///     false,
+    ///     None,
+    ///     None,
+    ///     protobufs::mesh_packet::Priority::Background,
+    /// )
+    /// .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Return an error based on whether the packet is successfully dispatched to the radio.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_mesh_packet_with_priority<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        packet_data: EncodedMeshPacketData,
+        port_num: protobufs::PortNum,
+        destination: PacketDestination,
+        channel: MeshChannel,
+        want_ack: bool,
+        want_response: bool,
+        echo_response: bool,
+        reply_id: Option<u32>,
+        emoji: Option<u32>,
+        priority: protobufs::mesh_packet::Priority,
+    ) -> Result<(), Error> {
+        let mut mesh_packet = MeshPacketBuilder::new(port_num, packet_data)
+            .destination(destination)
+            .channel(channel)
+            .want_ack(want_ack)
+            .want_response(want_response)
+            .reply_id(reply_id)
+            .emoji(emoji)
+            .priority(priority)
+            .build(packet_router.source_node_id());
 
         if echo_response {
-            mesh_packet.rx_time = current_epoch_secs_u32();
-            packet_router
-                .handle_mesh_packet(mesh_packet.clone())
-                .map_err(|e| Error::PacketHandlerFailure {
-                    source: Box::new(e),
-                })?;
+            echo_mesh_packet(packet_router, &mut mesh_packet)?;
         }
 
         let payload_variant = Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet));
@@ -226,6 +326,152 @@ impl<State> ConnectedStreamApi<State> {
         Ok(())
     }
 
+    /// A helper method to reliably send encoded byte data to the radio within a MeshPacket wrapper.
+    /// The packet is sent with `want_ack` set, and this method waits for the packet to be acknowledged.
+    /// If no acknowledgement is received within the configured timeout, the packet is retransmitted
+    /// with the same packet id, up to the configured maximum number of retransmissions.
+    ///
+    /// Broadcast packets are considered delivered once an implicit acknowledgement is received. Direct
+    /// packets are retransmitted until an explicit acknowledgement or a negative acknowledgement is received.
+    /// If a direct packet only received an implicit acknowledgement before all retransmissions were exhausted,
+    /// the implicit acknowledgement is returned.
+    ///
+    /// This is intended for critical messages sent over lossy links. Note that the radio also retransmits
+    /// packets with `want_ack` set, so this method provides additional application-layer reliability.
+    ///
+    /// The number of reliable sends awaiting an acknowledgement at once is limited, to avoid overflowing the
+    /// outgoing packet queue of the radio. See the `set_max_in_flight_reliable_sends` method for details.
+    /// As this method borrows the connection until the packet is acknowledged, use the `ReliableSender`
+    /// returned by the `reliable_sender` method to await several acknowledgements concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    /// * `packet_data` - An `EncodedMeshPacketData` containing the byte data to send.
+    /// * `port_num` - A `PortNum` enum that specifies the port number to send the packet on.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the packet on.
+    /// * `want_response` - A `bool` that specifies whether or not the radio should wait for a response
+    ///     from other nodes on the mesh.
+    /// * `echo_response` - A `bool` that specifies whether or not the radio should echo the packet back
+    ///     to the client. The packet is only echoed once, regardless of retransmissions.
+    /// * `send_config` - A `ReliableSendConfig` that specifies the retransmission count and base timeout.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the final `AckStatus` of the packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let byte_data = "Hello, world!".to_string().into_bytes();
+    ///
+    /// let ack_status = stream_api
+    ///     .send_mesh_packet_reliable(
+    ///         packet_router,
+    ///         byte_data.into(),
+    ///         protobufs::PortNum::TextMessageApp,
+    ///         PacketDestination::Node(node_id),
+    ///         0.into(),
+    ///         false,
+    ///         true,
+    ///         ReliableSendConfig::default(),
+    ///     )
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send, or with `Error::MaxRetransmit` if no acknowledgement
+    /// is received after all retransmissions.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_mesh_packet_reliable<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        packet_data: EncodedMeshPacketData,
+        port_num: protobufs::PortNum,
+        destination: PacketDestination,
+        channel: MeshChannel,
+        want_response: bool,
+        echo_response: bool,
+        send_config: ReliableSendConfig,
+    ) -> Result<AckStatus, Error> {
+        self.reliable_sender()
+            .send_mesh_packet_reliable(
+                packet_router,
+                packet_data,
+                port_num,
+                destination,
+                channel,
+                want_response,
+                echo_response,
+                send_config,
+            )
+            .await
+    }
+
+    /// Returns a cloneable handle for sending mesh packets with application-layer retransmission.
+    ///
+    /// The `send_mesh_packet_reliable` method borrows the connection until the packet is acknowledged,
+    /// so only one reliable send can be in flight at a time through it. Each clone of the returned
+    /// `ReliableSender` can await an acknowledgement independently, e.g. from a separate task, bounded
+    /// by the limit set with the `set_max_in_flight_reliable_sends` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `ReliableSender` sharing the write queue, rate limit and in-flight limit of this connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reliable_sender = stream_api.reliable_sender();
+    ///
+    /// tokio::spawn(async move {
+    ///     reliable_sender
+    ///         .send_mesh_packet_reliable(
+    ///             &mut packet_router,
+    ///             byte_data.into(),
+    ///             protobufs::PortNum::TextMessageApp,
+    ///             PacketDestination::Node(node_id),
+    ///             0.into(),
+    ///             false,
+    ///             false,
+    ///             ReliableSendConfig::default(),
+    ///         )
+    ///         .await
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn reliable_sender(&self) -> ReliableSender {
+        ReliableSender::new(
+            self.packet_sender.clone(),
+            self.packet_broadcast_tx.clone(),
+            self.request_registry.clone(),
+            self.in_flight_limit.clone(),
+        )
+    }
+
     /// A helper method to send a raw `ToRadio` packet to the radio based on a provided `protobufs::to_radio::PayloadVariant`.
     /// This method is generally intended for advanced users and should only be used when the
     /// more specific "send" methods are not sufficient.
@@ -250,6 +496,8 @@ impl<State> ConnectedStreamApi<State> {
     /// # Errors
     ///
     /// Returns an error based on whether the packet is successfully encoded and dispatched to the radio.
+    /// Fails with `Error::ChannelDisabled` if a mesh packet is sent on a channel that the radio has reported
+    /// as disabled, rather than waiting for the radio to reject the packet.
     ///
     /// # Panics
     ///
@@ -259,66 +507,117 @@ impl<State> ConnectedStreamApi<State> {
         &mut self,
         payload_variant: Option<protobufs::to_radio::PayloadVariant>,
     ) -> Result<(), Error> {
-        let packet = protobufs::ToRadio { payload_variant };
-
-        let mut packet_buf = vec![];
-        packet.encode(&mut packet_buf)?;
-        self.send_raw(packet_buf.into()).await
+        self.packet_sender
+            .send_to_radio_packet(payload_variant)
+            .await
     }
 
-    /// A helper method to send a raw `ToRadio` packet to the radio based on an encoded `ToRadio` packet.
-    /// This method is generally intended for advanced users and should only be used when the
-    /// more specific "send" methods are not sufficient.
+    /// Returns the most recent `QueueStatus` reported by the radio, if any. The number of free
+    /// slots is decremented locally for every mesh packet sent since the status was reported.
     ///
     /// # Arguments
     ///
-    /// * `packet_buf` - A `Vec<u8>` containing the encoded `ToRadio` packet to send.
+    /// None
     ///
     /// # Returns
     ///
-    /// A result indicating whether the packet was successfully dispatched to the radio.
+    /// An optional `QueueStatus` containing the state of the radio's outgoing packet queue.
     ///
     /// # Examples
     ///
     /// ```
-    /// // Example 1: Encode and send a `ToRadio` packet based on variant
-    /// let packet = protobufs::ToRadio { payload_variant };
-    ///
-    /// let mut packet_buf: Vec<u8> = vec![];
-    /// packet.encode::<Vec<u8>>(&mut packet_buf)?;
-    /// self.send_raw(packet_buf).await?;
+    /// if let Some(queue_status) = stream_api.queue_status() {
+    ///     println!("{}/{} queue slots free", queue_status.free, queue_status.maxlen);
+    /// }
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error based on whether the packet is successfully encoded and dispatched to the radio.
-    /// This method will fail if the channel fails to send the encoded packet.
+    /// None
     ///
     /// # Panics
     ///
     /// None
     ///
-    pub async fn send_raw(&mut self, data: EncodedToRadioPacket) -> Result<(), Error> {
-        let channel = self.write_input_tx.clone();
-        let data_with_header = utils::format_data_packet(data)?;
+    pub fn queue_status(&self) -> Option<protobufs::QueueStatus> {
+        self.radio_state.queue_status()
+    }
 
-        channel
-            .send(data_with_header)
-            .map_err(|e| Error::InternalChannelError(e.into()))?;
+    /// Returns the node id of the connected radio, as reported within the `MyNodeInfo` packet the radio
+    /// sends during the `configure` handshake. This is commonly used to detect direct messages, or to
+    /// filter out packets sent by the local node.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The node id of the connected radio, or `None` if the radio has not yet reported it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(my_node_id) = stream_api.my_node_id() {
+    ///     println!("Connected to node {}", my_node_id);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn my_node_id(&self) -> Option<NodeId> {
+        self.radio_state.my_node_id()
+    }
 
-        Ok(())
+    /// Resolves the `id` field of a `User`, e.g. `!a1b2c3d4`, to the node number of the node that reported
+    /// it, by looking it up within the node database reported by the radio. Unlike `NodeId::from_user_id`,
+    /// this doesn't assume that the user id matches the node number of the node.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The user id to resolve.
+    ///
+    /// # Returns
+    ///
+    /// The `NodeId` of the node with the specified user id, or `None` if no such node has been reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(node_id) = stream_api.resolve_user_id("!a1b2c3d4") {
+    ///     println!("User !a1b2c3d4 is node {}", node_id);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn resolve_user_id(&self, user_id: &str) -> Option<NodeId> {
+        self.radio_state.resolve_user_id(user_id)
     }
 
-    /// A helper method to allow advanced users access to the internal `UnboundedSender` channel
-    /// used to send raw data to the radio. This method is generally intended for advanced users
-    /// and should only be used when the more specific "send" methods are not sufficient. This
-    /// method returns a copy of the internal `tokio::sync::mpsc::UnboundedSender` sender channel.
+    /// Returns the channel table of the connected radio, ordered by channel index.
     ///
-    /// This method is intended to be used when a user needs very low-level access to the radio
-    /// interface, for example to send a packet that isn't supported by the current "send" methods.
+    /// The channel table is populated from the `Channel` packets the radio sends during the
+    /// `configure` handshake, and is kept up to date when channels are updated via the
+    /// `update_channel_config` method, or when the radio reports a changed channel afterwards.
+    /// The table is empty until the radio has reported its channels.
     ///
-    /// **Note:** This sender is only intended to be used to send encoded packet data. This **does not**
-    /// include the 4 packet header bytes, which are attached to the payload in an internal worker thread.
+    /// **Note:** This method returns a copy of the channel table, as the table is shared with
+    /// the worker thread that processes incoming packets. Every call clones all channels, so code
+    /// that looks up channels frequently, e.g. for every received packet, should keep the returned
+    /// `Vec` or look up single channels using the `channel` method instead.
     ///
     /// # Arguments
     ///
@@ -326,12 +625,14 @@ impl<State> ConnectedStreamApi<State> {
     ///
     /// # Returns
     ///
-    /// Returns an `UnboundedSender` channel that can be used to send raw data to the radio.
+    /// A `Vec` containing all channels reported by the radio, including disabled channels.
     ///
     /// # Examples
     ///
     /// ```
-    /// let write_input_sender = stream_api.write_input_sender();
+    /// for channel in stream_api.channels() {
+    ///     println!("Channel {}: {:?}", channel.index, channel.settings);
+    /// }
     /// ```
     ///
     /// # Errors
@@ -342,15 +643,31 @@ impl<State> ConnectedStreamApi<State> {
     ///
     /// None
     ///
-    pub fn write_input_sender(&self) -> UnboundedSender<EncodedToRadioPacketWithHeader> {
-        self.write_input_tx.clone()
+    pub fn channels(&self) -> Vec<protobufs::Channel> {
+        self.radio_state.channels()
     }
-}
 
-// Public connection management API
+    /// Returns the channel with the specified index from the channel table of the connected radio,
+    /// if the radio has reported it. See the `channels` method for details on the channel table.
+    pub fn channel(&self, index: u32) -> Option<protobufs::Channel> {
+        self.radio_state.channel(index)
+    }
 
-impl StreamApi {
-    /// A method to create an unconfigured instance of the `StreamApi` struct.
+    /// Returns the channel that admin messages are sent on, which is the channel named `admin` (in any
+    /// case) if the channel table contains one, and the primary channel otherwise. This matches the
+    /// channel the other Meshtastic clients use for administration.
+    pub fn admin_channel(&self) -> MeshChannel {
+        self.radio_state
+            .channel_index_by_name("admin")
+            .and_then(|index| MeshChannel::new(index).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the capabilities of the connected radio, derived from its firmware version and the
+    /// `DeviceMetadata` it reports during the `configure` handshake. This allows applications to gate
+    /// features on the firmware version of the radio, e.g. PKI-encrypted direct messages. The capabilities
+    /// are informational only, as the library sends and decodes packets identically for all firmware
+    /// versions.
     ///
     /// # Arguments
     ///
@@ -358,12 +675,14 @@ impl StreamApi {
     ///
     /// # Returns
     ///
-    /// Returns an instance of the `StreamApi` struct with default values for all private fields.
+    /// The `DeviceCapabilities` of the radio, or `None` if the radio has not yet reported its metadata.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
+    /// if let Some(capabilities) = stream_api.device_capabilities() {
+    ///     println!("Radio runs firmware {:?}", capabilities.firmware_version);
+    /// }
     /// ```
     ///
     /// # Errors
@@ -374,39 +693,938 @@ impl StreamApi {
     ///
     /// None
     ///
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> StreamApi {
-        StreamApi
+    pub fn device_capabilities(&self) -> Option<DeviceCapabilities> {
+        self.radio_state.device_capabilities()
     }
 
-    /// A method to connect to a radio via a provided stream. This method is generic,
-    /// and requires the `stream` parameter to implement the `AsyncReadExt + AsyncWriteExt`.
+    /// Returns a `DeviceSnapshot` bundling the state the radio has reported, i.e. its `MyNodeInfo`,
+    /// metadata, node database, channel table, and configuration. Once the `configure` handshake has
+    /// completed, the snapshot contains the full state of the radio.
     ///
-    /// This method is used to configure a `StreamApi` instance to communicate with a radio,
-    /// usually via a serial port or a TCP connection. The user is expected to call the `connect`
-    /// method before calling the `configure` method. This method will spawn read and write worker
-    /// threads that will manage communication with the radio, as well as a message processing
-    /// thread. This method will also initialize a cancellation token used in the `disconnect` method.
+    /// If the `serde` feature is enabled, the snapshot can be serialized, e.g. so that applications can
+    /// persist it and restore their user interface on the next launch before reconnecting.
     ///
     /// # Arguments
     ///
-    /// * `stream` - A generic stream that implements the `AsyncReadExt + AsyncWriteExt` traits.
+    /// None
     ///
     /// # Returns
     ///
-    /// Returns an `UnboundedReceiver` that is used to receive decoded `FromRadio` packets.
+    /// A `DeviceSnapshot` of the state reported by the radio so far.
     ///
     /// # Examples
     ///
     /// ```
-    /// // Example 1: Connect to a serial port
-    /// let stream_api = StreamApi::new();
-    /// let serial_stream = build_serial_stream("/dev/ttyUSB0".to_string(), None, None, None)?;
-    /// let (decoded_listener, stream_api) = stream_api.connect(serial_stream).await;
+    /// stream_api.wait_for_config(Duration::from_secs(60)).await?;
     ///
-    /// // Example 2: Connect to a TCP port
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    /// let snapshot = stream_api.snapshot();
+    /// std::fs::write("snapshot.json", serde_json::to_string(&snapshot)?)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn snapshot(&self) -> DeviceSnapshot {
+        self.radio_state.snapshot()
+    }
+
+    /// Returns the most recent log records reported by the radio, oldest first, e.g. to display a device
+    /// console. The number of retained records is set by the `StreamApi::with_log_buffer_size` method.
+    ///
+    /// **Note:** The radio only sends log records to clients while an API client is connected if the
+    /// `debug_log_enabled` field of its device configuration is set.
+    pub fn recent_logs(&self) -> Vec<protobufs::LogRecord> {
+        self.radio_state.recent_logs()
+    }
+
+    /// Returns the device configuration of the connected radio, as reported within the `Config` packets
+    /// sent by the radio. Like the channel table, the configuration is populated during the `configure`
+    /// handshake and kept up to date when the radio reports changes afterwards, in which case a
+    /// `ConnectionEvent::ConfigChanged` event is emitted. Sections that have not been reported are `None`.
+    pub fn local_config(&self) -> protobufs::LocalConfig {
+        self.radio_state.local_config()
+    }
+
+    /// Returns whether the connected radio is managed by a mesh administrator, as indicated by the
+    /// `is_managed` field of its device configuration. Clients should limit the configuration and
+    /// administrative options they offer for managed radios, e.g. by disabling their admin UI.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// `true` if the radio reported that it is managed, or `false` if it is not managed or has not
+    /// yet reported its device configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if stream_api.is_managed() {
+    ///     println!("This radio is managed by a mesh administrator");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn is_managed(&self) -> bool {
+        self.radio_state.is_managed()
+    }
+
+    /// Returns the module configuration of the connected radio, as reported within the `ModuleConfig`
+    /// packets sent by the radio. See the `local_config` method for details.
+    pub fn local_module_config(&self) -> protobufs::LocalModuleConfig {
+        self.radio_state.local_module_config()
+    }
+
+    /// Sets the behavior of the "send" methods when the radio reports that its outgoing packet
+    /// queue is full. By default, packets are sent regardless of the reported queue status.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue_full_behavior` - A `QueueFullBehavior` enum that specifies how to handle a full radio queue.
+    ///
+    /// # Returns
+    ///
+    /// None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.set_queue_full_behavior(QueueFullBehavior::Await);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn set_queue_full_behavior(&mut self, queue_full_behavior: QueueFullBehavior) {
+        self.packet_sender
+            .set_queue_full_behavior(queue_full_behavior);
+    }
+
+    /// Limits the rate at which mesh packets are sent to the radio, to avoid flooding the mesh.
+    /// Rate limiting is disabled by default.
+    ///
+    /// The limit is enforced using a token bucket, which allows a burst of up to `packets_per_minute`
+    /// packets to be sent at once, and then refills at a rate of `packets_per_minute` packets per minute.
+    /// Once the limit is reached, the "send" methods wait until another packet may be sent. Only mesh
+    /// packets are limited; other packets sent to the radio (e.g. heartbeats) are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `packets_per_minute` - The maximum number of mesh packets to send per minute, or `None`
+    ///     to disable rate limiting. A limit of zero is treated as a limit of one packet per minute.
+    ///
+    /// # Returns
+    ///
+    /// None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Send at most 10 packets per minute
+    /// stream_api.set_rate_limit(Some(10));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn set_rate_limit(&mut self, packets_per_minute: Option<u32>) {
+        self.packet_sender.set_rate_limit(packets_per_minute);
+    }
+
+    /// Returns the maximum number of mesh packets sent per minute, or `None` if rate limiting is disabled.
+    pub fn rate_limit(&self) -> Option<u32> {
+        self.packet_sender.rate_limit()
+    }
+
+    /// Limits the number of reliable sends that may await an acknowledgement at once. Once the limit is
+    /// reached, further calls to the `send_mesh_packet_reliable` method wait until an in-flight packet
+    /// has been acknowledged or has failed.
+    ///
+    /// The outgoing packet queue of the radio is small, so sending many reliable packets at once could
+    /// otherwise overflow the queue, causing the radio to drop packets. By default, the limit is the queue
+    /// length reported by the radio's `QueueStatus` packets, or `DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS` if the
+    /// radio has not reported its queue status.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of in-flight reliable sends, or `None` to use the default limit.
+    ///     A limit of zero is treated as a limit of one.
+    ///
+    /// # Returns
+    ///
+    /// None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.set_max_in_flight_reliable_sends(Some(2));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn set_max_in_flight_reliable_sends(&mut self, limit: Option<usize>) {
+        self.in_flight_limit.set_configured(limit);
+    }
+
+    /// Returns the maximum number of reliable sends that may currently await an acknowledgement at once.
+    pub fn max_in_flight_reliable_sends(&self) -> usize {
+        self.in_flight_limit.limit()
+    }
+
+    /// Returns a receiver that tracks the health of the connection to the radio.
+    ///
+    /// The health is updated by the worker threads whenever data is received from the radio, a packet
+    /// is decoded, or a heartbeat is sent, and reflects the state of the connection. This can be used
+    /// to detect a radio that has silently stopped responding, e.g. when a TCP connection is dropped
+    /// without being closed.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `watch::Receiver` containing the current `ConnectionHealth`, which is notified whenever
+    /// the health of the connection changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut health = stream_api.health();
+    ///
+    /// while health.changed().await.is_ok() {
+    ///     if health.borrow().is_stale(Duration::from_secs(60)) {
+    ///         println!("Radio has stopped responding");
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn health(&self) -> watch::Receiver<ConnectionHealth> {
+        self.health_monitor.subscribe()
+    }
+
+    /// Returns a receiver of the lifecycle events of the connection to the radio, such as the start
+    /// and completion of the configuration handshake, reboots of the radio, and disconnection.
+    ///
+    /// The events are delivered separately from the packets received through the `PacketReceiver`, so
+    /// that applications can drive their connection state without inferring it from packet contents.
+    /// The current state of the connection (e.g. `ConnectionEvent::Connected`) is delivered as the
+    /// first event. Each call returns an independent receiver, which buffers up to
+    /// `CONNECTION_EVENT_CAPACITY` events; further events are dropped until the receiver catches up.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// An `mpsc::Receiver` of `ConnectionEvent` enums.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut connection_events = stream_api.connection_events();
+    ///
+    /// while let Some(event) = connection_events.recv().await {
+    ///     match event {
+    ///         ConnectionEvent::ConfigComplete => println!("Radio is ready"),
+    ///         ConnectionEvent::Disconnected => break,
+    ///         _ => (),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn connection_events(&self) -> mpsc::Receiver<ConnectionEvent> {
+        self.health_monitor.subscribe_events()
+    }
+
+    /// Returns a receiver of the data discarded by the receive pipeline after this call, such as
+    /// packets that fail to decode, along with a `DropReason` describing why the data was discarded.
+    /// Packets skipped by the packet and event streams of this struct, e.g. because the stream fell
+    /// behind, are reported as well. This is intended for debugging flaky links, where packets
+    /// silently disappear otherwise.
+    ///
+    /// Diagnostics must be enabled with the `StreamApi::with_drop_diagnostics` method before connecting.
+    /// Each call returns an independent receiver, which buffers up to `DROPPED_PACKET_CAPACITY` reports;
+    /// the oldest reports are skipped if the receiver falls further behind.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `broadcast::Receiver` of `DroppedPacket` structs, or `None` if diagnostics are disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new().with_drop_diagnostics(true);
+    /// let (decoded_listener, stream_api) = stream_api.connect(stream_handle).await;
+    ///
+    /// if let Some(mut dropped_packets) = stream_api.dropped_packets() {
+    ///     while let Ok(dropped_packet) = dropped_packets.recv().await {
+    ///         println!("Dropped {} bytes: {:?}", dropped_packet.raw.len(), dropped_packet.reason);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn dropped_packets(&self) -> Option<broadcast::Receiver<DroppedPacket>> {
+        self.drop_diagnostics.subscribe()
+    }
+
+    /// Registers a callback that is called with every packet received from the radio after this call,
+    /// as an alternative to receiving packets through the `PacketReceiver` channel.
+    ///
+    /// The callback is called from a spawned task, in the order the packets were received. The task
+    /// stops once the `disconnect` method is called or the connection to the radio is closed. Packets
+    /// are still delivered to the `PacketReceiver` channel, so several callbacks can be registered
+    /// alongside it.
+    ///
+    /// **Note:** The callback should return quickly, as packets are skipped if the callback falls too
+    /// far behind the incoming packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - A closure that is called with each received `FromRadio` packet.
+    ///
+    /// # Returns
+    ///
+    /// None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.on_packet(|packet| {
+    ///     println!("Received packet: {:?}", packet);
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    ///
+    pub fn on_packet(&mut self, mut callback: impl FnMut(protobufs::FromRadio) + Send + 'static) {
+        let mut packet_rx = self.subscribe_packets();
+        let cancellation_token = self.cancellation_token.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let packet = tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    packet = packet_rx.recv() => packet,
+                };
+
+                match packet {
+                    Ok(received_packet) => callback(received_packet.packet),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Packet callback lagged behind, skipped {skipped} packets");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+
+            debug!("Packet callback task finished");
+        });
+    }
+
+    /// A helper method to send a raw `ToRadio` packet to the radio based on an encoded `ToRadio` packet.
+    /// This method is generally intended for advanced users and should only be used when the
+    /// more specific "send" methods are not sufficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_buf` - A `Vec<u8>` containing the encoded `ToRadio` packet to send.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully dispatched to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Example 1: Encode and send a `ToRadio` packet based on variant
+    /// let packet = protobufs::ToRadio { payload_variant };
+    ///
+    /// let mut packet_buf: Vec<u8> = vec![];
+    /// packet.encode::<Vec<u8>>(&mut packet_buf)?;
+    /// self.send_raw(packet_buf).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error based on whether the packet is successfully encoded and dispatched to the radio.
+    /// This method will fail if the channel fails to send the encoded packet.
+    ///
+    /// Packets are written to the stream after this method returns. If writing a packet fails with a
+    /// recoverable error before any of it was written, the packet is dropped and the error is reported by
+    /// the next call to the `flush` method, while the connection stays open. Fatal stream errors, e.g. a
+    /// broken pipe or a detached serial port, and errors after part of a packet was written close the
+    /// connection, after which this method fails.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_raw(&mut self, data: EncodedToRadioPacket) -> Result<(), Error> {
+        // Raw packets are written in the order they were sent, relative to all other packets
+        self.packet_sender.enqueue_raw(data, None)
+    }
+
+    /// Sends packet data that already carries the 4-byte packet header to the radio, without re-encoding
+    /// it. This allows bridges to forward packets received from another source as-is, and allows tests
+    /// to inject exact byte sequences.
+    ///
+    /// The header is validated before the data is queued, i.e. the data must start with the `START1`
+    /// and `START2` magic bytes, followed by the big-endian length of the remaining data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - An `EncodedToRadioPacketWithHeader` containing a single framed `ToRadio` packet.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully dispatched to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let framed_packet = utils::format_data_packet(packet.encode_to_vec().into())?;
+    /// stream_api.send_raw_to_radio(framed_packet).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidFramedPacket` if the data does not start with a valid packet header, or
+    /// if the length within the header does not match the length of the data. Fails if the channel
+    /// fails to send the packet, as described for the `send_raw` method.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_raw_to_radio(
+        &mut self,
+        data: EncodedToRadioPacketWithHeader,
+    ) -> Result<(), Error> {
+        let (length, payload) = match data.data() {
+            [START1, START2, msb, lsb, payload @ ..] => {
+                (usize::from(*msb) << 8 | usize::from(*lsb), payload)
+            }
+            _ => {
+                return Err(Error::InvalidFramedPacket {
+                    description: "Packet does not start with the packet header magic bytes"
+                        .to_string(),
+                })
+            }
+        };
+
+        if length != payload.len() {
+            return Err(Error::InvalidFramedPacket {
+                description: format!(
+                    "Packet header specifies {length} bytes, but {} bytes follow the header",
+                    payload.len()
+                ),
+            });
+        }
+
+        self.packet_sender.enqueue_framed(data, None)
+    }
+
+    /// Returns the number of packets that are queued on the client side, waiting to be written to the radio.
+    /// Queued packets are written in order of their `MeshPacket.priority`.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The number of packets waiting to be written to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// println!("{} packets waiting to be written", stream_api.write_queue_depth());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn write_queue_depth(&self) -> usize {
+        self.packet_sender.write_queue_depth()
+    }
+
+    /// A method that waits until all packets that have been sent so far have been written to the
+    /// underlying stream, and the stream has been flushed. Once this method returns, the packet data
+    /// has left the host's buffers (e.g. the OS or serial driver buffers).
+    ///
+    /// This is intended for short-lived applications, such as CLI tools, that send a packet and then
+    /// immediately disconnect or exit. Without flushing, the packet may be lost when the process exits.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether all queued packets were written and the stream was flushed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.send_text(packet_router, "Hello world!".to_string(), PacketDestination::Broadcast, true, 0.into()).await?;
+    /// stream_api.flush().await?;
+    /// stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the write worker thread has terminated, or if writing to or flushing the stream fails.
+    /// A failure to write any packet sent since the previous flush is reported here, even though the
+    /// connection stays open when the write error is recoverable.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+
+        self.flush_request_tx
+            .send(response_tx)
+            .map_err(|_| Error::InternalChannelError(InternalChannelError::ChannelClosedEarly))?;
+
+        response_rx
+            .await
+            .map_err(|_| Error::InternalChannelError(InternalChannelError::ChannelClosedEarly))?
+    }
+
+    /// A helper method to allow advanced users access to the internal `UnboundedSender` channel
+    /// used to send raw data to the radio. This method is generally intended for advanced users
+    /// and should only be used when the more specific "send" methods are not sufficient. This
+    /// method returns a copy of the internal `tokio::sync::mpsc::UnboundedSender` sender channel.
+    ///
+    /// This method is intended to be used when a user needs very low-level access to the radio
+    /// interface, for example to send a packet that isn't supported by the current "send" methods.
+    ///
+    /// **Note:** This sender is only intended to be used to send encoded packet data. This **does not**
+    /// include the 4 packet header bytes, which are attached to the payload in an internal worker thread.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// Returns an `UnboundedSender` channel that can be used to send raw data to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let write_input_sender = stream_api.write_input_sender();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn write_input_sender(&self) -> UnboundedSender<EncodedToRadioPacketWithHeader> {
+        self.packet_sender.write_input_sender()
+    }
+
+    /// Injects a packet into a simulated node (e.g. one started by the Meshtasticator simulator) as
+    /// if the node had received the packet over LoRa. The packet is wrapped in a `SimulatorApp` packet
+    /// using the `MeshPacket::to_simulator_packet` method before it is sent to the radio.
+    ///
+    /// **Note:** This method is only supported by firmware built with a simulated radio.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesh_packet` - The packet to inject, which must contain a decoded payload.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully dispatched to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Forward a packet transmitted by one simulated node to another
+    /// if let Some(transmitted_packet) = mesh_packet.from_simulator_packet() {
+    ///     other_node_api.send_simulated_packet(transmitted_packet).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidSimulatorPacket` if the packet does not contain a decoded payload, or
+    /// if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_simulated_packet(
+        &mut self,
+        mesh_packet: protobufs::MeshPacket,
+    ) -> Result<(), Error> {
+        let simulator_packet =
+            mesh_packet
+                .to_simulator_packet()
+                .ok_or_else(|| Error::InvalidSimulatorPacket {
+                    description: "simulated packets must contain a decoded payload".to_string(),
+                })?;
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            simulator_packet,
+        )))
+        .await
+    }
+
+    /// Waits for the response to the request with the specified packet id, i.e. a packet on the
+    /// specified port with a `request_id` matching the request, and returns the response along with
+    /// its decoded payload. Routing errors reported for the request are returned as `Error::RequestFailed`.
+    async fn await_response(
+        &self,
+        packet_rx: &mut broadcast::Receiver<ReceivedPacket>,
+        request: &PendingRequest,
+        port_num: protobufs::PortNum,
+        timeout: Duration,
+    ) -> Result<(protobufs::MeshPacket, protobufs::Data), Error> {
+        let packet_id = request.packet_id();
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            let received = tokio::select! {
+                _ = request.cancelled() => return Err(Error::RequestCancelled { packet_id }),
+                received = tokio::time::timeout_at(deadline, packet_rx.recv()) => received,
+            };
+
+            let packet = match received {
+                Err(_elapsed) => return Err(Error::ResponseTimeout { packet_id, timeout }),
+                Ok(Ok(packet)) => packet,
+                Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+                    warn!("Skipped {skipped} packets while awaiting response to {packet_id}");
+                    continue;
+                }
+                Ok(Err(broadcast::error::RecvError::Closed)) => {
+                    return Err(Error::InternalChannelError(
+                        InternalChannelError::ChannelClosedEarly,
+                    ));
+                }
+            };
+
+            let Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) =
+                packet.packet.payload_variant
+            else {
+                continue;
+            };
+
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant.clone()
+            else {
+                continue;
+            };
+
+            if data.request_id != packet_id {
+                continue;
+            }
+
+            if data.portnum == port_num as i32 {
+                return Ok((mesh_packet, data));
+            }
+
+            if data.portnum != protobufs::PortNum::RoutingApp as i32 {
+                continue;
+            }
+
+            let error_reason = match protobufs::Routing::decode(data.payload.as_slice()) {
+                Ok(protobufs::Routing {
+                    variant: Some(protobufs::routing::Variant::ErrorReason(error_reason)),
+                }) => error_reason,
+                _ => continue,
+            };
+
+            match protobufs::routing::Error::try_from(error_reason) {
+                Ok(protobufs::routing::Error::None) => continue,
+                reason => {
+                    return Err(Error::RequestFailed {
+                        packet_id,
+                        reason: reason.unwrap_or(protobufs::routing::Error::GotNak),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Subscribes to all decoded packets received from the radio after this call.
+    pub(crate) fn subscribe_packets(&self) -> broadcast::Receiver<ReceivedPacket> {
+        self.packet_broadcast_tx.subscribe()
+    }
+
+    /// Subscribes to all decoded packets received from the radio after this call, for a stream
+    /// that reports the packets it skips to the drop diagnostics of this connection.
+    pub(crate) fn packet_subscription(&self) -> PacketSubscription {
+        PacketSubscription::new(self.subscribe_packets(), self.drop_diagnostics.clone())
+    }
+
+    /// Returns a handle to the state reported by the radio, which is shared with the worker threads.
+    pub(crate) fn radio_state(&self) -> Arc<RadioState> {
+        self.radio_state.clone()
+    }
+
+    /// Returns the token that shuts down the worker threads of this connection when cancelled.
+    pub(crate) fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+}
+
+/// Echoes an outgoing `MeshPacket` back to the client through the provided packet router.
+pub(crate) fn echo_mesh_packet<
+    M,
+    E: Display + std::error::Error + Send + Sync + 'static,
+    R: PacketRouter<M, E>,
+>(
+    packet_router: &mut R,
+    mesh_packet: &mut protobufs::MeshPacket,
+) -> Result<(), Error> {
+    mesh_packet.rx_time = current_epoch_secs_u32();
+    packet_router
+        .handle_mesh_packet(mesh_packet.clone())
+        .map_err(|e| Error::PacketHandlerFailure {
+            source: Box::new(e),
+        })?;
+
+    Ok(())
+}
+
+// Public connection management API
+
+impl StreamApi {
+    /// A method to create an unconfigured instance of the `StreamApi` struct.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// Returns an instance of the `StreamApi` struct with default values for all private fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> StreamApi {
+        StreamApi {
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            log_buffer_size: DEFAULT_LOG_BUFFER_SIZE,
+            drop_diagnostics: false,
+        }
+    }
+
+    /// Sets the maximum number of bytes of undecoded data buffered while waiting for the remainder
+    /// of a packet from the radio. This bounds the memory used when a device sends garbage data.
+    /// If the limit is exceeded, the buffered data is discarded and decoding resumes at the next
+    /// packet header, and a `ConnectionEvent::BufferOverflow` event is emitted. Defaults to
+    /// `DEFAULT_MAX_BUFFER_SIZE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_buffer_size` - The maximum number of bytes of undecoded data to buffer.
+    ///
+    /// # Returns
+    ///
+    /// The `StreamApi` instance with the updated buffer size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new().with_max_buffer_size(16 * 1024);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_max_buffer_size(mut self, max_buffer_size: usize) -> StreamApi {
+        self.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Sets the number of log records reported by the radio that are retained for the `recent_logs`
+    /// method of the `ConnectedStreamApi` struct. Once the buffer is full, the oldest records are
+    /// discarded. A size of 0 disables log buffering. Defaults to `DEFAULT_LOG_BUFFER_SIZE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_buffer_size` - The maximum number of log records to retain.
+    ///
+    /// # Returns
+    ///
+    /// The `StreamApi` instance with the updated log buffer size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new().with_log_buffer_size(500);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_log_buffer_size(mut self, log_buffer_size: usize) -> StreamApi {
+        self.log_buffer_size = log_buffer_size;
+        self
+    }
+
+    /// Enables reporting of the data discarded by the receive pipeline, e.g. packets that fail to
+    /// decode, through the `dropped_packets` method of the `ConnectedStreamApi` struct. This is
+    /// disabled by default, as every discarded packet is copied while diagnostics are enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether discarded data should be reported.
+    ///
+    /// # Returns
+    ///
+    /// The `StreamApi` instance with the updated diagnostics setting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new().with_drop_diagnostics(true);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_drop_diagnostics(mut self, enabled: bool) -> StreamApi {
+        self.drop_diagnostics = enabled;
+        self
+    }
+
+    /// A method to connect to a radio via a provided stream. This method is generic,
+    /// and requires the `stream` parameter to implement the `AsyncReadExt + AsyncWriteExt`.
+    ///
+    /// This method is used to configure a `StreamApi` instance to communicate with a radio,
+    /// usually via a serial port or a TCP connection. The user is expected to call the `connect`
+    /// method before calling the `configure` method. This method will spawn read and write worker
+    /// threads that will manage communication with the radio, as well as a message processing
+    /// thread. This method will also initialize a cancellation token used in the `disconnect` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A generic stream that implements the `AsyncReadExt + AsyncWriteExt` traits.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `UnboundedReceiver` that is used to receive decoded `FromRadio` packets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Example 1: Connect to a serial port
+    /// let stream_api = StreamApi::new();
+    /// let serial_stream = build_serial_stream("/dev/ttyUSB0".to_string(), None, None, None)?;
+    /// let (decoded_listener, stream_api) = stream_api.connect(serial_stream).await;
+    ///
+    /// // Example 2: Connect to a TCP port
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
     /// ```
     ///
     /// # Errors
@@ -421,226 +1639,1737 @@ impl StreamApi {
         self,
         stream_handle: StreamHandle<S>,
     ) -> (PacketReceiver, ConnectedStreamApi<state::Connected>)
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+    {
+        let radio_state = RadioState::with_log_capacity(self.log_buffer_size);
+
+        self.connect_with_radio_state(stream_handle, radio_state)
+    }
+
+    /// Connects to a radio like the `connect` method, but seeds the state of the connection with a
+    /// `DeviceSnapshot` previously returned by the `snapshot` method of the `ConnectedStreamApi` struct.
+    ///
+    /// This allows applications that repeatedly connect to the same radio to display its known nodes,
+    /// channels, and configuration immediately, rather than waiting for the `configure` handshake to
+    /// complete. The restored state is updated as the radio reports its current state; nodes the radio
+    /// no longer reports are retained until the `refresh_config` method is called.
+    ///
+    /// **Note:** The snapshot is not checked against the connected radio. Applications should only
+    /// restore a snapshot of the radio they are connecting to, e.g. by storing snapshots per device.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream_handle` - A handle of the stream to the radio, as passed to the `connect` method.
+    /// * `snapshot` - A `DeviceSnapshot` of the radio, e.g. saved when the application last exited.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `UnboundedReceiver` that is used to receive decoded `FromRadio` packets, along with
+    /// the connected `ConnectedStreamApi` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let snapshot: DeviceSnapshot = serde_json::from_str(&std::fs::read_to_string("snapshot.json")?)?;
+    ///
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (decoded_listener, stream_api) = StreamApi::new()
+    ///     .connect_with_snapshot(tcp_stream, snapshot)
+    ///     .await;
+    ///
+    /// println!("Known nodes: {}", stream_api.snapshot().nodes.len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn connect_with_snapshot<S>(
+        self,
+        stream_handle: StreamHandle<S>,
+        snapshot: DeviceSnapshot,
+    ) -> (PacketReceiver, ConnectedStreamApi<state::Connected>)
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+    {
+        let radio_state = RadioState::with_log_capacity(self.log_buffer_size);
+        radio_state.restore_snapshot(snapshot);
+
+        self.connect_with_radio_state(stream_handle, radio_state)
+    }
+
+    fn connect_with_radio_state<S>(
+        self,
+        stream_handle: StreamHandle<S>,
+        radio_state: RadioState,
+    ) -> (PacketReceiver, ConnectedStreamApi<state::Connected>)
     where
         S: AsyncReadExt + AsyncWriteExt + Send + 'static,
     {
         // Create message channels
 
-        let (write_input_tx, write_input_rx) =
-            tokio::sync::mpsc::unbounded_channel::<EncodedToRadioPacketWithHeader>();
+        let (write_input_tx, write_input_rx) =
+            tokio::sync::mpsc::unbounded_channel::<EncodedToRadioPacketWithHeader>();
+
+        let (read_output_tx, read_output_rx) =
+            tokio::sync::mpsc::unbounded_channel::<IncomingStreamData>();
+
+        let (dispatch_input_tx, dispatch_input_rx) =
+            tokio::sync::mpsc::unbounded_channel::<ReceivedPacket>();
+
+        let (decoded_packet_tx, decoded_packet_rx) =
+            tokio::sync::mpsc::unbounded_channel::<protobufs::FromRadio>();
+
+        let (packet_broadcast_tx, _) =
+            broadcast::channel::<ReceivedPacket>(handlers::PACKET_BROADCAST_CAPACITY);
+
+        // Spawn worker threads with kill switch
+
+        let (read_stream, write_stream) = tokio::io::split(stream_handle.stream);
+        let cancellation_token = CancellationToken::new();
+
+        let health_monitor = stream_handle.health_monitor.unwrap_or_default();
+
+        let read_handle = handlers::spawn_read_handler(
+            cancellation_token.clone(),
+            read_stream,
+            read_output_tx,
+            health_monitor.clone(),
+        );
+
+        let write_queue = Arc::new(WriteQueue::new());
+
+        let (flush_request_tx, flush_request_rx) =
+            tokio::sync::mpsc::unbounded_channel::<handlers::FlushRequest>();
+
+        let write_handle = handlers::spawn_write_handler(
+            cancellation_token.clone(),
+            write_stream,
+            write_input_rx,
+            write_queue.clone(),
+            flush_request_rx,
+        );
+
+        let drop_diagnostics = if self.drop_diagnostics {
+            DropDiagnostics::enabled()
+        } else {
+            DropDiagnostics::disabled()
+        };
+
+        let processing_handle = handlers::spawn_processing_handler(
+            cancellation_token.clone(),
+            read_output_rx,
+            dispatch_input_tx,
+            self.max_buffer_size,
+            health_monitor.clone(),
+            drop_diagnostics.clone(),
+        );
+
+        let radio_state = Arc::new(radio_state);
+        let admin_session = AdminSession::new();
+
+        let dispatch_handle = handlers::spawn_dispatch_handler(
+            cancellation_token.clone(),
+            dispatch_input_rx,
+            decoded_packet_tx,
+            packet_broadcast_tx.clone(),
+            radio_state.clone(),
+            admin_session.clone(),
+            health_monitor.clone(),
+            drop_diagnostics.clone(),
+        );
+
+        let heartbeat_handle = handlers::spawn_heartbeat_handler(
+            cancellation_token.clone(),
+            write_input_tx.clone(),
+            health_monitor.clone(),
+        );
+
+        // Persist channels and kill switch to struct
+
+        let packet_sender = PacketSender::new(write_input_tx, write_queue, radio_state.clone());
+        let in_flight_limit = InFlightLimit::new(radio_state.clone());
+        let cancellation_token = cancellation_token;
+
+        // Return channel for receiving decoded packets
+
+        (
+            decoded_packet_rx,
+            ConnectedStreamApi::<state::Connected> {
+                packet_sender,
+                flush_request_tx,
+                read_handle,
+                write_handle,
+                processing_handle,
+                dispatch_handle,
+                heartbeat_handle,
+                packet_broadcast_tx,
+                radio_state,
+                admin_session,
+                request_registry: RequestRegistry::new(),
+                health_monitor,
+                in_flight_limit,
+                max_buffer_size: self.max_buffer_size,
+                drop_diagnostics,
+                cancellation_token,
+                typestate: PhantomData,
+            },
+        )
+    }
+
+    /// Connects to a radio in a passive, read-only mode, for observing a radio without disturbing it,
+    /// e.g. when monitoring a serial console that is shared with another client.
+    ///
+    /// If a `config_id` is passed, the `configure` handshake is started by sending a single `WantConfigId`
+    /// packet, so that the radio sends its configuration and starts forwarding packets. Afterwards, nothing
+    /// is written to the radio: no heartbeats are sent, and the returned `ReadOnlyStreamApi` struct exposes
+    /// no methods that send packets. If no `config_id` is passed, nothing is ever written to the radio.
+    ///
+    /// This mode comes with several tradeoffs:
+    ///
+    /// * Without the handshake, the radio only sends packets if it was put into API mode by another client.
+    ///     Serial consoles that are not in API mode only output text logs, which are discarded.
+    /// * Without heartbeats, the radio may consider the client gone and stop forwarding packets, e.g. the
+    ///     serial API of the firmware times out after 15 minutes without any writes from the client.
+    /// * The firmware quiets its debug logs while an API client is connected, so even the single
+    ///     `WantConfigId` packet changes what other observers of the stream see.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream_handle` - A handle of the stream to the radio, as passed to the `connect` method.
+    /// * `config_id` - An optional, randomly generated configuration ID used to start the handshake.
+    ///
+    /// # Returns
+    ///
+    /// A `PacketReceiver` of the decoded `FromRadio` packets, along with a `ReadOnlyStreamApi` struct that
+    /// reports the state of the connection and disconnects from the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let serial_stream = build_serial_stream("/dev/ttyUSB0".to_string(), None, None, None)?;
+    /// let (mut decoded_listener, stream_api) = StreamApi::new()
+    ///     .connect_readonly(serial_stream, Some(generate_rand_id()))
+    ///     .await?;
+    ///
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     println!("Observed packet: {:?}", packet);
+    /// }
+    ///
+    /// stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the `WantConfigId` packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn connect_readonly<S>(
+        self,
+        stream_handle: StreamHandle<S>,
+        config_id: Option<u32>,
+    ) -> Result<(PacketReceiver, ReadOnlyStreamApi), Error>
+    where
+        S: AsyncReadExt + AsyncWriteExt + Send + 'static,
+    {
+        let (packet_receiver, stream_api) = self.connect(stream_handle).await;
+
+        // The heartbeat handler waits for a full interval before its first heartbeat, so none is sent
+        stream_api.heartbeat_handle.abort();
+
+        let stream_api = match config_id {
+            Some(config_id) => stream_api.configure(config_id).await?,
+            None => stream_api.into_state(),
+        };
+
+        Ok((packet_receiver, ReadOnlyStreamApi::new(stream_api)))
+    }
+}
+
+impl ConnectedStreamApi<state::Connected> {
+    /// This method is used to trigger the transmission of the current state of the
+    /// radio, as well as to subscribe to future `FromRadio` mesh packets. This method
+    /// can only be called after the `connect` method has been called.
+    ///
+    /// This method triggers a `WantConfigId` packet to be sent to the radio containing
+    /// an arbitrary configuration identifier. This will tell the radio that the client
+    /// wants to connect. The radio will respond by sending its current configuration,
+    /// module configuration, and channel configuration. The radio will indicate that it
+    /// has finished transmission by sending a `ConfigComplete` packet, which will contain
+    /// the same configuration identifier that was sent in the `WantConfigId` packet. Tracking
+    /// whether or not configuration completes successfully is not handled by this method.
+    ///
+    /// Once a radio connection has been configured, the radio will send all future packets
+    /// it receives through the decoded packet channel. This will continue until the
+    /// `disconnect` method is called.
+    ///
+    /// The handshake can be awaited or cancelled through the `ConfigHandle` returned by the
+    /// `config_handle` method of the configured instance, and retried using the `reconfigure` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - A randomly generated configuration ID that will be used
+    ///     to check that the configuration process has completed.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating whether or not the configuration was successful.
+    /// The configuration will fail if the `WantConfigId` packet fails to send.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    ///
+    /// let config_id = gen_random_id();
+    /// stream_api.configure(config_id).await?;
+    ///
+    /// while let Some(packet) = decoded_listener.recv().await {
+    ///     // Process packets
+    /// }
+    ///
+    /// stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the `WantConfigId` packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn configure(
+        mut self,
+        config_id: u32,
+    ) -> Result<ConnectedStreamApi<state::Configured>, Error> {
+        let to_radio = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::WantConfigId(config_id)),
+        };
+
+        let packet_buf: EncodedToRadioPacket = to_radio.encode_to_vec().into();
+
+        self.radio_state.start_config_handshake(config_id);
+
+        if let Err(e) = self.send_raw(packet_buf).await {
+            self.radio_state.cancel_config_handshake(config_id);
+            return Err(e);
+        }
+
+        self.health_monitor.emit(ConnectionEvent::ConfigStarted);
+
+        Ok(self.into_state())
+    }
+
+    /// Moves the connection into another typestate, without communicating with the radio.
+    fn into_state<NewState>(self) -> ConnectedStreamApi<NewState> {
+        ConnectedStreamApi::<NewState> {
+            packet_sender: self.packet_sender,
+            flush_request_tx: self.flush_request_tx,
+            read_handle: self.read_handle,
+            write_handle: self.write_handle,
+            processing_handle: self.processing_handle,
+            dispatch_handle: self.dispatch_handle,
+            heartbeat_handle: self.heartbeat_handle,
+            packet_broadcast_tx: self.packet_broadcast_tx,
+            radio_state: self.radio_state,
+            admin_session: self.admin_session,
+            request_registry: self.request_registry,
+            health_monitor: self.health_monitor,
+            in_flight_limit: self.in_flight_limit,
+            max_buffer_size: self.max_buffer_size,
+            drop_diagnostics: self.drop_diagnostics,
+            cancellation_token: self.cancellation_token,
+            typestate: PhantomData,
+        }
+    }
+}
+
+impl ConnectedStreamApi<state::Configured> {
+    /// Collects all packets that are currently queued within the passed `PacketReceiver`, without
+    /// waiting for further packets to arrive. This is intended to be called right before the
+    /// `disconnect` method, so that packets received shortly before shutting down are not lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_receiver` - The `PacketReceiver` returned by the `connect` method.
+    ///
+    /// # Returns
+    ///
+    /// The queued `FromRadio` packets, in the order they were received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for packet in stream_api.drain(&mut decoded_listener) {
+    ///     log_packet(packet);
+    /// }
+    ///
+    /// stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn drain(&mut self, packet_receiver: &mut PacketReceiver) -> Vec<protobufs::FromRadio> {
+        let mut packets = Vec::with_capacity(packet_receiver.len());
+
+        while let Ok(packet) = packet_receiver.try_recv() {
+            packets.push(packet);
+        }
+
+        packets
+    }
+
+    /// Returns a `ConfigHandle` for the most recent `configure` handshake, which can be used to wait
+    /// for the radio to finish sending its configuration, or to cancel a stalled handshake.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `ConfigHandle` for the most recent handshake, or `None` if no handshake has been started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = stream_api.configure(config_id).await?;
+    /// let config_handle = stream_api.config_handle().unwrap();
+    ///
+    /// tokio::time::timeout(Duration::from_secs(30), config_handle.wait()).await??;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn config_handle(&self) -> Option<ConfigHandle> {
+        let config_id = match self.radio_state.config_handshake()? {
+            ConfigHandshake::Pending(config_id)
+            | ConfigHandshake::Complete(config_id)
+            | ConfigHandshake::Cancelled(config_id) => config_id,
+        };
+
+        Some(ConfigHandle::new(config_id, self.radio_state.clone()))
+    }
+
+    /// Restarts the `configure` handshake by sending a new `WantConfigId` packet, without reconnecting
+    /// to the radio. Any handshake that is still pending is superseded, and waiting on its `ConfigHandle`
+    /// resolves to `Error::ConfigCancelled`. The radio responds by sending its full configuration again.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - A randomly generated configuration ID, which must differ from the id of the
+    ///     handshake being retried.
+    ///
+    /// # Returns
+    ///
+    /// A `ConfigHandle` for the new handshake.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config_handle = stream_api.config_handle().unwrap();
+    ///
+    /// if tokio::time::timeout(Duration::from_secs(30), config_handle.wait()).await.is_err() {
+    ///     let config_handle = stream_api.reconfigure(utils::generate_rand_id()).await?;
+    ///     config_handle.wait().await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the `WantConfigId` packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn reconfigure(&mut self, config_id: u32) -> Result<ConfigHandle, Error> {
+        let to_radio = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::WantConfigId(config_id)),
+        };
+
+        self.radio_state.start_config_handshake(config_id);
+
+        if let Err(e) = self.send_raw(to_radio.encode_to_vec().into()).await {
+            self.radio_state.cancel_config_handshake(config_id);
+            return Err(e);
+        }
+
+        self.health_monitor.emit(ConnectionEvent::ConfigStarted);
+
+        Ok(ConfigHandle::new(config_id, self.radio_state.clone()))
+    }
+
+    /// Waits until the radio has completed the most recent `configure` handshake, distinguishing radios
+    /// that are slow to respond from devices that are not Meshtastic radios at all.
+    ///
+    /// Every packet sent by a Meshtastic radio begins with the `START1` and `START2` framing bytes. If no
+    /// validly framed packet has been received from the device by the end of the timeout, the device is
+    /// most likely not a Meshtastic radio, e.g. because the wrong serial port was selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for the handshake to complete.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the handshake completed within the timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = stream_api.configure(config_id).await?;
+    ///
+    /// match stream_api.wait_for_config(Duration::from_secs(10)).await {
+    ///     Err(Error::NotAMeshtasticDevice { .. }) => println!("Please select a different port"),
+    ///     result => result?,
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::NotAMeshtasticDevice` if no valid packets were received from the device within
+    /// the timeout, with `Error::ConfigTimeout` if valid packets were received but the handshake did not
+    /// complete, and with `Error::ConfigCancelled` if the handshake was cancelled or superseded.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn wait_for_config(&self, timeout: Duration) -> Result<(), Error> {
+        let Some(config_handle) = self.config_handle() else {
+            return Ok(());
+        };
+
+        if let Ok(result) = tokio::time::timeout(timeout, config_handle.wait()).await {
+            return result;
+        }
+
+        let health = *self.health_monitor.subscribe().borrow();
+
+        match (health.last_byte_received, health.last_packet_received) {
+            (_, Some(_)) => Err(Error::ConfigTimeout {
+                config_id: config_handle.config_id(),
+                timeout,
+            }),
+            (Some(_), None) => Err(Error::NotAMeshtasticDevice {
+                description: "Data was received, but none of it was framed as a Meshtastic packet"
+                    .to_string(),
+            }),
+            (None, None) => Err(Error::NotAMeshtasticDevice {
+                description: "No data was received from the device".to_string(),
+            }),
+        }
+    }
+
+    /// Re-requests the configuration and node database of the radio without reconnecting, e.g. after
+    /// the configuration was changed remotely or the node database of the radio was reset.
+    ///
+    /// This method restarts the `configure` handshake with a fresh config id (see the `reconfigure`
+    /// method), and waits for the radio to finish sending its configuration. Once the handshake has
+    /// completed, the cached node database is replaced by the nodes reported during the handshake, so
+    /// that nodes the radio no longer knows about are not retained. If the handshake fails, is cancelled,
+    /// or the returned future is dropped, the cached node database is kept.
+    ///
+    /// **Note:** This method does not time out on its own, and is usually awaited within `tokio::time::timeout`.
+    /// If it times out, the handshake can be cancelled through the `ConfigHandle` returned by the
+    /// `config_handle` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `DeviceSnapshot` of the state reported by the radio once the handshake has completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let snapshot = tokio::time::timeout(Duration::from_secs(60), stream_api.refresh_config()).await??;
+    /// println!("Radio knows about {} nodes", snapshot.nodes.len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the `WantConfigId` packet fails to send, or with `Error::ConfigCancelled` if the handshake
+    /// is cancelled or superseded before it completes.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn refresh_config(&mut self) -> Result<DeviceSnapshot, Error> {
+        let node_refresh = self.radio_state.begin_node_refresh();
+
+        let config_handle = self.reconfigure(generate_rand_id()).await?;
+        config_handle.wait().await?;
+
+        node_refresh.finish();
+
+        Ok(self.radio_state.snapshot())
+    }
+
+    /// A method to disconnect from a radio. This method will close all channels and
+    /// join all worker threads. If connected via serial or TCP, this will also trigger
+    /// the radio to terminate its current connection.
+    ///
+    /// This method can only be called after the `configure` method has been called.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating whether or not the disconnection was successful.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    ///
+    /// // Process packets from the `decoded_listener` channel
+    ///
+    /// stream_api.disconnect().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will fail if any of the worker threads fail to join.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn disconnect(self) -> Result<StreamApi, Error> {
+        // Tell worker threads to shut down
+        self.cancellation_token.cancel();
+
+        // Close writer channel, which will kill worker threads
+
+        drop(self.packet_sender);
+
+        // Close worker threads
+
+        let (read_result, write_result, processing_result, dispatch_result) = join4(
+            self.read_handle,
+            self.write_handle,
+            self.processing_handle,
+            self.dispatch_handle,
+        )
+        .await;
+
+        // Note: we only return the first error.
+        read_result??;
+        write_result??;
+        processing_result??;
+        dispatch_result??;
+
+        self.health_monitor.set_state(ConnectionState::Disconnected);
+
+        trace!("Handlers fully disconnected");
+
+        Ok(StreamApi {
+            max_buffer_size: self.max_buffer_size,
+            log_buffer_size: self.radio_state.log_capacity(),
+            drop_diagnostics: self.drop_diagnostics.is_enabled(),
+        })
+    }
+}
+
+// Public node management API
+
+impl ConnectedStreamApi<state::Configured> {
+    /// Sends an arbitrary payload on a private application port. Port numbers from 256 (`PrivateApp`)
+    /// to 511 are reserved for custom applications, and are never used by the official firmware.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `port_num` - The private application port number to send the payload on, in the range [256..511].
+    /// * `payload` - The application-defined payload to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the packet on.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api
+    ///     .send_private(packet_router, 300, sensor_reading.to_le_bytes().to_vec(), PacketDestination::Broadcast, MeshChannel::default())
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidPrivatePortNum` if the port number is outside of the private range,
+    /// or if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_private<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        port_num: u32,
+        payload: Vec<u8>,
+        destination: PacketDestination,
+        channel: MeshChannel,
+    ) -> Result<(), Error> {
+        let mut mesh_packet = MeshPacketBuilder::new_private(port_num, payload.into())?
+            .destination(destination)
+            .channel(channel)
+            .build(packet_router.source_node_id());
+
+        echo_mesh_packet(packet_router, &mut mesh_packet)?;
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
+        .await
+    }
+
+    /// Sends the specified text content over the mesh.
+    ///
+    /// Texts longer than `DATA_PAYLOAD_LEN` bytes don't fit into a single mesh packet, so they are split
+    /// into chunks of at most `DATA_PAYLOAD_LEN` bytes, which are sent as separate `TextMessageApp` packets
+    /// in order. Chunks are only split at character boundaries, so that no multibyte character is split
+    /// between two packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `text` - A `String` containing the text to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    ///
+    /// # Returns
+    ///
+    /// A result containing the ids of the sent packets, in the order of the chunks of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    ///
+    /// let config_id = generate_rand_id();
+    /// let mut stream_api = stream_api.configure(config_id).await?;
+    ///
+    /// let packet_ids = stream_api.send_text(packet_router, "Hello world!".to_string(), PacketDestination::Broadcast, true, 0).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the packets fails to send. The chunks sent before the failure are not retracted.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_text<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        text: String,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel: MeshChannel,
+    ) -> Result<Vec<PacketId>, Error> {
+        let mut packet_ids = vec![];
+
+        for chunk in utils::split_text(&text, utils::DATA_PAYLOAD_LEN) {
+            let mut mesh_packet = MeshPacketBuilder::new(
+                protobufs::PortNum::TextMessageApp,
+                chunk.as_bytes().to_vec().into(),
+            )
+            .destination(destination)
+            .channel(channel)
+            .want_ack(want_ack)
+            .build(packet_router.source_node_id());
+
+            echo_mesh_packet(packet_router, &mut mesh_packet)?;
+
+            let packet_id = PacketId::new(mesh_packet.id);
+            self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+                mesh_packet,
+            )))
+            .await?;
+
+            packet_ids.push(packet_id);
+        }
+
+        Ok(packet_ids)
+    }
+
+    /// Sends the specified text content over the mesh, on the channel with the specified name.
+    ///
+    /// The channel name is resolved to a channel index using the channel table reported by the
+    /// radio during the `configure` handshake, ignoring case. Disabled channels are never matched.
+    /// Long texts are split into several packets, as described for the `send_text` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `text` - A `String` containing the text to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel_name` - The name of the channel to send the packet on.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the ids of the sent packets, in the order of the chunks of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api
+    ///     .send_text_on_channel(packet_router, "Hello admins!".to_string(), PacketDestination::Broadcast, true, "admin")
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::ChannelNotFound` if no enabled channel with the specified name has been
+    /// reported by the radio, or if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_text_on_channel<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        text: String,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel_name: &str,
+    ) -> Result<Vec<PacketId>, Error> {
+        let channel_index = self
+            .radio_state
+            .channel_index_by_name(channel_name)
+            .ok_or_else(|| Error::ChannelNotFound {
+                name: channel_name.to_string(),
+            })?;
+
+        self.send_text(
+            packet_router,
+            text,
+            destination,
+            want_ack,
+            MeshChannel::new(channel_index)?,
+        )
+        .await
+    }
+
+    /// Sends the specified `Waypoint` over the mesh.
+    ///
+    /// If the specified `Waypoint` struct has an `id` field of `0`, this method will generate
+    /// a random id for the waypoint and update the struct before sending. This is because 0
+    /// is an invalid waypoint ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `waypoint` - An instance of the `Waypoint` struct to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    ///
+    /// let config_id = generate_rand_id();
+    /// let mut stream_api = stream_api.configure(config_id).await?;
+    ///
+    /// let waypoint = crate::protobufs::Waypoint { ... };
+    /// stream_api.send_waypoint(packet_router, waypoint, PacketDestination::Broadcast, true, 0).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_waypoint<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        waypoint: crate::protobufs::Waypoint,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel: MeshChannel,
+    ) -> Result<(), Error> {
+        let mut waypoint = waypoint;
+
+        // Waypoint with ID of zero denotes a new waypoint; check whether to generate its ID on backend
+        if waypoint.id == 0 {
+            waypoint.id = generate_rand_id();
+        }
+
+        let byte_data: EncodedMeshPacketData = waypoint.encode_to_vec().into();
+
+        self.send_mesh_packet(
+            packet_router,
+            byte_data,
+            protobufs::PortNum::WaypointApp,
+            destination,
+            channel,
+            want_ack,
+            false,
+            true,
+            None,
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sends the specified `Positon` over the mesh.
+    ///
+    /// Sending a `Position` packet will update the internal position of the connected radio
+    /// in addition to sending the packet over the mesh.
+    ///
+    /// **Note:** If you want to send a point of interest (POI) over the mesh, use `send_waypoint`.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `position` - An instance of the `Position` struct to send.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream_api = StreamApi::new();
+    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
+    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
+    ///
+    /// let config_id = generate_rand_id();
+    /// let mut stream_api = stream_api.configure(config_id).await?;
+    ///
+    /// let position = crate::protobufs::Position { ... };
+    /// stream_api.send_position(packet_router, position, PacketDestination::Broadcast, true, 0).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_position<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        position: crate::protobufs::Position,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel: MeshChannel,
+    ) -> Result<(), Error> {
+        let byte_data: EncodedMeshPacketData = position.encode_to_vec().into();
+
+        self.send_mesh_packet(
+            packet_router,
+            byte_data,
+            protobufs::PortNum::PositionApp,
+            destination,
+            channel,
+            want_ack,
+            false,
+            true,
+            None,
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sets the clock of the connected radio to the specified time, e.g. to time-sync a radio without a GPS
+    /// receiver or RTC from the host.
+    ///
+    /// The protobufs do not define an admin message that only sets the time, so the time is sent to the
+    /// radio within a `Position` packet, in the same way as the official apps sync the time of the radio.
+    /// The packet is addressed to the local node, so it is not forwarded over the mesh.
+    ///
+    /// **Note:** The firmware also stores the coordinates of a `Position` packet sent by the client as the
+    /// position of the local node. To leave the position of the radio unchanged, the last position the radio
+    /// reported for the local node is sent along with the time. If no position has been reported, only the
+    /// `time` field is set. The firmware only sets its clock from packets sent on the primary channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `epoch_secs` - The time to set, in seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api.set_time(packet_router, utils::current_epoch_secs_u32()).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn set_time<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        epoch_secs: u32,
+    ) -> Result<(), Error> {
+        let last_position = self
+            .radio_state
+            .node(packet_router.source_node_id())
+            .and_then(|node_info| node_info.position);
+
+        let position = protobufs::Position {
+            time: epoch_secs,
+            ..last_position.unwrap_or_default()
+        };
+
+        self.send_position(
+            packet_router,
+            position,
+            PacketDestination::Local,
+            false,
+            MeshChannel::default(),
+        )
+        .await
+    }
+
+    /// Returns an estimate of the current time of the connected radio's clock, in seconds since the Unix
+    /// epoch. The estimate is based on the most recent timestamp reported by the radio, i.e. the `rx_time`
+    /// of a received packet or the `time` of a log record, advanced by the time elapsed since.
+    ///
+    /// **Note:** Packets that were queued by the radio while no client was connected carry older
+    /// timestamps, so the estimate is only accurate once live packets are received.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The estimated time of the radio, or `None` if the radio has not reported a timestamp, e.g.
+    /// because it does not know the time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if stream_api.device_time().is_none() {
+    ///     stream_api.set_time(packet_router, utils::current_epoch_secs_u32()).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn device_time(&self) -> Option<u32> {
+        self.radio_state.device_time()
+    }
+
+    /// Parses a GGA sentence reported by an external NMEA GPS receiver, and sends the resulting `Position`
+    /// over the mesh. See the `Position::from_nmea_gga` method for details on how the sentence is parsed.
+    ///
+    /// As with the `send_position` method, this also updates the internal position of the connected radio.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `nmea_gga` - A GGA sentence reported by the GPS receiver, e.g. `$GPGGA,...`.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
+    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
+    ///     from other nodes on the mesh.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the packet on.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the `Position` that was sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// while let Some(line) = gps_lines.next_line().await? {
+    ///     if line.contains("GGA") {
+    ///         stream_api
+    ///             .send_position_from_nmea(packet_router, &line, PacketDestination::Broadcast, false, 0.into())
+    ///             .await?;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidNmeaSentence` if the sentence cannot be parsed or reports no fix, or if
+    /// the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_position_from_nmea<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        nmea_gga: &str,
+        destination: PacketDestination,
+        want_ack: bool,
+        channel: MeshChannel,
+    ) -> Result<protobufs::Position, Error> {
+        let position = protobufs::Position::from_nmea_gga(nmea_gga)?;
 
-        let (read_output_tx, read_output_rx) =
-            tokio::sync::mpsc::unbounded_channel::<IncomingStreamData>();
+        self.send_position(packet_router, position, destination, want_ack, channel)
+            .await?;
 
-        let (decoded_packet_tx, decoded_packet_rx) =
-            tokio::sync::mpsc::unbounded_channel::<protobufs::FromRadio>();
+        Ok(position)
+    }
 
-        // Spawn worker threads with kill switch
+    /// Requests the current position of the specified node, and waits for the node to respond.
+    ///
+    /// This sends an empty `Position` packet to the node with `want_response` set, which causes the
+    /// node to reply with its current position. This is useful for refreshing the position of a
+    /// specific node without waiting for its next periodic position broadcast.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    /// * `node_id` - The id of the node to request the position of.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the request on.
+    /// * `timeout` - The maximum time to wait for a response.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the `Position` reported by the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let position = stream_api
+    ///     .request_position(packet_router, node_id, 0.into(), Duration::from_secs(60))
+    ///     .await?;
+    ///
+    /// println!("Node {} is at {}, {}", node_id, position.latitude_i, position.longitude_i);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails to send, with `Error::RequestFailed` if the mesh reports a routing
+    /// error for the request (e.g. `NoResponse` if the node does not share its position), or with
+    /// `Error::ResponseTimeout` if no response is received within the timeout.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn request_position<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        channel: MeshChannel,
+        timeout: Duration,
+    ) -> Result<protobufs::Position, Error> {
+        self.request_from_node(
+            packet_router,
+            node_id,
+            protobufs::PortNum::PositionApp,
+            channel,
+            timeout,
+        )
+        .await
+    }
 
-        let (read_stream, write_stream) = tokio::io::split(stream_handle.stream);
-        let cancellation_token = CancellationToken::new();
+    /// Requests the `User` information (e.g. the names and hardware model) of the specified node,
+    /// and waits for the node to respond.
+    ///
+    /// This sends an empty `User` packet to the node with `want_response` set, which causes the
+    /// node to reply with its `User` information.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    /// * `node_id` - The id of the node to request the information of.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the request on.
+    /// * `timeout` - The maximum time to wait for a response.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the `User` reported by the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let user = stream_api
+    ///     .request_node_info(packet_router, node_id, 0.into(), Duration::from_secs(60))
+    ///     .await?;
+    ///
+    /// println!("Node {} is named {}", node_id, user.long_name);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails to send, with `Error::RequestFailed` if the mesh reports a routing
+    /// error for the request, or with `Error::ResponseTimeout` if no response is received within the timeout.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn request_node_info<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        channel: MeshChannel,
+        timeout: Duration,
+    ) -> Result<protobufs::User, Error> {
+        self.request_from_node(
+            packet_router,
+            node_id,
+            protobufs::PortNum::NodeinfoApp,
+            channel,
+            timeout,
+        )
+        .await
+    }
+
+    /// Measures the round-trip time to the specified node, e.g. to check whether the node is reachable.
+    ///
+    /// This sends a packet on the `ReplyApp` port with `want_response` set, which the reply module of
+    /// the node answers with a reply packet. The round-trip time is measured from sending the packet to
+    /// the radio until the reply is received from the radio, and therefore includes the time the packet
+    /// spends within the transmit queues of the radios along the route.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    /// * `node_id` - The id of the node to ping.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the ping on.
+    /// * `timeout` - The maximum time to wait for the reply.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the round-trip time of the ping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let round_trip_time = stream_api
+    ///     .ping(packet_router, node_id, 0.into(), Duration::from_secs(60))
+    ///     .await?;
+    ///
+    /// println!("Reply from {} after {:?}", node_id, round_trip_time);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ping fails to send, with `Error::RequestFailed` if the mesh reports a routing
+    /// error for the ping (e.g. `NoResponse` if the reply module of the node is disabled), or with
+    /// `Error::ResponseTimeout` if no reply is received within the timeout.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn ping<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        channel: MeshChannel,
+        timeout: Duration,
+    ) -> Result<Duration, Error> {
+        let mesh_packet =
+            MeshPacketBuilder::new(protobufs::PortNum::ReplyApp, b"ping".to_vec().into())
+                .destination(PacketDestination::Node(node_id))
+                .channel(channel)
+                .want_response(true)
+                .build(packet_router.source_node_id());
 
-        let read_handle =
-            handlers::spawn_read_handler(cancellation_token.clone(), read_stream, read_output_tx);
+        let request = self.request_registry.register(mesh_packet.id);
 
-        let write_handle =
-            handlers::spawn_write_handler(cancellation_token.clone(), write_stream, write_input_rx);
+        // Subscribe before sending to avoid missing a fast reply
+        let mut packet_rx = self.subscribe_packets();
+        let sent_at = tokio::time::Instant::now();
 
-        let processing_handle = handlers::spawn_processing_handler(
-            cancellation_token.clone(),
-            read_output_rx,
-            decoded_packet_tx,
-        );
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
+        .await?;
 
-        let heartbeat_handle =
-            handlers::spawn_heartbeat_handler(cancellation_token.clone(), write_input_tx.clone());
+        self.await_response(
+            &mut packet_rx,
+            &request,
+            protobufs::PortNum::ReplyApp,
+            timeout,
+        )
+        .await?;
 
-        // Persist channels and kill switch to struct
+        Ok(sent_at.elapsed())
+    }
 
-        let write_input_tx = write_input_tx;
-        let cancellation_token = cancellation_token;
+    /// Starts a traceroute to the specified node, which discovers the nodes that relay packets between
+    /// the connected radio and the node.
+    ///
+    /// This sends an empty `RouteDiscovery` packet on the `TracerouteApp` port with `want_response` set.
+    /// Each node relaying the request appends its node id to the route, and the destination node answers
+    /// with a `RouteDiscovery` reply whose `request_id` is the packet id returned by this method. Unlike
+    /// `ping`, this method does not wait for the reply, as traceroutes across several hops can take minutes
+    /// to complete. The hops of the reply can be listed with the `RouteDiscovery::hops` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     The node id of the router is used as the source of the request.
+    /// * `node_id` - The id of the node to trace the route to.
+    /// * `channel` - A `MeshChannel` that specifies the message channel to send the request on.
+    ///
+    /// # Returns
+    ///
+    /// A result resolving to the packet id of the request, which is the `request_id` of the reply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request_id = stream_api
+    ///     .send_traceroute(packet_router, node_id, 0.into())
+    ///     .await?;
+    ///
+    /// while let Some(packet) = packet_receiver.recv().await {
+    ///     if let Some(protobufs::from_radio::PayloadVariant::Packet(mesh_packet)) = packet.payload_variant {
+    ///         if let Some(DecodedPayload::Traceroute(route)) = decoded_payload(&mesh_packet) {
+    ///             println!("Route to {}: {:?}", node_id, route.hops());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_traceroute<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        channel: MeshChannel,
+    ) -> Result<u32, Error> {
+        let mesh_packet = MeshPacketBuilder::new(
+            protobufs::PortNum::TracerouteApp,
+            protobufs::RouteDiscovery::default().encode_to_vec().into(),
+        )
+        .destination(PacketDestination::Node(node_id))
+        .channel(channel)
+        .want_response(true)
+        .build(packet_router.source_node_id());
+
+        let request_id = mesh_packet.id;
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
+        .await?;
+
+        Ok(request_id)
+    }
+
+    /// Sends an empty request with `want_response` set to the specified node on the specified port,
+    /// and decodes the payload of the response.
+    async fn request_from_node<
+        T: Message + Default,
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        port_num: protobufs::PortNum,
+        channel: MeshChannel,
+        timeout: Duration,
+    ) -> Result<T, Error> {
+        let mesh_packet = MeshPacketBuilder::new(port_num, T::default().encode_to_vec().into())
+            .destination(PacketDestination::Node(node_id))
+            .channel(channel)
+            .want_response(true)
+            .build(packet_router.source_node_id());
+
+        let request = self.request_registry.register(mesh_packet.id);
+
+        // Subscribe before sending to avoid missing a fast response
+        let mut packet_rx = self.subscribe_packets();
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
+        .await?;
+
+        let (_, data) = self
+            .await_response(&mut packet_rx, &request, port_num, timeout)
+            .await?;
+
+        Ok(T::decode(data.payload.as_slice())?)
+    }
+
+    /// Announces the local node to the mesh by sending a `User` packet on the `NodeinfoApp` port.
+    ///
+    /// Nodes joining the mesh broadcast their `User` information, so that other nodes can display
+    /// their names. The `id` of the sent `User` is derived from the node id of the packet router, in
+    /// the `!`-prefixed hexadecimal form used by the firmware (e.g. `!a1b2c3d4`).
+    ///
+    /// **Note:** This method only announces the names to other nodes. To persistently change the
+    /// names of the connected radio, use the `update_user` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     This router is used in the event a packet needs to be echoed.
+    /// * `long_name` - The long name of the local node.
+    /// * `short_name` - The short name of the local node, which is usually at most 4 characters long.
+    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet,
+    ///     which is usually `PacketDestination::Broadcast`.
+    /// * `want_response` - A `bool` that specifies whether receiving nodes should respond with their
+    ///     own `User` information.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating whether the packet was successfully sent to the radio.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stream_api
+    ///     .send_node_info(
+    ///         packet_router,
+    ///         "Base Station".to_string(),
+    ///         "BASE".to_string(),
+    ///         PacketDestination::Broadcast,
+    ///         false,
+    ///     )
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the packet fails to send.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub async fn send_node_info<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        long_name: String,
+        short_name: String,
+        destination: PacketDestination,
+        want_response: bool,
+    ) -> Result<(), Error> {
+        let user = protobufs::User {
+            id: format!("!{:08x}", packet_router.source_node_id().id()),
+            long_name,
+            short_name,
+            ..Default::default()
+        };
 
-        // Return channel for receiving decoded packets
+        let byte_data: EncodedMeshPacketData = user.encode_to_vec().into();
 
-        (
-            decoded_packet_rx,
-            ConnectedStreamApi::<state::Connected> {
-                write_input_tx,
-                read_handle,
-                write_handle,
-                processing_handle,
-                heartbeat_handle,
-                cancellation_token,
-                typestate: PhantomData,
-            },
+        self.send_mesh_packet(
+            packet_router,
+            byte_data,
+            protobufs::PortNum::NodeinfoApp,
+            destination,
+            MeshChannel::default(),
+            false,
+            want_response,
+            true,
+            None,
+            None,
         )
+        .await
     }
-}
 
-impl ConnectedStreamApi<state::Connected> {
-    /// This method is used to trigger the transmission of the current state of the
-    /// radio, as well as to subscribe to future `FromRadio` mesh packets. This method
-    /// can only be called after the `connect` method has been called.
+    /// Sends an admin request to the connected radio, and returns a future that resolves to the
+    /// radio's response.
     ///
-    /// This method triggers a `WantConfigId` packet to be sent to the radio containing
-    /// an arbitrary configuration identifier. This will tell the radio that the client
-    /// wants to connect. The radio will respond by sending its current configuration,
-    /// module configuration, and channel configuration. The radio will indicate that it
-    /// has finished transmission by sending a `ConfigComplete` packet, which will contain
-    /// the same configuration identifier that was sent in the `WantConfigId` packet. Tracking
-    /// whether or not configuration completes successfully is not handled by this method.
+    /// The request is tracked by the admin session of the `ConnectedStreamApi` struct, which correlates
+    /// responses with requests via the `request_id` of the response packet. This allows several admin
+    /// requests to be outstanding at once, as the returned future does not borrow the `ConnectedStreamApi`.
     ///
-    /// Once a radio connection has been configured, the radio will send all future packets
-    /// it receives through the decoded packet channel. This will continue until the
-    /// `disconnect` method is called.
+    /// **Note:** Only requests that the radio answers with an admin response (e.g. `GetConfigRequest` or
+    /// `GetOwnerRequest`) resolve successfully. Commands such as `SetConfig` are not answered, and the
+    /// returned future only resolves if a routing error is reported.
     ///
     /// # Arguments
     ///
-    /// * `config_id` - A randomly generated configuration ID that will be used
-    ///     to check that the configuration process has completed.
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     The node id of the router is used as the source of the request.
+    /// * `request` - The admin request to send to the radio.
     ///
     /// # Returns
     ///
-    /// Returns a `Result` indicating whether or not the configuration was successful.
-    /// The configuration will fail if the `WantConfigId` packet fails to send.
+    /// A `PendingAdminResponse` future that resolves to the `AdminResponse` answering the request.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
-    ///
-    /// let config_id = gen_random_id();
-    /// stream_api.configure(config_id).await?;
-    ///
-    /// while let Some(packet) = decoded_listener.recv().await {
-    ///     // Process packets
-    /// }
+    /// let request = protobufs::AdminMessage {
+    ///     payload_variant: Some(protobufs::admin_message::PayloadVariant::GetOwnerRequest(true)),
+    /// };
     ///
-    /// stream_api.disconnect().await?;
+    /// let pending_response = stream_api.send_admin_request(packet_router, request).await?;
+    /// let response = tokio::time::timeout(Duration::from_secs(10), pending_response).await??;
     /// ```
     ///
     /// # Errors
     ///
-    /// Fails if the `WantConfigId` packet fails to send.
+    /// Fails if the packet fails to send. The returned future fails with `Error::RequestFailed` if the
+    /// radio reports a routing error for the request, e.g. `NotAuthorized` or `BadRequest`.
     ///
     /// # Panics
     ///
     /// None
     ///
-    pub async fn configure(
-        mut self,
-        config_id: u32,
-    ) -> Result<ConnectedStreamApi<state::Configured>, Error> {
-        let to_radio = protobufs::ToRadio {
-            payload_variant: Some(protobufs::to_radio::PayloadVariant::WantConfigId(config_id)),
-        };
-
-        let packet_buf: EncodedToRadioPacket = to_radio.encode_to_vec().into();
-        self.send_raw(packet_buf).await?;
+    pub async fn send_admin_request<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        request: protobufs::AdminMessage,
+    ) -> Result<PendingAdminResponse, Error> {
+        let mesh_packet =
+            MeshPacketBuilder::new(protobufs::PortNum::AdminApp, request.encode_to_vec().into())
+                .destination(PacketDestination::Local)
+                .want_response(true)
+                .build(packet_router.source_node_id());
 
-        Ok(ConnectedStreamApi::<state::Configured> {
-            write_input_tx: self.write_input_tx,
-            read_handle: self.read_handle,
-            write_handle: self.write_handle,
-            processing_handle: self.processing_handle,
-            heartbeat_handle: self.heartbeat_handle,
-            cancellation_token: self.cancellation_token,
-            typestate: PhantomData,
-        })
+        self.send_tracked_admin_request(mesh_packet, request).await
     }
-}
 
-impl ConnectedStreamApi<state::Configured> {
-    /// A method to disconnect from a radio. This method will close all channels and
-    /// join all worker threads. If connected via serial or TCP, this will also trigger
-    /// the radio to terminate its current connection.
-    ///
-    /// This method can only be called after the `configure` method has been called.
+    /// Sends an admin request to a remote node, and returns a future that resolves to the node's response.
+    /// This is the remote counterpart of the `send_admin_request` method, e.g. to read the configuration of
+    /// a repeater that has no client connected. See the `send_admin_to` method for the requirements remote
+    /// administration places on the channel and the firmware of the node.
     ///
     /// # Arguments
     ///
-    /// None
+    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
+    ///     The node id of the router is used as the source of the request.
+    /// * `node_id` - The id of the node to send the request to.
+    /// * `channel` - A `MeshChannel` that specifies the admin channel shared with the node.
+    /// * `request` - The admin request to send to the node.
     ///
     /// # Returns
     ///
-    /// Returns a `Result` indicating whether or not the disconnection was successful.
+    /// A `PendingAdminResponse` future that resolves to the `AdminResponse` answering the request.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
-    ///
-    /// // Process packets from the `decoded_listener` channel
-    ///
-    /// stream_api.disconnect().await?;
+    /// let request = protobufs::AdminMessage {
+    ///     payload_variant: Some(protobufs::admin_message::PayloadVariant::GetOwnerRequest(true)),
+    /// };
+    ///
+    /// let admin_channel = stream_api.admin_channel();
+    /// let pending_response = stream_api
+    ///     .send_admin_request_to(packet_router, repeater_id, admin_channel, request)
+    ///     .await?;
+    /// let response = tokio::time::timeout(Duration::from_secs(60), pending_response).await??;
     /// ```
     ///
     /// # Errors
     ///
-    /// Will fail if any of the worker threads fail to join.
+    /// Fails if the packet fails to send. The returned future fails with `Error::RequestFailed` if the
+    /// mesh reports a routing error for the request, e.g. `NotAuthorized` if the node does not accept
+    /// admin messages on the channel.
     ///
     /// # Panics
     ///
     /// None
     ///
-    pub async fn disconnect(self) -> Result<StreamApi, Error> {
-        // Tell worker threads to shut down
-        self.cancellation_token.cancel();
-
-        // Close writer channel, which will kill worker threads
-
-        drop(self.write_input_tx);
-
-        // Close worker threads
-
-        let (read_result, write_result, processing_result) =
-            join3(self.read_handle, self.write_handle, self.processing_handle).await;
-
-        // Note: we only return the first error.
-        read_result??;
-        write_result??;
-        processing_result??;
-
-        trace!("Handlers fully disconnected");
+    pub async fn send_admin_request_to<
+        M,
+        E: Display + std::error::Error + Send + Sync + 'static,
+        R: PacketRouter<M, E>,
+    >(
+        &mut self,
+        packet_router: &mut R,
+        node_id: NodeId,
+        channel: MeshChannel,
+        request: protobufs::AdminMessage,
+    ) -> Result<PendingAdminResponse, Error> {
+        let mesh_packet =
+            MeshPacketBuilder::new(protobufs::PortNum::AdminApp, request.encode_to_vec().into())
+                .destination(PacketDestination::Node(node_id))
+                .channel(channel)
+                .want_response(true)
+                .build(packet_router.source_node_id());
 
-        Ok(StreamApi)
+        self.send_tracked_admin_request(mesh_packet, request).await
     }
-}
-
-// Public node management API
 
-impl ConnectedStreamApi<state::Configured> {
-    /// Sends the specified text content over the mesh.
+    /// Sends an admin message to a remote node without awaiting a response, e.g. to push configuration
+    /// to a fleet of repeaters. Get-style requests (see `AdminMessage::is_get_request`) are sent with
+    /// `want_response` set, so that the node routes its response back; all other messages are sent with
+    /// `want_ack` set instead. Responses can be awaited via the `send_admin_request_to` method.
+    ///
+    /// Nodes only accept admin messages from other nodes on a channel that is configured for administration,
+    /// i.e. a channel named `admin` that is shared by both nodes, as returned by the `admin_channel` method.
+    ///
+    /// **Note:** Firmware versions that support PKI (see `PKI_MIN_FIRMWARE_VERSION`) authenticate remote
+    /// admin messages with a session key and the public keys of the administrators instead. The session key
+    /// flow is out of scope for this method, as the protobufs of this crate predate it; it would extend the
+    /// `AdminMessage` before it is sent, without changing how the packet is addressed.
     ///
     /// # Arguments
     ///
     /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
-    ///     This router is used in the event a packet needs to be echoed.
-    /// * `text` - A `String` containing the text to send.
-    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
-    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
-    ///     from other nodes on the mesh.
-    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    ///     The node id of the router is used as the source of the message.
+    /// * `node_id` - The id of the node to send the message to.
+    /// * `channel` - A `MeshChannel` that specifies the admin channel shared with the node.
+    /// * `admin_message` - The admin message to send to the node.
     ///
     /// # Returns
     ///
-    /// A result indicating whether the packet was successfully sent to the radio.
+    /// A result resolving to the packet id of the message, which is the `request_id` of the node's response.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
-    ///
-    /// let config_id = generate_rand_id();
-    /// let mut stream_api = stream_api.configure(config_id).await?;
-    ///
-    /// stream_api.send_text(packet_router, "Hello world!".to_string(), PacketDestination::Broadcast, true, 0).await?;
+    /// let set_owner = protobufs::AdminMessage {
+    ///     payload_variant: Some(protobufs::admin_message::PayloadVariant::SetOwner(owner)),
+    /// };
+    ///
+    /// let admin_channel = stream_api.admin_channel();
+    /// for repeater_id in repeater_ids {
+    ///     stream_api
+    ///         .send_admin_to(packet_router, repeater_id, admin_channel, set_owner.clone())
+    ///         .await?;
+    /// }
     /// ```
     ///
     /// # Errors
@@ -651,189 +3380,215 @@ impl ConnectedStreamApi<state::Configured> {
     ///
     /// None
     ///
-    pub async fn send_text<
+    pub async fn send_admin_to<
         M,
         E: Display + std::error::Error + Send + Sync + 'static,
         R: PacketRouter<M, E>,
     >(
         &mut self,
         packet_router: &mut R,
-        text: String,
-        destination: PacketDestination,
-        want_ack: bool,
+        node_id: NodeId,
         channel: MeshChannel,
-    ) -> Result<(), Error> {
-        let byte_data: EncodedMeshPacketData = text.into_bytes().into();
+        admin_message: protobufs::AdminMessage,
+    ) -> Result<u32, Error> {
+        let want_response = admin_message.is_get_request();
 
-        self.send_mesh_packet(
-            packet_router,
-            byte_data,
-            protobufs::PortNum::TextMessageApp,
-            destination,
-            channel,
-            want_ack,
-            false,
-            true,
-            None,
-            None,
+        let mesh_packet = MeshPacketBuilder::new(
+            protobufs::PortNum::AdminApp,
+            admin_message.encode_to_vec().into(),
         )
+        .destination(PacketDestination::Node(node_id))
+        .channel(channel)
+        .want_ack(!want_response)
+        .want_response(want_response)
+        .build(packet_router.source_node_id());
+
+        let packet_id = mesh_packet.id;
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
         .await?;
 
-        Ok(())
+        Ok(packet_id)
     }
 
-    /// Sends the specified `Waypoint` over the mesh.
-    ///
-    /// If the specified `Waypoint` struct has an `id` field of `0`, this method will generate
-    /// a random id for the waypoint and update the struct before sending. This is because 0
-    /// is an invalid waypoint ID.
+    /// Registers an admin request with the admin session and sends the packet containing it.
+    async fn send_tracked_admin_request(
+        &mut self,
+        mesh_packet: protobufs::MeshPacket,
+        request: protobufs::AdminMessage,
+    ) -> Result<PendingAdminResponse, Error> {
+        // Register before sending to avoid missing a fast response. If sending fails, dropping the
+        // pending response stops tracking the request.
+        let pending_response = self.admin_session.register(mesh_packet.id, request);
+
+        self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+            mesh_packet,
+        )))
+        .await?;
+
+        Ok(pending_response)
+    }
+
+    /// Sends a sequence of admin requests that together form one logical operation to the connected
+    /// radio, and returns a future that resolves once the radio has answered every request. This is
+    /// intended for results that span several response packets, e.g. the full channel table, which is
+    /// retrieved with one `GetChannelRequest` per channel index. See the `AdminSession` struct for details.
     ///
     /// # Arguments
     ///
     /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
-    ///     This router is used in the event a packet needs to be echoed.
-    /// * `waypoint` - An instance of the `Waypoint` struct to send.
-    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
-    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
-    ///     from other nodes on the mesh.
-    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    ///     The node id of the router is used as the source of the requests.
+    /// * `requests` - The admin requests to send to the radio, in order.
     ///
     /// # Returns
     ///
-    /// A result indicating whether the packet was successfully sent to the radio.
+    /// A `PendingAdminResponses` future that resolves to the responses in the order of the requests.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
-    ///
-    /// let config_id = generate_rand_id();
-    /// let mut stream_api = stream_api.configure(config_id).await?;
+    /// let requests = (1..=8).map(|index| protobufs::AdminMessage {
+    ///     payload_variant: Some(protobufs::admin_message::PayloadVariant::GetChannelRequest(index)),
+    /// });
     ///
-    /// let waypoint = crate::protobufs::Waypoint { ... };
-    /// stream_api.send_waypoint(packet_router, waypoint, PacketDestination::Broadcast, true, 0).await?;
+    /// let pending_responses = stream_api.send_admin_requests(packet_router, requests).await?;
+    /// let channels = tokio::time::timeout(Duration::from_secs(60), pending_responses).await??;
     /// ```
     ///
     /// # Errors
     ///
-    /// Fails if the packet fails to send.
+    /// Fails if any of the packets fails to send, in which case all requests of the sequence are
+    /// cancelled. The returned future fails with the error of the first request that fails.
     ///
     /// # Panics
     ///
     /// None
     ///
-    pub async fn send_waypoint<
+    pub async fn send_admin_requests<
         M,
         E: Display + std::error::Error + Send + Sync + 'static,
         R: PacketRouter<M, E>,
     >(
         &mut self,
         packet_router: &mut R,
-        waypoint: crate::protobufs::Waypoint,
-        destination: PacketDestination,
-        want_ack: bool,
-        channel: MeshChannel,
-    ) -> Result<(), Error> {
-        let mut waypoint = waypoint;
+        requests: impl IntoIterator<Item = protobufs::AdminMessage>,
+    ) -> Result<PendingAdminResponses, Error> {
+        let (mesh_packets, requests): (Vec<_>, Vec<_>) = requests
+            .into_iter()
+            .map(|request| {
+                let mesh_packet = MeshPacketBuilder::new(
+                    protobufs::PortNum::AdminApp,
+                    request.encode_to_vec().into(),
+                )
+                .destination(PacketDestination::Local)
+                .want_response(true)
+                .build(packet_router.source_node_id());
 
-        // Waypoint with ID of zero denotes a new waypoint; check whether to generate its ID on backend
-        if waypoint.id == 0 {
-            waypoint.id = generate_rand_id();
+                let packet_id = mesh_packet.id;
+                (mesh_packet, (packet_id, request))
+            })
+            .unzip();
+
+        // Register before sending to avoid missing a fast response. If sending fails, dropping the
+        // pending responses stops tracking the requests.
+        let pending_responses = self.admin_session.register_all(requests);
+
+        for mesh_packet in mesh_packets {
+            self.send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+                mesh_packet,
+            )))
+            .await?;
         }
 
-        let byte_data: EncodedMeshPacketData = waypoint.encode_to_vec().into();
+        Ok(pending_responses)
+    }
 
-        self.send_mesh_packet(
-            packet_router,
-            byte_data,
-            protobufs::PortNum::WaypointApp,
-            destination,
-            channel,
-            want_ack,
-            false,
-            true,
-            None,
-            None,
-        )
-        .await?;
+    /// Returns a handle to the admin session that tracks the outstanding requests sent via the
+    /// `send_admin_request` method, e.g. to cancel requests that are no longer of interest.
+    pub fn admin_session(&self) -> AdminSession {
+        self.admin_session.clone()
+    }
 
-        Ok(())
+    /// Returns a handle to the registry of requests whose responses or acknowledgements are being awaited
+    /// by methods such as `send_mesh_packet_reliable` and `request_position`. As these methods borrow the
+    /// `ConnectedStreamApi` while they wait, the handle allows their requests to be listed and cancelled
+    /// from elsewhere, e.g. from another task. Admin requests are tracked by the `admin_session` instead.
+    pub fn request_registry(&self) -> RequestRegistry {
+        self.request_registry.clone()
     }
 
-    /// Sends the specified `Positon` over the mesh.
+    /// Returns the packet ids of all requests whose responses or acknowledgements are still being awaited,
+    /// i.e. reliable sends, requests to other nodes (e.g. `request_position`), and admin requests.
     ///
-    /// Sending a `Position` packet will update the internal position of the connected radio
-    /// in addition to sending the packet over the mesh.
+    /// # Arguments
     ///
-    /// **Note:** If you want to send a point of interest (POI) over the mesh, use `send_waypoint`.
+    /// None
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
-    ///     This router is used in the event a packet needs to be echoed.
-    /// * `position` - An instance of the `Position` struct to send.
-    /// * `destination` - A `PacketDestination` enum that specifies the destination of the packet.
-    /// * `want_ack` - A `bool` that specifies whether or not the radio should wait for acknowledgement
-    ///     from other nodes on the mesh.
-    /// * `channel` - A `u32` that specifies the message channel to send the packet on [0..7).
+    /// The ids of the pending requests, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for packet_id in stream_api.pending_requests() {
+    ///     println!("Awaiting response to packet {packet_id}");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn pending_requests(&self) -> Vec<PacketId> {
+        let mut packet_ids = self.request_registry.pending_request_ids();
+        packet_ids.extend(self.admin_session.pending_request_ids());
+
+        packet_ids.sort_unstable();
+        packet_ids.dedup();
+        packet_ids.into_iter().map(PacketId::from).collect()
+    }
+
+    /// Cancels the pending request with the specified packet id, without affecting other pending requests.
+    /// The method awaiting the request fails with `Error::RequestCancelled`. Packets that were already sent
+    /// to the radio are not recalled, so a reliable send stops being retransmitted, but the radio may still
+    /// deliver the packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet_id` - The id of the packet containing the request, as returned by the `pending_requests` method.
     ///
     /// # Returns
     ///
-    /// A result indicating whether the packet was successfully sent to the radio.
+    /// `true` if the request was pending, otherwise `false`.
     ///
     /// # Examples
     ///
     /// ```
-    /// let stream_api = StreamApi::new();
-    /// let tcp_stream = build_tcp_stream("localhost:4403".to_string()).await?;
-    /// let (_decoded_listener, stream_api) = stream_api.connect(tcp_stream).await;
-    ///
-    /// let config_id = generate_rand_id();
-    /// let mut stream_api = stream_api.configure(config_id).await?;
-    ///
-    /// let position = crate::protobufs::Position { ... };
-    /// stream_api.send_position(packet_router, position, PacketDestination::Broadcast, true, 0).await?;
+    /// if stream_api.cancel_request(packet_id) {
+    ///     println!("Abandoned request {packet_id}");
+    /// }
     /// ```
     ///
     /// # Errors
     ///
-    /// Fails if the packet fails to send.
+    /// None
     ///
     /// # Panics
     ///
     /// None
     ///
-    pub async fn send_position<
-        M,
-        E: Display + std::error::Error + Send + Sync + 'static,
-        R: PacketRouter<M, E>,
-    >(
-        &mut self,
-        packet_router: &mut R,
-        position: crate::protobufs::Position,
-        destination: PacketDestination,
-        want_ack: bool,
-        channel: MeshChannel,
-    ) -> Result<(), Error> {
-        let byte_data: EncodedMeshPacketData = position.encode_to_vec().into();
-
-        self.send_mesh_packet(
-            packet_router,
-            byte_data,
-            protobufs::PortNum::PositionApp,
-            destination,
-            channel,
-            want_ack,
-            false,
-            true,
-            None,
-            None,
-        )
-        .await?;
+    pub fn cancel_request(&self, packet_id: PacketId) -> bool {
+        let cancelled_request = self.request_registry.cancel(packet_id.id());
+        let cancelled_admin_request = self.admin_session.cancel(packet_id.id());
 
-        Ok(())
+        cancelled_request || cancelled_admin_request
     }
 
     /// Updates the configuration of the radio to the specified configuration.
@@ -1037,6 +3792,8 @@ impl ConnectedStreamApi<state::Configured> {
     ) -> Result<(), Error> {
         // Tell device to update channels
 
+        self.radio_state.update_channel(channel_config.clone());
+
         let channel_packet = protobufs::AdminMessage {
             payload_variant: Some(protobufs::admin_message::PayloadVariant::SetChannel(
                 channel_config,
@@ -1377,6 +4134,14 @@ impl ConnectedStreamApi<state::Configured> {
     /// field that is specified in the `LocalModuleConfig` struct. This method is intended
     /// to be used with the `start_config_transaction` and `commit_config_transaction` methods.
     ///
+    /// The sections are split by the `LocalModuleConfig::into_module_configs` method, and are sent in the
+    /// order of the `ModuleConfigType` enum: MQTT, serial, external notification, store and forward, range
+    /// test, telemetry, canned message, audio, remote hardware, neighbor info, ambient lighting, detection
+    /// sensor, and paxcounter.
+    ///
+    /// **Note:** The neighbor info, ambient lighting, detection sensor, and paxcounter sections used to be
+    /// skipped by this method, and are now sent to the radio as well if they are set.
+    ///
     /// # Arguments
     ///
     /// * `packet_router` - A generic packet router field that implements the `PacketRouter` trait.
@@ -1412,102 +4177,9 @@ impl ConnectedStreamApi<state::Configured> {
         packet_router: &mut R,
         local_module_config: protobufs::LocalModuleConfig,
     ) -> Result<(), Error> {
-        if let Some(c) = local_module_config.audio {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::Audio(c)),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.canned_message {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::CannedMessage(
-                        c,
-                    )),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.external_notification {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(
-                        protobufs::module_config::PayloadVariant::ExternalNotification(c),
-                    ),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.mqtt {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::Mqtt(c)),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.range_test {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::RangeTest(c)),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.remote_hardware {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(
-                        protobufs::module_config::PayloadVariant::RemoteHardware(c),
-                    ),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.serial {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::Serial(c)),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.store_forward {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::StoreForward(
-                        c,
-                    )),
-                },
-            )
-            .await?;
-        }
-
-        if let Some(c) = local_module_config.telemetry {
-            self.update_module_config(
-                packet_router,
-                protobufs::ModuleConfig {
-                    payload_variant: Some(protobufs::module_config::PayloadVariant::Telemetry(c)),
-                },
-            )
-            .await?;
+        for module_config in local_module_config.into_module_configs() {
+            self.update_module_config(packet_router, module_config)
+                .await?;
         }
 
         Ok(())
@@ -1562,3 +4234,726 @@ impl ConnectedStreamApi<state::Configured> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::connections::test_utils::{
+        read_mesh_packet, read_to_radio, write_from_radio, TestRouter,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn detects_devices_that_are_not_meshtastic_radios() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let stream_api = stream_api.configure(1).await.unwrap();
+
+        server.write_all(b"Printer ready\r\n").await.unwrap();
+
+        assert!(matches!(
+            stream_api.wait_for_config(Duration::from_millis(100)).await,
+            Err(Error::NotAMeshtasticDevice { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_buffer_overflows_as_connection_events() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .with_max_buffer_size(16)
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut events = stream_api.connection_events();
+        assert_eq!(events.recv().await, Some(ConnectionEvent::Connected));
+
+        // The header announces a packet that exceeds the maximum buffer size
+        server.write_all(&[0x94, 0xc3, 0x01, 0x00]).await.unwrap();
+        server.write_all(&[0x00; 16]).await.unwrap();
+
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(1), events.recv())
+                .await
+                .unwrap(),
+            Some(ConnectionEvent::BufferOverflow {
+                discarded_bytes: 20
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshes_node_database_once_config_completes() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let node_info = |num| {
+            protobufs::from_radio::PayloadVariant::NodeInfo(protobufs::NodeInfo {
+                num,
+                ..Default::default()
+            })
+        };
+        let node_nums = |snapshot: DeviceSnapshot| -> Vec<u32> {
+            snapshot
+                .nodes
+                .iter()
+                .map(|node_info| node_info.num)
+                .collect()
+        };
+
+        read_to_radio(&mut server).await;
+        write_from_radio(&mut server, node_info(1)).await;
+        write_from_radio(&mut server, node_info(2)).await;
+        write_from_radio(
+            &mut server,
+            protobufs::from_radio::PayloadVariant::ConfigCompleteId(1),
+        )
+        .await;
+        stream_api
+            .wait_for_config(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let radio = tokio::spawn(async move {
+            let config_id = match read_to_radio(&mut server).await.payload_variant {
+                Some(protobufs::to_radio::PayloadVariant::WantConfigId(config_id)) => config_id,
+                payload_variant => panic!("Unexpected packet {payload_variant:?}"),
+            };
+
+            write_from_radio(&mut server, node_info(2)).await;
+            write_from_radio(
+                &mut server,
+                protobufs::from_radio::PayloadVariant::ConfigCompleteId(config_id),
+            )
+            .await;
+
+            server
+        });
+
+        // The node database is only replaced once the radio has finished sending it
+        let snapshot = stream_api.refresh_config().await.unwrap();
+        assert_eq!(node_nums(snapshot), [2]);
+        radio.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_pre_framed_packets() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, mut stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+
+        for invalid in [
+            &[0x94, 0xc4, 0x00, 0x01, 0x00][..],
+            &[0x94, 0xc3, 0x00, 0x02, 0x00],
+        ] {
+            assert!(matches!(
+                stream_api.send_raw_to_radio(invalid.into()).await,
+                Err(Error::InvalidFramedPacket { .. })
+            ));
+        }
+
+        let framed_packet = [0x94, 0xc3, 0x00, 0x02, 0x18, 0x01];
+        stream_api
+            .send_raw_to_radio(framed_packet[..].into())
+            .await
+            .unwrap();
+        stream_api.flush().await.unwrap();
+
+        let mut written = [0; 6];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(written, framed_packet);
+    }
+
+    #[tokio::test]
+    async fn readonly_connections_only_send_the_handshake() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (mut packet_receiver, stream_api) = StreamApi::new()
+            .connect_readonly(StreamHandle::from_stream(client), Some(42))
+            .await
+            .unwrap();
+
+        let want_config = protobufs::ToRadio {
+            payload_variant: Some(protobufs::to_radio::PayloadVariant::WantConfigId(42)),
+        };
+        let expected = utils::format_data_packet(want_config.encode_to_vec().into()).unwrap();
+        let mut written = vec![0; expected.data().len()];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(written, expected.data());
+
+        let from_radio = protobufs::FromRadio {
+            id: 1,
+            payload_variant: Some(protobufs::from_radio::PayloadVariant::ConfigCompleteId(42)),
+        };
+        let framed = utils::format_data_packet(from_radio.encode_to_vec().into()).unwrap();
+        server.write_all(framed.data()).await.unwrap();
+
+        assert_eq!(packet_receiver.recv().await, Some(from_radio));
+        stream_api
+            .wait_for_config(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        // Neither heartbeats nor any other packets are written after the handshake
+        let mut remaining = [0; 1];
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), server.read(&mut remaining))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn measures_ping_round_trip_time() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        // Answer the ping with a reply from the pinged node
+        tokio::spawn(async move {
+            let ping = read_mesh_packet(&mut server).await;
+
+            let reply = protobufs::MeshPacket {
+                from: ping.to,
+                to: ping.from,
+                payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                    protobufs::Data {
+                        portnum: protobufs::PortNum::ReplyApp as i32,
+                        request_id: ping.id,
+                        ..Default::default()
+                    },
+                )),
+                ..Default::default()
+            };
+            write_from_radio(
+                &mut server,
+                protobufs::from_radio::PayloadVariant::Packet(reply),
+            )
+            .await;
+        });
+
+        let round_trip_time = stream_api
+            .ping(
+                &mut TestRouter,
+                NodeId::new(2),
+                MeshChannel::default(),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        assert!(round_trip_time < Duration::from_secs(5));
+        assert!(stream_api.pending_requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_traceroute_requests() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let request_id = stream_api
+            .send_traceroute(
+                &mut TestRouter,
+                NodeId::new(2),
+                MeshChannel::new(1).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let request = read_mesh_packet(&mut server).await;
+
+        let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) = &request.payload_variant
+        else {
+            panic!("expected a decoded payload");
+        };
+
+        assert_eq!(request.id, request_id);
+        assert_eq!(request.from, 1);
+        assert_eq!(request.to, 2);
+        assert_eq!(request.channel, 1);
+        assert_eq!(data.portnum(), protobufs::PortNum::TracerouteApp);
+        assert!(data.want_response);
+        assert_eq!(
+            protobufs::RouteDiscovery::decode(data.payload.as_slice()).unwrap(),
+            protobufs::RouteDiscovery::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_time_with_last_known_position() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let read_position = |mesh_packet: protobufs::MeshPacket| {
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant
+            else {
+                panic!("expected a decoded payload");
+            };
+
+            assert_eq!(mesh_packet.from, 1);
+            assert_eq!(mesh_packet.to, 1);
+            assert_eq!(mesh_packet.channel, 0);
+            assert!(!mesh_packet.want_ack);
+            assert_eq!(data.portnum(), protobufs::PortNum::PositionApp);
+            assert!(!data.want_response);
+            protobufs::Position::decode(data.payload.as_slice()).unwrap()
+        };
+
+        // Without a known position, only the time is set
+        stream_api
+            .set_time(&mut TestRouter, 1_700_000_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            read_position(read_mesh_packet(&mut server).await),
+            protobufs::Position {
+                time: 1_700_000_000,
+                ..Default::default()
+            }
+        );
+
+        let last_position = protobufs::Position {
+            latitude_i: 523_000_000,
+            longitude_i: 134_000_000,
+            altitude: 34,
+            time: 1_600_000_000,
+            ..Default::default()
+        };
+        write_from_radio(
+            &mut server,
+            protobufs::from_radio::PayloadVariant::NodeInfo(protobufs::NodeInfo {
+                num: 1,
+                position: Some(last_position),
+                ..Default::default()
+            }),
+        )
+        .await;
+        write_from_radio(
+            &mut server,
+            protobufs::from_radio::PayloadVariant::ConfigCompleteId(1),
+        )
+        .await;
+        stream_api
+            .wait_for_config(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        stream_api
+            .set_time(&mut TestRouter, 1_700_000_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            read_position(read_mesh_packet(&mut server).await),
+            protobufs::Position {
+                time: 1_700_000_000,
+                ..last_position
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_config_transactions_in_order() {
+        use protobufs::admin_message::PayloadVariant;
+
+        async fn read_admin_message(server: &mut tokio::io::DuplexStream) -> PayloadVariant {
+            let mut header = [0; 4];
+            server.read_exact(&mut header).await.unwrap();
+            let mut payload = vec![0; u16::from_be_bytes([header[2], header[3]]) as usize];
+            server.read_exact(&mut payload).await.unwrap();
+
+            // The transaction control messages are written as bare admin messages
+            if let Ok(protobufs::ToRadio {
+                payload_variant: Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)),
+            }) = protobufs::ToRadio::decode(payload.as_slice())
+            {
+                let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                    mesh_packet.payload_variant
+                else {
+                    panic!("expected a decoded payload");
+                };
+                payload = data.payload;
+            }
+
+            protobufs::AdminMessage::decode(payload.as_slice())
+                .unwrap()
+                .payload_variant
+                .unwrap()
+        }
+
+        // A single byte buffer stalls the writer until the radio side reads, so every packet below is
+        // queued before the first one is written
+        let (client, mut server) = tokio::io::duplex(1);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        stream_api.start_config_transaction().await.unwrap();
+        stream_api
+            .update_config(&mut TestRouter, protobufs::Config::default())
+            .await
+            .unwrap();
+        stream_api.commit_config_transaction().await.unwrap();
+
+        assert!(matches!(
+            read_to_radio(&mut server).await.payload_variant,
+            Some(protobufs::to_radio::PayloadVariant::WantConfigId(1))
+        ));
+        assert_eq!(
+            read_admin_message(&mut server).await,
+            PayloadVariant::BeginEditSettings(true)
+        );
+        assert_eq!(
+            read_admin_message(&mut server).await,
+            PayloadVariant::SetConfig(protobufs::Config::default())
+        );
+        assert_eq!(
+            read_admin_message(&mut server).await,
+            PayloadVariant::CommitEditSettings(true)
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_module_config_sections_in_order() {
+        use protobufs::module_config::PayloadVariant;
+
+        let (client, mut server) = tokio::io::duplex(8192);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let local_module_config = protobufs::LocalModuleConfig {
+            mqtt: Some(Default::default()),
+            serial: Some(Default::default()),
+            external_notification: Some(Default::default()),
+            store_forward: Some(Default::default()),
+            range_test: Some(Default::default()),
+            telemetry: Some(Default::default()),
+            canned_message: Some(Default::default()),
+            audio: Some(Default::default()),
+            remote_hardware: Some(Default::default()),
+            neighbor_info: Some(Default::default()),
+            ambient_lighting: Some(Default::default()),
+            detection_sensor: Some(Default::default()),
+            paxcounter: Some(Default::default()),
+            ..Default::default()
+        };
+
+        stream_api
+            .set_local_module_config(&mut TestRouter, local_module_config)
+            .await
+            .unwrap();
+
+        let mut sent_sections = vec![];
+        while sent_sections.len() < 13 {
+            let mesh_packet = read_mesh_packet(&mut server).await;
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant
+            else {
+                panic!("expected a decoded payload");
+            };
+
+            let admin_message = protobufs::AdminMessage::decode(data.payload.as_slice()).unwrap();
+            let Some(protobufs::admin_message::PayloadVariant::SetModuleConfig(module_config)) =
+                admin_message.payload_variant
+            else {
+                panic!("expected a SetModuleConfig admin message");
+            };
+
+            sent_sections.push(module_config.payload_variant.unwrap());
+        }
+
+        assert_eq!(
+            sent_sections,
+            [
+                PayloadVariant::Mqtt(Default::default()),
+                PayloadVariant::Serial(Default::default()),
+                PayloadVariant::ExternalNotification(Default::default()),
+                PayloadVariant::StoreForward(Default::default()),
+                PayloadVariant::RangeTest(Default::default()),
+                PayloadVariant::Telemetry(Default::default()),
+                PayloadVariant::CannedMessage(Default::default()),
+                PayloadVariant::Audio(Default::default()),
+                PayloadVariant::RemoteHardware(Default::default()),
+                PayloadVariant::NeighborInfo(Default::default()),
+                PayloadVariant::AmbientLighting(Default::default()),
+                PayloadVariant::DetectionSensor(Default::default()),
+                PayloadVariant::Paxcounter(Default::default()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_simulated_packets_without_decoded_payload() {
+        let (client, _server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, mut stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+
+        let mesh_packet = protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![
+                0;
+                8
+            ])),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            stream_api.send_simulated_packet(mesh_packet).await,
+            Err(Error::InvalidSimulatorPacket { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn sends_admin_messages_to_remote_nodes() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let get_owner = protobufs::AdminMessage {
+            payload_variant: Some(protobufs::admin_message::PayloadVariant::GetOwnerRequest(
+                true,
+            )),
+        };
+        let reboot = protobufs::AdminMessage {
+            payload_variant: Some(protobufs::admin_message::PayloadVariant::RebootSeconds(5)),
+        };
+
+        let mut packet_ids = vec![];
+        for admin_message in [get_owner.clone(), reboot.clone()] {
+            let packet_id = stream_api
+                .send_admin_to(
+                    &mut TestRouter,
+                    NodeId::new(0x1234),
+                    MeshChannel::new(2).unwrap(),
+                    admin_message,
+                )
+                .await
+                .unwrap();
+            packet_ids.push(packet_id);
+        }
+
+        let mut requests = vec![];
+        while requests.len() < 2 {
+            requests.push(read_mesh_packet(&mut server).await);
+        }
+
+        // Packets with want_ack set are queued with a higher priority, so they may be written first
+        for (packet_id, (admin_message, is_get_request)) in packet_ids
+            .into_iter()
+            .zip([(get_owner, true), (reboot, false)])
+        {
+            let request = requests
+                .iter()
+                .find(|request| request.id == packet_id)
+                .unwrap();
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                &request.payload_variant
+            else {
+                panic!("expected a decoded payload");
+            };
+
+            assert_eq!(request.to, 0x1234);
+            assert_eq!(request.channel, 2);
+            assert_eq!(request.want_ack, !is_get_request);
+            assert_eq!(data.portnum(), protobufs::PortNum::AdminApp);
+            assert_eq!(data.want_response, is_get_request);
+            assert_eq!(
+                protobufs::AdminMessage::decode(data.payload.as_slice()).unwrap(),
+                admin_message
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn cancels_pending_requests() {
+        let (client, _server) = tokio::io::duplex(1024);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        let request_registry = stream_api.request_registry();
+        tokio::spawn(async move {
+            loop {
+                if let Some(packet_id) = request_registry.pending_request_ids().pop() {
+                    request_registry.cancel(packet_id);
+                    break;
+                }
+
+                tokio::task::yield_now().await;
+            }
+        });
+
+        let result = stream_api
+            .request_position(
+                &mut TestRouter,
+                NodeId::new(2),
+                MeshChannel::default(),
+                Duration::from_secs(60),
+            )
+            .await;
+
+        assert!(matches!(result, Err(Error::RequestCancelled { .. })));
+        assert!(stream_api.pending_requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn bounds_concurrent_reliable_sends() {
+        let (client, mut server) = tokio::io::duplex(4096);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+        stream_api.set_max_in_flight_reliable_sends(Some(2));
+
+        let sends = (0..4)
+            .map(|_| {
+                let reliable_sender = stream_api.reliable_sender();
+
+                tokio::spawn(async move {
+                    reliable_sender
+                        .send_mesh_packet_reliable(
+                            &mut TestRouter,
+                            b"ping".to_vec().into(),
+                            protobufs::PortNum::TextMessageApp,
+                            PacketDestination::Node(NodeId::new(2)),
+                            MeshChannel::default(),
+                            false,
+                            false,
+                            ReliableSendConfig {
+                                max_retransmits: 0,
+                                base_timeout: Duration::from_secs(60),
+                            },
+                        )
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut in_flight = vec![
+            read_mesh_packet(&mut server).await,
+            read_mesh_packet(&mut server).await,
+        ];
+        let mut sent = in_flight.len();
+
+        while !in_flight.is_empty() {
+            // No further packet is sent until an in-flight packet is acknowledged
+            assert!(tokio::time::timeout(
+                Duration::from_millis(100),
+                read_mesh_packet(&mut server)
+            )
+            .await
+            .is_err());
+
+            let acked = in_flight.remove(0);
+            let ack = protobufs::MeshPacket {
+                from: acked.to,
+                to: acked.from,
+                payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                    protobufs::Data {
+                        portnum: protobufs::PortNum::RoutingApp as i32,
+                        payload: protobufs::Routing {
+                            variant: Some(protobufs::routing::Variant::ErrorReason(0)),
+                        }
+                        .encode_to_vec(),
+                        request_id: acked.id,
+                        ..Default::default()
+                    },
+                )),
+                ..Default::default()
+            };
+            write_from_radio(
+                &mut server,
+                protobufs::from_radio::PayloadVariant::Packet(ack),
+            )
+            .await;
+
+            if sent < 4 {
+                in_flight.push(read_mesh_packet(&mut server).await);
+                sent += 1;
+            }
+        }
+
+        for send in sends {
+            assert_eq!(send.await.unwrap().unwrap(), AckStatus::ExplicitAck);
+        }
+    }
+
+    #[tokio::test]
+    async fn fragments_long_text_messages() {
+        let (client, mut server) = tokio::io::duplex(4096);
+
+        let (_packet_receiver, stream_api) = StreamApi::new()
+            .connect(StreamHandle::from_stream(client))
+            .await;
+        let mut stream_api = stream_api.configure(1).await.unwrap();
+
+        // 100 three-byte CJK characters followed by 30 four-byte emoji
+        let text = format!("{}{}", "漢".repeat(100), "\u{1f4e1}".repeat(30));
+
+        let packet_ids = stream_api
+            .send_text(
+                &mut TestRouter,
+                text.clone(),
+                PacketDestination::Node(NodeId::new(2)),
+                false,
+                MeshChannel::new(1).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(packet_ids.len(), 2);
+
+        let mut chunks = vec![];
+        while chunks.len() < packet_ids.len() {
+            let mesh_packet = read_mesh_packet(&mut server).await;
+            let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) =
+                mesh_packet.payload_variant
+            else {
+                panic!("expected a decoded payload");
+            };
+
+            assert_eq!(mesh_packet.id, packet_ids[chunks.len()].id());
+            assert_eq!(mesh_packet.to, 2);
+            assert_eq!(mesh_packet.channel, 1);
+            assert_eq!(data.portnum(), protobufs::PortNum::TextMessageApp);
+            assert!(data.payload.len() <= utils::DATA_PAYLOAD_LEN);
+
+            chunks.push(String::from_utf8(data.payload).unwrap());
+        }
+
+        assert_eq!(chunks.concat(), text);
+    }
+}
@@ -1,19 +1,80 @@
+use std::time::SystemTime;
+
+use crate::connections::diagnostics::{DropDiagnostics, DropReason};
+use crate::errors_internal::Error;
 use crate::protobufs;
 use log::{debug, error, trace};
 use prost::Message;
 use thiserror::Error;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::bytes::{Bytes, BytesMut};
+
+/// A struct that represents a `FromRadio` packet decoded from a radio stream, along with the
+/// raw bytes the packet was decoded from (excluding the packet header) and the host time at
+/// which the packet was decoded.
+///
+/// The raw bytes share the allocation of the buffer they were received into, so cloning a
+/// `ReceivedPacket`, e.g. to broadcast it to several subscribers, does not copy them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedPacket {
+    pub packet: protobufs::FromRadio,
+    pub raw: Bytes,
+    pub received_at: SystemTime,
+}
+
+/// The default maximum number of bytes the `StreamBuffer` holds while waiting for the remainder of a
+/// packet. The firmware sends packets of at most 512 bytes, so this leaves ample room for a complete
+/// packet while bounding the memory used when a device streams garbage.
+pub const DEFAULT_MAX_BUFFER_SIZE: usize = 4096;
+
+/// The first magic byte of the header that precedes every packet sent to or received from a radio.
+pub const START1: u8 = 0x94;
+
+/// The second magic byte of the header that precedes every packet sent to or received from a radio.
+pub const START2: u8 = 0xc3;
+
+/// The maximum size of an encoded packet accepted by the firmware, excluding the packet header.
+/// Larger packets are discarded by the radio.
+pub const MAX_PACKET_SIZE: usize = 512;
+
+/// A struct that defines the framing of packets sent to and received from a radio. Each packet is
+/// preceded by a 4-byte header, consisting of the `start1` and `start2` magic bytes followed by the
+/// big-endian length of the packet.
+///
+/// The default framing matches the framing used by the firmware. Overriding the framing is only
+/// intended for experimenting with protocol variants, e.g. within the `MeshtasticCodec` struct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FramingConfig {
+    /// The first magic byte of the packet header, `START1` by default.
+    pub start1: u8,
+    /// The second magic byte of the packet header, `START2` by default.
+    pub start2: u8,
+    /// The maximum size of an encoded outgoing packet, `MAX_PACKET_SIZE` by default.
+    pub max_packet_size: usize,
+}
 
-use super::wrappers::encoded_data::IncomingStreamData;
+impl Default for FramingConfig {
+    fn default() -> Self {
+        Self {
+            start1: START1,
+            start2: START2,
+            max_packet_size: MAX_PACKET_SIZE,
+        }
+    }
+}
 
-/// A struct that represents a buffer of bytes received from a radio stream.
-/// This struct is used to store bytes received from a radio stream, and is
-/// used to incrementally decode bytes from the received stream into valid
-/// FromRadio packets.
+/// A struct that incrementally decodes the bytes received from a radio stream into valid
+/// FromRadio packets. The received bytes are held in a `BytesMut` buffer owned by the caller,
+/// from which the `next_packet` method removes each decoded packet, along with any bytes that
+/// are not part of a valid packet.
+///
+/// The buffer never holds more than `max_buffer_size` bytes of undecoded data. If this limit is
+/// exceeded, e.g. because a corrupted packet header announces a packet that never completes, the
+/// buffered data is discarded and the buffer resynchronizes on the next packet header.
 #[derive(Clone, Debug)]
 pub struct StreamBuffer {
-    buffer: Vec<u8>,
-    decoded_packet_tx: UnboundedSender<protobufs::FromRadio>,
+    max_buffer_size: usize,
+    framing: FramingConfig,
+    drop_diagnostics: DropDiagnostics,
 }
 
 /// An enum that represents the possible errors that can occur when processing
@@ -21,10 +82,13 @@ pub struct StreamBuffer {
 /// should wait to receive more data or if the buffer should be purged.
 #[derive(Error, Debug, Clone)]
 pub enum StreamBufferError {
-    #[error("Could not find header sequence [0x94, 0xc3] in buffer")]
+    #[error("Could not find packet header sequence in buffer")]
     MissingHeaderBytes,
-    #[error("Incorrect framing byte: got {found_framing_byte}, expected 0xc3")]
-    IncorrectFramingByte { found_framing_byte: u8 },
+    #[error("Incorrect framing byte: got {found_framing_byte}, expected {expected_framing_byte}")]
+    IncorrectFramingByte {
+        found_framing_byte: u8,
+        expected_framing_byte: u8,
+    },
     #[error("Buffer data is shorter than packet header size: buffer contains {buffer_size} bytes, expected at least {packet_size} bytes")]
     IncompletePacket {
         buffer_size: usize,
@@ -36,6 +100,8 @@ pub enum StreamBufferError {
     MissingLSB { lsb_index: usize },
     #[error("Detected malformed packet, packet buffer contains a framing byte at index {next_packet_start_idx}")]
     MalformedPacket { next_packet_start_idx: usize },
+    #[error("Received a zero-length packet")]
+    EmptyPacket,
     #[error(transparent)]
     DecodeFailure(#[from] prost::DecodeError),
 }
@@ -43,118 +109,171 @@ pub enum StreamBufferError {
 const PACKET_HEADER_SIZE: usize = 4;
 
 impl StreamBuffer {
-    /// Creates a new StreamBuffer instance that will send decoded FromRadio packets
-    /// to the given broadcast channel.
-    pub fn new(decoded_packet_tx: UnboundedSender<protobufs::FromRadio>) -> Self {
+    /// Creates a new StreamBuffer instance that holds at most `DEFAULT_MAX_BUFFER_SIZE` bytes
+    /// of undecoded data.
+    #[cfg(test)]
+    pub fn new() -> Self {
+        StreamBuffer::with_max_buffer_size(DEFAULT_MAX_BUFFER_SIZE)
+    }
+
+    /// Creates a new StreamBuffer instance that holds at most `max_buffer_size` bytes of
+    /// undecoded data.
+    pub fn with_max_buffer_size(max_buffer_size: usize) -> Self {
+        StreamBuffer::with_framing(max_buffer_size, FramingConfig::default())
+    }
+
+    /// Creates a new StreamBuffer instance that holds at most `max_buffer_size` bytes of
+    /// undecoded data, and decodes packets framed with the specified magic bytes.
+    pub fn with_framing(max_buffer_size: usize, framing: FramingConfig) -> Self {
         StreamBuffer {
-            buffer: vec![],
-            decoded_packet_tx,
+            max_buffer_size,
+            framing,
+            drop_diagnostics: DropDiagnostics::disabled(),
         }
     }
 
-    /// Takes in a portion of a stream message, stores it in a buffer,
-    /// and attempts to decode the buffer into valid FromRadio packets.
+    /// Reports the data discarded by this buffer, e.g. packets that fail to decode, to the passed
+    /// `DropDiagnostics` instance. Discarded data is not reported by default.
+    pub fn with_drop_diagnostics(mut self, drop_diagnostics: DropDiagnostics) -> Self {
+        self.drop_diagnostics = drop_diagnostics;
+        self
+    }
+
+    /// Attempts to decode the next FromRadio packet from a buffer of bytes received from a radio
+    /// stream. The decoded packet is removed from the buffer, along with any preceding bytes that
+    /// are not part of a valid packet, e.g. debug log output or malformed packets.
     ///
     /// # Arguments
     ///
-    /// * `message` - A vector of bytes received from a radio stream
+    /// * `buffer` - The bytes received from a radio stream that have not been decoded yet.
+    ///
+    /// # Returns
+    ///
+    /// The next decoded packet, or `None` if the buffer does not contain a complete packet. In this
+    /// case, the incomplete packet is left in the buffer until more data is received.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferOverflow` if the undecoded data left in the buffer exceeds the maximum
+    /// buffer size. The buffered data is discarded in this case, so processing can continue.
     ///
     /// # Example
     ///
     /// ```
-    /// let (rx, mut tx) = broadcast::channel::<protobufs::FromRadio>(32);
-    /// let buffer = StreamBuffer::new(tx);
+    /// let mut stream_buffer = StreamBuffer::with_max_buffer_size(DEFAULT_MAX_BUFFER_SIZE);
+    /// let mut buffer = BytesMut::new();
     ///
     /// while let Some(message) = stream.try_next().await? {
-    ///    buffer.process_incoming_bytes(message);
+    ///     buffer.extend_from_slice(&message);
+    ///
+    ///     while let Some(packet) = stream_buffer.next_packet(&mut buffer)? {
+    ///         println!("Received packet: {:?}", packet.packet);
+    ///     }
     /// }
     /// ```
-    pub fn process_incoming_bytes(&mut self, message: IncomingStreamData) {
-        let message = message.data();
-        self.buffer.append(Vec::from(message).as_mut());
-
+    pub fn next_packet(&mut self, buffer: &mut BytesMut) -> Result<Option<ReceivedPacket>, Error> {
         // While there are still bytes in the buffer and processing isn't completed,
         // continue processing the buffer
-        while !self.buffer.is_empty() {
-            let decoded_packet = match self.process_packet_buffer() {
-                Ok(packet) => packet,
-                Err(err) => match err {
-                    StreamBufferError::MissingHeaderBytes => {
-                        error!("Could not find header sequence [0x94, 0xc3], purging buffer and waiting for more data");
-
-                        break; // Wait for more data
-                    }
-                    StreamBufferError::IncorrectFramingByte { found_framing_byte } => {
-                        error!(
-                            "Byte {} not equal to 0xc3, waiting for more data",
-                            found_framing_byte
-                        );
-
-                        break; // Wait for more data
-                    }
-                    StreamBufferError::IncompletePacket {
-                        buffer_size,
-                        packet_size,
-                    } => {
-                        error!(
-                            "Incomplete packet data, expected {} bytes, found {} bytes",
-                            packet_size, buffer_size
-                        );
-
-                        break; // Wait for more data
-                    }
-                    StreamBufferError::MissingMSB { msb_index } => {
-                        error!(
-                            "Could not find MSB at index {}, waiting for more data",
-                            msb_index
-                        );
-
-                        break; // Wait for more data
-                    }
-                    StreamBufferError::MissingLSB { lsb_index } => {
-                        error!(
-                            "Could not find LSB at index {}, waiting for more data",
-                            lsb_index
-                        );
-
-                        break; // Wait for more data
-                    }
-                    StreamBufferError::MalformedPacket {
-                        next_packet_start_idx,
-                    } => {
-                        error!(
-                              "Detected malformed packet with next packet starting at index {}, purged malformed packet",
-                              next_packet_start_idx
-                          );
-
-                        continue; // Don't need more data to continue, purge from buffer
-                    }
-                    StreamBufferError::DecodeFailure { .. } => {
-                        error!("Failed to decode chunk from packet, this does not affect the next iteration");
-
-                        continue; // Don't need more data to continue, ignore decode failure
-                    }
-                },
+        while !buffer.is_empty() {
+            let err = match self.process_packet_buffer(buffer) {
+                Ok(decoded_packet) => {
+                    trace!("Successfully decoded packet");
+                    return Ok(Some(decoded_packet));
+                }
+                Err(err) => err,
             };
 
-            trace!("Successfully decoded packet");
+            match err {
+                StreamBufferError::MissingHeaderBytes => {
+                    error!(
+                        "Could not find header sequence [{:#x}, {:#x}], purging buffer and waiting for more data",
+                        self.framing.start1, self.framing.start2
+                    );
 
-            match self.decoded_packet_tx.send(decoded_packet) {
-                Ok(_) => {
-                    trace!("Successfully sent decoded packet");
-                    continue;
+                    break; // Wait for more data
                 }
-                Err(e) => {
-                    error!("Failed to send decoded packet: {}", e.to_string());
-                    break;
+                StreamBufferError::IncorrectFramingByte {
+                    found_framing_byte,
+                    expected_framing_byte,
+                } => {
+                    error!(
+                        "Byte {} not equal to {:#x}, waiting for more data",
+                        found_framing_byte, expected_framing_byte
+                    );
+
+                    break; // Wait for more data
                 }
-            };
+                StreamBufferError::IncompletePacket {
+                    buffer_size,
+                    packet_size,
+                } => {
+                    error!(
+                        "Incomplete packet data, expected {} bytes, found {} bytes",
+                        packet_size, buffer_size
+                    );
+
+                    break; // Wait for more data
+                }
+                StreamBufferError::MissingMSB { msb_index } => {
+                    error!(
+                        "Could not find MSB at index {}, waiting for more data",
+                        msb_index
+                    );
+
+                    break; // Wait for more data
+                }
+                StreamBufferError::MissingLSB { lsb_index } => {
+                    error!(
+                        "Could not find LSB at index {}, waiting for more data",
+                        lsb_index
+                    );
+
+                    break; // Wait for more data
+                }
+                StreamBufferError::MalformedPacket {
+                    next_packet_start_idx,
+                } => {
+                    error!(
+                          "Detected malformed packet with next packet starting at index {}, purged malformed packet",
+                          next_packet_start_idx
+                      );
+
+                    continue; // Don't need more data to continue, purge from buffer
+                }
+                StreamBufferError::EmptyPacket => {
+                    debug!("Skipping zero-length packet");
+
+                    continue; // The empty packet has been purged from the buffer
+                }
+                StreamBufferError::DecodeFailure { .. } => {
+                    error!("Failed to decode chunk from packet, this does not affect the next iteration");
+
+                    continue; // Don't need more data to continue, ignore decode failure
+                }
+            }
         }
 
         trace!(
             "Processing complete, buffer contains {} bytes",
-            self.buffer.len()
+            buffer.len()
         );
+
+        if buffer.len() > self.max_buffer_size {
+            let buffer_size = buffer.len();
+
+            self.drop_diagnostics
+                .report(DropReason::BufferOverflow, buffer);
+
+            // Discard the incomplete data, the next packet header will resynchronize the buffer
+            buffer.clear();
+
+            return Err(Error::BufferOverflow {
+                buffer_size,
+                max_buffer_size: self.max_buffer_size,
+            });
+        }
+
+        Ok(None)
     }
 
     /// An internal helper function that is called iteratively on the internal buffer. This
@@ -164,42 +283,59 @@ impl StreamBuffer {
     /// enough data to decode a packet, and is able to successfully decode the packet.
     ///
     /// **Note:** This function should only be called when not all received data in the buffer has been processed.
-    fn process_packet_buffer(&mut self) -> Result<protobufs::FromRadio, StreamBufferError> {
-        trace!(
-            "Packet buffer with length {:?}: {:?}",
-            self.buffer.len(),
-            self.buffer
-        );
+    fn process_packet_buffer(
+        &mut self,
+        buffer: &mut BytesMut,
+    ) -> Result<ReceivedPacket, StreamBufferError> {
+        trace!("Packet buffer with length {:?}: {:?}", buffer.len(), buffer);
 
         // Check that the buffer can potentially contain a packet header
-        if self.buffer.len() < PACKET_HEADER_SIZE {
+        if buffer.len() < PACKET_HEADER_SIZE {
             return Err(StreamBufferError::IncompletePacket {
-                buffer_size: self.buffer.len(),
+                buffer_size: buffer.len(),
                 packet_size: PACKET_HEADER_SIZE,
             });
         }
 
-        let framing_index = StreamBuffer::shift_buffer_to_first_valid_header(&mut self.buffer)?;
+        let framing_index =
+            StreamBuffer::shift_buffer_to_first_valid_header(buffer, &self.framing)?;
 
         // Note: the framing index should always be 0 at this point, keeping for clarity
-        let incoming_packet_data_size = self.get_data_size_from_header(framing_index)?;
+        let incoming_packet_data_size = self.get_data_size_from_header(buffer, framing_index)?;
 
-        self.validate_packet_in_buffer(incoming_packet_data_size, framing_index)?;
+        self.validate_packet_in_buffer(buffer, incoming_packet_data_size, framing_index)?;
 
         // Get packet data, excluding magic bytes
         let packet_data =
-            self.extract_packet_from_buffer(incoming_packet_data_size, framing_index)?;
+            self.extract_packet_from_buffer(buffer, incoming_packet_data_size, framing_index)?;
+
+        // Zero-length packets decode to an empty `FromRadio` packet, which carries no information
+        if packet_data.is_empty() {
+            return Err(StreamBufferError::EmptyPacket);
+        }
 
         // Attempt to decode the current packet
-        let decoded_packet = protobufs::FromRadio::decode(packet_data.as_slice())?;
+        let decoded_packet = match protobufs::FromRadio::decode(&packet_data[..]) {
+            Ok(decoded_packet) => decoded_packet,
+            Err(e) => {
+                self.drop_diagnostics
+                    .report(DropReason::DecodeFailure, &packet_data);
+                return Err(e.into());
+            }
+        };
 
-        Ok(decoded_packet)
+        Ok(ReceivedPacket {
+            packet: decoded_packet,
+            raw: packet_data,
+            received_at: SystemTime::now(),
+        })
     }
 
     fn shift_buffer_to_first_valid_header(
-        buffer: &mut Vec<u8>,
+        buffer: &mut BytesMut,
+        framing: &FramingConfig,
     ) -> Result<usize, StreamBufferError> {
-        let mut framing_index = StreamBuffer::find_framing_index_or_clear_buffer(buffer)?;
+        let mut framing_index = StreamBuffer::find_framing_index_or_clear_buffer(buffer, framing)?;
 
         if framing_index != 0 {
             debug!(
@@ -207,11 +343,11 @@ impl StreamBuffer {
                 framing_index
             );
 
-            buffer.drain(0..framing_index);
+            let _ = buffer.split_to(framing_index);
 
             log::trace!("Buffer after shifting: {:?}", buffer);
 
-            framing_index = StreamBuffer::find_framing_index_or_clear_buffer(buffer)?;
+            framing_index = StreamBuffer::find_framing_index_or_clear_buffer(buffer, framing)?;
         }
 
         trace!("Returning framing index: {}", framing_index);
@@ -220,9 +356,10 @@ impl StreamBuffer {
     }
 
     fn find_framing_index_or_clear_buffer(
-        buffer: &mut Vec<u8>,
+        buffer: &mut BytesMut,
+        framing: &FramingConfig,
     ) -> Result<usize, StreamBufferError> {
-        let framing_index = match StreamBuffer::find_framing_index(buffer)? {
+        let framing_index = match StreamBuffer::find_framing_index(buffer, framing)? {
             Some(idx) => idx,
             None => {
                 buffer.clear(); // Clear buffer since no packets exist
@@ -233,47 +370,56 @@ impl StreamBuffer {
         Ok(framing_index)
     }
 
-    // All valid packets start with the sequence [0x94 0xc3 size_msb size_lsb], where
+    // All valid packets start with the sequence [START1 START2 size_msb size_lsb], where
     // size_msb and size_lsb collectively give the size of the incoming packet
-    // We need to also validate that, if the 0x94 is found and not at the end of the
-    // buffer, that the next byte is 0xc3
+    // We need to also validate that, if START1 is found and not at the end of the
+    // buffer, that the next byte is START2
     // Note that the maximum packet size currently stands at 240 bytes, meaning an MSB is not needed
-    fn find_framing_index(buffer: &mut [u8]) -> Result<Option<usize>, StreamBufferError> {
+    fn find_framing_index(
+        buffer: &[u8],
+        framing: &FramingConfig,
+    ) -> Result<Option<usize>, StreamBufferError> {
         // Not possible to have a two-byte sequence in a buffer with less than two bytes
         // Vec::windows will also panic if the buffer is empty
         if buffer.len() < 2 {
             return Ok(None);
         }
 
-        let framing_index = buffer.windows(2).position(|b| b == [0x94, 0xc3]);
+        let framing_index = buffer
+            .windows(2)
+            .position(|b| b == [framing.start1, framing.start2]);
 
         Ok(framing_index)
     }
 
     fn get_data_size_from_header(
-        &mut self,
+        &self,
+        buffer: &[u8],
         framing_index: usize,
     ) -> Result<usize, StreamBufferError> {
         // Get the "framing byte" after the start of the packet header, or fail if not found
-        let found_framing_byte = match self.buffer.get(framing_index + 1) {
+        let found_framing_byte = match buffer.get(framing_index + 1) {
             Some(val) => val.to_owned(),
             None => {
                 debug!("Could not find framing byte, waiting for more data");
                 return Err(StreamBufferError::IncompletePacket {
-                    buffer_size: self.buffer.len(),
+                    buffer_size: buffer.len(),
                     packet_size: PACKET_HEADER_SIZE,
                 });
             }
         };
 
         // Check that the framing byte is correct, and fail if not
-        if found_framing_byte != 0xc3 {
-            return Err(StreamBufferError::IncorrectFramingByte { found_framing_byte });
+        if found_framing_byte != self.framing.start2 {
+            return Err(StreamBufferError::IncorrectFramingByte {
+                found_framing_byte,
+                expected_framing_byte: self.framing.start2,
+            });
         }
 
         // Get the MSB of the packet header size, or wait to receive all data
         let msb_index: usize = 2;
-        let msb = match self.buffer.get(msb_index) {
+        let msb = match buffer.get(msb_index) {
             Some(val) => val,
             None => {
                 return Err(StreamBufferError::MissingMSB { msb_index });
@@ -282,7 +428,7 @@ impl StreamBuffer {
 
         // Get the LSB of the packet header size, or wait to receive all data
         let lsb_index: usize = 3;
-        let lsb = match self.buffer.get(lsb_index) {
+        let lsb = match buffer.get(lsb_index) {
             Some(val) => val,
             None => {
                 return Err(StreamBufferError::MissingLSB { lsb_index });
@@ -298,12 +444,13 @@ impl StreamBuffer {
 
     fn validate_packet_in_buffer(
         &mut self,
+        buffer: &mut BytesMut,
         packet_data_size: usize,
         framing_index: usize,
     ) -> Result<(), StreamBufferError> {
-        if self.buffer.len() < PACKET_HEADER_SIZE + packet_data_size {
+        if buffer.len() < PACKET_HEADER_SIZE + packet_data_size {
             return Err(StreamBufferError::IncompletePacket {
-                buffer_size: self.buffer.len(),
+                buffer_size: buffer.len(),
                 packet_size: PACKET_HEADER_SIZE + packet_data_size,
             });
         }
@@ -311,10 +458,10 @@ impl StreamBuffer {
         let packet_data_start_index = framing_index + PACKET_HEADER_SIZE;
         let mut packet_data_end_index = packet_data_start_index + packet_data_size;
 
-        // In the event that the last byte is 0x94, we need to account for the possibility of
-        // the next byte being 0xc3, which would indicate that the packet is malformed.
+        // In the event that the last byte is START1, we need to account for the possibility of
+        // the next byte being START2, which would indicate that the packet is malformed.
         // We can only do this when the buffer has enough data to avoid a slice index panic.
-        if self.buffer.len() > packet_data_end_index {
+        if buffer.len() > packet_data_end_index {
             packet_data_end_index += 1;
         }
 
@@ -324,16 +471,21 @@ impl StreamBuffer {
         //     packet_data_start_index + packet_data_size
         // );
 
-        let mut packet_buffer =
-            self.buffer[packet_data_start_index..packet_data_end_index].to_vec();
+        let packet_buffer = &buffer[packet_data_start_index..packet_data_end_index];
 
-        let next_packet_start_index = StreamBuffer::find_framing_index(&mut packet_buffer)?
-            // We need to re-normalize to the original buffer since we're working with a sub-slice
-            .map(|idx| idx + packet_data_start_index);
+        let next_packet_start_index =
+            StreamBuffer::find_framing_index(packet_buffer, &self.framing)?
+                // We need to re-normalize to the original buffer since we're working with a sub-slice
+                .map(|idx| idx + packet_data_start_index);
 
         if let Some(next_packet_start_idx) = next_packet_start_index {
             // Remove malformed packet from buffer
-            self.buffer.drain(..next_packet_start_idx);
+            let malformed_packet = buffer.split_to(next_packet_start_idx);
+
+            self.drop_diagnostics.report(
+                DropReason::MalformedPacket,
+                &malformed_packet[PACKET_HEADER_SIZE..],
+            );
 
             return Err(StreamBufferError::MalformedPacket {
                 next_packet_start_idx,
@@ -345,67 +497,50 @@ impl StreamBuffer {
 
     fn extract_packet_from_buffer(
         &mut self,
+        buffer: &mut BytesMut,
         packet_data_size: usize,
         framing_index: usize,
-    ) -> Result<Vec<u8>, StreamBufferError> {
-        if self.buffer.len() < packet_data_size {
+    ) -> Result<Bytes, StreamBufferError> {
+        if buffer.len() < packet_data_size {
             return Err(StreamBufferError::IncompletePacket {
-                buffer_size: self.buffer.len(),
+                buffer_size: buffer.len(),
                 packet_size: PACKET_HEADER_SIZE + packet_data_size,
             });
         }
 
-        let packet_start_index = framing_index;
-        let packet_end_index = framing_index + PACKET_HEADER_SIZE + packet_data_size;
-
-        // Extract packet with header before removing header
-        let mut packet_data_with_header: Vec<u8> = self
-            .buffer
-            .drain(packet_start_index..packet_end_index)
-            .collect();
-
-        // trace!(
-        //     "Extracted packet data with header of length {:?} from buffer: {:?}",
-        //     packet_data_with_header.len(),
-        //     packet_data_with_header
-        // );
+        // Discard any bytes preceding the packet, then extract the packet with its header
+        let _ = buffer.split_to(framing_index);
+        let mut packet_data_with_header = buffer.split_to(PACKET_HEADER_SIZE + packet_data_size);
 
         // Remove header bytes
-        let packet_data: Vec<u8> = packet_data_with_header
-            .drain(PACKET_HEADER_SIZE..)
-            .collect();
-
-        // trace!(
-        //     "Extracted packet data of length {:?} from buffer: {:?}",
-        //     packet_data.len(),
-        //     packet_data
-        // );
-
-        Ok(packet_data)
+        Ok(packet_data_with_header
+            .split_off(PACKET_HEADER_SIZE)
+            .freeze())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
-
     use crate::{protobufs, utils_internal::format_data_packet};
-    use futures_util::FutureExt;
     use prost::Message;
-    use tokio::sync::mpsc::unbounded_channel;
 
     use super::*;
 
-    async fn timeout_test<F, T>(future: F, timeout: impl Into<Option<Duration>>) -> T
-    where
-        F: FutureExt<Output = T> + Send,
-    {
-        let timeout_opt: Option<Duration> = timeout.into();
-        let timeout_duration = timeout_opt.unwrap_or(Duration::from_millis(100));
+    /// Appends the incoming bytes to the buffer, and decodes packets until the buffer contains
+    /// no complete packet.
+    fn process_incoming_bytes(
+        stream_buffer: &mut StreamBuffer,
+        buffer: &mut BytesMut,
+        data: &[u8],
+    ) -> Result<Vec<protobufs::FromRadio>, Error> {
+        buffer.extend_from_slice(data);
+
+        let mut decoded_packets = vec![];
+        while let Some(decoded_packet) = stream_buffer.next_packet(buffer)? {
+            decoded_packets.push(decoded_packet.packet);
+        }
 
-        tokio::time::timeout(timeout_duration, future)
-            .await
-            .expect("Future timed out")
+        Ok(decoded_packets)
     }
 
     fn mock_encoded_from_radio_packet(
@@ -425,8 +560,8 @@ mod tests {
     /// Test for processing a single complete packet.
     /// The buffer contains one complete packet with a correct header, length, and matching data size.
     /// Expected behavior is that the function extracts this packet correctly and leaves the buffer empty if no extra bytes are present.
-    #[tokio::test]
-    async fn process_single_complete_packet() {
+    #[test]
+    fn process_single_complete_packet() {
         // Arrange
 
         let payload_variant_1 =
@@ -435,24 +570,27 @@ mod tests {
         let (packet_1, packet_data_1) = mock_encoded_from_radio_packet(payload_variant_1, None);
         let encoded_packet_1 = format_data_packet(packet_data_1.into()).unwrap();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1.data().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_1]);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for handling an incomplete packet at the buffer's end.
     /// The buffer ends with a partial packet, only the header and part of the length are present.
     /// Expected behavior is that the function should not process this incomplete packet and leave it in the buffer for the next chunk to complete it.
-    #[tokio::test]
-    async fn handle_incomplete_packet_at_end() {
+    #[test]
+    fn handle_incomplete_packet_at_end() {
         // Arrange
 
         let payload_variant_1 =
@@ -472,26 +610,36 @@ mod tests {
             .take(6)
             .collect::<Vec<u8>>();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1.data().into());
-        buffer.process_incoming_bytes(incomplete_encoded_packet_2.clone().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(
+                &mut stream_buffer,
+                &mut buffer,
+                &incomplete_encoded_packet_2,
+            )
+            .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(buffer.buffer.len(), 6);
-        assert_eq!(buffer.buffer, incomplete_encoded_packet_2);
+        assert_eq!(decoded_packets, vec![packet_1]);
+        assert_eq!(buffer.len(), 6);
+        assert_eq!(buffer, incomplete_encoded_packet_2);
     }
 
     /// Test for processing multiple complete packets in a buffer.
     /// The buffer contains several complete packets back-to-back.
     /// Expected behavior is that the function processes and extracts all packets, leaving the buffer empty if no incomplete packet is trailing.
-    #[tokio::test]
-    async fn process_multiple_complete_packets() {
+    #[test]
+    fn process_multiple_complete_packets() {
         // Arrange
 
         let payload_variant_1 =
@@ -505,26 +653,31 @@ mod tests {
         let encoded_packet_1 = format_data_packet(packet_data_1.into()).unwrap();
         let encoded_packet_2 = format_data_packet(packet_data_2.into()).unwrap();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1.data().into());
-        buffer.process_incoming_bytes(encoded_packet_2.data().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_2.data())
+                .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_2));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_1, packet_2]);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for processing a buffer containing a valid packet followed by a malformed packet.
     /// The expected behavior is to process the first valid packet, then recognize the malformed packet and discard it,
     /// and resume processing with the next valid packet.
-    #[tokio::test]
-    async fn handle_malformed_packet_amid_valid_packets() {
+    #[test]
+    fn handle_malformed_packet_amid_valid_packets() {
         // Arrange
 
         let payload_variant_1 =
@@ -548,26 +701,34 @@ mod tests {
             .take(6)
             .collect::<Vec<u8>>();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1.data().into());
-        buffer.process_incoming_bytes(malformed_encoded_packet_2.clone().into());
-        buffer.process_incoming_bytes(encoded_packet_3.data().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &malformed_encoded_packet_2)
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_3.data())
+                .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_3));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_1, packet_3]);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for handling a buffer that ends with a false start (`0x94` without `0xc3` following).
     /// Expected behavior is that the function leaves the byte in the buffer, waiting for the next chunk to resolve the ambiguity.
-    #[tokio::test]
-    async fn handle_buffer_ending_with_false_start() {
+    #[test]
+    fn handle_buffer_ending_with_false_start() {
         // Arrange
 
         let payload_variant_1 =
@@ -576,46 +737,52 @@ mod tests {
         let (packet_1, packet_data_1) = mock_encoded_from_radio_packet(payload_variant_1, None);
         let encoded_packet_1 = format_data_packet(packet_data_1.into()).unwrap();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1.data().into());
-        buffer.process_incoming_bytes(vec![0x94].into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+        decoded_packets
+            .extend(process_incoming_bytes(&mut stream_buffer, &mut buffer, &[0x94]).unwrap());
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(buffer.buffer, vec![0x94]);
+        assert_eq!(decoded_packets, vec![packet_1]);
+        assert_eq!(buffer, vec![0x94]);
     }
 
     /// Test for processing a packet when the buffer starts with `0x94` but the next byte is not `0xc3`,
     /// and no valid packet header (`0x94 0xc3`) appears later in the buffer.
     /// Expected behavior is that the function should clear the buffer until a valid packet header is found or the buffer is proven to contain no valid packets.
-    #[tokio::test]
-    async fn clear_buffer_on_invalid_packet_start() {
+    #[test]
+    fn clear_buffer_on_invalid_packet_start() {
         // Arrange
 
         let malformed_packet_1 = vec![0x94, 0x00, 0x94, 0x94, 0x00];
 
-        let (mock_tx, mut _mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(malformed_packet_1.into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &malformed_packet_1).unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for processing packets when the buffer contains multiple instances of `0x94` not followed by `0xc3`
     /// before finally presenting a valid packet header.
     /// Expected behavior is that the function discards all bytes up to the first valid packet header and then processes the valid packet(s) thereafter.
-    #[tokio::test]
-    async fn process_after_repeated_false_starts() {
+    #[test]
+    fn process_after_repeated_false_starts() {
         // Arrange
 
         let payload_variant_2 =
@@ -626,24 +793,29 @@ mod tests {
 
         let malformed_packet_1 = vec![0x94, 0x00, 0x94, 0x94, 0x00];
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(malformed_packet_1.into());
-        buffer.process_incoming_bytes(encoded_packet_2.data().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &malformed_packet_1).unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_2.data())
+                .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_2));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_2]);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for processing a large packet that spans multiple buffer chunks.
     /// Expected behavior is that the function correctly aggregates data across chunks until the full packet is received and then processes it.
-    #[tokio::test]
-    async fn process_large_packet_spanning_multiple_chunks() {
+    #[test]
+    fn process_large_packet_spanning_multiple_chunks() {
         // Arrange
 
         let payload_variant_1 =
@@ -665,32 +837,37 @@ mod tests {
             .skip(6)
             .collect::<Vec<u8>>();
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_packet_1_chunk_1.into());
-        buffer.process_incoming_bytes(encoded_packet_1_chunk_2.into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &encoded_packet_1_chunk_1)
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &encoded_packet_1_chunk_2)
+                .unwrap(),
+        );
 
         // Assert
 
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_1));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_1]);
+        assert_eq!(buffer.len(), 0);
     }
 
     // /// Test for handling overlapping packet header and length bytes.
     // /// A valid packet header is immediately followed by bytes that could be interpreted as another packet header within the data segment.
     // /// Expected behavior is that the function processes the entire packet without misinterpreting internal data as a new header.
     // /// Note that this is an edge case, as current Meshtastic firmware does not allow for packets long enough for this to happen.
-    // #[tokio::test]
-    // async fn handle_overlapping_header_and_length_bytes() {}
+    // #[test]
+    // fn handle_overlapping_header_and_length_bytes() {}
 
     /// Test for processing a packet with length bytes indicating a length of 0.
-    /// Expected behavior is that the function processes the packet as an empty packet and continues with the next packet in the buffer.
-    /// Note: An "empty" packet is interpreted as a packet with an empty payload variant with a packet id of 0.
-    #[tokio::test]
-    async fn process_packet_with_zero_length() {
+    /// Expected behavior is that the function skips the empty packet without emitting it, and continues with the next packet in the buffer.
+    #[test]
+    fn process_packet_with_zero_length() {
         let payload_variant_2 =
             protobufs::from_radio::PayloadVariant::MyInfo(protobufs::MyNodeInfo::default());
 
@@ -699,24 +876,24 @@ mod tests {
 
         let encoded_zero_length_packet = vec![0x94, 0xc3, 0x00, 0x00];
 
-        let (mock_tx, mut mock_rx) = unbounded_channel::<protobufs::FromRadio>();
-
         // Act
 
-        let mut buffer = StreamBuffer::new(mock_tx);
-        buffer.process_incoming_bytes(encoded_zero_length_packet.into());
-        buffer.process_incoming_bytes(encoded_packet_2.data().into());
+        let mut stream_buffer = StreamBuffer::new();
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &encoded_zero_length_packet)
+                .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_2.data())
+                .unwrap(),
+        );
 
         // Assert
 
-        let empty_packet = protobufs::FromRadio {
-            id: 0,
-            payload_variant: None,
-        };
-
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(empty_packet));
-        assert_eq!(timeout_test(mock_rx.recv(), None).await, Some(packet_2));
-        assert_eq!(buffer.buffer.len(), 0);
+        assert_eq!(decoded_packets, vec![packet_2]);
+        assert_eq!(buffer.len(), 0);
     }
 
     /// Test for detecting malformed packets when the packet data contains 0x94 bytes followed by 0x94 0xC3 sequence.
@@ -725,8 +902,93 @@ mod tests {
     /// length bytes, but contains a 0x94 byte followed, at some point, by a 0x94 0xC3 sequence within the data payload.
     /// Expected behavior is for the algorithm to identify this packet as malformed upon detecting the 0x94 0xC3 sequence
     /// within the payload and to discard the malformed packet.
-    #[tokio::test]
-    async fn detect_malformed_packets_with_internal_header_sequence() {}
+    #[test]
+    fn detect_malformed_packets_with_internal_header_sequence() {}
 
     // TODO need to test that we update the framing index after shifting the buffer
+
+    /// Test for bounding the size of the buffer.
+    /// The buffer receives a header announcing a packet larger than the maximum buffer size.
+    /// Expected behavior is that the buffer reports an overflow once the limit is exceeded, discards the
+    /// buffered data, and resumes decoding with the next packet.
+    #[test]
+    fn discard_data_exceeding_max_buffer_size() {
+        // Arrange
+
+        let payload_variant_1 =
+            protobufs::from_radio::PayloadVariant::MyInfo(protobufs::MyNodeInfo::default());
+
+        let (packet_1, packet_data_1) = mock_encoded_from_radio_packet(payload_variant_1, None);
+        let encoded_packet_1 = format_data_packet(packet_data_1.into()).unwrap();
+
+        // Act
+
+        let mut stream_buffer = StreamBuffer::with_max_buffer_size(16);
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, &[0x94, 0xc3, 0x01, 0x00])
+                .unwrap(),
+        );
+        let overflow = process_incoming_bytes(&mut stream_buffer, &mut buffer, &[0x00; 16]);
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+
+        // Assert
+
+        assert!(matches!(
+            overflow,
+            Err(Error::BufferOverflow {
+                buffer_size: 20,
+                max_buffer_size: 16
+            })
+        ));
+        assert_eq!(decoded_packets, vec![packet_1]);
+        assert_eq!(buffer.len(), 0);
+    }
+
+    /// Test for reporting discarded data.
+    /// The buffer receives a packet that fails to decode, followed by a valid packet.
+    /// Expected behavior is that the undecodable packet is reported to the drop diagnostics, and the valid
+    /// packet is still decoded.
+    #[test]
+    fn report_packets_failing_to_decode() {
+        // Arrange
+
+        let payload_variant_1 =
+            protobufs::from_radio::PayloadVariant::MyInfo(protobufs::MyNodeInfo::default());
+
+        let (packet_1, packet_data_1) = mock_encoded_from_radio_packet(payload_variant_1, None);
+        let encoded_packet_1 = format_data_packet(packet_data_1.into()).unwrap();
+
+        let drop_diagnostics = DropDiagnostics::enabled();
+        let mut dropped_packets = drop_diagnostics.subscribe().unwrap();
+
+        // Act
+
+        let mut stream_buffer = StreamBuffer::new().with_drop_diagnostics(drop_diagnostics);
+        let mut buffer = BytesMut::new();
+        let mut decoded_packets = vec![];
+        decoded_packets.extend(
+            process_incoming_bytes(
+                &mut stream_buffer,
+                &mut buffer,
+                &[0x94, 0xc3, 0x00, 0x02, 0xff, 0xff],
+            )
+            .unwrap(),
+        );
+        decoded_packets.extend(
+            process_incoming_bytes(&mut stream_buffer, &mut buffer, encoded_packet_1.data())
+                .unwrap(),
+        );
+
+        // Assert
+
+        let dropped_packet = dropped_packets.try_recv().unwrap();
+        assert_eq!(dropped_packet.reason, DropReason::DecodeFailure);
+        assert_eq!(dropped_packet.raw, vec![0xff, 0xff]);
+        assert_eq!(decoded_packets, vec![packet_1]);
+    }
 }
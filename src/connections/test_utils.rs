@@ -0,0 +1,64 @@
+use prost::Message;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+use crate::protobufs;
+use crate::utils_internal::format_data_packet;
+
+use super::{wrappers::NodeId, PacketRouter};
+
+/// A packet router that discards all packets, with a node id of `1`.
+pub(crate) struct TestRouter;
+
+impl PacketRouter<(), std::convert::Infallible> for TestRouter {
+    fn handle_packet_from_radio(
+        &mut self,
+        _packet: protobufs::FromRadio,
+    ) -> Result<(), std::convert::Infallible> {
+        Ok(())
+    }
+
+    fn handle_mesh_packet(
+        &mut self,
+        _packet: protobufs::MeshPacket,
+    ) -> Result<(), std::convert::Infallible> {
+        Ok(())
+    }
+
+    fn source_node_id(&self) -> NodeId {
+        NodeId::new(1)
+    }
+}
+
+/// Reads the next `ToRadio` packet written to the radio side of a duplex stream.
+pub(crate) async fn read_to_radio(server: &mut DuplexStream) -> protobufs::ToRadio {
+    let mut header = [0; 4];
+    server.read_exact(&mut header).await.unwrap();
+    let mut payload = vec![0; u16::from_be_bytes([header[2], header[3]]) as usize];
+    server.read_exact(&mut payload).await.unwrap();
+
+    protobufs::ToRadio::decode(payload.as_slice()).unwrap()
+}
+
+/// Reads the next `MeshPacket` written to the radio side of a duplex stream, skipping all other packets.
+pub(crate) async fn read_mesh_packet(server: &mut DuplexStream) -> protobufs::MeshPacket {
+    loop {
+        if let Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet)) =
+            read_to_radio(server).await.payload_variant
+        {
+            return mesh_packet;
+        }
+    }
+}
+
+/// Writes a framed `FromRadio` packet with the specified payload to the radio side of a duplex stream.
+pub(crate) async fn write_from_radio(
+    server: &mut DuplexStream,
+    payload_variant: protobufs::from_radio::PayloadVariant,
+) {
+    let from_radio = protobufs::FromRadio {
+        id: 1,
+        payload_variant: Some(payload_variant),
+    };
+    let framed = format_data_packet(from_radio.encode_to_vec().into()).unwrap();
+    server.write_all(framed.data()).await.unwrap();
+}
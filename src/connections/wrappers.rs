@@ -32,6 +32,52 @@ impl NodeId {
     pub fn id(&self) -> u32 {
         self.0
     }
+
+    /// Parses the `id` field of a `User`, i.e. the node number formatted as `!` followed by eight
+    /// hexadecimal digits, e.g. `!a1b2c3d4`.
+    ///
+    /// **Note:** The `id` of a `User` is chosen by the node that reports it, so it is not guaranteed to
+    /// match the node number. The `resolve_user_id` method of the `ConnectedStreamApi` struct looks up
+    /// the node number within the node database instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The user id to parse.
+    ///
+    /// # Returns
+    ///
+    /// The `NodeId` formatted within the user id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let node_id = NodeId::from_user_id("!a1b2c3d4")?;
+    /// assert_eq!(node_id, 0xa1b2c3d4);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidUserId` if the user id is not `!` followed by eight hexadecimal digits.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_user_id(user_id: &str) -> Result<NodeId, Error> {
+        user_id
+            .strip_prefix('!')
+            .filter(|hex| hex.len() == 8 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(NodeId)
+            .ok_or_else(|| Error::InvalidUserId {
+                user_id: user_id.to_string(),
+            })
+    }
+
+    /// Formats the node id as the `id` field of a `User`, e.g. `!a1b2c3d4`.
+    pub fn to_user_id(&self) -> String {
+        format!("!{:08x}", self.0)
+    }
 }
 
 impl From<u32> for NodeId {
@@ -40,6 +86,40 @@ impl From<u32> for NodeId {
     }
 }
 
+/// A helper struct representing the ID of a mesh packet.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PacketId(u32);
+
+impl std::fmt::Display for PacketId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PartialEq<u32> for PacketId {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PacketId {
+    /// Creates a new `PacketId` from a `u32`.
+    pub fn new(id: u32) -> PacketId {
+        PacketId(id)
+    }
+
+    /// Returns the `u32` id of the `PacketId`.
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for PacketId {
+    fn from(value: u32) -> Self {
+        PacketId(value)
+    }
+}
+
 pub mod encoded_data {
     /// A struct that represents incoming encoded data from a radio connection.
     /// The wrapped data may contain a whole packet, a partial packet, or multiple packets.
@@ -239,3 +319,211 @@ pub mod mesh_channel {
         }
     }
 }
+
+pub mod channel_psk {
+    use super::*;
+
+    /// The key used by channels with the default PSK (shorthand `1`), i.e. the key of the default
+    /// `LongFast` channel. Shorthand PSKs `2` to `255` increment the last byte of this key.
+    pub const DEFAULT_PSK: [u8; 16] = [
+        0xd4, 0xf1, 0xbb, 0x3a, 0x20, 0x29, 0x07, 0x59, 0xf0, 0xbc, 0xff, 0xab, 0xcf, 0x4e, 0x69,
+        0x01,
+    ];
+
+    /// An enum that defines the key size of a randomly generated `ChannelPsk`.
+    ///
+    /// # Variants
+    ///
+    /// * `Aes128` - A 128-bit (16-byte) key, used with AES-128.
+    /// * `Aes256` - A 256-bit (32-byte) key, used with AES-256.
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub enum PskBits {
+        Aes128,
+        #[default]
+        Aes256,
+    }
+
+    /// A struct that represents the pre-shared key (PSK) of a channel, as stored within the `psk`
+    /// field of `ChannelSettings`.
+    ///
+    /// The firmware interprets the PSK based on its length. An empty PSK disables encryption, and a
+    /// single-byte PSK is a shorthand for a well-known key: `0` disables encryption, `1` selects the
+    /// default key, and `2` to `255` select the default key with its last byte incremented by the
+    /// shorthand minus one. 16-byte and 32-byte PSKs are used directly as AES-128 and AES-256 keys.
+    /// All other lengths are invalid. This struct ensures that only valid PSKs can be constructed.
+    ///
+    /// # Default
+    ///
+    /// The default value for this struct is the default PSK, i.e. the shorthand `1`.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct ChannelPsk(Vec<u8>);
+
+    impl Default for ChannelPsk {
+        fn default() -> Self {
+            ChannelPsk::shorthand(1)
+        }
+    }
+
+    impl ChannelPsk {
+        /// Creates a PSK that disables encryption on the channel.
+        pub fn none() -> ChannelPsk {
+            ChannelPsk(vec![])
+        }
+
+        /// Creates a shorthand PSK, which selects one of the well-known keys derived from `DEFAULT_PSK`.
+        /// A shorthand of `0` disables encryption.
+        pub fn shorthand(index: u8) -> ChannelPsk {
+            ChannelPsk(vec![index])
+        }
+
+        /// Creates a PSK from a 16-byte AES-128 key.
+        pub fn aes128(key: [u8; 16]) -> ChannelPsk {
+            ChannelPsk(key.to_vec())
+        }
+
+        /// Creates a PSK from a 32-byte AES-256 key.
+        pub fn aes256(key: [u8; 32]) -> ChannelPsk {
+            ChannelPsk(key.to_vec())
+        }
+
+        /// Creates a PSK from a randomly generated key of the specified size, e.g. to set up a private channel.
+        pub fn random(bits: PskBits) -> ChannelPsk {
+            match bits {
+                PskBits::Aes128 => ChannelPsk::aes128(rand::random()),
+                PskBits::Aes256 => ChannelPsk::aes256(rand::random()),
+            }
+        }
+
+        /// Creates a PSK from the bytes of the `psk` field of `ChannelSettings`.
+        ///
+        /// # Arguments
+        ///
+        /// * `bytes` - The PSK bytes, which must be 0, 1, 16, or 32 bytes long.
+        ///
+        /// # Returns
+        ///
+        /// A `ChannelPsk` wrapping the passed bytes.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// let psk = ChannelPsk::from_bytes(&channel_settings.psk)?;
+        /// println!("Channel is encrypted: {}", psk.is_encrypted());
+        /// ```
+        ///
+        /// # Errors
+        ///
+        /// Fails with `Error::InvalidPskLength` if the passed bytes are not 0, 1, 16, or 32 bytes long.
+        ///
+        /// # Panics
+        ///
+        /// None
+        ///
+        pub fn from_bytes(bytes: &[u8]) -> Result<ChannelPsk, Error> {
+            match bytes.len() {
+                0 | 1 | 16 | 32 => Ok(ChannelPsk(bytes.to_vec())),
+                length => Err(Error::InvalidPskLength { length }),
+            }
+        }
+
+        /// Returns the canonical bytes of the PSK, as stored within the `psk` field of `ChannelSettings`.
+        pub fn as_bytes(&self) -> &[u8] {
+            &self.0
+        }
+
+        /// Consumes the PSK, returning the canonical bytes stored within the `psk` field of `ChannelSettings`.
+        pub fn into_bytes(self) -> Vec<u8> {
+            self.0
+        }
+
+        /// Returns whether packets sent on a channel with this PSK are encrypted.
+        pub fn is_encrypted(&self) -> bool {
+            !matches!(self.0.as_slice(), [] | [0])
+        }
+
+        /// Returns the AES key that the firmware uses for this PSK, expanding shorthand PSKs into the
+        /// full key. Returns `None` if the PSK disables encryption.
+        pub fn expanded_key(&self) -> Option<Vec<u8>> {
+            match self.0.as_slice() {
+                [] | [0] => None,
+                [index] => {
+                    let mut key = DEFAULT_PSK;
+                    key[15] = key[15].wrapping_add(index - 1);
+                    Some(key.to_vec())
+                }
+                key => Some(key.to_vec()),
+            }
+        }
+    }
+
+    impl From<ChannelPsk> for Vec<u8> {
+        fn from(psk: ChannelPsk) -> Self {
+            psk.into_bytes()
+        }
+    }
+
+    impl TryFrom<&[u8]> for ChannelPsk {
+        type Error = Error;
+
+        fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+            ChannelPsk::from_bytes(bytes)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn expands_shorthand_psks() {
+            assert_eq!(ChannelPsk::default().as_bytes(), [1]);
+            assert_eq!(
+                ChannelPsk::default().expanded_key(),
+                Some(DEFAULT_PSK.to_vec())
+            );
+            assert_eq!(
+                ChannelPsk::shorthand(3).expanded_key().map(|key| key[15]),
+                Some(0x03)
+            );
+
+            assert!(!ChannelPsk::none().is_encrypted());
+            assert!(!ChannelPsk::shorthand(0).is_encrypted());
+            assert_eq!(ChannelPsk::shorthand(0).expanded_key(), None);
+
+            assert_eq!(ChannelPsk::random(PskBits::Aes128).as_bytes().len(), 16);
+            assert_eq!(Vec::from(ChannelPsk::random(PskBits::Aes256)).len(), 32);
+
+            assert!(matches!(
+                ChannelPsk::from_bytes(&[0; 8]),
+                Err(Error::InvalidPskLength { length: 8 })
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_user_ids() {
+        let node_id = NodeId::from_user_id("!a1b2c3d4").unwrap();
+        assert_eq!(node_id, 0xa1b2_c3d4);
+        assert_eq!(NodeId::new(0xabcd).to_user_id(), "!0000abcd");
+        assert_eq!(NodeId::from_user_id("!A1B2C3D4").unwrap(), node_id);
+
+        for invalid in [
+            "a1b2c3d4",
+            "!abcd",
+            "!a1b2c3d4e5",
+            "!a1b2c3dx",
+            "!+1b2c3d4",
+            "",
+        ] {
+            assert!(matches!(
+                NodeId::from_user_id(invalid),
+                Err(Error::InvalidUserId { .. })
+            ));
+        }
+    }
+}
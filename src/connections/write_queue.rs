@@ -0,0 +1,265 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{Mutex, MutexGuard},
+};
+
+use tokio::sync::Notify;
+
+use crate::protobufs;
+use crate::types::EncodedToRadioPacketWithHeader;
+
+/// A packet waiting to be written to the radio, ordered by epoch, then by priority, and then by
+/// insertion order.
+#[derive(Debug)]
+struct QueuedWrite {
+    epoch: u64,
+    priority: i32,
+    sequence: u64,
+    data: EncodedToRadioPacketWithHeader,
+}
+
+impl PartialEq for QueuedWrite {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedWrite {}
+
+impl PartialOrd for QueuedWrite {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedWrite {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Packets from older epochs are popped first, higher priorities are popped first within an
+        // epoch, and older packets are popped first within a priority
+        other
+            .epoch
+            .cmp(&self.epoch)
+            .then_with(|| self.priority.cmp(&other.priority))
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Debug, Default)]
+struct WriteQueueInner {
+    heap: BinaryHeap<QueuedWrite>,
+    epoch: u64,
+    next_sequence: u64,
+}
+
+impl WriteQueueInner {
+    fn push(&mut self, data: EncodedToRadioPacketWithHeader, priority: i32) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.heap.push(QueuedWrite {
+            epoch: self.epoch,
+            priority,
+            sequence,
+            data,
+        });
+    }
+}
+
+/// A client-side priority queue of encoded packets that are waiting to be written to the radio.
+///
+/// Packets are written in order of their `MeshPacket.priority`, mirroring the priority queue used
+/// within the firmware. Packets with equal priority are written in the order they were queued.
+/// This ensures that e.g. routing acknowledgements are written ahead of background position updates
+/// when many packets are sent in quick succession.
+///
+/// Packets queued with `push_in_order` act as barriers: they are written after every packet that was
+/// queued before them, and before every packet that is queued after them, regardless of priority.
+/// This keeps control packets such as `BeginEditSettings` and `CommitEditSettings` in order with the
+/// mesh packets they enclose.
+#[derive(Debug, Default)]
+pub struct WriteQueue {
+    inner: Mutex<WriteQueueInner>,
+    notify: Notify,
+}
+
+impl WriteQueue {
+    /// Creates a new, empty `WriteQueue`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an encoded packet to be written to the radio with the specified priority.
+    pub fn push(
+        &self,
+        data: EncodedToRadioPacketWithHeader,
+        priority: protobufs::mesh_packet::Priority,
+    ) {
+        self.lock().push(data, priority as i32);
+        self.notify.notify_one();
+    }
+
+    /// Queues an encoded packet to be written to the radio in the order it was queued, i.e. after all
+    /// packets that are already queued and before all packets that are queued later.
+    pub fn push_in_order(&self, data: EncodedToRadioPacketWithHeader) {
+        {
+            let mut inner = self.lock();
+
+            // The packet gets an epoch of its own, which separates earlier packets from later ones
+            inner.epoch += 1;
+            inner.push(data, protobufs::mesh_packet::Priority::Default as i32);
+            inner.epoch += 1;
+        }
+
+        self.notify.notify_one();
+    }
+
+    /// Removes and returns the highest priority packet from the queue, if any.
+    pub fn pop(&self) -> Option<EncodedToRadioPacketWithHeader> {
+        self.lock().heap.pop().map(|queued| queued.data)
+    }
+
+    /// Returns the number of packets waiting to be written to the radio.
+    pub fn len(&self) -> usize {
+        self.lock().heap.len()
+    }
+
+    /// Waits until a packet has been pushed onto the queue.
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WriteQueueInner> {
+        // The queue is never left in an inconsistent state, so a poisoned lock can be recovered
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Returns the priority a `MeshPacket` is queued with on the client side.
+///
+/// If the packet has an explicit priority set, that priority is used. Otherwise the priority is
+/// derived in the same way as the firmware: routing packets are sent with `Ack` priority, packets
+/// requesting an acknowledgement are sent with `Reliable` priority, position packets are sent with
+/// `Background` priority, and all other packets are sent with `Default` priority.
+pub fn effective_priority(packet: &protobufs::MeshPacket) -> protobufs::mesh_packet::Priority {
+    use protobufs::mesh_packet::Priority;
+
+    match Priority::try_from(packet.priority) {
+        Ok(Priority::Unset) | Err(_) => (),
+        Ok(priority) => return priority,
+    };
+
+    let port_num = match packet.payload_variant.as_ref() {
+        Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) => {
+            protobufs::PortNum::try_from(data.portnum).ok()
+        }
+        _ => None,
+    };
+
+    if port_num == Some(protobufs::PortNum::RoutingApp) {
+        Priority::Ack
+    } else if packet.want_ack {
+        Priority::Reliable
+    } else if port_num == Some(protobufs::PortNum::PositionApp) {
+        Priority::Background
+    } else {
+        Priority::Default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protobufs::mesh_packet::Priority;
+
+    fn data(byte: u8) -> EncodedToRadioPacketWithHeader {
+        vec![byte].into()
+    }
+
+    #[test]
+    fn pops_highest_priority_first() {
+        let queue = WriteQueue::new();
+
+        queue.push(data(1), Priority::Background);
+        queue.push(data(2), Priority::Ack);
+        queue.push(data(3), Priority::Default);
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.pop(), Some(data(2)));
+        assert_eq!(queue.pop(), Some(data(3)));
+        assert_eq!(queue.pop(), Some(data(1)));
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn preserves_order_within_priority() {
+        let queue = WriteQueue::new();
+
+        for byte in 0..5 {
+            queue.push(data(byte), Priority::Default);
+        }
+
+        for byte in 0..5 {
+            assert_eq!(queue.pop(), Some(data(byte)));
+        }
+    }
+
+    #[test]
+    fn never_reorders_packets_around_in_order_packets() {
+        let queue = WriteQueue::new();
+
+        queue.push(data(1), Priority::Background);
+        queue.push_in_order(data(2));
+        queue.push(data(3), Priority::Background);
+        queue.push(data(4), Priority::Ack);
+        queue.push_in_order(data(5));
+        queue.push(data(6), Priority::Ack);
+
+        for byte in [1, 2, 4, 3, 5, 6] {
+            assert_eq!(queue.pop(), Some(data(byte)));
+        }
+    }
+
+    #[test]
+    fn derives_priority_of_unset_packets() {
+        let routing_packet = protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: protobufs::PortNum::RoutingApp as i32,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+
+        let reliable_packet = protobufs::MeshPacket {
+            want_ack: true,
+            ..Default::default()
+        };
+
+        let position_packet = protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: protobufs::PortNum::PositionApp as i32,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+
+        let explicit_packet = protobufs::MeshPacket {
+            priority: Priority::Background as i32,
+            want_ack: true,
+            ..Default::default()
+        };
+
+        assert_eq!(effective_priority(&routing_packet), Priority::Ack);
+        assert_eq!(effective_priority(&reliable_packet), Priority::Reliable);
+        assert_eq!(effective_priority(&position_packet), Priority::Background);
+        assert_eq!(effective_priority(&explicit_packet), Priority::Background);
+        assert_eq!(
+            effective_priority(&protobufs::MeshPacket::default()),
+            Priority::Default
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use aes::{Aes128, Aes256};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use prost::Message;
+
+use crate::connections::wrappers::channel_psk::ChannelPsk;
+use crate::errors_internal::Error;
+use crate::protobufs;
+
+// The firmware increments the last 4 bytes of the nonce as a big-endian block counter
+type Aes128Ctr = ctr::Ctr32BE<Aes128>;
+type Aes256Ctr = ctr::Ctr32BE<Aes256>;
+
+/// Builds the 16-byte AES-CTR nonce the firmware uses for a packet, which consists of the packet id
+/// as a little-endian 64-bit integer, followed by the node id of the sender as a little-endian 32-bit
+/// integer, followed by 4 zero bytes that hold the block counter.
+fn packet_nonce(from: u32, packet_id: u32) -> [u8; 16] {
+    let mut nonce = [0; 16];
+    nonce[..8].copy_from_slice(&u64::from(packet_id).to_le_bytes());
+    nonce[8..12].copy_from_slice(&from.to_le_bytes());
+    nonce
+}
+
+/// Encrypts or decrypts the passed buffer in place with the AES-CTR scheme of the firmware. As CTR
+/// mode XORs the buffer with a keystream, encryption and decryption are the same operation.
+///
+/// Returns whether the buffer was transformed, which is not the case if the PSK disables encryption.
+pub(crate) fn apply_keystream(
+    psk: &[u8],
+    from: u32,
+    packet_id: u32,
+    buffer: &mut [u8],
+) -> Result<bool, Error> {
+    let Some(key) = ChannelPsk::from_bytes(psk)?.expanded_key() else {
+        return Ok(false);
+    };
+
+    let nonce = packet_nonce(from, packet_id);
+
+    match key.len() {
+        16 => Aes128Ctr::new(key.as_slice().into(), &nonce.into()).apply_keystream(buffer),
+        32 => Aes256Ctr::new(key.as_slice().into(), &nonce.into()).apply_keystream(buffer),
+        length => return Err(Error::InvalidPskLength { length }),
+    }
+
+    Ok(true)
+}
+
+/// Decrypts the payload of a `MeshPacket` that the radio passed on encrypted, e.g. because it was
+/// received on a channel that is not within the channel table of the radio, or was received via MQTT.
+///
+/// Packets are encrypted with AES-CTR, using the expanded PSK of the channel as the key, and a nonce
+/// derived from the `from` and `id` fields of the packet. Packets with a `Decoded` payload are returned
+/// as-is, and PSKs that disable encryption (an empty PSK or the shorthand `0`) leave the payload unchanged.
+///
+/// # Arguments
+///
+/// * `packet` - The `MeshPacket` to decrypt.
+/// * `psk` - The PSK of the channel the packet was sent on, as stored within the `psk` field of
+///     `ChannelSettings`. Shorthand PSKs are expanded into the keys they select.
+///
+/// # Returns
+///
+/// The decrypted `Data` message of the packet.
+///
+/// # Examples
+///
+/// ```
+/// let channel_settings = protobufs::ChannelSettings::default_primary();
+///
+/// if let Ok(data) = decrypt_mesh_packet(&mesh_packet, &channel_settings.psk) {
+///     println!("Received a packet on port {:?}", data.portnum());
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::InvalidPskLength` if the PSK is not 0, 1, 16, or 32 bytes long, and with
+/// `Error::DecryptionFailed` if the packet has no payload, or if the decrypted payload is not a valid
+/// `Data` message, which usually indicates that the packet was encrypted with a different key.
+///
+/// # Panics
+///
+/// None
+///
+pub fn decrypt_mesh_packet(
+    packet: &protobufs::MeshPacket,
+    psk: &[u8],
+) -> Result<protobufs::Data, Error> {
+    let mut payload = match packet.payload_variant.as_ref() {
+        Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) => return Ok(data.clone()),
+        Some(protobufs::mesh_packet::PayloadVariant::Encrypted(payload)) => payload.clone(),
+        None => {
+            return Err(Error::DecryptionFailed {
+                packet_id: packet.id,
+                description: "The packet has no payload".to_string(),
+            })
+        }
+    };
+
+    apply_keystream(psk, packet.from, packet.id, &mut payload)?;
+
+    protobufs::Data::decode(payload.as_slice()).map_err(|e| Error::DecryptionFailed {
+        packet_id: packet.id,
+        description: format!("The decrypted payload is not a valid Data message ({e})"),
+    })
+}
+
+/// Encrypts a `Data` message into the payload the firmware expects within the `encrypted` field of a
+/// `MeshPacket`, which allows packets to be forwarded between radios without ever holding them as plaintext
+/// within the `decoded` field.
+///
+/// The message is encoded and encrypted with AES-CTR, using the expanded PSK of the channel as the key, and
+/// the same nonce as the firmware, which is derived from the sender and id of the packet. Both must therefore
+/// match the `from` and `id` fields of the packet the payload is sent in. PSKs that disable encryption (an
+/// empty PSK or the shorthand `0`) return the encoded message unchanged.
+///
+/// # Arguments
+///
+/// * `data` - The `Data` message to encrypt.
+/// * `psk` - The PSK of the channel the packet is sent on, as stored within the `psk` field of `ChannelSettings`.
+/// * `from` - The node id of the sender of the packet.
+/// * `packet_id` - The id of the packet.
+///
+/// # Returns
+///
+/// The encrypted payload of the packet.
+///
+/// # Examples
+///
+/// ```
+/// let payload = encrypt_data(&data, &channel_settings.psk, mesh_packet.from, mesh_packet.id)?;
+/// mesh_packet.payload_variant = Some(protobufs::mesh_packet::PayloadVariant::Encrypted(payload));
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::InvalidPskLength` if the PSK is not 0, 1, 16, or 32 bytes long.
+///
+/// # Panics
+///
+/// None
+///
+pub fn encrypt_data(
+    data: &protobufs::Data,
+    psk: &[u8],
+    from: u32,
+    packet_id: u32,
+) -> Result<Vec<u8>, Error> {
+    let mut payload = data.encode_to_vec();
+    apply_keystream(psk, from, packet_id, &mut payload)?;
+
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypted_packet(data: &protobufs::Data, psk: &[u8]) -> protobufs::MeshPacket {
+        let payload = encrypt_data(data, psk, 0xa1b2c3d4, 0x12345678).unwrap();
+
+        protobufs::MeshPacket {
+            from: 0xa1b2c3d4,
+            to: u32::MAX,
+            id: 0x12345678,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(payload)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_firmware_nonces() {
+        assert_eq!(
+            packet_nonce(0xa1b2c3d4, 0x12345678),
+            [0x78, 0x56, 0x34, 0x12, 0, 0, 0, 0, 0xd4, 0xc3, 0xb2, 0xa1, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn decrypts_packets_with_the_default_key() {
+        let data = protobufs::Data {
+            portnum: protobufs::PortNum::TextMessageApp as i32,
+            payload: "Hello mesh, this is a message spanning several AES blocks".into(),
+            ..Default::default()
+        };
+
+        let default_psk = ChannelPsk::default().into_bytes();
+        let packet = encrypted_packet(&data, &default_psk);
+
+        let Some(protobufs::mesh_packet::PayloadVariant::Encrypted(payload)) =
+            &packet.payload_variant
+        else {
+            unreachable!()
+        };
+        assert_ne!(payload, &data.encode_to_vec());
+
+        // The shorthand and the expanded default key are equivalent
+        assert_eq!(decrypt_mesh_packet(&packet, &default_psk).unwrap(), data);
+        assert_eq!(
+            decrypt_mesh_packet(&packet, &ChannelPsk::default().expanded_key().unwrap()).unwrap(),
+            data
+        );
+
+        let aes256_psk = [7; 32];
+        let packet = encrypted_packet(&data, &aes256_psk);
+        assert_eq!(decrypt_mesh_packet(&packet, &aes256_psk).unwrap(), data);
+    }
+
+    #[test]
+    fn decrypts_known_answer_vector() {
+        // The ciphertext was computed with `openssl enc -aes-128-ctr` from the expanded default key and
+        // the nonce built from the packet id and sender below, independently of this crate. It is not a
+        // packet captured from a radio, so it pins the key, nonce layout and counter mode against another
+        // AES implementation, but not against the firmware itself.
+        let packet = protobufs::MeshPacket {
+            from: 0xa1b2c3d4,
+            to: u32::MAX,
+            id: 0x12345678,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![
+                0xff, 0xf0, 0x53, 0xb1, 0xab, 0x21, 0xa9, 0xeb, 0x40, 0x3d, 0x92, 0x1b, 0xa9, 0x60,
+                0x2d, 0x28, 0x53, 0xc4, 0x5a, 0x45, 0xb7, 0x04, 0xe7, 0x2c, 0xa2, 0x4d, 0x6c, 0x9c,
+                0xe4, 0x1d, 0x0d, 0xf4, 0x23, 0xfb, 0x4f, 0xf2, 0x96, 0x9f, 0x4a, 0xd0, 0xde, 0x34,
+                0xaa, 0xd2, 0xf6, 0x54, 0xa3, 0x71, 0x1b,
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decrypt_mesh_packet(&packet, &ChannelPsk::default().into_bytes()).unwrap(),
+            protobufs::Data {
+                portnum: protobufs::PortNum::TextMessageApp as i32,
+                payload: "Known-answer vector spanning three AES blocks".into(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        let packet = encrypted_packet(&protobufs::Data::default(), &[1]);
+
+        assert!(matches!(
+            decrypt_mesh_packet(&packet, &[1, 2, 3]),
+            Err(Error::InvalidPskLength { length: 3 })
+        ));
+    }
+
+    #[test]
+    fn passes_through_unencrypted_payloads() {
+        let data = protobufs::Data {
+            portnum: protobufs::PortNum::TextMessageApp as i32,
+            payload: "Hello mesh".into(),
+            ..Default::default()
+        };
+
+        for psk in [&[][..], &[0][..]] {
+            assert_eq!(
+                encrypt_data(&data, psk, 0xa1b2c3d4, 0x12345678).unwrap(),
+                data.encode_to_vec()
+            );
+            assert_eq!(
+                decrypt_mesh_packet(&encrypted_packet(&data, psk), psk).unwrap(),
+                data
+            );
+        }
+    }
+}
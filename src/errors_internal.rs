@@ -11,10 +11,32 @@ pub enum Error {
     #[error("Invalid channel {channel} entered. Valid channels are in the range [0..7]")]
     InvalidChannelIndex { channel: u32 },
 
+    /// An error indicating that no enabled channel with the specified name has been reported by the radio.
+    #[error("No enabled channel named \"{name}\" is configured on the radio")]
+    ChannelNotFound { name: String },
+
+    /// An error indicating that a packet was sent on a channel that the radio has reported as disabled,
+    /// or without settings. The radio would reject such a packet with a `NoChannel` routing error.
+    #[error("Channel {channel} is disabled on the radio")]
+    ChannelDisabled { channel: u32 },
+
+    /// An error indicating that a channel PSK has an invalid length. Valid PSKs are 0, 1, 16, or 32 bytes long.
+    #[error("Invalid PSK length of {length} bytes. Valid PSKs are 0, 1, 16, or 32 bytes long")]
+    InvalidPskLength { length: usize },
+
+    /// An error indicating that the payload of an encrypted mesh packet could not be decrypted, e.g. because
+    /// the packet was encrypted with a different key.
+    #[error("Failed to decrypt packet {packet_id}: {description}")]
+    DecryptionFailed { packet_id: u32, description: String },
+
     /// An error indicating that the library failed to encode a protocol buffer message.
     #[error(transparent)]
     EncodeError(#[from] prost::EncodeError),
 
+    /// An error indicating that the library failed to decode a protocol buffer message.
+    #[error(transparent)]
+    DecodeError(#[from] prost::DecodeError),
+
     /// An error indicating that the library failed to join a spawned worker task.
     #[error(transparent)]
     JoinError(#[from] tokio::task::JoinError),
@@ -32,6 +54,13 @@ pub enum Error {
         description: String,
     },
 
+    /// An error indicating that a connection to the radio could not be opened within the specified timeout.
+    #[error("Timed out after {timeout:?} connecting to {address}. Check that the radio is on, network is enabled, and the address is correct.")]
+    ConnectTimeout {
+        address: String,
+        timeout: std::time::Duration,
+    },
+
     /// An error indicating that too much data is being sent.
     #[error("Trying to send too much data")]
     InvalidaDataSize { data_length: usize },
@@ -43,6 +72,123 @@ pub enum Error {
         packet: EncodedToRadioPacketWithHeader,
     },
 
+    /// An error indicating that a reliably sent packet was not acknowledged after all retransmissions.
+    #[error("Packet {packet_id} was not acknowledged after {attempts} transmission attempts")]
+    MaxRetransmit { packet_id: u32, attempts: u32 },
+
+    /// An error indicating that the mesh reported a routing error in response to a request, e.g.
+    /// `NoResponse` if the destination node does not respond to requests on the requested port.
+    #[error("Request {packet_id} failed: {reason}")]
+    RequestFailed {
+        packet_id: u32,
+        reason: crate::protobufs::routing::Error,
+    },
+
+    /// An error indicating that no response to a request was received within the specified timeout.
+    #[error("No response to request {packet_id} was received within {timeout:?}")]
+    ResponseTimeout {
+        packet_id: u32,
+        timeout: std::time::Duration,
+    },
+
+    /// An error indicating that a port number outside of the private application range [256..511] was
+    /// used to send a private application payload.
+    #[error(
+        "Invalid private port number {port_num}. Private port numbers are in the range [256..511]"
+    )]
+    InvalidPrivatePortNum { port_num: u32 },
+
+    /// An error indicating that a packet payload was received on a different port than expected.
+    #[error("Expected a packet on port {expected:?}, but received a packet on port {received}")]
+    UnexpectedPortNum {
+        expected: crate::protobufs::PortNum,
+        received: i32,
+    },
+
+    /// An error indicating that a request was cancelled before a response was received.
+    #[error("Request {packet_id} was cancelled before a response was received")]
+    RequestCancelled { packet_id: u32 },
+
+    /// An error indicating that a packet destination could not be parsed.
+    #[error("Invalid destination {destination}. Valid destinations are ^local, ^all, or a node id such as !a1b2c3d4")]
+    InvalidDestination { destination: String },
+
+    /// An error indicating that the `id` field of a `User` is not a node number formatted as `!` followed
+    /// by eight hexadecimal digits.
+    #[error("Invalid user id {user_id}. User ids are formatted as ! followed by eight hexadecimal digits, such as !a1b2c3d4")]
+    InvalidUserId { user_id: String },
+
+    /// An error indicating that a `configure` handshake was cancelled before the radio completed it.
+    #[error("Configuration handshake {config_id} was cancelled before it completed")]
+    ConfigCancelled { config_id: u32 },
+
+    /// An error indicating that the radio did not complete a `configure` handshake within the specified timeout,
+    /// although it did send valid packets.
+    #[error("Configuration handshake {config_id} did not complete within {timeout:?}")]
+    ConfigTimeout {
+        config_id: u32,
+        timeout: std::time::Duration,
+    },
+
+    /// An error indicating that no valid Meshtastic packets were received from the device during the `configure`
+    /// handshake, e.g. because the wrong serial port was selected.
+    #[error("The device does not appear to be a Meshtastic radio: {description}. Check that the correct port was selected.")]
+    NotAMeshtasticDevice { description: String },
+
+    /// An error indicating that there is currently no connection to the radio, e.g. because a lost
+    /// connection is being re-established.
+    #[error("Not connected to the radio")]
+    NotConnected,
+
+    /// An error indicating that the radio reported that its outgoing packet queue is full.
+    #[error("The radio's outgoing packet queue is full ({maxlen} entries)")]
+    QueueFull { maxlen: u32 },
+
+    /// An error indicating that the undecoded data received from the radio exceeded the maximum buffer
+    /// size, and was discarded.
+    #[error("Discarded {buffer_size} bytes of undecoded data exceeding the maximum buffer size of {max_buffer_size} bytes")]
+    BufferOverflow {
+        buffer_size: usize,
+        max_buffer_size: usize,
+    },
+
+    /// An error indicating that a set of channels cannot be shared, e.g. because it does not contain
+    /// exactly one primary channel.
+    #[error("Invalid channel set: {description}")]
+    InvalidChannelSet { description: String },
+
+    /// An error indicating that a channel URL could not be parsed, e.g. because it is not a `meshtastic.org` URL.
+    #[error("Invalid channel URL {url}: {description}")]
+    InvalidChannelUrl { url: String, description: String },
+
+    /// An error indicating that a raw LoRa packet could not be parsed.
+    #[error("Invalid LoRa packet: {description}")]
+    InvalidLoraPacket { description: String },
+
+    /// An error indicating that a packet could not be injected into a simulated node.
+    #[error("Invalid simulator packet: {description}")]
+    InvalidSimulatorPacket { description: String },
+
+    /// An error indicating that an I/O operation on a transport framed with the `MeshtasticCodec` failed.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    /// An error indicating that an NMEA sentence reported by a GPS receiver could not be parsed.
+    #[error("Invalid NMEA sentence: {description}")]
+    InvalidNmeaSentence { description: String },
+
+    /// An error indicating that the compressed payload of a `TextMessageCompressedApp` packet could not be decompressed.
+    #[error("Invalid compressed text: {description}")]
+    InvalidCompressedText { description: String },
+
+    /// An error indicating that a payload could not be compressed, e.g. because it is not valid UTF-8.
+    #[error("Invalid compression input: {description}")]
+    InvalidCompressionInput { description: String },
+
+    /// An error indicating that pre-framed packet data does not start with a valid packet header.
+    #[error("Invalid framed packet: {description}")]
+    InvalidFramedPacket { description: String },
+
     /// An error indicating that the library failed when performing an operation on an internal data stream.
     #[error(transparent)]
     InternalStreamError(#[from] InternalStreamError),
@@ -0,0 +1,281 @@
+use prost::Message;
+
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::protobufs::admin_message::{ConfigType, ModuleConfigType, PayloadVariant};
+
+/// Decodes the `AdminMessage` contained within the payload of an `AdminApp` packet.
+///
+/// # Arguments
+///
+/// * `data` - The decoded payload of a mesh packet received from the radio or a remote node.
+///
+/// # Returns
+///
+/// The decoded `AdminMessage`, which can be classified using `AdminResponse::from_admin_message`.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(protobufs::mesh_packet::PayloadVariant::Decoded(data)) = mesh_packet.payload_variant {
+///     let admin_message = decode_admin_response(&data)?;
+///     println!("Received admin message: {:?}", admin_message);
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::UnexpectedPortNum` if the payload was not sent on the `AdminApp` port, and
+/// with `Error::DecodeError` if the payload is not a valid `AdminMessage`.
+///
+/// # Panics
+///
+/// None
+///
+pub fn decode_admin_response(data: &protobufs::Data) -> Result<protobufs::AdminMessage, Error> {
+    if data.portnum != protobufs::PortNum::AdminApp as i32 {
+        return Err(Error::UnexpectedPortNum {
+            expected: protobufs::PortNum::AdminApp,
+            received: data.portnum,
+        });
+    }
+
+    Ok(protobufs::AdminMessage::decode(data.payload.as_slice())?)
+}
+
+/// An enum that represents the responses a node can send to an admin request. Admin messages that
+/// are requests or commands, rather than responses, are not represented by this enum.
+///
+/// # Variants
+///
+/// * `Channel` - A `GetChannelResponse`, containing a single channel of the node's channel table.
+/// * `Owner` - A `GetOwnerResponse`, containing the user information of the node.
+/// * `Config` - A `GetConfigResponse`, containing a single section of the node's device configuration.
+/// * `ModuleConfig` - A `GetModuleConfigResponse`, containing a single section of the node's module configuration.
+/// * `CannedMessageModuleMessages` - A `GetCannedMessageModuleMessagesResponse`, containing the node's canned messages.
+/// * `DeviceMetadata` - A `GetDeviceMetadataResponse`, containing the node's firmware and hardware metadata.
+/// * `Ringtone` - A `GetRingtoneResponse`, containing the node's ringtone in RTTTL format.
+/// * `DeviceConnectionStatus` - A `GetDeviceConnectionStatusResponse`, containing the status of the node's network interfaces.
+/// * `NodeRemoteHardwarePins` - A `GetNodeRemoteHardwarePinsResponse`, containing the remote hardware pins known to the node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdminResponse {
+    Channel(protobufs::Channel),
+    Owner(protobufs::User),
+    Config(protobufs::Config),
+    ModuleConfig(protobufs::ModuleConfig),
+    CannedMessageModuleMessages(String),
+    DeviceMetadata(protobufs::DeviceMetadata),
+    Ringtone(String),
+    DeviceConnectionStatus(protobufs::DeviceConnectionStatus),
+    NodeRemoteHardwarePins(protobufs::NodeRemoteHardwarePinsResponse),
+}
+
+impl AdminResponse {
+    /// Classifies an `AdminMessage` as one of the responses a node can send to an admin request.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_message` - The admin message to classify, e.g. as returned by `decode_admin_response`.
+    ///
+    /// # Returns
+    ///
+    /// The matching `AdminResponse`, or `None` if the admin message is not a response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match AdminResponse::from_admin_message(decode_admin_response(&data)?) {
+    ///     Some(AdminResponse::Config(config)) => println!("Received config: {:?}", config),
+    ///     Some(response) => println!("Received other response: {:?}", response),
+    ///     None => println!("Received an admin message that is not a response"),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_admin_message(admin_message: protobufs::AdminMessage) -> Option<Self> {
+        let response = match admin_message.payload_variant? {
+            PayloadVariant::GetChannelResponse(channel) => AdminResponse::Channel(channel),
+            PayloadVariant::GetOwnerResponse(user) => AdminResponse::Owner(user),
+            PayloadVariant::GetConfigResponse(config) => AdminResponse::Config(config),
+            PayloadVariant::GetModuleConfigResponse(module_config) => {
+                AdminResponse::ModuleConfig(module_config)
+            }
+            PayloadVariant::GetCannedMessageModuleMessagesResponse(messages) => {
+                AdminResponse::CannedMessageModuleMessages(messages)
+            }
+            PayloadVariant::GetDeviceMetadataResponse(metadata) => {
+                AdminResponse::DeviceMetadata(metadata)
+            }
+            PayloadVariant::GetRingtoneResponse(ringtone) => AdminResponse::Ringtone(ringtone),
+            PayloadVariant::GetDeviceConnectionStatusResponse(status) => {
+                AdminResponse::DeviceConnectionStatus(status)
+            }
+            PayloadVariant::GetNodeRemoteHardwarePinsResponse(pins) => {
+                AdminResponse::NodeRemoteHardwarePins(pins)
+            }
+            _ => return None,
+        };
+
+        Some(response)
+    }
+
+    /// Returns whether this response answers the specified admin request. This can be used to
+    /// correlate responses with requests when several requests of different kinds are outstanding.
+    ///
+    /// **Note:** Channel indices within a `GetChannelRequest` are one-based, while the indices of the
+    /// channels within a `GetChannelResponse` are zero-based.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The admin request that was sent to the node.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the response is of the kind requested, and contains the requested channel or
+    /// configuration section where applicable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if response.answers(&request) {
+    ///     println!("Received response to request: {:?}", response);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn answers(&self, request: &protobufs::AdminMessage) -> bool {
+        let Some(request) = request.payload_variant.as_ref() else {
+            return false;
+        };
+
+        match (self, request) {
+            (AdminResponse::Channel(channel), PayloadVariant::GetChannelRequest(index)) => {
+                i64::from(channel.index) + 1 == i64::from(*index)
+            }
+            (AdminResponse::Owner(_), PayloadVariant::GetOwnerRequest(_)) => true,
+            (AdminResponse::Config(config), PayloadVariant::GetConfigRequest(config_type)) => {
+                config.config_type() == ConfigType::try_from(*config_type).ok()
+            }
+            (
+                AdminResponse::ModuleConfig(module_config),
+                PayloadVariant::GetModuleConfigRequest(module_config_type),
+            ) => {
+                module_config.module_config_type()
+                    == ModuleConfigType::try_from(*module_config_type).ok()
+            }
+            (
+                AdminResponse::CannedMessageModuleMessages(_),
+                PayloadVariant::GetCannedMessageModuleMessagesRequest(_),
+            ) => true,
+            (AdminResponse::DeviceMetadata(_), PayloadVariant::GetDeviceMetadataRequest(_)) => true,
+            (AdminResponse::Ringtone(_), PayloadVariant::GetRingtoneRequest(_)) => true,
+            (
+                AdminResponse::DeviceConnectionStatus(_),
+                PayloadVariant::GetDeviceConnectionStatusRequest(_),
+            ) => true,
+            (
+                AdminResponse::NodeRemoteHardwarePins(_),
+                PayloadVariant::GetNodeRemoteHardwarePinsRequest(_),
+            ) => true,
+            _ => false,
+        }
+    }
+}
+
+impl protobufs::AdminMessage {
+    /// Returns whether this admin message is a get-style request, e.g. a `GetConfigRequest`, which the
+    /// receiving node answers with one of the responses represented by the `AdminResponse` enum. Such
+    /// requests must be sent with `want_response` set, so that the node routes its response back.
+    pub fn is_get_request(&self) -> bool {
+        matches!(
+            self.payload_variant,
+            Some(
+                PayloadVariant::GetChannelRequest(_)
+                    | PayloadVariant::GetOwnerRequest(_)
+                    | PayloadVariant::GetConfigRequest(_)
+                    | PayloadVariant::GetModuleConfigRequest(_)
+                    | PayloadVariant::GetCannedMessageModuleMessagesRequest(_)
+                    | PayloadVariant::GetDeviceMetadataRequest(_)
+                    | PayloadVariant::GetRingtoneRequest(_)
+                    | PayloadVariant::GetDeviceConnectionStatusRequest(_)
+                    | PayloadVariant::GetNodeRemoteHardwarePinsRequest(_)
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin_data(payload_variant: PayloadVariant) -> protobufs::Data {
+        protobufs::Data {
+            portnum: protobufs::PortNum::AdminApp as i32,
+            payload: protobufs::AdminMessage {
+                payload_variant: Some(payload_variant),
+            }
+            .encode_to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classifies_admin_responses() {
+        let lora_config = protobufs::Config {
+            payload_variant: Some(protobufs::config::PayloadVariant::Lora(Default::default())),
+        };
+
+        let admin_message =
+            decode_admin_response(&admin_data(PayloadVariant::GetConfigResponse(lora_config)))
+                .unwrap();
+        let response = AdminResponse::from_admin_message(admin_message).unwrap();
+
+        let lora_request = protobufs::AdminMessage {
+            payload_variant: Some(PayloadVariant::GetConfigRequest(
+                ConfigType::LoraConfig as i32,
+            )),
+        };
+        let device_request = protobufs::AdminMessage {
+            payload_variant: Some(PayloadVariant::GetConfigRequest(
+                ConfigType::DeviceConfig as i32,
+            )),
+        };
+
+        assert!(response.answers(&lora_request));
+        assert!(!response.answers(&device_request));
+        assert!(lora_request.is_get_request());
+        assert!(!protobufs::AdminMessage {
+            payload_variant: Some(PayloadVariant::RebootSeconds(5)),
+        }
+        .is_get_request());
+
+        let request = decode_admin_response(&admin_data(PayloadVariant::GetOwnerRequest(true)));
+        assert_eq!(
+            request.ok().and_then(AdminResponse::from_admin_message),
+            None
+        );
+
+        let text_data = protobufs::Data {
+            portnum: protobufs::PortNum::TextMessageApp as i32,
+            ..Default::default()
+        };
+        assert!(matches!(
+            decode_admin_response(&text_data),
+            Err(Error::UnexpectedPortNum { .. })
+        ));
+    }
+}
@@ -0,0 +1,603 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use prost::Message;
+
+use crate::connections::wrappers::channel_psk::ChannelPsk;
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::protobufs::channel::Role;
+use crate::protobufs::config::lo_ra_config::{ModemPreset, RegionCode};
+
+/// The hop limit configured by the firmware for new devices.
+const DEFAULT_HOP_LIMIT: u32 = 3;
+
+/// The position precision the firmware configures for the default primary channel, which
+/// shares positions with an accuracy of about 2.9 km, as estimated by `Position::effective_precision_m`.
+const DEFAULT_POSITION_PRECISION: u32 = 13;
+
+/// The host of the URLs used to share channels.
+const CHANNEL_URL_HOST: &str = "meshtastic.org";
+
+/// The path of the URLs used to share channels, which is followed by the encoded `ChannelSet` as the fragment.
+const CHANNEL_URL_PATH: &str = "/e/";
+
+impl protobufs::ChannelSettings {
+    /// Returns the settings of the well-known default primary channel, which is shown as
+    /// `LongFast` by the apps. The channel has an empty name, so that its name is derived from the
+    /// modem preset, and uses the default PSK shorthand, so that it can be joined by any device.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The `ChannelSettings` of the default primary channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let settings = protobufs::ChannelSettings::default_primary();
+    /// assert!(settings.name.is_empty());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn default_primary() -> Self {
+        Self {
+            psk: ChannelPsk::shorthand(1).into_bytes(),
+            module_settings: Some(protobufs::ModuleSettings {
+                position_precision: DEFAULT_POSITION_PRECISION,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the pre-shared key of the channel as a `ChannelPsk`, which validates the key and
+    /// expands shorthand keys.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidPskLength` if the `psk` field is not 0, 1, 16, or 32 bytes long.
+    pub fn channel_psk(&self) -> Result<ChannelPsk, Error> {
+        ChannelPsk::from_bytes(&self.psk)
+    }
+
+    /// Replaces the pre-shared key of the channel with the canonical bytes of the passed `ChannelPsk`.
+    pub fn set_channel_psk(&mut self, psk: ChannelPsk) {
+        self.psk = psk.into_bytes();
+    }
+
+    /// Returns the hash of the channel, which the firmware places within the `channel` field of packets
+    /// with an encrypted payload instead of the channel index. The hash is the xor of all bytes of the
+    /// channel name, combined using xor with all bytes of the expanded PSK.
+    ///
+    /// **Note:** The firmware hashes the name of the modem preset (e.g. `LongFast`) for channels with an
+    /// empty name, which is not known to the channel settings. The `name` field of such channels needs to
+    /// be set to the name of the preset before hashing them.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The hash of the channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let settings = protobufs::ChannelSettings {
+    ///     name: "LongFast".to_string(),
+    ///     ..protobufs::ChannelSettings::default_primary()
+    /// };
+    ///
+    /// assert_eq!(settings.channel_hash()?, 8);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidPskLength` if the `psk` field is not 0, 1, 16, or 32 bytes long.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn channel_hash(&self) -> Result<u8, Error> {
+        let key = self.channel_psk()?.expanded_key().unwrap_or_default();
+
+        Ok(self
+            .name
+            .bytes()
+            .chain(key)
+            .fold(0, |hash, byte| hash ^ byte))
+    }
+}
+
+impl protobufs::ChannelSet {
+    /// Builds a `ChannelSet` for sharing from the channel table of a radio, e.g. as returned by the
+    /// `channels` method of the `ConnectedStreamApi` struct.
+    ///
+    /// Only the primary channel and the enabled secondary channels are included, as disabled channels
+    /// are not shared. The settings of the primary channel are placed first, followed by the settings of
+    /// the secondary channels in order of their channel index, as required by the sharing format.
+    ///
+    /// **Note:** The `lora_config` field of the returned `ChannelSet` is not set, as the LoRa
+    /// configuration is not part of the channel table.
+    ///
+    /// # Arguments
+    ///
+    /// * `channels` - The channels of the radio's channel table, in any order.
+    ///
+    /// # Returns
+    ///
+    /// A `ChannelSet` containing the settings of the primary and enabled secondary channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut channel_set = protobufs::ChannelSet::from_channels(&stream_api.channels())?;
+    /// channel_set.lora_config = Some(lora_config);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidChannelSet` if the channels do not contain exactly one primary channel.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_channels(channels: &[protobufs::Channel]) -> Result<Self, Error> {
+        let num_primary_channels = channels
+            .iter()
+            .filter(|channel| channel.role() == Role::Primary)
+            .count();
+
+        match num_primary_channels {
+            1 => (),
+            0 => {
+                return Err(Error::InvalidChannelSet {
+                    description: "No channel is marked as the primary channel".to_string(),
+                })
+            }
+            n => {
+                return Err(Error::InvalidChannelSet {
+                    description: format!(
+                        "{n} channels are marked as the primary channel, but only one is allowed"
+                    ),
+                })
+            }
+        }
+
+        let mut shared_channels: Vec<&protobufs::Channel> = channels
+            .iter()
+            .filter(|channel| channel.role() != Role::Disabled)
+            .collect();
+
+        shared_channels.sort_by_key(|channel| (channel.role() != Role::Primary, channel.index));
+
+        Ok(Self {
+            settings: shared_channels
+                .into_iter()
+                .map(|channel| channel.settings.clone().unwrap_or_default())
+                .collect(),
+            lora_config: None,
+        })
+    }
+
+    /// Returns a `ChannelSet` that joins the public default mesh within the specified region,
+    /// containing the default primary channel and the default LoRa configuration of the region.
+    /// This is the most common configuration of a fresh device.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The region the device is operated in.
+    ///
+    /// # Returns
+    ///
+    /// A `ChannelSet` containing the settings of `ChannelSettings::default_primary` and the
+    /// `LoRaConfig` of `LoRaConfig::default_for_region`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let channel_set = protobufs::ChannelSet::default_public(RegionCode::Us);
+    /// assert_eq!(channel_set.settings.len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn default_public(region: RegionCode) -> Self {
+        Self {
+            settings: vec![protobufs::ChannelSettings::default_primary()],
+            lora_config: Some(protobufs::config::LoRaConfig::default_for_region(region)),
+        }
+    }
+
+    /// Converts this `ChannelSet` into the channel table entries that apply it to a radio, as the
+    /// inverse of the `from_channels` method. The first settings become the primary channel, and the
+    /// remaining settings become secondary channels at the following channel indices.
+    ///
+    /// **Note:** The `lora_config` field is not part of the channel table, and needs to be applied
+    /// separately using the `update_config` method of the `ConnectedStreamApi` struct.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A vector containing a `Channel` for each channel within this `ChannelSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let channel_set = protobufs::ChannelSet::default_public(RegionCode::Eu868);
+    ///
+    /// stream_api.start_config_transaction().await?;
+    /// stream_api.set_message_channel_config(&mut router, channel_set.clone().into_channels()).await?;
+    ///
+    /// if let Some(lora_config) = channel_set.lora_config {
+    ///     let config = protobufs::Config { payload_variant: Some(protobufs::config::PayloadVariant::Lora(lora_config)) };
+    ///     stream_api.update_config(&mut router, config).await?;
+    /// }
+    ///
+    /// stream_api.commit_config_transaction().await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn into_channels(self) -> Vec<protobufs::Channel> {
+        self.settings
+            .into_iter()
+            .enumerate()
+            .map(|(index, settings)| {
+                let role = match index {
+                    0 => Role::Primary,
+                    _ => Role::Secondary,
+                };
+
+                protobufs::Channel {
+                    index: index as i32,
+                    settings: Some(settings),
+                    role: role as i32,
+                }
+            })
+            .collect()
+    }
+
+    /// Encodes this `ChannelSet` into a `https://meshtastic.org/e/#...` URL, as used by the Meshtastic
+    /// apps to share channels, e.g. within a QR code. The URL fragment contains the encoded `ChannelSet`
+    /// protobuf, encoded using the URL-safe base64 alphabet without padding.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The channel URL of this `ChannelSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let channel_set = protobufs::ChannelSet::from_channels(&stream_api.channels())?;
+    /// println!("Join my mesh: {}", channel_set.to_url());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn to_url(&self) -> String {
+        format!(
+            "https://{CHANNEL_URL_HOST}{CHANNEL_URL_PATH}#{}",
+            URL_SAFE_NO_PAD.encode(self.encode_to_vec())
+        )
+    }
+
+    /// Decodes a `ChannelSet` from a `https://meshtastic.org/e/#...` channel URL, as the inverse of
+    /// the `to_url` method.
+    ///
+    /// The scheme and a `www.` prefix of the host are optional, and a query (e.g. the `?add=true` query
+    /// used to add channels rather than replacing them) is ignored. Padded fragments and fragments
+    /// encoded with the standard base64 alphabet are accepted, as some apps produce them.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The channel URL to decode.
+    ///
+    /// # Returns
+    ///
+    /// The `ChannelSet` encoded within the URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let channel_set = protobufs::ChannelSet::from_url("https://meshtastic.org/e/#CgMSAQESBggBQANIAQ")?;
+    /// assert_eq!(channel_set.settings.len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidChannelUrl` if the URL is not a `meshtastic.org` channel URL, or if its
+    /// fragment does not contain a valid base64-encoded `ChannelSet`.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_url(url: &str) -> Result<Self, Error> {
+        let invalid_url = |description: &str| Error::InvalidChannelUrl {
+            url: url.to_string(),
+            description: description.to_string(),
+        };
+
+        let trimmed = url.trim();
+        let without_scheme = trimmed
+            .strip_prefix("https://")
+            .or_else(|| trimmed.strip_prefix("http://"))
+            .unwrap_or(trimmed);
+
+        let (location, fragment) = without_scheme
+            .split_once('#')
+            .ok_or_else(|| invalid_url("the URL has no fragment containing the channels"))?;
+
+        let location = location.split_once('?').map_or(location, |(path, _)| path);
+        let path_start = location.find('/').unwrap_or(location.len());
+        let (host, path) = location.split_at(path_start);
+
+        let host = host.to_ascii_lowercase();
+        if host.strip_prefix("www.").unwrap_or(&host) != CHANNEL_URL_HOST {
+            return Err(invalid_url(&format!(
+                "the host {host} is not {CHANNEL_URL_HOST}"
+            )));
+        }
+
+        if path != CHANNEL_URL_PATH && path != CHANNEL_URL_PATH.trim_end_matches('/') {
+            return Err(invalid_url(&format!(
+                "the path {path} is not {CHANNEL_URL_PATH}"
+            )));
+        }
+
+        let normalized_fragment: String = fragment
+            .trim_end_matches('=')
+            .chars()
+            .map(|c| match c {
+                '+' => '-',
+                '/' => '_',
+                c => c,
+            })
+            .collect();
+
+        if normalized_fragment.is_empty() {
+            return Err(invalid_url("the fragment is empty"));
+        }
+
+        let bytes = URL_SAFE_NO_PAD
+            .decode(normalized_fragment)
+            .map_err(|e| invalid_url(&format!("the fragment is not valid base64 ({e})")))?;
+
+        Self::decode(bytes.as_slice())
+            .map_err(|e| invalid_url(&format!("the fragment is not a valid channel set ({e})")))
+    }
+}
+
+impl protobufs::config::LoRaConfig {
+    /// Returns the LoRa configuration of a new device within the specified region, which uses the
+    /// default `LongFast` modem preset and hop limit, and transmits at the maximum legal power.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The region the device is operated in, which determines the frequency band.
+    ///
+    /// # Returns
+    ///
+    /// A `LoRaConfig` with the default settings for the region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let lora_config = protobufs::config::LoRaConfig::default_for_region(RegionCode::Eu868);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn default_for_region(region: RegionCode) -> Self {
+        Self {
+            use_preset: true,
+            modem_preset: ModemPreset::LongFast as i32,
+            region: region as i32,
+            hop_limit: DEFAULT_HOP_LIMIT,
+            tx_enabled: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(index: i32, name: &str, role: Role) -> protobufs::Channel {
+        protobufs::Channel {
+            index,
+            settings: Some(protobufs::ChannelSettings {
+                name: name.to_string(),
+                ..Default::default()
+            }),
+            role: role as i32,
+        }
+    }
+
+    #[test]
+    fn builds_channel_set_from_enabled_channels() {
+        let channels = [
+            channel(2, "second", Role::Secondary),
+            channel(1, "disabled", Role::Disabled),
+            channel(3, "third", Role::Secondary),
+            channel(0, "primary", Role::Primary),
+        ];
+
+        let channel_set = protobufs::ChannelSet::from_channels(&channels).unwrap();
+
+        let names: Vec<&str> = channel_set
+            .settings
+            .iter()
+            .map(|settings| settings.name.as_str())
+            .collect();
+
+        assert_eq!(names, ["primary", "second", "third"]);
+        assert_eq!(channel_set.lora_config, None);
+    }
+
+    #[test]
+    fn requires_exactly_one_primary_channel() {
+        let no_primary = [channel(1, "second", Role::Secondary)];
+        let two_primaries = [
+            channel(0, "primary", Role::Primary),
+            channel(1, "other", Role::Primary),
+        ];
+
+        for channels in [&no_primary[..], &two_primaries[..]] {
+            assert!(matches!(
+                protobufs::ChannelSet::from_channels(channels),
+                Err(Error::InvalidChannelSet { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn builds_default_public_channel_set() {
+        let channel_set = protobufs::ChannelSet::default_public(RegionCode::Eu868);
+
+        assert_eq!(channel_set.settings[0].name, "");
+        assert_eq!(
+            channel_set.settings[0].channel_psk().unwrap(),
+            ChannelPsk::shorthand(1)
+        );
+        assert_eq!(
+            channel_set.lora_config.as_ref().unwrap().region(),
+            RegionCode::Eu868
+        );
+
+        let channels = channel_set.into_channels();
+
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].index, 0);
+        assert_eq!(channels[0].role(), Role::Primary);
+        assert_eq!(
+            protobufs::ChannelSet::from_channels(&channels)
+                .unwrap()
+                .settings,
+            [protobufs::ChannelSettings::default_primary()]
+        );
+    }
+
+    #[test]
+    fn hashes_channels_like_the_firmware() {
+        let mut settings = protobufs::ChannelSettings::default_primary();
+        settings.name = "LongFast".to_string();
+
+        assert_eq!(settings.channel_hash().unwrap(), 8);
+
+        settings.set_channel_psk(ChannelPsk::none());
+        assert_eq!(settings.channel_hash().unwrap(), 0x0a);
+
+        settings.psk = vec![0; 8];
+        assert!(matches!(
+            settings.channel_hash(),
+            Err(Error::InvalidPskLength { length: 8 })
+        ));
+    }
+
+    #[test]
+    fn decodes_known_channel_url() {
+        let channel_set =
+            protobufs::ChannelSet::from_url("https://meshtastic.org/e/#CgMSAQESBggBQANIAQ")
+                .unwrap();
+
+        assert_eq!(channel_set.settings.len(), 1);
+        assert_eq!(channel_set.settings[0].psk, [1]);
+        assert_eq!(channel_set.settings[0].name, "");
+
+        let lora_config = channel_set.lora_config.unwrap();
+        assert!(lora_config.use_preset);
+        assert_eq!(lora_config.hop_limit, DEFAULT_HOP_LIMIT);
+        assert!(lora_config.tx_enabled);
+
+        // The query, padding, and standard base64 alphabet are accepted
+        assert_eq!(
+            protobufs::ChannelSet::from_url("meshtastic.org/e/?add=true#CgMSAQESBggBQANIAQ==")
+                .unwrap()
+                .settings[0]
+                .psk,
+            [1]
+        );
+    }
+
+    #[test]
+    fn round_trips_channel_urls() {
+        let mut channel_set = protobufs::ChannelSet::default_public(RegionCode::Eu868);
+        channel_set.settings.push(protobufs::ChannelSettings {
+            name: "admin".to_string(),
+            psk: vec![0xfb; 32],
+            ..Default::default()
+        });
+
+        let url = channel_set.to_url();
+
+        let fragment = url.strip_prefix("https://meshtastic.org/e/#").unwrap();
+        assert!(!fragment.contains(['=', '+', '/']));
+        assert_eq!(protobufs::ChannelSet::from_url(&url).unwrap(), channel_set);
+    }
+
+    #[test]
+    fn rejects_invalid_channel_urls() {
+        for url in [
+            "https://example.com/e/#CgMSAQESBggBQANIAQ",
+            "https://meshtastic.org/x/#CgMSAQESBggBQANIAQ",
+            "https://meshtastic.org/e/",
+            "https://meshtastic.org/e/#",
+            "https://meshtastic.org/e/#not*base64",
+            "https://meshtastic.org/e/#_____w",
+        ] {
+            assert!(
+                matches!(
+                    protobufs::ChannelSet::from_url(url),
+                    Err(Error::InvalidChannelUrl { .. })
+                ),
+                "{url} was accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,708 @@
+use std::collections::BTreeSet;
+
+use crate::protobufs;
+use crate::protobufs::admin_message::{ConfigType, ModuleConfigType};
+use crate::protobufs::config::PayloadVariant as ConfigVariant;
+use crate::protobufs::module_config::PayloadVariant as ModuleConfigVariant;
+
+impl protobufs::Config {
+    /// Returns the `ConfigType` that corresponds to the payload of this `Config` message.
+    /// This is the value that would be used within a `GetConfigRequest` to request this
+    /// configuration from the radio.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The matching `ConfigType`, or `None` if the message has no payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config = protobufs::Config { payload_variant: Some(...) };
+    /// let config_type = config.config_type();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn config_type(&self) -> Option<ConfigType> {
+        let config_type = match self.payload_variant.as_ref()? {
+            ConfigVariant::Device(_) => ConfigType::DeviceConfig,
+            ConfigVariant::Position(_) => ConfigType::PositionConfig,
+            ConfigVariant::Power(_) => ConfigType::PowerConfig,
+            ConfigVariant::Network(_) => ConfigType::NetworkConfig,
+            ConfigVariant::Display(_) => ConfigType::DisplayConfig,
+            ConfigVariant::Lora(_) => ConfigType::LoraConfig,
+            ConfigVariant::Bluetooth(_) => ConfigType::BluetoothConfig,
+        };
+
+        Some(config_type)
+    }
+}
+
+impl protobufs::ModuleConfig {
+    /// Returns the `ModuleConfigType` that corresponds to the payload of this `ModuleConfig`
+    /// message. This is the value that would be used within a `GetModuleConfigRequest` to
+    /// request this module configuration from the radio.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The matching `ModuleConfigType`, or `None` if the message has no payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let module_config = protobufs::ModuleConfig { payload_variant: Some(...) };
+    /// let module_config_type = module_config.module_config_type();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn module_config_type(&self) -> Option<ModuleConfigType> {
+        let module_config_type = match self.payload_variant.as_ref()? {
+            ModuleConfigVariant::Mqtt(_) => ModuleConfigType::MqttConfig,
+            ModuleConfigVariant::Serial(_) => ModuleConfigType::SerialConfig,
+            ModuleConfigVariant::ExternalNotification(_) => ModuleConfigType::ExtnotifConfig,
+            ModuleConfigVariant::StoreForward(_) => ModuleConfigType::StoreforwardConfig,
+            ModuleConfigVariant::RangeTest(_) => ModuleConfigType::RangetestConfig,
+            ModuleConfigVariant::Telemetry(_) => ModuleConfigType::TelemetryConfig,
+            ModuleConfigVariant::CannedMessage(_) => ModuleConfigType::CannedmsgConfig,
+            ModuleConfigVariant::Audio(_) => ModuleConfigType::AudioConfig,
+            ModuleConfigVariant::RemoteHardware(_) => ModuleConfigType::RemotehardwareConfig,
+            ModuleConfigVariant::NeighborInfo(_) => ModuleConfigType::NeighborinfoConfig,
+            ModuleConfigVariant::AmbientLighting(_) => ModuleConfigType::AmbientlightingConfig,
+            ModuleConfigVariant::DetectionSensor(_) => ModuleConfigType::DetectionsensorConfig,
+            ModuleConfigVariant::Paxcounter(_) => ModuleConfigType::PaxcounterConfig,
+        };
+
+        Some(module_config_type)
+    }
+}
+
+impl protobufs::LocalConfig {
+    /// Stores the payload of a single `Config` message within the matching field of this
+    /// `LocalConfig`, overwriting any previously stored value.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The `Config` message to store.
+    ///
+    /// # Returns
+    ///
+    /// The `ConfigType` of the stored message, or `None` if the message had no payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut local_config = protobufs::LocalConfig::default();
+    /// local_config.apply_config(config);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn apply_config(&mut self, config: protobufs::Config) -> Option<ConfigType> {
+        let config_type = config.config_type()?;
+
+        match config.payload_variant? {
+            ConfigVariant::Device(c) => self.device = Some(c),
+            ConfigVariant::Position(c) => self.position = Some(c),
+            ConfigVariant::Power(c) => self.power = Some(c),
+            ConfigVariant::Network(c) => self.network = Some(c),
+            ConfigVariant::Display(c) => self.display = Some(c),
+            ConfigVariant::Lora(c) => self.lora = Some(c),
+            ConfigVariant::Bluetooth(c) => self.bluetooth = Some(c),
+        }
+
+        Some(config_type)
+    }
+}
+
+impl protobufs::LocalModuleConfig {
+    /// Splits this aggregate `LocalModuleConfig` into the individual `ModuleConfig` messages
+    /// that are sent to the radio. Module configurations that are set to `None` are skipped.
+    ///
+    /// The messages are returned in the order of the `ModuleConfigType` enum.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A vector containing one `ModuleConfig` message for each populated module configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for module_config in local_module_config.into_module_configs() {
+    ///     stream_api.update_module_config(packet_router, module_config).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn into_module_configs(self) -> Vec<protobufs::ModuleConfig> {
+        let variants = [
+            self.mqtt.map(ModuleConfigVariant::Mqtt),
+            self.serial.map(ModuleConfigVariant::Serial),
+            self.external_notification
+                .map(ModuleConfigVariant::ExternalNotification),
+            self.store_forward.map(ModuleConfigVariant::StoreForward),
+            self.range_test.map(ModuleConfigVariant::RangeTest),
+            self.telemetry.map(ModuleConfigVariant::Telemetry),
+            self.canned_message.map(ModuleConfigVariant::CannedMessage),
+            self.audio.map(ModuleConfigVariant::Audio),
+            self.remote_hardware
+                .map(ModuleConfigVariant::RemoteHardware),
+            self.neighbor_info.map(ModuleConfigVariant::NeighborInfo),
+            self.ambient_lighting
+                .map(ModuleConfigVariant::AmbientLighting),
+            self.detection_sensor
+                .map(ModuleConfigVariant::DetectionSensor),
+            self.paxcounter.map(ModuleConfigVariant::Paxcounter),
+        ];
+
+        variants
+            .into_iter()
+            .flatten()
+            .map(|payload_variant| protobufs::ModuleConfig {
+                payload_variant: Some(payload_variant),
+            })
+            .collect()
+    }
+
+    /// Splits this aggregate `LocalModuleConfig` into the `SetModuleConfig` admin messages
+    /// that need to be sent to the radio to apply it. Module configurations that are set to
+    /// `None` are skipped.
+    ///
+    /// These messages are intended to be sent within a configuration transaction, see the
+    /// `start_config_transaction` and `commit_config_transaction` methods.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A vector containing one `SetModuleConfig` admin message for each populated module configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let admin_messages = local_module_config.into_admin_messages();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn into_admin_messages(self) -> Vec<protobufs::AdminMessage> {
+        self.into_module_configs()
+            .into_iter()
+            .map(|module_config| protobufs::AdminMessage {
+                payload_variant: Some(protobufs::admin_message::PayloadVariant::SetModuleConfig(
+                    module_config,
+                )),
+            })
+            .collect()
+    }
+
+    /// Stores the payload of a single `ModuleConfig` message within the matching field of
+    /// this `LocalModuleConfig`, overwriting any previously stored value.
+    ///
+    /// # Arguments
+    ///
+    /// * `module_config` - The `ModuleConfig` message to store.
+    ///
+    /// # Returns
+    ///
+    /// The `ModuleConfigType` of the stored message, or `None` if the message had no payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut local_module_config = protobufs::LocalModuleConfig::default();
+    /// local_module_config.apply_module_config(module_config);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn apply_module_config(
+        &mut self,
+        module_config: protobufs::ModuleConfig,
+    ) -> Option<ModuleConfigType> {
+        let module_config_type = module_config.module_config_type()?;
+
+        match module_config.payload_variant? {
+            ModuleConfigVariant::Mqtt(c) => self.mqtt = Some(c),
+            ModuleConfigVariant::Serial(c) => self.serial = Some(c),
+            ModuleConfigVariant::ExternalNotification(c) => self.external_notification = Some(c),
+            ModuleConfigVariant::StoreForward(c) => self.store_forward = Some(c),
+            ModuleConfigVariant::RangeTest(c) => self.range_test = Some(c),
+            ModuleConfigVariant::Telemetry(c) => self.telemetry = Some(c),
+            ModuleConfigVariant::CannedMessage(c) => self.canned_message = Some(c),
+            ModuleConfigVariant::Audio(c) => self.audio = Some(c),
+            ModuleConfigVariant::RemoteHardware(c) => self.remote_hardware = Some(c),
+            ModuleConfigVariant::NeighborInfo(c) => self.neighbor_info = Some(c),
+            ModuleConfigVariant::AmbientLighting(c) => self.ambient_lighting = Some(c),
+            ModuleConfigVariant::DetectionSensor(c) => self.detection_sensor = Some(c),
+            ModuleConfigVariant::Paxcounter(c) => self.paxcounter = Some(c),
+        }
+
+        Some(module_config_type)
+    }
+}
+
+impl Extend<protobufs::ModuleConfig> for protobufs::LocalModuleConfig {
+    fn extend<T: IntoIterator<Item = protobufs::ModuleConfig>>(&mut self, iter: T) {
+        for module_config in iter {
+            self.apply_module_config(module_config);
+        }
+    }
+}
+
+impl FromIterator<protobufs::ModuleConfig> for protobufs::LocalModuleConfig {
+    fn from_iter<T: IntoIterator<Item = protobufs::ModuleConfig>>(iter: T) -> Self {
+        let mut local_module_config = protobufs::LocalModuleConfig::default();
+        local_module_config.extend(iter);
+        local_module_config
+    }
+}
+
+/// An enum that represents a single section of the device or module configuration, as stored within
+/// a `LocalConfig` or `LocalModuleConfig`. This allows code such as a settings UI to handle every
+/// section uniformly, e.g. to render a list of sections, without matching on every payload variant.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigSection {
+    /// A section of the device configuration.
+    Config(ConfigVariant),
+
+    /// A section of the module configuration.
+    ModuleConfig(ModuleConfigVariant),
+}
+
+impl ConfigSection {
+    /// Returns a human-readable name of this section, e.g. `"LoRa"` or `"Store & Forward"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigSection::Config(variant) => match variant {
+                ConfigVariant::Device(_) => "Device",
+                ConfigVariant::Position(_) => "Position",
+                ConfigVariant::Power(_) => "Power",
+                ConfigVariant::Network(_) => "Network",
+                ConfigVariant::Display(_) => "Display",
+                ConfigVariant::Lora(_) => "LoRa",
+                ConfigVariant::Bluetooth(_) => "Bluetooth",
+            },
+            ConfigSection::ModuleConfig(variant) => match variant {
+                ModuleConfigVariant::Mqtt(_) => "MQTT",
+                ModuleConfigVariant::Serial(_) => "Serial",
+                ModuleConfigVariant::ExternalNotification(_) => "External Notification",
+                ModuleConfigVariant::StoreForward(_) => "Store & Forward",
+                ModuleConfigVariant::RangeTest(_) => "Range Test",
+                ModuleConfigVariant::Telemetry(_) => "Telemetry",
+                ModuleConfigVariant::CannedMessage(_) => "Canned Message",
+                ModuleConfigVariant::Audio(_) => "Audio",
+                ModuleConfigVariant::RemoteHardware(_) => "Remote Hardware",
+                ModuleConfigVariant::NeighborInfo(_) => "Neighbor Info",
+                ModuleConfigVariant::AmbientLighting(_) => "Ambient Lighting",
+                ModuleConfigVariant::DetectionSensor(_) => "Detection Sensor",
+                ModuleConfigVariant::Paxcounter(_) => "Paxcounter",
+            },
+        }
+    }
+
+    /// Returns whether this section is part of the module configuration.
+    pub fn is_module_config(&self) -> bool {
+        matches!(self, ConfigSection::ModuleConfig(_))
+    }
+
+    /// Converts this section into the `SetConfig` or `SetModuleConfig` admin message that applies it
+    /// to a radio, e.g. after it has been edited within a settings UI.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The admin message that sets this section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let admin_messages = local_config
+    ///     .sections()
+    ///     .filter(|section| edited.contains(section.name()))
+    ///     .map(ConfigSection::into_admin_message)
+    ///     .collect::<Vec<_>>();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn into_admin_message(self) -> protobufs::AdminMessage {
+        let payload_variant = match self {
+            ConfigSection::Config(variant) => {
+                protobufs::admin_message::PayloadVariant::SetConfig(protobufs::Config {
+                    payload_variant: Some(variant),
+                })
+            }
+            ConfigSection::ModuleConfig(variant) => {
+                protobufs::admin_message::PayloadVariant::SetModuleConfig(protobufs::ModuleConfig {
+                    payload_variant: Some(variant),
+                })
+            }
+        };
+
+        protobufs::AdminMessage {
+            payload_variant: Some(payload_variant),
+        }
+    }
+}
+
+impl protobufs::LocalConfig {
+    /// Returns an iterator over the populated sections of this `LocalConfig`, in the order of the
+    /// `ConfigType` enum. Sections that are set to `None` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding a `ConfigSection::Config` for each populated section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for section in stream_api.local_config().sections() {
+    ///     println!("{}: {:?}", section.name(), section);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn sections(&self) -> impl Iterator<Item = ConfigSection> {
+        let local_config = self.clone();
+        let variants = [
+            local_config.device.map(ConfigVariant::Device),
+            local_config.position.map(ConfigVariant::Position),
+            local_config.power.map(ConfigVariant::Power),
+            local_config.network.map(ConfigVariant::Network),
+            local_config.display.map(ConfigVariant::Display),
+            local_config.lora.map(ConfigVariant::Lora),
+            local_config.bluetooth.map(ConfigVariant::Bluetooth),
+        ];
+
+        variants.into_iter().flatten().map(ConfigSection::Config)
+    }
+}
+
+impl protobufs::LocalModuleConfig {
+    /// Returns an iterator over the populated sections of this `LocalModuleConfig`, in the order of
+    /// the `ModuleConfigType` enum. Sections that are set to `None` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding a `ConfigSection::ModuleConfig` for each populated section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let sections = stream_api
+    ///     .local_config()
+    ///     .sections()
+    ///     .chain(stream_api.local_module_config().sections());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn sections(&self) -> impl Iterator<Item = ConfigSection> {
+        self.clone()
+            .into_module_configs()
+            .into_iter()
+            .filter_map(|module_config| module_config.payload_variant)
+            .map(ConfigSection::ModuleConfig)
+    }
+}
+
+/// A struct that reassembles a `LocalModuleConfig` from a stream of `GetModuleConfigResponse`
+/// admin messages, as sent by the radio in response to `GetModuleConfigRequest` messages.
+///
+/// The collector tracks which module configuration types have been received, which allows
+/// the caller to determine when all requested module configurations have arrived.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleConfigCollector {
+    local_module_config: protobufs::LocalModuleConfig,
+    received: BTreeSet<ModuleConfigType>,
+}
+
+impl ModuleConfigCollector {
+    /// Creates a new, empty `ModuleConfigCollector`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a single `ModuleConfig` message within the collector.
+    ///
+    /// # Arguments
+    ///
+    /// * `module_config` - The `ModuleConfig` message to store.
+    ///
+    /// # Returns
+    ///
+    /// The `ModuleConfigType` of the stored message, or `None` if the message had no payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut collector = ModuleConfigCollector::new();
+    /// collector.push(module_config);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn push(&mut self, module_config: protobufs::ModuleConfig) -> Option<ModuleConfigType> {
+        let module_config_type = self
+            .local_module_config
+            .apply_module_config(module_config)?;
+
+        self.received.insert(module_config_type);
+
+        Some(module_config_type)
+    }
+
+    /// Stores the module configuration contained within a `GetModuleConfigResponse` admin message.
+    /// Admin messages with any other payload are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_message` - The admin message received from the radio.
+    ///
+    /// # Returns
+    ///
+    /// The `ModuleConfigType` of the stored message, or `None` if the message was ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let admin_message = protobufs::AdminMessage::decode(data.payload.as_slice())?;
+    /// collector.push_admin_message(admin_message);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn push_admin_message(
+        &mut self,
+        admin_message: protobufs::AdminMessage,
+    ) -> Option<ModuleConfigType> {
+        match admin_message.payload_variant? {
+            protobufs::admin_message::PayloadVariant::GetModuleConfigResponse(module_config) => {
+                self.push(module_config)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether a module configuration of the specified type has been received.
+    pub fn has_received(&self, module_config_type: ModuleConfigType) -> bool {
+        self.received.contains(&module_config_type)
+    }
+
+    /// Returns whether all of the specified module configuration types have been received.
+    pub fn is_complete(&self, expected: &[ModuleConfigType]) -> bool {
+        expected.iter().all(|t| self.has_received(*t))
+    }
+
+    /// Returns a reference to the `LocalModuleConfig` that has been collected so far.
+    pub fn local_module_config(&self) -> &protobufs::LocalModuleConfig {
+        &self.local_module_config
+    }
+
+    /// Consumes the collector, returning the collected `LocalModuleConfig`.
+    pub fn into_local_module_config(self) -> protobufs::LocalModuleConfig {
+        self.local_module_config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_local_module_config() -> protobufs::LocalModuleConfig {
+        protobufs::LocalModuleConfig {
+            mqtt: Some(protobufs::module_config::MqttConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            telemetry: Some(protobufs::module_config::TelemetryConfig {
+                device_update_interval: 900,
+                ..Default::default()
+            }),
+            paxcounter: Some(protobufs::module_config::PaxcounterConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn into_module_configs_skips_unset_modules() {
+        let module_configs = mock_local_module_config().into_module_configs();
+
+        let module_config_types = module_configs
+            .iter()
+            .map(|c| c.module_config_type().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            module_config_types,
+            vec![
+                ModuleConfigType::MqttConfig,
+                ModuleConfigType::TelemetryConfig,
+                ModuleConfigType::PaxcounterConfig
+            ]
+        );
+    }
+
+    #[test]
+    fn into_admin_messages_wraps_set_module_config() {
+        let admin_messages = mock_local_module_config().into_admin_messages();
+
+        assert_eq!(admin_messages.len(), 3);
+        assert!(admin_messages.iter().all(|m| matches!(
+            m.payload_variant,
+            Some(protobufs::admin_message::PayloadVariant::SetModuleConfig(_))
+        )));
+    }
+
+    #[test]
+    fn iterates_config_sections() {
+        let local_config = protobufs::LocalConfig {
+            device: Some(Default::default()),
+            lora: Some(Default::default()),
+            ..Default::default()
+        };
+
+        let names = local_config
+            .sections()
+            .chain(mock_local_module_config().sections())
+            .map(|section| section.name())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["Device", "LoRa", "MQTT", "Telemetry", "Paxcounter"]);
+
+        let admin_message = local_config.sections().nth(1).unwrap().into_admin_message();
+
+        assert!(matches!(
+            admin_message.payload_variant,
+            Some(protobufs::admin_message::PayloadVariant::SetConfig(
+                protobufs::Config {
+                    payload_variant: Some(ConfigVariant::Lora(_))
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn collector_reassembles_responses() {
+        let local_module_config = mock_local_module_config();
+        let expected = [
+            ModuleConfigType::MqttConfig,
+            ModuleConfigType::TelemetryConfig,
+            ModuleConfigType::PaxcounterConfig,
+        ];
+
+        let mut collector = ModuleConfigCollector::new();
+
+        for module_config in local_module_config.clone().into_module_configs() {
+            assert!(!collector.is_complete(&expected));
+
+            collector.push_admin_message(protobufs::AdminMessage {
+                payload_variant: Some(
+                    protobufs::admin_message::PayloadVariant::GetModuleConfigResponse(
+                        module_config,
+                    ),
+                ),
+            });
+        }
+
+        assert!(collector.is_complete(&expected));
+        assert_eq!(collector.into_local_module_config(), local_module_config);
+    }
+}
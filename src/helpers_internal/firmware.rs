@@ -0,0 +1,330 @@
+use crate::helpers_internal::hardware::HwCaps;
+use crate::protobufs;
+use crate::protobufs::admin_message::{ConfigType, ModuleConfigType};
+
+/// The first firmware version that supports PKI-encrypted direct messages.
+pub const PKI_MIN_FIRMWARE_VERSION: (u32, u32, u32) = (2, 5, 0);
+
+/// The first firmware version that reports its configuration as `Config` and `ModuleConfig` sections.
+/// Earlier firmware versions report a single `RadioConfig` instead, so they answer no section requests.
+pub const CONFIG_SECTIONS_MIN_FIRMWARE_VERSION: (u32, u32, u32) = (2, 0, 0);
+
+/// The first firmware versions that answer requests for module configuration sections which were
+/// added after the 2.0 release. All other sections are answered by every 2.x firmware version.
+const MODULE_CONFIG_MIN_FIRMWARE_VERSIONS: [(ModuleConfigType, (u32, u32, u32)); 5] = [
+    (ModuleConfigType::RemotehardwareConfig, (2, 1, 0)),
+    (ModuleConfigType::NeighborinfoConfig, (2, 2, 0)),
+    (ModuleConfigType::AmbientlightingConfig, (2, 2, 18)),
+    (ModuleConfigType::DetectionsensorConfig, (2, 2, 22)),
+    (ModuleConfigType::PaxcounterConfig, (2, 2, 23)),
+];
+
+/// A struct that summarizes the capabilities of a device, derived from its firmware version and
+/// the `DeviceMetadata` it reports.
+///
+/// Firmware-dependent capabilities are only reported as supported if the firmware version of the
+/// device could be parsed, so that devices running unknown firmware fall back to the behavior
+/// supported by all firmware versions.
+///
+/// **Note:** The capabilities are exposed for applications to gate features on. The library itself
+/// does not change how packets are encoded or decoded based on them, as the protobufs bundled with
+/// this version of the library predate PKI-encrypted packets.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DeviceCapabilities {
+    /// The `(major, minor, patch)` firmware version of the device, if it could be parsed.
+    pub firmware_version: Option<(u32, u32, u32)>,
+
+    /// The hardware capabilities of the device.
+    pub hardware: HwCaps,
+
+    /// Whether the device can shut down its CPU, e.g. in response to a shutdown admin message.
+    pub can_shutdown: bool,
+
+    /// Whether the device has the remote hardware module enabled.
+    pub remote_hardware: bool,
+
+    /// Whether the firmware sends and accepts PKI-encrypted direct messages, rather than encrypting
+    /// direct messages with the channel key. The firmware encrypts and decrypts these messages itself,
+    /// so they are received as decoded packets either way.
+    pub pki_direct_messages: bool,
+}
+
+impl DeviceCapabilities {
+    /// Derives the capabilities of a device from the `DeviceMetadata` it reports.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - The `DeviceMetadata` reported by the device.
+    ///
+    /// # Returns
+    ///
+    /// A `DeviceCapabilities` struct summarizing the capabilities of the device.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let capabilities = DeviceCapabilities::from_metadata(&metadata);
+    ///
+    /// if !capabilities.pki_direct_messages {
+    ///     println!("Direct messages are encrypted with the channel key");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_metadata(metadata: &protobufs::DeviceMetadata) -> Self {
+        let firmware_version = metadata.firmware_semver();
+
+        Self {
+            firmware_version,
+            hardware: metadata.capabilities(),
+            can_shutdown: metadata.can_shutdown,
+            remote_hardware: metadata.has_remote_hardware,
+            pki_direct_messages: firmware_version >= Some(PKI_MIN_FIRMWARE_VERSION),
+        }
+    }
+
+    /// Returns whether the firmware of the device is at least the specified version. Devices whose
+    /// firmware version could not be parsed are assumed to run older firmware.
+    pub fn firmware_at_least(&self, version: (u32, u32, u32)) -> bool {
+        self.firmware_version >= Some(version)
+    }
+}
+
+impl protobufs::DeviceMetadata {
+    /// Parses the leading `major.minor.patch` version triple of the `firmware_version` reported by the
+    /// device, ignoring the git hash suffix, e.g. `2.3.2.abc1234` is parsed as `(2, 3, 2)`. The returned
+    /// tuple can be compared directly, to gate features on the firmware version of the device.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The `(major, minor, patch)` version of the firmware, or `None` if the version string does not
+    /// begin with three numeric components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if metadata.firmware_semver() >= Some((2, 5, 0)) {
+    ///     println!("Device supports PKI-encrypted direct messages");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn firmware_semver(&self) -> Option<(u32, u32, u32)> {
+        parse_firmware_version(&self.firmware_version)
+    }
+
+    /// Returns the capabilities of the device, derived from its firmware version and this metadata.
+    /// See the `DeviceCapabilities` struct for details.
+    pub fn device_capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities::from_metadata(self)
+    }
+}
+
+/// Returns the `ConfigType` sections that a device answers requests for, based on its firmware version,
+/// so that a full configuration sync only requests sections the device supports. Every section known to
+/// this library is supported from `CONFIG_SECTIONS_MIN_FIRMWARE_VERSION` onwards, while earlier firmware
+/// versions support none of them.
+///
+/// Devices whose firmware version could not be parsed are assumed to run 2.x firmware, and are asked for
+/// every section.
+///
+/// # Arguments
+///
+/// * `metadata` - The `DeviceMetadata` reported by the device.
+///
+/// # Returns
+///
+/// The supported `ConfigType` sections, in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// let requests = supported_config_types(&metadata).into_iter().map(|config_type| protobufs::AdminMessage {
+///     payload_variant: Some(protobufs::admin_message::PayloadVariant::GetConfigRequest(config_type as i32)),
+/// });
+///
+/// let responses = stream_api.send_admin_requests(packet_router, requests).await?.await?;
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn supported_config_types(metadata: &protobufs::DeviceMetadata) -> Vec<ConfigType> {
+    if predates_config_sections(metadata) {
+        return vec![];
+    }
+
+    (0..).map_while(|v| ConfigType::try_from(v).ok()).collect()
+}
+
+/// Returns the `ModuleConfigType` sections that a device answers requests for, based on its firmware
+/// version. Requesting a section that the firmware doesn't know stalls a configuration sync, as the
+/// device never responds to the request.
+///
+/// Firmware versions before `CONFIG_SECTIONS_MIN_FIRMWARE_VERSION` support none of the sections. Devices
+/// whose firmware version could not be parsed are assumed to run older 2.x firmware, and are only asked
+/// for the sections supported by every 2.x firmware version.
+///
+/// # Arguments
+///
+/// * `metadata` - The `DeviceMetadata` reported by the device.
+///
+/// # Returns
+///
+/// The supported `ModuleConfigType` sections, in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// let config_sync = ConfigSync::with_expected(
+///     config_id,
+///     supported_config_types(&metadata),
+///     supported_module_config_types(&metadata),
+///     0..MAX_NUM_CHANNELS,
+/// );
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn supported_module_config_types(
+    metadata: &protobufs::DeviceMetadata,
+) -> Vec<ModuleConfigType> {
+    if predates_config_sections(metadata) {
+        return vec![];
+    }
+
+    let capabilities = metadata.device_capabilities();
+
+    (0..)
+        .map_while(|v| ModuleConfigType::try_from(v).ok())
+        .filter(|module_config_type| {
+            MODULE_CONFIG_MIN_FIRMWARE_VERSIONS
+                .iter()
+                .find(|(t, _)| t == module_config_type)
+                .is_none_or(|(_, version)| capabilities.firmware_at_least(*version))
+        })
+        .collect()
+}
+
+/// Returns whether the device runs a firmware version that is known to predate configuration sections.
+fn predates_config_sections(metadata: &protobufs::DeviceMetadata) -> bool {
+    metadata
+        .firmware_semver()
+        .is_some_and(|version| version < CONFIG_SECTIONS_MIN_FIRMWARE_VERSION)
+}
+
+/// Parses the leading version triple of a firmware version string, e.g. `2.3.2.abc1234` or `2.5.0-alpha`.
+pub(crate) fn parse_firmware_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut components = version.trim().trim_start_matches('v').splitn(3, '.');
+
+    let major = components.next()?.parse().ok()?;
+    let minor = components.next()?.parse().ok()?;
+
+    // The patch component is followed by the git hash or a pre-release suffix
+    let patch = components.next()?;
+    let patch_len = patch
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(patch.len());
+    let patch = patch[..patch_len].parse().ok()?;
+
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_firmware_versions() {
+        let metadata = protobufs::DeviceMetadata {
+            firmware_version: "2.3.2.abc1234".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(metadata.firmware_semver(), Some((2, 3, 2)));
+        assert_eq!(parse_firmware_version("2.5.10"), Some((2, 5, 10)));
+        assert_eq!(parse_firmware_version("v2.6.0-alpha"), Some((2, 6, 0)));
+        assert_eq!(parse_firmware_version("2.3"), None);
+        assert_eq!(parse_firmware_version("2.x.1"), None);
+        assert_eq!(parse_firmware_version(""), None);
+    }
+
+    #[test]
+    fn gates_capabilities_on_firmware_version() {
+        let mut metadata = protobufs::DeviceMetadata {
+            firmware_version: "2.4.3.91d6612".to_string(),
+            can_shutdown: true,
+            ..Default::default()
+        };
+
+        let capabilities = metadata.device_capabilities();
+        assert!(capabilities.can_shutdown);
+        assert!(!capabilities.pki_direct_messages);
+        assert!(capabilities.firmware_at_least((2, 4, 0)));
+
+        metadata.firmware_version = "2.5.6.d55c08d".to_string();
+        assert!(metadata.device_capabilities().pki_direct_messages);
+
+        metadata.firmware_version = "unknown".to_string();
+        let capabilities = metadata.device_capabilities();
+        assert!(!capabilities.pki_direct_messages);
+        assert!(!capabilities.firmware_at_least((0, 0, 0)));
+    }
+
+    #[test]
+    fn gates_module_config_types_on_firmware_version() {
+        let mut metadata = protobufs::DeviceMetadata {
+            firmware_version: "2.5.6.d55c08d".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(supported_config_types(&metadata).len(), 7);
+        assert_eq!(supported_module_config_types(&metadata).len(), 13);
+
+        metadata.firmware_version = "2.1.5.abcdef0".to_string();
+        let module_config_types = supported_module_config_types(&metadata);
+        assert!(module_config_types.contains(&ModuleConfigType::RemotehardwareConfig));
+        assert!(!module_config_types.contains(&ModuleConfigType::NeighborinfoConfig));
+        assert!(!module_config_types.contains(&ModuleConfigType::PaxcounterConfig));
+
+        metadata.firmware_version = "unknown".to_string();
+        assert_eq!(supported_config_types(&metadata).len(), 7);
+        assert_eq!(
+            supported_module_config_types(&metadata).last(),
+            Some(&ModuleConfigType::AudioConfig)
+        );
+
+        metadata.firmware_version = "1.3.48.7a1f2c3".to_string();
+        assert!(supported_config_types(&metadata).is_empty());
+        assert!(supported_module_config_types(&metadata).is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+use crate::protobufs;
+use crate::protobufs::HardwareModel;
+
+/// A struct that summarizes the hardware capabilities of a device.
+///
+/// Capabilities derived from a `HardwareModel` are a best-effort summary of the stock hardware,
+/// and do not account for add-on modules (e.g. a GPS module attached to a RAK WisBlock base board).
+/// The `with_metadata` method can be used to refine the summary using the `DeviceMetadata`
+/// reported by the device itself, which is authoritative for the capabilities it reports.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HwCaps {
+    /// Whether the device has WiFi.
+    pub wifi: bool,
+
+    /// Whether the device has Bluetooth.
+    pub bluetooth: bool,
+
+    /// Whether the device has Ethernet.
+    pub ethernet: bool,
+
+    /// Whether the device has a built-in GPS receiver.
+    pub gps: bool,
+
+    /// Whether the device has a built-in screen.
+    pub screen: bool,
+}
+
+impl HwCaps {
+    /// Refines the capabilities using the `DeviceMetadata` reported by the device. The WiFi,
+    /// Bluetooth, and Ethernet capabilities are replaced by the values reported by the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - The `DeviceMetadata` reported by the device.
+    ///
+    /// # Returns
+    ///
+    /// The refined `HwCaps`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let hw_model = protobufs::HardwareModel::try_from(metadata.hw_model)?;
+    /// let capabilities = hw_model.capabilities().with_metadata(&metadata);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn with_metadata(self, metadata: &protobufs::DeviceMetadata) -> Self {
+        Self {
+            wifi: metadata.has_wifi,
+            bluetooth: metadata.has_bluetooth,
+            ethernet: metadata.has_ethernet,
+            ..self
+        }
+    }
+}
+
+/// The microcontroller platform of a hardware model, which determines its radio capabilities.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Platform {
+    Esp32,
+    Nrf52,
+    Rp2040,
+    Linux,
+    Unknown,
+}
+
+impl HardwareModel {
+    /// Returns a human-friendly name for this hardware model, e.g. `"Heltec V3"` rather than `HELTEC_V3`.
+    /// This is intended to be displayed within device-picker UIs.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A static string containing the display name of the hardware model.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(protobufs::HardwareModel::Tbeam.display_name(), "T-Beam");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HardwareModel::Unset => "Unknown",
+            HardwareModel::TloraV2 => "T-LoRa V2",
+            HardwareModel::TloraV1 => "T-LoRa V1",
+            HardwareModel::TloraV211p6 => "T-LoRa V2.1-1.6",
+            HardwareModel::Tbeam => "T-Beam",
+            HardwareModel::HeltecV20 => "Heltec V2.0",
+            HardwareModel::TbeamV0p7 => "T-Beam V0.7",
+            HardwareModel::TEcho => "T-Echo",
+            HardwareModel::TloraV11p3 => "T-LoRa V1.1-1.3",
+            HardwareModel::Rak4631 => "RAK4631",
+            HardwareModel::HeltecV21 => "Heltec V2.1",
+            HardwareModel::HeltecV1 => "Heltec V1",
+            HardwareModel::LilygoTbeamS3Core => "T-Beam S3 Core",
+            HardwareModel::Rak11200 => "RAK11200",
+            HardwareModel::NanoG1 => "Nano G1",
+            HardwareModel::TloraV211p8 => "T-LoRa V2.1-1.8",
+            HardwareModel::TloraT3S3 => "T-LoRa T3-S3",
+            HardwareModel::NanoG1Explorer => "Nano G1 Explorer",
+            HardwareModel::NanoG2Ultra => "Nano G2 Ultra",
+            HardwareModel::LoraType => "LoRaType",
+            HardwareModel::StationG1 => "Station G1",
+            HardwareModel::Rak11310 => "RAK11310",
+            HardwareModel::SenseloraRp2040 => "SenseLoRa RP2040",
+            HardwareModel::SenseloraS3 => "SenseLoRa S3",
+            HardwareModel::Canaryone => "CanaryOne",
+            HardwareModel::Rp2040Lora => "RP2040 LoRa",
+            HardwareModel::StationG2 => "Station G2",
+            HardwareModel::LoraRelayV1 => "LoRa Relay V1",
+            HardwareModel::Nrf52840dk => "nRF52840 DK",
+            HardwareModel::Ppr => "PPR",
+            HardwareModel::Genieblocks => "GenieBlocks",
+            HardwareModel::Nrf52Unknown => "nRF52 (Unknown)",
+            HardwareModel::Portduino => "Portduino",
+            HardwareModel::AndroidSim => "Android Simulator",
+            HardwareModel::DiyV1 => "DIY V1",
+            HardwareModel::Nrf52840Pca10059 => "nRF52840 Dongle",
+            HardwareModel::DrDev => "DR-DEV",
+            HardwareModel::M5stack => "M5Stack",
+            HardwareModel::HeltecV3 => "Heltec V3",
+            HardwareModel::HeltecWslV3 => "Heltec Wireless Stick Lite V3",
+            HardwareModel::Betafpv2400Tx => "BETAFPV 2.4GHz TX",
+            HardwareModel::Betafpv900NanoTx => "BETAFPV 900MHz Nano TX",
+            HardwareModel::RpiPico => "Raspberry Pi Pico",
+            HardwareModel::HeltecWirelessTracker => "Heltec Wireless Tracker",
+            HardwareModel::HeltecWirelessPaper => "Heltec Wireless Paper",
+            HardwareModel::TDeck => "T-Deck",
+            HardwareModel::TWatchS3 => "T-Watch S3",
+            HardwareModel::PicomputerS3 => "PiComputer S3",
+            HardwareModel::HeltecHt62 => "Heltec HT62",
+            HardwareModel::EbyteEsp32S3 => "EBYTE ESP32-S3",
+            HardwareModel::Esp32S3Pico => "ESP32-S3 Pico",
+            HardwareModel::Chatter2 => "Chatter 2",
+            HardwareModel::HeltecWirelessPaperV10 => "Heltec Wireless Paper V1.0",
+            HardwareModel::HeltecWirelessTrackerV10 => "Heltec Wireless Tracker V1.0",
+            HardwareModel::Unphone => "unPhone",
+            HardwareModel::TdLorac => "TD-LoRaC",
+            HardwareModel::CdebyteEoraS3 => "CDEBYTE EoRa-S3",
+            HardwareModel::PrivateHw => "Private Hardware",
+        }
+    }
+
+    /// Returns a best-effort summary of the capabilities of the stock hardware of this model.
+    /// See the `HwCaps` struct for details on how to refine this summary using `DeviceMetadata`.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `HwCaps` struct summarizing the capabilities of the hardware model.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let capabilities = protobufs::HardwareModel::TEcho.capabilities();
+    /// assert!(capabilities.gps && capabilities.screen && !capabilities.wifi);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn capabilities(&self) -> HwCaps {
+        let (wifi, bluetooth) = match self.platform() {
+            Platform::Esp32 => (true, true),
+            Platform::Nrf52 => (false, true),
+            Platform::Linux => (true, false),
+            Platform::Rp2040 | Platform::Unknown => (false, false),
+        };
+
+        let gps = matches!(
+            self,
+            HardwareModel::Tbeam
+                | HardwareModel::TbeamV0p7
+                | HardwareModel::LilygoTbeamS3Core
+                | HardwareModel::TEcho
+                | HardwareModel::NanoG1
+                | HardwareModel::NanoG1Explorer
+                | HardwareModel::NanoG2Ultra
+                | HardwareModel::Canaryone
+                | HardwareModel::HeltecWirelessTracker
+                | HardwareModel::HeltecWirelessTrackerV10
+                | HardwareModel::TWatchS3
+        );
+
+        let screen = matches!(
+            self,
+            HardwareModel::TloraV2
+                | HardwareModel::TloraV1
+                | HardwareModel::TloraV211p6
+                | HardwareModel::TloraV211p8
+                | HardwareModel::TloraV11p3
+                | HardwareModel::TloraT3S3
+                | HardwareModel::HeltecV1
+                | HardwareModel::HeltecV20
+                | HardwareModel::HeltecV21
+                | HardwareModel::HeltecV3
+                | HardwareModel::TEcho
+                | HardwareModel::NanoG1
+                | HardwareModel::NanoG1Explorer
+                | HardwareModel::NanoG2Ultra
+                | HardwareModel::StationG1
+                | HardwareModel::StationG2
+                | HardwareModel::Canaryone
+                | HardwareModel::M5stack
+                | HardwareModel::HeltecWirelessTracker
+                | HardwareModel::HeltecWirelessTrackerV10
+                | HardwareModel::HeltecWirelessPaper
+                | HardwareModel::HeltecWirelessPaperV10
+                | HardwareModel::TDeck
+                | HardwareModel::TWatchS3
+                | HardwareModel::PicomputerS3
+                | HardwareModel::Chatter2
+                | HardwareModel::Unphone
+        );
+
+        HwCaps {
+            wifi,
+            bluetooth,
+            ethernet: false,
+            gps,
+            screen,
+        }
+    }
+
+    fn platform(&self) -> Platform {
+        match self {
+            HardwareModel::TEcho
+            | HardwareModel::Rak4631
+            | HardwareModel::NanoG2Ultra
+            | HardwareModel::Canaryone
+            | HardwareModel::Nrf52840dk
+            | HardwareModel::Ppr
+            | HardwareModel::Nrf52Unknown
+            | HardwareModel::Nrf52840Pca10059 => Platform::Nrf52,
+            HardwareModel::Rak11310
+            | HardwareModel::SenseloraRp2040
+            | HardwareModel::Rp2040Lora
+            | HardwareModel::RpiPico => Platform::Rp2040,
+            HardwareModel::Portduino => Platform::Linux,
+            HardwareModel::Unset
+            | HardwareModel::LoraType
+            | HardwareModel::LoraRelayV1
+            | HardwareModel::AndroidSim
+            | HardwareModel::PrivateHw => Platform::Unknown,
+            _ => Platform::Esp32,
+        }
+    }
+}
+
+impl protobufs::DeviceMetadata {
+    /// Returns the capabilities of the device, combining the reported `hw_model` with the
+    /// capabilities the device reports within this metadata.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `HwCaps` struct summarizing the capabilities of the device.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let capabilities = metadata.capabilities();
+    /// println!("Device has WiFi: {}", capabilities.wifi);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn capabilities(&self) -> HwCaps {
+        HardwareModel::try_from(self.hw_model)
+            .unwrap_or(HardwareModel::Unset)
+            .capabilities()
+            .with_metadata(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn names_every_hardware_model_uniquely() {
+        let hardware_models: Vec<HardwareModel> = (0..=u8::MAX as i32)
+            .filter_map(|value| HardwareModel::try_from(value).ok())
+            .collect();
+
+        let display_names: HashSet<&str> = hardware_models
+            .iter()
+            .map(|hardware_model| hardware_model.display_name())
+            .collect();
+
+        assert_eq!(display_names.len(), hardware_models.len());
+        assert!(!display_names.contains(""));
+
+        assert_eq!(HardwareModel::HeltecV3.display_name(), "Heltec V3");
+        assert_eq!(HardwareModel::Unset.display_name(), "Unknown");
+    }
+
+    #[test]
+    fn summarizes_capabilities_by_platform() {
+        assert_eq!(
+            HardwareModel::TEcho.capabilities(),
+            HwCaps {
+                bluetooth: true,
+                gps: true,
+                screen: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            HardwareModel::HeltecV3.capabilities(),
+            HwCaps {
+                wifi: true,
+                bluetooth: true,
+                screen: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            HardwareModel::Portduino.capabilities(),
+            HwCaps {
+                wifi: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(HardwareModel::RpiPico.capabilities(), HwCaps::default());
+        assert_eq!(HardwareModel::Unset.capabilities(), HwCaps::default());
+    }
+
+    #[test]
+    fn refines_capabilities_with_metadata() {
+        let metadata = protobufs::DeviceMetadata {
+            hw_model: HardwareModel::Rak4631 as i32,
+            has_bluetooth: true,
+            has_ethernet: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            metadata.capabilities(),
+            HwCaps {
+                bluetooth: true,
+                ethernet: true,
+                ..Default::default()
+            }
+        );
+
+        // Unknown hardware models only report the capabilities within the metadata
+        let metadata = protobufs::DeviceMetadata {
+            hw_model: 10_000,
+            has_wifi: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            metadata.capabilities(),
+            HwCaps {
+                wifi: true,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use crate::errors_internal::Error;
+use crate::protobufs;
+
+/// The length of the unencrypted header that precedes the payload of every packet sent over LoRa.
+pub const LORA_HEADER_LEN: usize = 16;
+
+/// The bits of the header flags byte that contain the remaining hop limit of the packet.
+pub(crate) const FLAGS_HOP_LIMIT_MASK: u8 = 0x07;
+
+/// The bit of the header flags byte that indicates whether the sender wants an acknowledgement.
+pub(crate) const FLAGS_WANT_ACK_MASK: u8 = 0x08;
+
+/// The bit of the header flags byte that indicates whether the packet has passed through MQTT.
+pub(crate) const FLAGS_VIA_MQTT_MASK: u8 = 0x10;
+
+/// The bits of the header flags byte that contain the hop limit the packet was originally sent with.
+pub(crate) const FLAGS_HOP_START_MASK: u8 = 0xe0;
+
+/// The offset of the hop start value within the header flags byte.
+pub(crate) const FLAGS_HOP_START_SHIFT: u8 = 5;
+
+impl protobufs::MeshPacket {
+    /// Parses a packet captured directly from the LoRa radio layer (e.g. using an SDR), rather than
+    /// received via the client API.
+    ///
+    /// Packets sent over LoRa begin with a packed 16-byte header, followed by the encrypted payload.
+    /// The header contains the destination, sender, and packet id as little-endian `u32` values,
+    /// followed by a flags byte and the hash of the channel the packet was sent on. The flags byte
+    /// contains the hop limit (bits 0-2), the want ack flag (bit 3), the via MQTT flag (bit 4), and
+    /// the hop start (bits 5-7) of the packet. The remaining two bytes of the header are not used.
+    ///
+    /// The returned packet contains the encrypted payload, and its `channel` field contains the
+    /// channel hash rather than a channel index, as is the case for encrypted packets reported by the radio.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw bytes of the LoRa packet, including the header.
+    ///
+    /// # Returns
+    ///
+    /// A `MeshPacket` containing the header fields and encrypted payload of the packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mesh_packet = protobufs::MeshPacket::from_lora_header(&captured_bytes)?;
+    /// println!("Packet {} from !{:08x} on channel hash {}", mesh_packet.id, mesh_packet.from, mesh_packet.channel);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidLoraPacket` if the passed bytes are shorter than the 16-byte header.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_lora_header(bytes: &[u8]) -> Result<protobufs::MeshPacket, Error> {
+        if bytes.len() < LORA_HEADER_LEN {
+            return Err(Error::InvalidLoraPacket {
+                description: format!(
+                    "packet of {} bytes is too short to contain the {LORA_HEADER_LEN}-byte header",
+                    bytes.len()
+                ),
+            });
+        }
+
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+
+        let flags = bytes[12];
+
+        Ok(protobufs::MeshPacket {
+            to: read_u32(0),
+            from: read_u32(4),
+            id: read_u32(8),
+            hop_limit: (flags & FLAGS_HOP_LIMIT_MASK) as u32,
+            want_ack: flags & FLAGS_WANT_ACK_MASK != 0,
+            via_mqtt: flags & FLAGS_VIA_MQTT_MASK != 0,
+            hop_start: ((flags & FLAGS_HOP_START_MASK) >> FLAGS_HOP_START_SHIFT) as u32,
+            channel: bytes[13] as u32,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(
+                bytes[LORA_HEADER_LEN..].to_vec(),
+            )),
+            ..Default::default()
+        })
+    }
+
+    /// Encodes the packet into the format sent over LoRa, consisting of a packed 16-byte header
+    /// followed by the encrypted payload. This is the inverse of the `from_lora_header` method.
+    ///
+    /// The packet must already contain an encrypted payload, as the payload is sent over LoRa as-is.
+    /// The unused trailing bytes of the header are set to zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_hash` - The hash of the channel the packet is sent on.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<u8>` containing the header and encrypted payload of the packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let bytes = mesh_packet.to_lora_bytes(channel_hash)?;
+    /// sdr.transmit(&bytes)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidLoraPacket` if the packet does not contain an encrypted payload, or if
+    /// the hop limit or hop start of the packet cannot be represented within 3 bits (i.e. is greater than 7).
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn to_lora_bytes(&self, channel_hash: u8) -> Result<Vec<u8>, Error> {
+        let max_hops = FLAGS_HOP_LIMIT_MASK as u32;
+
+        if self.hop_limit > max_hops || self.hop_start > max_hops {
+            return Err(Error::InvalidLoraPacket {
+                description: format!(
+                    "hop limit {} and hop start {} must not exceed {max_hops}",
+                    self.hop_limit, self.hop_start
+                ),
+            });
+        }
+
+        let encrypted = match self.payload_variant.as_ref() {
+            Some(protobufs::mesh_packet::PayloadVariant::Encrypted(encrypted)) => encrypted,
+            _ => {
+                return Err(Error::InvalidLoraPacket {
+                    description: "packet does not contain an encrypted payload".to_string(),
+                })
+            }
+        };
+
+        let mut flags = self.hop_limit as u8 & FLAGS_HOP_LIMIT_MASK;
+        flags |= ((self.hop_start as u8) << FLAGS_HOP_START_SHIFT) & FLAGS_HOP_START_MASK;
+
+        if self.want_ack {
+            flags |= FLAGS_WANT_ACK_MASK;
+        }
+
+        if self.via_mqtt {
+            flags |= FLAGS_VIA_MQTT_MASK;
+        }
+
+        let mut bytes = Vec::with_capacity(LORA_HEADER_LEN + encrypted.len());
+        bytes.extend_from_slice(&self.to.to_le_bytes());
+        bytes.extend_from_slice(&self.from.to_le_bytes());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&[flags, channel_hash, 0, 0]);
+        bytes.extend_from_slice(encrypted);
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_packed_header() {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+        bytes.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        bytes.extend_from_slice(&0x0102_0304u32.to_le_bytes());
+        bytes.extend_from_slice(&[0b0111_1011, 0x08, 0, 0]);
+        bytes.extend_from_slice(&[0xaa, 0xbb]);
+
+        let mesh_packet = protobufs::MeshPacket::from_lora_header(&bytes).unwrap();
+
+        assert_eq!(mesh_packet.to, 0xffff_ffff);
+        assert_eq!(mesh_packet.from, 0xa1b2_c3d4);
+        assert_eq!(mesh_packet.id, 0x0102_0304);
+        assert_eq!(mesh_packet.hop_limit, 3);
+        assert!(mesh_packet.want_ack);
+        assert!(mesh_packet.via_mqtt);
+        assert_eq!(mesh_packet.hop_start, 3);
+        assert_eq!(mesh_packet.channel, 8);
+        assert_eq!(
+            mesh_packet.payload_variant,
+            Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![
+                0xaa, 0xbb
+            ]))
+        );
+
+        assert!(protobufs::MeshPacket::from_lora_header(&bytes[..15]).is_err());
+    }
+
+    #[test]
+    fn encodes_packed_header() {
+        let mesh_packet = protobufs::MeshPacket {
+            to: 0xffff_ffff,
+            from: 0xa1b2_c3d4,
+            id: 42,
+            hop_limit: 2,
+            hop_start: 7,
+            want_ack: true,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Encrypted(vec![
+                1, 2,
+            ])),
+            ..Default::default()
+        };
+
+        let bytes = mesh_packet.to_lora_bytes(0x08).unwrap();
+
+        assert_eq!(bytes.len(), LORA_HEADER_LEN + 2);
+        assert_eq!(bytes[12], 0b1110_1010);
+        assert_eq!(bytes[13], 0x08);
+
+        let decoded = protobufs::MeshPacket::from_lora_header(&bytes).unwrap();
+        assert_eq!(decoded.to_lora_bytes(0x08).unwrap(), bytes);
+        assert_eq!(
+            protobufs::MeshPacket {
+                channel: 0,
+                ..decoded
+            },
+            mesh_packet
+        );
+
+        let too_many_hops = protobufs::MeshPacket {
+            hop_limit: 8,
+            ..mesh_packet
+        };
+        assert!(too_many_hops.to_lora_bytes(0x08).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use prost::Message;
+
+use crate::protobufs;
+
+impl protobufs::MapReport {
+    /// Decodes the `MapReport` contained within a `MapReportApp` packet.
+    ///
+    /// Map reports are published unencrypted to MQTT, wrapped within a `ServiceEnvelope`. The
+    /// `MeshPacket` within the envelope can be passed directly to this method.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesh_packet` - A `MeshPacket` received from the radio or from an MQTT broker.
+    ///
+    /// # Returns
+    ///
+    /// The decoded `MapReport`, or `None` if the packet was not sent on the `MapReportApp` port,
+    /// is encrypted, or fails to decode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let envelope = protobufs::ServiceEnvelope::decode(mqtt_payload.as_slice())?;
+    ///
+    /// if let Some(map_report) = envelope.packet.as_ref().and_then(protobufs::MapReport::from_mesh_packet) {
+    ///     println!("{} is at {:?}", map_report.long_name, map_report.position());
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_mesh_packet(mesh_packet: &protobufs::MeshPacket) -> Option<Self> {
+        let data = match mesh_packet.payload_variant.as_ref()? {
+            protobufs::mesh_packet::PayloadVariant::Decoded(data) => data,
+            _ => return None,
+        };
+
+        if data.portnum != protobufs::PortNum::MapReportApp as i32 {
+            return None;
+        }
+
+        Self::decode(data.payload.as_slice()).ok()
+    }
+
+    /// Returns the position reported within this map report as a `Position`, so that it can be handled
+    /// in the same way as positions received on the `PositionApp` port.
+    pub fn position(&self) -> protobufs::Position {
+        protobufs::Position {
+            latitude_i: self.latitude_i,
+            longitude_i: self.longitude_i,
+            altitude: self.altitude,
+            precision_bits: self.position_precision,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_map_report_packets() {
+        let map_report = protobufs::MapReport {
+            long_name: "Repeater".to_string(),
+            short_name: "RPT".to_string(),
+            firmware_version: "2.3.2".to_string(),
+            latitude_i: 473_977_000,
+            longitude_i: 85_456_000,
+            altitude: 408,
+            position_precision: 13,
+            ..Default::default()
+        };
+
+        let envelope = protobufs::ServiceEnvelope {
+            packet: Some(protobufs::MeshPacket {
+                payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                    protobufs::Data {
+                        portnum: protobufs::PortNum::MapReportApp as i32,
+                        payload: map_report.encode_to_vec(),
+                        ..Default::default()
+                    },
+                )),
+                ..Default::default()
+            }),
+            channel_id: "LongFast".to_string(),
+            gateway_id: "!a1b2c3d4".to_string(),
+        };
+
+        let decoded = envelope
+            .packet
+            .as_ref()
+            .and_then(protobufs::MapReport::from_mesh_packet)
+            .unwrap();
+
+        assert_eq!(decoded, map_report);
+        assert_eq!(decoded.position().latitude_i, 473_977_000);
+        assert_eq!(decoded.position().precision_bits, 13);
+    }
+}
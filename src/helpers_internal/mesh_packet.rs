@@ -0,0 +1,137 @@
+use crate::protobufs;
+use crate::protobufs::mesh_packet::Delayed;
+
+const BROADCAST_NODE_ID: u32 = u32::MAX;
+
+impl protobufs::MeshPacket {
+    /// Returns the value of the deprecated `delayed` field, which older firmware sets on messages that
+    /// are replayed by a store and forward server rather than being sent in real time. Unknown values
+    /// are treated as `Delayed::NoDelay`.
+    pub fn delay(&self) -> Delayed {
+        // NOTE: The field is deprecated, but is still set by nodes running older firmware
+        #[allow(deprecated)]
+        Delayed::try_from(self.delayed).unwrap_or(Delayed::NoDelay)
+    }
+
+    /// Returns whether this packet is a delayed replay, e.g. of a message stored by a store and forward
+    /// server, as indicated by the deprecated `delayed` field.
+    pub fn is_delayed(&self) -> bool {
+        self.delay() != Delayed::NoDelay
+    }
+
+    /// Returns whether this packet was originally sent as a broadcast.
+    ///
+    /// Store and forward servers replay stored broadcasts as direct packets to the requesting node,
+    /// and older firmware marks these replays with the deprecated `delayed` field. This method takes the
+    /// field into account, so that replayed broadcasts are not mistaken for direct messages, and falls
+    /// back to the destination of the packet if the packet is not delayed.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// `true` if the packet was originally a broadcast, `false` if it was a direct message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if !mesh_packet.is_broadcast() {
+    ///     println!("Direct message from {}", mesh_packet.from);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn is_broadcast(&self) -> bool {
+        match self.delay() {
+            Delayed::Broadcast => true,
+            Delayed::Direct => false,
+            Delayed::NoDelay => self.to == BROADCAST_NODE_ID,
+        }
+    }
+
+    /// Returns the difference between the time at which the local node reports having received this
+    /// packet and the specified host time, in seconds. A large skew indicates that the clock of the
+    /// local node is wrong, which is common for nodes without an RTC or GPS, and that timestamps reported
+    /// by the node should not be trusted.
+    ///
+    /// **Note:** Packets that were queued by the radio while no client was connected are delivered late,
+    /// and appear skewed by the time they spent in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current host time, in seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// The skew in seconds, which is positive if the clock of the node is ahead of the host clock, or
+    /// `None` if the node did not timestamp the packet, i.e. if `rx_time` is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(skew) = mesh_packet.clock_skew(utils::current_epoch_secs_u32()) {
+    ///     if skew.abs() > 300 {
+    ///         println!("Node clock is off by {skew} seconds");
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn clock_skew(&self, now: u32) -> Option<i64> {
+        match self.rx_time {
+            0 => None,
+            rx_time => Some(i64::from(rx_time) - i64::from(now)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_clock_skew() {
+        let mut mesh_packet = protobufs::MeshPacket::default();
+        assert_eq!(mesh_packet.clock_skew(1_700_000_000), None);
+
+        mesh_packet.rx_time = 1_700_000_600;
+        assert_eq!(mesh_packet.clock_skew(1_700_000_000), Some(600));
+        assert_eq!(mesh_packet.clock_skew(1_700_001_000), Some(-400));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn classifies_delayed_packets() {
+        let mut mesh_packet = protobufs::MeshPacket {
+            to: 0x0a1b2c3d,
+            ..Default::default()
+        };
+        assert!(!mesh_packet.is_delayed());
+        assert!(!mesh_packet.is_broadcast());
+
+        mesh_packet.delayed = Delayed::Broadcast as i32;
+        assert!(mesh_packet.is_delayed());
+        assert!(mesh_packet.is_broadcast());
+
+        mesh_packet.to = BROADCAST_NODE_ID;
+        mesh_packet.delayed = Delayed::Direct as i32;
+        assert!(mesh_packet.is_delayed());
+        assert!(!mesh_packet.is_broadcast());
+    }
+}
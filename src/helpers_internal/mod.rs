@@ -0,0 +1,20 @@
+pub mod admin;
+pub mod channel;
+pub mod config;
+pub mod firmware;
+pub mod hardware;
+pub mod lora;
+pub mod map_report;
+pub mod mesh_packet;
+pub mod modem_preset;
+pub mod network;
+pub mod nmea;
+pub mod node_info;
+pub mod position;
+pub mod queue_status;
+pub mod route_discovery;
+pub mod routing;
+pub mod simulator;
+pub mod telemetry;
+pub mod text_compression;
+pub mod waypoint;
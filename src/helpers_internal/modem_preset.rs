@@ -0,0 +1,112 @@
+use crate::protobufs::config::lo_ra_config::ModemPreset;
+
+/// An enum that represents the approximate range class of a modem preset, as reflected in its name.
+///
+/// # Variants
+///
+/// * `Short` - Short range presets, which trade range for the highest data rates.
+/// * `Medium` - Medium range presets.
+/// * `Long` - Long range presets, including the default `LongFast` preset.
+/// * `VeryLong` - Very long range presets, which trade data rate for the highest link budget.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PresetRange {
+    Short,
+    Medium,
+    Long,
+    VeryLong,
+}
+
+/// A struct that describes the LoRa modulation parameters of a modem preset, as configured by the firmware.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PresetInfo {
+    /// The LoRa spreading factor, in the range [7..12]. Each increment doubles the airtime of a packet.
+    pub spread_factor: u32,
+
+    /// The LoRa bandwidth, in kHz.
+    pub bandwidth_khz: f32,
+
+    /// The denominator of the LoRa coding rate, in the range [5..8], i.e. a value of 5 represents a
+    /// coding rate of 4/5.
+    pub coding_rate: u32,
+
+    /// The approximate range class of the preset.
+    pub approx_range: PresetRange,
+
+    /// The approximate raw data rate of the preset, in bits per second.
+    pub approx_speed: f32,
+}
+
+impl ModemPreset {
+    /// Returns the LoRa modulation parameters used by the firmware for this modem preset, along with
+    /// the approximate range and data rate that result from them. This can be used to explain the
+    /// tradeoffs between presets to users, or to estimate the airtime of packets.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `PresetInfo` struct describing the preset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let info = ModemPreset::LongFast.characteristics();
+    /// println!("SF{} at {} kHz, ~{:.0} bps", info.spread_factor, info.bandwidth_khz, info.approx_speed);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn characteristics(&self) -> PresetInfo {
+        // Mirrors the preset table within `RadioInterface::applyModemConfig` of the firmware
+        let (spread_factor, bandwidth_khz, coding_rate, approx_range) = match self {
+            ModemPreset::ShortFast => (7, 250.0, 5, PresetRange::Short),
+            ModemPreset::ShortSlow => (8, 250.0, 5, PresetRange::Short),
+            ModemPreset::MediumFast => (9, 250.0, 5, PresetRange::Medium),
+            ModemPreset::MediumSlow => (10, 250.0, 5, PresetRange::Medium),
+            ModemPreset::LongFast => (11, 250.0, 5, PresetRange::Long),
+            ModemPreset::LongModerate => (11, 125.0, 8, PresetRange::Long),
+            ModemPreset::LongSlow => (12, 125.0, 8, PresetRange::Long),
+            ModemPreset::VeryLongSlow => (12, 62.5, 8, PresetRange::VeryLong),
+        };
+
+        // Each symbol carries `spread_factor` bits, of which a fraction of 4/coding_rate are payload bits
+        let symbol_rate = bandwidth_khz * 1000.0 / (1u32 << spread_factor) as f32;
+        let approx_speed = symbol_rate * spread_factor as f32 * 4.0 / coding_rate as f32;
+
+        PresetInfo {
+            spread_factor,
+            bandwidth_khz,
+            coding_rate,
+            approx_range,
+            approx_speed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_modem_presets() {
+        let long_fast = ModemPreset::LongFast.characteristics();
+
+        assert_eq!(long_fast.spread_factor, 11);
+        assert_eq!(long_fast.bandwidth_khz, 250.0);
+        assert_eq!(long_fast.coding_rate, 5);
+        assert_eq!(long_fast.approx_range, PresetRange::Long);
+        assert!((long_fast.approx_speed - 1074.2).abs() < 1.0);
+
+        let short_fast = ModemPreset::ShortFast.characteristics();
+        assert!(short_fast.approx_speed > long_fast.approx_speed);
+        assert!(short_fast.approx_range < long_fast.approx_range);
+    }
+}
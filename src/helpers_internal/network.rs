@@ -0,0 +1,115 @@
+use std::net::Ipv4Addr;
+
+use crate::protobufs;
+use crate::protobufs::config::network_config::IpV4Config;
+
+/// Converts an IPv4 address stored within a `fixed32` protobuf field into an `Ipv4Addr`.
+///
+/// The firmware stores IPv4 addresses in the byte order of the radio's memory, which is little-endian
+/// on all supported platforms. The first octet of the address is therefore the least significant byte
+/// of the field, e.g. `192.168.1.10` is stored as `0x0a01a8c0`. Interpreting the field as a big-endian
+/// number, as `Ipv4Addr::from(u32)` does, reverses the address.
+///
+/// # Arguments
+///
+/// * `value` - The value of the `fixed32` field, e.g. `IpV4Config::ip`.
+///
+/// # Returns
+///
+/// The `Ipv4Addr` represented by the field.
+///
+/// # Examples
+///
+/// ```
+/// let address = ipv4_from_fixed32(status.ip_address);
+/// println!("Radio is reachable at {address}");
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn ipv4_from_fixed32(value: u32) -> Ipv4Addr {
+    Ipv4Addr::from(value.to_le_bytes())
+}
+
+/// Converts an `Ipv4Addr` into the value of a `fixed32` protobuf field. This is the inverse of the
+/// `ipv4_from_fixed32` function, which describes the byte order of the field.
+pub fn ipv4_to_fixed32(address: Ipv4Addr) -> u32 {
+    u32::from_le_bytes(address.octets())
+}
+
+impl IpV4Config {
+    /// Returns the static IP address of the device.
+    pub fn ip_addr(&self) -> Ipv4Addr {
+        ipv4_from_fixed32(self.ip)
+    }
+
+    /// Returns the static gateway address of the device.
+    pub fn gateway_addr(&self) -> Ipv4Addr {
+        ipv4_from_fixed32(self.gateway)
+    }
+
+    /// Returns the static subnet mask of the device.
+    pub fn subnet_mask(&self) -> Ipv4Addr {
+        ipv4_from_fixed32(self.subnet)
+    }
+
+    /// Returns the static DNS server address of the device.
+    pub fn dns_addr(&self) -> Ipv4Addr {
+        ipv4_from_fixed32(self.dns)
+    }
+
+    /// Builds a static IPv4 configuration from the specified addresses, in the byte order expected
+    /// by the firmware.
+    pub fn from_addrs(ip: Ipv4Addr, gateway: Ipv4Addr, subnet: Ipv4Addr, dns: Ipv4Addr) -> Self {
+        Self {
+            ip: ipv4_to_fixed32(ip),
+            gateway: ipv4_to_fixed32(gateway),
+            subnet: ipv4_to_fixed32(subnet),
+            dns: ipv4_to_fixed32(dns),
+        }
+    }
+}
+
+impl protobufs::NetworkConnectionStatus {
+    /// Returns the IP address of the device's network connection.
+    pub fn ip_addr(&self) -> Ipv4Addr {
+        ipv4_from_fixed32(self.ip_address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_firmware_byte_order_for_ipv4_addresses() {
+        // Values as reported by an ESP32 radio with a static IPv4 configuration
+        let ip_config = IpV4Config {
+            ip: 0x0a01_a8c0,
+            gateway: 0x0101_a8c0,
+            subnet: 0x00ff_ffff,
+            dns: 0x0808_0808,
+        };
+
+        assert_eq!(ip_config.ip_addr(), Ipv4Addr::new(192, 168, 1, 10));
+        assert_eq!(ip_config.gateway_addr(), Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(ip_config.subnet_mask(), Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(ip_config.dns_addr(), Ipv4Addr::new(8, 8, 8, 8));
+
+        assert_eq!(
+            IpV4Config::from_addrs(
+                Ipv4Addr::new(192, 168, 1, 10),
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(255, 255, 255, 0),
+                Ipv4Addr::new(8, 8, 8, 8),
+            ),
+            ip_config
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use crate::errors_internal::Error;
+use crate::protobufs;
+use crate::protobufs::position::{AltSource, LocSource};
+
+impl protobufs::Position {
+    /// Parses a GGA sentence reported by an NMEA GPS receiver into a `Position`, e.g. to send the position
+    /// of an external GPS receiver over the mesh.
+    ///
+    /// The latitude and longitude are converted to the fixed-point representation used by `Position`,
+    /// and the altitude above mean sea level, geoidal separation, fix quality, number of satellites, and
+    /// HDOP are copied from the sentence. The location and altitude sources are set to `LocExternal` and
+    /// `AltExternal` respectively. The sentence may use any talker id (e.g. `$GPGGA` or `$GNGGA`), and its
+    /// checksum is verified if present.
+    ///
+    /// **Note:** GGA sentences only contain the time of day of the fix, so the `timestamp` field of the
+    /// returned position is not set.
+    ///
+    /// # Arguments
+    ///
+    /// * `sentence` - A GGA sentence, with or without a trailing line ending.
+    ///
+    /// # Returns
+    ///
+    /// A `Position` containing the fix reported by the GPS receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let position = protobufs::Position::from_nmea_gga(
+    ///     "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+    /// )?;
+    ///
+    /// println!("{} {}", position.latitude_i, position.longitude_i);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidNmeaSentence` if the sentence is not a well-formed GGA sentence, if its
+    /// checksum does not match, or if the GPS receiver reports that it has no fix.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_nmea_gga(sentence: &str) -> Result<Self, Error> {
+        let invalid = |description: &str| Error::InvalidNmeaSentence {
+            description: description.to_string(),
+        };
+
+        let sentence = sentence
+            .trim_end()
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("sentence does not start with '$'"))?;
+
+        let body = match sentence.split_once('*') {
+            Some((body, checksum)) => {
+                let expected = u8::from_str_radix(checksum, 16)
+                    .map_err(|_| invalid("checksum is not a hexadecimal byte"))?;
+                let actual = body.bytes().fold(0, |checksum, byte| checksum ^ byte);
+
+                if actual != expected {
+                    return Err(invalid(&format!(
+                        "checksum mismatch, expected {expected:02X} but computed {actual:02X}"
+                    )));
+                }
+
+                body
+            }
+            None => sentence,
+        };
+
+        let fields: Vec<&str> = body.split(',').collect();
+
+        if fields[0].len() != 5 || !fields[0].ends_with("GGA") {
+            return Err(invalid("sentence is not a GGA sentence"));
+        }
+
+        if fields.len() < 12 {
+            return Err(invalid("sentence has too few fields"));
+        }
+
+        let fix_quality: u32 = fields[6]
+            .parse()
+            .map_err(|_| invalid("fix quality is not a number"))?;
+
+        if fix_quality == 0 {
+            return Err(invalid("GPS receiver has no fix"));
+        }
+
+        let latitude = parse_coordinate(fields[2], fields[3], 2, 'N', 'S')
+            .ok_or_else(|| invalid("latitude is malformed"))?;
+        let longitude = parse_coordinate(fields[4], fields[5], 3, 'E', 'W')
+            .ok_or_else(|| invalid("longitude is malformed"))?;
+
+        let parse_optional = |field: &str| -> Result<Option<f64>, Error> {
+            if field.is_empty() {
+                return Ok(None);
+            }
+
+            field
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid(&format!("field \"{field}\" is not a number")))
+        };
+
+        let altitude = parse_optional(fields[9])?;
+        let geoidal_separation = parse_optional(fields[11])?;
+
+        let mut position = protobufs::Position {
+            location_source: LocSource::LocExternal as i32,
+            fix_quality,
+            sats_in_view: fields[7].parse().unwrap_or_default(),
+            hdop: parse_optional(fields[8])?.map_or(0, |hdop| (hdop * 100.0).round() as u32),
+            ..protobufs::Position::from_degrees(latitude, longitude)
+        };
+
+        if let Some(altitude) = altitude {
+            position.altitude = altitude.round() as i32;
+            position.altitude_source = AltSource::AltExternal as i32;
+
+            if let Some(geoidal_separation) = geoidal_separation {
+                position.altitude_geoidal_separation = geoidal_separation.round() as i32;
+                position.altitude_hae = (altitude + geoidal_separation).round() as i32;
+            }
+        }
+
+        Ok(position)
+    }
+}
+
+/// Parses an NMEA coordinate in the `[d]ddmm.mmmm` format into signed decimal degrees.
+fn parse_coordinate(
+    value: &str,
+    hemisphere: &str,
+    degree_digits: usize,
+    positive: char,
+    negative: char,
+) -> Option<f64> {
+    if value.len() < degree_digits + 2 || !value.is_char_boundary(degree_digits) {
+        return None;
+    }
+
+    let (degrees, minutes) = value.split_at(degree_digits);
+    let degrees: f64 = degrees.parse().ok()?;
+    let minutes: f64 = minutes.parse().ok()?;
+
+    if minutes >= 60.0 {
+        return None;
+    }
+
+    let coordinate = degrees + minutes / 60.0;
+
+    match hemisphere.chars().next()? {
+        c if c == positive => Some(coordinate),
+        c if c == negative => Some(-coordinate),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gga_sentences() {
+        let position = protobufs::Position::from_nmea_gga(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(position.latitude_i, 481_173_000);
+        assert_eq!(position.longitude_i, 115_166_667);
+        assert_eq!(position.altitude, 545);
+        assert_eq!(position.altitude_hae, 592);
+        assert_eq!(position.hdop, 90);
+        assert_eq!(position.sats_in_view, 8);
+        assert_eq!(position.location_source(), LocSource::LocExternal);
+
+        let southern = protobufs::Position::from_nmea_gga(
+            "$GNGGA,000000,3351.000,S,15112.000,W,1,05,1.0,10.0,M,,M,,",
+        )
+        .unwrap();
+        assert_eq!(southern.latitude_i, -338_500_000);
+        assert_eq!(southern.longitude_i, -1_512_000_000);
+
+        for invalid in [
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48",
+            "$GPGGA,123519,4807.038,N,01131.000,E,0,00,,,M,,M,,",
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W",
+            "GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,",
+        ] {
+            assert!(matches!(
+                protobufs::Position::from_nmea_gga(invalid),
+                Err(Error::InvalidNmeaSentence { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::protobufs;
+
+impl protobufs::NodeInfo {
+    /// Returns the name that should be used to display this node within a UI.
+    ///
+    /// The firmware may report nodes that have not yet shared their `User` information, or that
+    /// have empty names. This method falls back from the long name, to the short name, to the
+    /// `!`-prefixed hexadecimal node id (e.g. `!a1b2c3d4`) derived from the node number. Names
+    /// that only contain whitespace are treated as empty.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A `String` containing the display name of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let node_info = protobufs::NodeInfo { num: 0xa1b2c3d4, user: None, ..Default::default() };
+    /// assert_eq!(node_info.display_name(), "!a1b2c3d4");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn display_name(&self) -> String {
+        let user_name = self.user.as_ref().and_then(|user| {
+            [&user.long_name, &user.short_name]
+                .into_iter()
+                .map(|name| name.trim())
+                .find(|name| !name.is_empty())
+        });
+
+        match user_name {
+            Some(name) => name.to_string(),
+            None => format!("!{:08x}", self.num),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protobufs;
+
+    fn node_info(long_name: &str, short_name: &str) -> protobufs::NodeInfo {
+        protobufs::NodeInfo {
+            num: 0x0a1b2c3d,
+            user: Some(protobufs::User {
+                long_name: long_name.to_string(),
+                short_name: short_name.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn display_name_falls_back() {
+        assert_eq!(
+            node_info("Base Station", "BASE").display_name(),
+            "Base Station"
+        );
+        assert_eq!(node_info("", "BASE").display_name(), "BASE");
+        assert_eq!(node_info(" ", "").display_name(), "!0a1b2c3d");
+
+        let no_user = protobufs::NodeInfo {
+            num: 0x0a1b2c3d,
+            ..Default::default()
+        };
+        assert_eq!(no_user.display_name(), "!0a1b2c3d");
+    }
+}
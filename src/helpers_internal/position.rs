@@ -0,0 +1,243 @@
+use crate::protobufs;
+
+/// The approximate length of one degree of latitude, in meters.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// The scale of the `latitude_i` and `longitude_i` fields, which hold degrees in units of 1e-7.
+const DEGREES_SCALE: f64 = 1e7;
+
+/// A struct that represents a coordinate in degrees, as converted from the fixed-point `latitude_i` and
+/// `longitude_i` fields of a `Position`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LatLng {
+    /// The latitude in degrees, from -90 (south) to 90 (north).
+    pub lat: f64,
+
+    /// The longitude in degrees, from -180 (west) to 180 (east).
+    pub lng: f64,
+}
+
+impl From<&protobufs::Position> for LatLng {
+    fn from(position: &protobufs::Position) -> Self {
+        let (lat, lng) = position.degrees();
+        LatLng { lat, lng }
+    }
+}
+
+impl From<LatLng> for protobufs::Position {
+    fn from(lat_lng: LatLng) -> Self {
+        protobufs::Position::from_degrees(lat_lng.lat, lat_lng.lng)
+    }
+}
+
+impl protobufs::Position {
+    /// Creates a `Position` containing only the specified coordinate, which is rounded to the nearest
+    /// 1e-7 degrees of the `latitude_i` and `longitude_i` fields.
+    ///
+    /// Latitudes beyond the poles are clamped to -90 or 90 degrees, and longitudes beyond the antimeridian
+    /// are wrapped into the range from -180 to 180 degrees, so that e.g. 190 degrees becomes -170 degrees.
+    ///
+    /// # Arguments
+    ///
+    /// * `lat` - The latitude in degrees.
+    /// * `lng` - The longitude in degrees.
+    ///
+    /// # Returns
+    ///
+    /// A `Position` with the coordinate set, and all other fields set to their default values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let position = protobufs::Position {
+    ///     time: current_epoch_secs_u32(),
+    ///     ..protobufs::Position::from_degrees(47.397_700, 8.545_600)
+    /// };
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_degrees(lat: f64, lng: f64) -> Self {
+        let lat = lat.clamp(-90.0, 90.0);
+        let lng = if (-180.0..=180.0).contains(&lng) {
+            lng
+        } else {
+            (lng + 180.0).rem_euclid(360.0) - 180.0
+        };
+
+        protobufs::Position {
+            latitude_i: (lat * DEGREES_SCALE).round() as i32,
+            longitude_i: (lng * DEGREES_SCALE).round() as i32,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the latitude and longitude of this position in degrees. A position without a fix has both
+    /// fields set to zero, which this method returns as `(0.0, 0.0)`; see `degrees_opt` to detect it.
+    pub fn degrees(&self) -> (f64, f64) {
+        (
+            f64::from(self.latitude_i) / DEGREES_SCALE,
+            f64::from(self.longitude_i) / DEGREES_SCALE,
+        )
+    }
+
+    /// Returns the latitude and longitude of this position in degrees, or `None` if both are zero.
+    ///
+    /// Nodes report a position without a fix with the `latitude_i` and `longitude_i` fields unset, which
+    /// is indistinguishable from a position at exactly 0° N 0° E. As there is only ocean at that coordinate,
+    /// it is treated as a missing fix.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The latitude and longitude in degrees, or `None` if the position has no fix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match position.degrees_opt() {
+    ///     Some((lat, lng)) => println!("Node is at {lat:.5}, {lng:.5}"),
+    ///     None => println!("Node has no GPS fix"),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn degrees_opt(&self) -> Option<(f64, f64)> {
+        if self.latitude_i == 0 && self.longitude_i == 0 {
+            return None;
+        }
+
+        Some(self.degrees())
+    }
+
+    /// Estimates the on-ground uncertainty of this position from its `precision_bits`, e.g. to draw an
+    /// accuracy circle around the position on a map.
+    ///
+    /// Nodes that share an imprecise position keep only the `precision_bits` most significant bits of
+    /// `latitude_i` and `longitude_i`, and move the position to the center of the resulting grid cell.
+    /// The returned value is half the north-south size of a grid cell, i.e. the maximum distance between
+    /// the reported position and the actual position along a meridian. This matches the accuracy shown
+    /// by the official apps, e.g. about 2.9 km for 13 bits and 46 m for 19 bits.
+    ///
+    /// **Note:** The east-west size of a grid cell shrinks towards the poles, so the estimate is an upper
+    /// bound for the east-west uncertainty.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The estimated uncertainty in meters, or `None` if `precision_bits` is not set or is greater than 32.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(radius) = position.effective_precision_m() {
+    ///     println!("Position is accurate to within {radius:.0} m");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn effective_precision_m(&self) -> Option<f64> {
+        if !(1..=32).contains(&self.precision_bits) {
+            return None;
+        }
+
+        // Each truncated bit doubles the size of the grid cell, in units of 1e-7 degrees
+        let cell_size_degrees = f64::from(1u32 << (32 - self.precision_bits)) * 1e-7;
+
+        Some(cell_size_degrees * METERS_PER_DEGREE / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn precision_m(precision_bits: u32) -> Option<f64> {
+        protobufs::Position {
+            precision_bits,
+            ..Default::default()
+        }
+        .effective_precision_m()
+    }
+
+    #[test]
+    fn estimates_precision_from_precision_bits() {
+        assert_eq!(precision_m(0), None);
+        assert_eq!(precision_m(33), None);
+
+        assert_eq!(precision_m(10).map(f64::round), Some(23_345.0));
+        assert_eq!(precision_m(13).map(f64::round), Some(2_918.0));
+        assert_eq!(precision_m(19).map(f64::round), Some(46.0));
+        assert!(precision_m(32).unwrap() < 0.01);
+    }
+
+    #[test]
+    fn converts_positions_to_and_from_degrees() {
+        // Rounded to the nearest 1e-7 degrees rather than truncated
+        let position = protobufs::Position::from_degrees(47.397_700_49, -8.545_600_06);
+        assert_eq!(position.latitude_i, 473_977_005);
+        assert_eq!(position.longitude_i, -85_456_001);
+
+        let north_pole = protobufs::Position::from_degrees(90.0, 0.0);
+        assert_eq!(north_pole.latitude_i, 900_000_000);
+        assert_eq!(north_pole.degrees_opt(), Some((90.0, 0.0)));
+        assert_eq!(
+            protobufs::Position::from_degrees(-91.0, 0.0).degrees(),
+            (-90.0, 0.0)
+        );
+
+        // Both sides of the antimeridian are valid, and longitudes beyond it wrap around
+        for lng in [180.0, -180.0, 179.999_999_9] {
+            let position = protobufs::Position::from_degrees(-16.5, lng);
+            assert_eq!(LatLng::from(&position), LatLng { lat: -16.5, lng });
+        }
+        assert_eq!(
+            protobufs::Position::from_degrees(0.0, 190.0).longitude_i,
+            -1_700_000_000
+        );
+
+        let lat_lng = LatLng {
+            lat: -33.856_784_4,
+            lng: 151.215_296_7,
+        };
+        assert_eq!(LatLng::from(&protobufs::Position::from(lat_lng)), lat_lng);
+    }
+
+    #[test]
+    fn distinguishes_missing_fixes() {
+        assert_eq!(protobufs::Position::default().degrees(), (0.0, 0.0));
+        assert_eq!(protobufs::Position::default().degrees_opt(), None);
+
+        // Positions on the equator or the prime meridian are valid fixes
+        assert_eq!(
+            protobufs::Position::from_degrees(0.0, -0.5).degrees_opt(),
+            Some((0.0, -0.5))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::protobufs;
+use crate::protobufs::routing::Error as RoutingError;
+
+/// An enum that describes why the radio failed to enqueue an outgoing packet, as reported within
+/// the `res` field of a `QueueStatus` packet.
+///
+/// The firmware reports either one of its internal error numbers, or the routing error the packet
+/// was rejected with, e.g. if it was too large or would exceed the duty cycle limit. The internal
+/// error numbers take precedence, as they overlap with the values of the `BadRequest` and
+/// `NotAuthorized` routing errors, which are never reported for outgoing packets.
+///
+/// # Variants
+///
+/// * `Unknown` - The packet could not be enqueued for an unspecified reason.
+/// * `NoInterfaces` - The radio has no interface that can transmit the packet.
+/// * `Disabled` - The interface that would transmit the packet is disabled.
+/// * `ShouldRelease` - The packet was consumed without being enqueued.
+/// * `Routing` - The packet was rejected with the specified routing error.
+/// * `Other` - An error code that is not known to this library.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum QueueError {
+    Unknown,
+    NoInterfaces,
+    Disabled,
+    ShouldRelease,
+    Routing(RoutingError),
+    Other(i32),
+}
+
+impl QueueError {
+    /// Converts the error code reported within the `res` field of a `QueueStatus` packet into a
+    /// `QueueError`, or `None` if the code indicates success.
+    pub fn from_code(code: i32) -> Option<Self> {
+        let error = match code {
+            0 => return None,
+            32 => QueueError::Unknown,
+            33 => QueueError::NoInterfaces,
+            34 => QueueError::Disabled,
+            35 => QueueError::ShouldRelease,
+            code => match RoutingError::try_from(code) {
+                Ok(routing_error) => QueueError::Routing(routing_error),
+                Err(_) => QueueError::Other(code),
+            },
+        };
+
+        Some(error)
+    }
+}
+
+impl std::fmt::Display for QueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueError::Unknown => write!(f, "The packet could not be enqueued"),
+            QueueError::NoInterfaces => write!(f, "No interface is available to send the packet"),
+            QueueError::Disabled => write!(f, "The interface to send the packet is disabled"),
+            QueueError::ShouldRelease => write!(f, "The packet was consumed without being sent"),
+            QueueError::Routing(routing_error) => write!(f, "{}", routing_error.description()),
+            QueueError::Other(code) => write!(f, "Unknown queue error code {code}"),
+        }
+    }
+}
+
+impl protobufs::QueueStatus {
+    /// Returns whether the radio succeeded to enqueue the packet this `QueueStatus` reports on, as
+    /// identified by the `mesh_packet_id` field. This allows apps that throttle sending based on the
+    /// queue status to detect packets that were rejected by the radio.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the packet was enqueued, or the `QueueError` describing why it was not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(queue_status) = stream_api.queue_status() {
+    ///     if let Err(e) = queue_status.result() {
+    ///         println!("Packet {} was not sent: {e}", queue_status.mesh_packet_id);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the `QueueError` reported by the radio if the packet could not be enqueued.
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn result(&self) -> Result<(), QueueError> {
+        match QueueError::from_code(self.res) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_queue_status_results() {
+        let queue_status = |res| protobufs::QueueStatus {
+            res,
+            free: 4,
+            maxlen: 16,
+            mesh_packet_id: 1,
+        };
+
+        assert_eq!(queue_status(0).result(), Ok(()));
+        assert_eq!(
+            queue_status(RoutingError::TooLarge as i32).result(),
+            Err(QueueError::Routing(RoutingError::TooLarge))
+        );
+        assert_eq!(queue_status(33).result(), Err(QueueError::NoInterfaces));
+        assert_eq!(queue_status(99).result(), Err(QueueError::Other(99)));
+    }
+}
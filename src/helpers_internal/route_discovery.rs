@@ -0,0 +1,68 @@
+use crate::connections::wrappers::NodeId;
+use crate::protobufs;
+
+impl protobufs::RouteDiscovery {
+    /// Returns the nodes that relayed a traceroute, in the order the request passed through them.
+    ///
+    /// Each node relaying a traceroute request appends its node id to the `route` field, so the route
+    /// within the reply to a request sent by `send_traceroute` lists the hops from the connected radio
+    /// towards the destination. Neither the node that sent the request nor the destination are part of
+    /// the route; they are the `to` and `from` fields of the `MeshPacket` containing the reply.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The ids of the relaying nodes, which is empty if the destination was reached directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(DecodedPayload::Traceroute(route)) = decoded_payload(&mesh_packet) {
+    ///     let hops = route.hops();
+    ///     println!("{} -> {:?} -> {}", mesh_packet.to, hops, mesh_packet.from);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn hops(&self) -> Vec<NodeId> {
+        self.route.iter().copied().map(NodeId::from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn lists_hops_in_route_order() {
+        // Route as encoded by the firmware, with the node ids as fixed32 values
+        let payload = protobufs::RouteDiscovery {
+            route: vec![0xa1b2c3d4, 0x0000_0003, 0xdeadbeef],
+        }
+        .encode_to_vec();
+
+        let route = protobufs::RouteDiscovery::decode(payload.as_slice()).unwrap();
+        assert_eq!(
+            route.hops(),
+            [
+                NodeId::new(0xa1b2c3d4),
+                NodeId::new(3),
+                NodeId::new(0xdeadbeef)
+            ]
+        );
+
+        assert!(protobufs::RouteDiscovery::default().hops().is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::protobufs::routing::Error as RoutingError;
+
+impl RoutingError {
+    /// Returns a short, human-readable description of this routing error.
+    ///
+    /// The raw enum values are opaque to end users, so this method is intended to be used
+    /// when displaying why a packet failed to send, e.g. after receiving a negative acknowledgement.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A static string describing the error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let error = protobufs::routing::Error::NoChannel;
+    /// println!("Failed to send packet: {}", error.description());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn description(&self) -> &'static str {
+        match self {
+            RoutingError::None => "The packet was delivered successfully",
+            RoutingError::NoRoute => "No route to the destination node is known",
+            RoutingError::GotNak => {
+                "A node forwarding the packet received a negative acknowledgement"
+            }
+            RoutingError::Timeout => "The packet timed out before it could be delivered",
+            RoutingError::NoInterface => "No interface was available to deliver the packet",
+            RoutingError::MaxRetransmit => {
+                "The packet was not acknowledged after the maximum number of retransmissions"
+            }
+            RoutingError::NoChannel => "The requested channel index is disabled",
+            RoutingError::TooLarge => "The packet is too large to be transmitted",
+            RoutingError::NoResponse => "The destination node did not respond to the request",
+            RoutingError::DutyCycleLimit => {
+                "The packet could not be sent without exceeding the regional duty cycle limit"
+            }
+            RoutingError::BadRequest => "The destination node considered the request invalid",
+            RoutingError::NotAuthorized => {
+                "The destination node considered the request not authorized"
+            }
+        }
+    }
+
+    /// Returns a user-facing suggestion on how to resolve this routing error, if one is available.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// A static string containing a suggestion, or `None` if the error does not indicate a failure
+    /// or no actionable suggestion exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let error = protobufs::routing::Error::NoChannel;
+    ///
+    /// if let Some(suggestion) = error.suggestion() {
+    ///     println!("{}: {}", error.description(), suggestion);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn suggestion(&self) -> Option<&'static str> {
+        let suggestion = match self {
+            RoutingError::None => return None,
+            RoutingError::NoRoute => {
+                "Wait for the destination node to be heard on the mesh, then try again"
+            }
+            RoutingError::GotNak | RoutingError::Timeout | RoutingError::MaxRetransmit => {
+                "The destination may be out of range or offline; try again later or move closer"
+            }
+            RoutingError::NoInterface => "Check that the radio's LoRa region is configured",
+            RoutingError::NoChannel => {
+                "Enable the channel on the radio, or send the packet on a configured channel"
+            }
+            RoutingError::TooLarge => "Reduce the size of the payload and try again",
+            RoutingError::NoResponse => {
+                "Check that the destination node runs a service for this port and shares the channel"
+            }
+            RoutingError::DutyCycleLimit => "Wait before sending more packets",
+            RoutingError::BadRequest => "Check the contents of the request",
+            RoutingError::NotAuthorized => {
+                "Send the request on the channel or with the credentials the destination node requires"
+            }
+        };
+
+        Some(suggestion)
+    }
+}
+
+impl std::fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routing_errors() -> impl Iterator<Item = RoutingError> {
+        (0..=u8::MAX as i32).filter_map(|value| RoutingError::try_from(value).ok())
+    }
+
+    #[test]
+    fn describes_every_routing_error() {
+        for error in routing_errors() {
+            assert!(!error.description().is_empty(), "{error:?}");
+            assert_eq!(error.to_string(), error.description());
+        }
+
+        assert_eq!(
+            RoutingError::NoChannel.description(),
+            "The requested channel index is disabled"
+        );
+    }
+
+    #[test]
+    fn suggests_resolutions_for_failures_only() {
+        for error in routing_errors() {
+            assert_eq!(
+                error.suggestion().is_none(),
+                error == RoutingError::None,
+                "{error:?}"
+            );
+        }
+
+        assert_eq!(
+            RoutingError::TooLarge.suggestion(),
+            Some("Reduce the size of the payload and try again")
+        );
+        assert_eq!(
+            RoutingError::Timeout.suggestion(),
+            RoutingError::MaxRetransmit.suggestion()
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use prost::Message;
+
+use crate::protobufs;
+
+impl protobufs::MeshPacket {
+    /// Wraps the packet in a `SimulatorApp` packet, in the format used by the firmware's simulated
+    /// radio and the Meshtasticator simulator to exchange packets as if they were sent over LoRa.
+    ///
+    /// The original port number and payload of the packet are stored in a `Compressed` message,
+    /// which becomes the payload of the returned `SimulatorApp` packet. All other fields of the
+    /// packet (e.g. the sender, destination, and packet id) are preserved. Sending the returned
+    /// packet to a simulated node causes the node to handle the original packet as if it had
+    /// been received over LoRa.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The wrapped `SimulatorApp` packet, or `None` if the packet does not contain a decoded payload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(simulator_packet) = mesh_packet.to_simulator_packet() {
+    ///     stream_api.send_to_radio_packet(Some(to_radio::PayloadVariant::Packet(simulator_packet))).await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn to_simulator_packet(&self) -> Option<protobufs::MeshPacket> {
+        let data = match self.payload_variant.as_ref()? {
+            protobufs::mesh_packet::PayloadVariant::Decoded(data) => data,
+            _ => return None,
+        };
+
+        let compressed = protobufs::Compressed {
+            portnum: data.portnum,
+            data: data.payload.clone(),
+        };
+
+        Some(protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: protobufs::PortNum::SimulatorApp as i32,
+                    payload: compressed.encode_to_vec(),
+                    ..data.clone()
+                },
+            )),
+            ..self.clone()
+        })
+    }
+
+    /// Unwraps a `SimulatorApp` packet produced by a simulated radio, restoring the port number and
+    /// payload of the packet that the simulated node transmitted. This is the inverse of the
+    /// `to_simulator_packet` method.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The unwrapped packet, or `None` if the packet is not a `SimulatorApp` packet, or if its
+    /// payload fails to decode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// if let Some(transmitted_packet) = mesh_packet.from_simulator_packet() {
+    ///     println!("Simulated node transmitted {:?}", transmitted_packet);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn from_simulator_packet(&self) -> Option<protobufs::MeshPacket> {
+        let data = match self.payload_variant.as_ref()? {
+            protobufs::mesh_packet::PayloadVariant::Decoded(data) => data,
+            _ => return None,
+        };
+
+        if data.portnum != protobufs::PortNum::SimulatorApp as i32 {
+            return None;
+        }
+
+        let compressed = protobufs::Compressed::decode(data.payload.as_slice()).ok()?;
+
+        Some(protobufs::MeshPacket {
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: compressed.portnum,
+                    payload: compressed.data,
+                    ..data.clone()
+                },
+            )),
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_simulator_packets() {
+        let mesh_packet = protobufs::MeshPacket {
+            from: 1,
+            to: 2,
+            id: 3,
+            payload_variant: Some(protobufs::mesh_packet::PayloadVariant::Decoded(
+                protobufs::Data {
+                    portnum: protobufs::PortNum::TextMessageApp as i32,
+                    payload: b"Hello".to_vec(),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+
+        let simulator_packet = mesh_packet.to_simulator_packet().unwrap();
+
+        assert_ne!(simulator_packet, mesh_packet);
+        assert_eq!(simulator_packet.id, 3);
+        assert_eq!(
+            simulator_packet.from_simulator_packet(),
+            Some(mesh_packet.clone())
+        );
+        assert_eq!(mesh_packet.from_simulator_packet(), None);
+    }
+}
@@ -0,0 +1,223 @@
+use std::ops::RangeInclusive;
+
+use crate::protobufs;
+
+/// The plausible range of temperatures in °C reported by the supported environment sensors.
+const TEMPERATURE_RANGE: RangeInclusive<f32> = -80.0..=150.0;
+
+/// The range of relative humidities in percent.
+const RELATIVE_HUMIDITY_RANGE: RangeInclusive<f32> = 0.0..=100.0;
+
+/// The plausible range of barometric pressures in hPa, covering the highest summits up to the
+/// highest recorded pressures at sea level.
+const BAROMETRIC_PRESSURE_RANGE: RangeInclusive<f32> = 300.0..=1100.0;
+
+/// The range of the relative IAQ scale of the Bosch BME680 sensor.
+const IAQ_RANGE: RangeInclusive<u32> = 0..=500;
+
+/// The state of a single reading within an `EnvironmentMetrics` message.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Reading<T> {
+    /// The field holds its default value, as the node doesn't have a sensor for it.
+    Unset,
+    /// The field holds a plausible reading.
+    Valid(T),
+    /// The sensor reported a sentinel or physically implausible value.
+    Invalid,
+}
+
+impl<T> Reading<T> {
+    fn value(self) -> Option<T> {
+        match self {
+            Reading::Valid(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn float_reading(value: f32, range: Option<&RangeInclusive<f32>>) -> Reading<f32> {
+    if value == 0.0 {
+        Reading::Unset
+    } else if !value.is_finite() || range.is_some_and(|range| !range.contains(&value)) {
+        Reading::Invalid
+    } else {
+        Reading::Valid(value)
+    }
+}
+
+impl protobufs::EnvironmentMetrics {
+    /// Returns whether this message contains at least one reading, and no readings that are
+    /// sentinel values or outside of the physically plausible range of the sensors (e.g. a
+    /// temperature of -999 °C, or a relative humidity of 120 %).
+    ///
+    /// The environment metrics protobuf doesn't distinguish unset fields from zero, so fields that are
+    /// zero are treated as unset by this method and the accessor methods of this struct. This includes
+    /// the temperature, as nodes without a temperature sensor report exactly 0 °C.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// `true` if the message can be persisted, `false` if it contains garbage readings or no readings at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut environment_metrics = Box::pin(stream_api.environment_metrics_stream());
+    ///
+    /// while let Some((node_id, metrics)) = environment_metrics.next().await {
+    ///     if metrics.is_valid() {
+    ///         logger.record(node_id, metrics.temperature(), metrics.relative_humidity());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn is_valid(&self) -> bool {
+        let float_readings = [
+            self.temperature_reading(),
+            self.relative_humidity_reading(),
+            self.barometric_pressure_reading(),
+            self.gas_resistance_reading(),
+            self.voltage_reading(),
+            self.current_reading(),
+        ];
+
+        let has_invalid =
+            float_readings.contains(&Reading::Invalid) || self.iaq_reading() == Reading::Invalid;
+        let has_valid = float_readings
+            .iter()
+            .any(|r| matches!(r, Reading::Valid(_)))
+            || matches!(self.iaq_reading(), Reading::Valid(_));
+
+        has_valid && !has_invalid
+    }
+
+    /// Returns the measured temperature in °C, or `None` if it is unset or outside of the plausible
+    /// range of the sensors.
+    pub fn temperature(&self) -> Option<f32> {
+        self.temperature_reading().value()
+    }
+
+    /// Returns the measured relative humidity in percent, or `None` if it is unset or outside of 0 to 100 %.
+    pub fn relative_humidity(&self) -> Option<f32> {
+        self.relative_humidity_reading().value()
+    }
+
+    /// Returns the measured barometric pressure in hPa, or `None` if it is unset or implausible.
+    pub fn barometric_pressure(&self) -> Option<f32> {
+        self.barometric_pressure_reading().value()
+    }
+
+    /// Returns the measured gas resistance in MOhm, or `None` if it is unset or negative.
+    pub fn gas_resistance(&self) -> Option<f32> {
+        self.gas_resistance_reading().value()
+    }
+
+    /// Returns the measured voltage, or `None` if it is unset or negative.
+    pub fn voltage(&self) -> Option<f32> {
+        self.voltage_reading().value()
+    }
+
+    /// Returns the measured current, or `None` if it is unset or not finite.
+    pub fn current(&self) -> Option<f32> {
+        self.current_reading().value()
+    }
+
+    /// Returns the measured IAQ value, or `None` if it is unset or outside of the 0 to 500 scale.
+    pub fn iaq(&self) -> Option<u32> {
+        self.iaq_reading().value()
+    }
+
+    fn temperature_reading(&self) -> Reading<f32> {
+        float_reading(self.temperature, Some(&TEMPERATURE_RANGE))
+    }
+
+    fn relative_humidity_reading(&self) -> Reading<f32> {
+        float_reading(self.relative_humidity, Some(&RELATIVE_HUMIDITY_RANGE))
+    }
+
+    fn barometric_pressure_reading(&self) -> Reading<f32> {
+        float_reading(self.barometric_pressure, Some(&BAROMETRIC_PRESSURE_RANGE))
+    }
+
+    fn gas_resistance_reading(&self) -> Reading<f32> {
+        float_reading(self.gas_resistance, Some(&(0.0..=f32::MAX)))
+    }
+
+    fn voltage_reading(&self) -> Reading<f32> {
+        float_reading(self.voltage, Some(&(0.0..=f32::MAX)))
+    }
+
+    fn current_reading(&self) -> Reading<f32> {
+        float_reading(self.current, None)
+    }
+
+    fn iaq_reading(&self) -> Reading<u32> {
+        match self.iaq {
+            0 => Reading::Unset,
+            iaq if IAQ_RANGE.contains(&iaq) => Reading::Valid(iaq),
+            _ => Reading::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protobufs;
+
+    #[test]
+    fn treats_sentinel_and_zero_readings_as_unset() {
+        let metrics = protobufs::EnvironmentMetrics {
+            temperature: 21.5,
+            relative_humidity: 45.5,
+            barometric_pressure: 0.0,
+            iaq: 120,
+            ..Default::default()
+        };
+
+        assert!(metrics.is_valid());
+        assert_eq!(metrics.temperature(), Some(21.5));
+        assert_eq!(metrics.voltage(), None);
+        assert_eq!(metrics.relative_humidity(), Some(45.5));
+        assert_eq!(metrics.barometric_pressure(), None);
+        assert_eq!(metrics.iaq(), Some(120));
+
+        let garbage = protobufs::EnvironmentMetrics {
+            temperature: -999.0,
+            relative_humidity: 45.5,
+            ..Default::default()
+        };
+
+        assert!(!garbage.is_valid());
+        assert_eq!(garbage.temperature(), None);
+
+        let humidity_out_of_range = protobufs::EnvironmentMetrics {
+            temperature: 21.0,
+            relative_humidity: 120.0,
+            ..Default::default()
+        };
+
+        assert!(!humidity_out_of_range.is_valid());
+        assert!(!protobufs::EnvironmentMetrics::default().is_valid());
+        assert!(!protobufs::EnvironmentMetrics {
+            temperature: f32::NAN,
+            ..Default::default()
+        }
+        .is_valid());
+        assert!(!protobufs::EnvironmentMetrics {
+            iaq: 9999,
+            ..Default::default()
+        }
+        .is_valid());
+    }
+}
@@ -0,0 +1,871 @@
+use crate::errors_internal::Error;
+use crate::protobufs;
+
+/// The character set states of the decoder, which are also the indices of the horizontal codes.
+const ALPHA: usize = 0;
+const SYM: usize = 1;
+const NUM: usize = 2;
+const DICT: usize = 3;
+const DELTA: usize = 4;
+
+/// The horizontal codes, which switch between character sets, and their lengths in bits.
+const HCODES: [u8; 5] = [0x00, 0x40, 0x80, 0xC0, 0xE0];
+const HCODE_LENS: [u8; 5] = [2, 2, 2, 3, 3];
+
+/// The vertical codes, which select a character within the current character set, and their lengths in bits.
+const VCODES: [u8; 28] = [
+    0x00, 0x40, 0x60, 0x80, 0x90, 0xA0, 0xB0, 0xC0, 0xD0, 0xD8, 0xE0, 0xE4, 0xE8, 0xEC, 0xEE, 0xF0,
+    0xF2, 0xF4, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF,
+];
+const VCODE_LENS: [u8; 28] = [
+    2, 3, 3, 4, 4, 4, 4, 4, 5, 5, 6, 6, 6, 7, 7, 7, 7, 7, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+];
+
+/// The alpha, symbol, and number character sets. A zero entry is either a switch code or a special
+/// code, e.g. a frequent sequence or the terminator.
+const SETS: [[u8; 28]; 3] = [
+    *b"\0 etaoinsrlcdhupmbgwfyvkqjxz",
+    *b"\"{}_<>:\n\0[]\\;'\t@*&?!^|\r~`\0\0\0",
+    *b"\0,.01925-/34678() =+$%#\0\0\0\0\0",
+];
+
+/// Frequently occurring sequences, selected by special codes within the symbol and number sets.
+const FREQ_SEQ: [&str; 6] = ["\": \"", "\": ", "</", "=\"", "\":\"", "://"];
+
+/// Templates for dates, times, and phone numbers, where `t`, `o`, `r`, and `f` are placeholders
+/// for digits encoded in 2, 1, 3, and 4 bits respectively.
+const TEMPLATES: [&str; 4] = [
+    "tfff-of-tfTtf:rf:rf.fffZ",
+    "tfff-of-tf",
+    "(fff) fff-ffff",
+    "tf:rf:rf",
+];
+
+/// The bit lengths of counts, and the values added to counts of each length.
+const COUNT_BIT_LENS: [u8; 5] = [2, 4, 7, 11, 16];
+const COUNT_ADDER: [u32; 5] = [4, 20, 148, 2196, 67732];
+
+/// The bit lengths of unicode code point deltas, and the values added to deltas of each length.
+const UNI_BIT_LENS: [u8; 5] = [6, 12, 14, 16, 21];
+const UNI_ADDER: [i32; 5] = [0, 64, 4160, 20544, 86080];
+
+/// The minimum length of a repeated sequence.
+const NICE_LEN: usize = 5;
+
+/// A value decoded from a unicode code point position.
+enum Unicode {
+    Delta(i32),
+    Special(usize),
+    End,
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_no: usize,
+    len: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            bit_no: 0,
+            len: data.len() * 8,
+        }
+    }
+
+    fn has_bits(&self) -> bool {
+        self.bit_no < self.len
+    }
+
+    /// Reads the next 8 bits without consuming them, padding with set bits past the end of the data.
+    fn peek_byte(&self) -> u8 {
+        let byte_pos = self.bit_no / 8;
+        let first = self.data.get(byte_pos).copied().unwrap_or(0xFF) as u16;
+        let second = self.data.get(byte_pos + 1).copied().unwrap_or(0xFF) as u16;
+
+        (((first << 8) | second) << (self.bit_no % 8) >> 8) as u8
+    }
+
+    fn bit(&self, bit_no: usize) -> u32 {
+        u32::from(self.data[bit_no / 8] >> (7 - bit_no % 8) & 1)
+    }
+
+    fn read_vcode(&mut self) -> Option<usize> {
+        if !self.has_bits() {
+            return None;
+        }
+
+        let code = self.peek_byte();
+        let index =
+            (0..VCODES.len()).find(|&i| code & (0xFF << (8 - VCODE_LENS[i])) == VCODES[i])?;
+
+        self.bit_no += VCODE_LENS[index] as usize;
+
+        match self.bit_no > self.len {
+            true => None,
+            false => Some(index),
+        }
+    }
+
+    fn read_hcode(&mut self) -> Option<usize> {
+        if !self.has_bits() {
+            return None;
+        }
+
+        let code = self.peek_byte();
+        let index =
+            (0..HCODES.len()).find(|&i| code & (0xFF << (8 - HCODE_LENS[i])) == HCODES[i])?;
+
+        self.bit_no += HCODE_LENS[index] as usize;
+
+        Some(index)
+    }
+
+    /// Reads a unary code of up to `limit` set bits, terminated by an unset bit.
+    fn read_step_code(&mut self, limit: usize) -> Option<usize> {
+        let mut index = 0;
+
+        while self.has_bits() && self.bit(self.bit_no) == 1 {
+            index += 1;
+            self.bit_no += 1;
+
+            if index == limit {
+                return Some(index);
+            }
+        }
+
+        if !self.has_bits() {
+            return None;
+        }
+
+        self.bit_no += 1;
+
+        Some(index)
+    }
+
+    /// Reads a big-endian number of `count` bits, without consuming the bits.
+    fn peek_number(&self, count: u8) -> Option<u32> {
+        let end = self.bit_no + count as usize;
+
+        if end > self.len {
+            return None;
+        }
+
+        Some((self.bit_no..end).fold(0, |number, bit_no| (number << 1) | self.bit(bit_no)))
+    }
+
+    fn read_number(&mut self, count: u8) -> Option<u32> {
+        let number = self.peek_number(count)?;
+        self.bit_no += count as usize;
+
+        Some(number)
+    }
+
+    fn read_count(&mut self) -> Option<usize> {
+        let index = self.read_step_code(4)?;
+        let adder = match index {
+            0 => 0,
+            _ => COUNT_ADDER[index - 1],
+        };
+
+        Some((self.read_number(COUNT_BIT_LENS[index])? + adder) as usize)
+    }
+
+    fn read_unicode(&mut self) -> Unicode {
+        let index = match self.read_step_code(5) {
+            Some(5) => {
+                return match self.read_step_code(4) {
+                    Some(special) => Unicode::Special(special),
+                    None => Unicode::End,
+                }
+            }
+            Some(index) => index,
+            None => return Unicode::End,
+        };
+
+        let is_negative = self.has_bits() && self.bit(self.bit_no) == 1;
+        self.bit_no += 1;
+
+        match self.read_number(UNI_BIT_LENS[index]) {
+            Some(delta) => {
+                let delta = delta as i32 + UNI_ADDER[index];
+                Unicode::Delta(if is_negative { -delta } else { delta })
+            }
+            None => Unicode::End,
+        }
+    }
+}
+
+/// Decompresses the payload of a `TextMessageCompressedApp` packet, which was compressed by the
+/// firmware using the default preset of the Unishox2 compression scheme.
+///
+/// Compression is an optimization of the firmware, which is invisible to app developers: radios
+/// decompress these payloads before forwarding them to connected clients, and `TextMessage::from_mesh_packet`
+/// decompresses any compressed text that does reach the client, e.g. via MQTT. As such, this function
+/// only needs to be called directly when handling raw packets.
+///
+/// # Arguments
+///
+/// * `compressed` - The compressed payload of a `TextMessageCompressedApp` packet.
+///
+/// # Returns
+///
+/// The decompressed text of the message.
+///
+/// # Examples
+///
+/// ```
+/// if data.portnum == protobufs::PortNum::TextMessageCompressedApp as i32 {
+///     println!("Received text: {}", decompress_text(&data.payload)?);
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::InvalidCompressedText` if the payload refers to text before the start of the
+/// message, or if the decompressed text is not valid UTF-8.
+///
+/// # Panics
+///
+/// None
+///
+pub fn decompress_text(compressed: &[u8]) -> Result<String, Error> {
+    let text = decompress_bytes(compressed)?;
+
+    String::from_utf8(text).map_err(|e| Error::InvalidCompressedText {
+        description: format!("Decompressed text is not valid UTF-8: {e}"),
+    })
+}
+
+/// Decompresses a Unishox2-compressed payload into raw bytes, which are usually, but not necessarily, valid UTF-8.
+pub(crate) fn decompress_bytes(compressed: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader::new(compressed);
+    let mut out = Vec::with_capacity(compressed.len() * 2);
+
+    let mut dstate = ALPHA;
+    let mut h = ALPHA;
+    let mut is_all_upper = false;
+    let mut prev_uni = 0;
+
+    while reader.has_bits() {
+        if dstate == DELTA || h == DELTA {
+            if dstate != DELTA {
+                h = dstate;
+            }
+
+            match reader.read_unicode() {
+                Unicode::End => break,
+                Unicode::Special(0) => {
+                    out.push(b' ');
+                    continue;
+                }
+                Unicode::Special(1) => match reader.read_hcode() {
+                    None => break,
+                    Some(next) if next == DELTA || next == ALPHA => {
+                        h = next;
+                        dstate = next;
+                        continue;
+                    }
+                    Some(DICT) => {
+                        decode_repeat(&mut reader, &mut out)?;
+                        h = dstate;
+                        continue;
+                    }
+                    Some(next) => h = next,
+                },
+                Unicode::Special(2) => out.push(b','),
+                Unicode::Special(3) => out.push(b'.'),
+                Unicode::Special(4) => out.push(b'\n'),
+                Unicode::Special(_) => (),
+                Unicode::Delta(delta) => {
+                    prev_uni += delta;
+                    push_code_point(&mut out, prev_uni)?;
+                }
+            }
+
+            if dstate == DELTA && h == DELTA {
+                continue;
+            }
+        } else {
+            h = dstate;
+        }
+
+        let mut is_upper = is_all_upper;
+
+        let Some(mut v) = reader.read_vcode() else {
+            break;
+        };
+
+        if v == 0 && h != SYM {
+            if !reader.has_bits() {
+                break;
+            }
+
+            if h != NUM || dstate != DELTA {
+                match reader.read_hcode() {
+                    Some(next) if reader.has_bits() => h = next,
+                    _ => break,
+                }
+            }
+
+            match h {
+                ALPHA if dstate == ALPHA => {
+                    if is_all_upper {
+                        is_all_upper = false;
+                        continue;
+                    }
+
+                    let Some(next) = reader.read_vcode() else {
+                        break;
+                    };
+                    v = next;
+
+                    if v == 0 {
+                        let Some(next) = reader.read_vcode() else {
+                            break;
+                        };
+                        v = next;
+
+                        if v == 0 {
+                            is_all_upper = true;
+                            continue;
+                        }
+                    }
+
+                    is_upper = true;
+                }
+                ALPHA => {
+                    dstate = ALPHA;
+                    continue;
+                }
+                DICT => {
+                    decode_repeat(&mut reader, &mut out)?;
+                    continue;
+                }
+                DELTA => continue,
+                _ => {
+                    if h != NUM || dstate != DELTA {
+                        let Some(next) = reader.read_vcode() else {
+                            break;
+                        };
+                        v = next;
+                    }
+
+                    if h == NUM && v == 0 {
+                        if decode_template_or_hex(&mut reader, &mut out).is_none() {
+                            break;
+                        }
+
+                        if dstate == DELTA {
+                            h = DELTA;
+                        }
+
+                        continue;
+                    }
+                }
+            }
+        }
+
+        if is_upper && v == 1 {
+            // An upper case space switches to continuous unicode delta coding
+            h = DELTA;
+            dstate = DELTA;
+            continue;
+        }
+
+        let mut c = SETS.get(h).map_or(0, |set| set[v]);
+
+        if c.is_ascii_lowercase() {
+            dstate = ALPHA;
+
+            if is_upper {
+                c = c.to_ascii_uppercase();
+            }
+        } else if c.is_ascii_digit() {
+            dstate = NUM;
+        } else if c == 0 {
+            match (h, v) {
+                (SYM, 8) => out.extend_from_slice(b"\r\n"),
+                (NUM, 26) => {
+                    let Some(count) = reader.read_count() else {
+                        break;
+                    };
+                    let Some(&last) = out.last() else {
+                        return Err(Error::InvalidCompressedText {
+                            description: "Repeat code found at the start of the text".to_string(),
+                        });
+                    };
+
+                    out.extend(std::iter::repeat_n(last, count + 4));
+                }
+                (SYM, 25..) => out.extend_from_slice(FREQ_SEQ[v - 25].as_bytes()),
+                (NUM, 23..=25) => out.extend_from_slice(FREQ_SEQ[v - 20].as_bytes()),
+                // Any other special code terminates the text
+                _ => break,
+            }
+
+            continue;
+        }
+
+        if dstate == DELTA {
+            h = DELTA;
+        }
+
+        out.push(c);
+    }
+
+    Ok(out)
+}
+
+/// Compresses text using the default preset of the Unishox2 compression scheme, e.g. for the payload of a
+/// `TextMessageCompressedApp` packet. The compressed text can be decompressed with the `decompress_text` function.
+///
+/// The encoder only uses the character sets and unicode coding of Unishox2, and doesn't search for repeated
+/// sequences or templates, so the compression ratio is lower than that of the firmware for repetitive text.
+///
+/// # Arguments
+///
+/// * `text` - The text to compress.
+///
+/// # Returns
+///
+/// The compressed text.
+///
+/// # Examples
+///
+/// ```
+/// let compressed = compress_text("Hello mesh");
+/// assert_eq!(decompress_text(&compressed)?, "Hello mesh");
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn compress_text(text: &str) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    let mut state = ALPHA;
+    let mut prev_uni = 0;
+
+    for c in text.chars() {
+        let byte = u8::try_from(c).unwrap_or(0);
+
+        if state == DELTA {
+            match byte {
+                b' ' => writer.write_unicode_special(0),
+                b',' => writer.write_unicode_special(2),
+                b'.' => writer.write_unicode_special(3),
+                b'\n' => writer.write_unicode_special(4),
+                _ if set_index(NUM, byte)
+                    .or_else(|| set_index(SYM, byte))
+                    .is_some()
+                    || set_index(ALPHA, byte.to_ascii_lowercase()).is_some() =>
+                {
+                    // Switch back to the alpha set, before encoding the character below
+                    writer.write_unicode_special(1);
+                    writer.write_hcode(ALPHA);
+                    state = ALPHA;
+                }
+                _ => writer
+                    .write_unicode_delta(c as i32 - std::mem::replace(&mut prev_uni, c as i32)),
+            }
+
+            if state == DELTA {
+                continue;
+            }
+        }
+
+        if let Some(v) = set_index(ALPHA, byte) {
+            if state != ALPHA {
+                writer.switch_to_alpha();
+                state = ALPHA;
+            }
+
+            writer.write_vcode(v);
+        } else if let Some(v) =
+            set_index(ALPHA, byte.to_ascii_lowercase()).filter(|_| byte.is_ascii_uppercase())
+        {
+            if state != ALPHA {
+                writer.switch_to_alpha();
+                state = ALPHA;
+            }
+
+            writer.write_vcode(0);
+            writer.write_hcode(ALPHA);
+            writer.write_vcode(v);
+        } else if let Some(v) = set_index(NUM, byte) {
+            if state != NUM {
+                writer.write_vcode(0);
+                writer.write_hcode(NUM);
+            }
+
+            writer.write_vcode(v);
+
+            if byte.is_ascii_digit() {
+                state = NUM;
+            }
+        } else if let Some(v) = set_index(SYM, byte) {
+            writer.write_vcode(0);
+            writer.write_hcode(SYM);
+            writer.write_vcode(v);
+        } else {
+            // An upper case space switches to continuous unicode delta coding
+            if state != ALPHA {
+                writer.switch_to_alpha();
+            }
+
+            writer.write_vcode(0);
+            writer.write_hcode(ALPHA);
+            writer.write_vcode(1);
+            writer.write_unicode_delta(c as i32 - std::mem::replace(&mut prev_uni, c as i32));
+            state = DELTA;
+        }
+    }
+
+    // Terminate the text with the terminator code of the number set
+    match state {
+        DELTA => {
+            writer.write_unicode_special(1);
+            writer.write_hcode(NUM);
+        }
+        ALPHA => {
+            writer.write_vcode(0);
+            writer.write_hcode(NUM);
+        }
+        _ => (),
+    }
+
+    writer.write_vcode(VCODES.len() - 1);
+
+    writer.into_bytes()
+}
+
+/// Compresses the payload of a packet into a `Compressed` message, for bandwidth-constrained applications
+/// that exchange compressed payloads on a private port. The payload is compressed with the `compress_text`
+/// function, and can be restored with the `decompress_payload` function.
+///
+/// # Arguments
+///
+/// * `portnum` - The port the payload is intended for.
+/// * `data` - The payload to compress, which must be valid UTF-8.
+///
+/// # Returns
+///
+/// A `Compressed` message containing the port and the compressed payload.
+///
+/// # Examples
+///
+/// ```
+/// let compressed = compress_payload(protobufs::PortNum::PrivateApp, b"temperature=21.5")?;
+/// stream_api.send_private(&mut router, 256, compressed.encode_to_vec(), PacketDestination::Broadcast, 0.into()).await?;
+/// ```
+///
+/// # Errors
+///
+/// Fails with `Error::InvalidCompressionInput` if the payload is not valid UTF-8, as Unishox2 only
+/// compresses text.
+///
+/// # Panics
+///
+/// None
+///
+pub fn compress_payload(
+    portnum: protobufs::PortNum,
+    data: &[u8],
+) -> Result<protobufs::Compressed, Error> {
+    let text = std::str::from_utf8(data).map_err(|e| Error::InvalidCompressionInput {
+        description: format!("Payload is not valid UTF-8: {e}"),
+    })?;
+
+    Ok(protobufs::Compressed {
+        portnum: portnum as i32,
+        data: compress_text(text),
+    })
+}
+
+/// Decompresses the payload of a `Compressed` message, as built by the `compress_payload` function. The
+/// port the payload is intended for is given by the `portnum` field of the message.
+///
+/// # Errors
+///
+/// Fails with `Error::InvalidCompressedText` if the payload refers to data before the start of the payload.
+pub fn decompress_payload(compressed: &protobufs::Compressed) -> Result<Vec<u8>, Error> {
+    decompress_bytes(&compressed.data)
+}
+
+/// Returns the index of a character within the specified character set, excluding the special codes.
+fn set_index(set: usize, c: u8) -> Option<usize> {
+    match c {
+        0 => None,
+        c => SETS[set].iter().position(|&set_c| set_c == c),
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    bit_no: usize,
+}
+
+impl BitWriter {
+    /// Writes the `count` most significant bits of the passed code.
+    fn write_code(&mut self, code: u8, count: u8) {
+        self.write_number(u32::from(code >> (8 - count)), count);
+    }
+
+    /// Writes a big-endian number of `count` bits.
+    fn write_number(&mut self, number: u32, count: u8) {
+        for i in (0..count).rev() {
+            if self.bit_no.is_multiple_of(8) {
+                self.data.push(0);
+            }
+
+            if number >> i & 1 == 1 {
+                self.data[self.bit_no / 8] |= 0x80 >> (self.bit_no % 8);
+            }
+
+            self.bit_no += 1;
+        }
+    }
+
+    fn write_vcode(&mut self, index: usize) {
+        self.write_code(VCODES[index], VCODE_LENS[index]);
+    }
+
+    fn write_hcode(&mut self, index: usize) {
+        self.write_code(HCODES[index], HCODE_LENS[index]);
+    }
+
+    /// Switches from the number set back to the alpha set.
+    fn switch_to_alpha(&mut self) {
+        self.write_vcode(0);
+        self.write_hcode(ALPHA);
+    }
+
+    /// Writes a unary code of `index` set bits, terminated by an unset bit unless `index` reaches `limit`.
+    fn write_step_code(&mut self, index: usize, limit: usize) {
+        for _ in 0..index {
+            self.write_number(1, 1);
+        }
+
+        if index < limit {
+            self.write_number(0, 1);
+        }
+    }
+
+    fn write_unicode_delta(&mut self, delta: i32) {
+        let magnitude = delta.abs();
+        let index = (0..UNI_BIT_LENS.len())
+            .find(|&i| magnitude - UNI_ADDER[i] < 1 << UNI_BIT_LENS[i])
+            .unwrap_or(UNI_BIT_LENS.len() - 1);
+
+        self.write_step_code(index, 5);
+        self.write_number(u32::from(delta < 0), 1);
+        self.write_number((magnitude - UNI_ADDER[index]) as u32, UNI_BIT_LENS[index]);
+    }
+
+    fn write_unicode_special(&mut self, special: usize) {
+        self.write_step_code(5, 5);
+        self.write_step_code(special, 4);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Copies a previously decoded sequence to the end of the output.
+fn decode_repeat(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), Error> {
+    let (Some(length), Some(distance)) = (reader.read_count(), reader.read_count()) else {
+        return Ok(());
+    };
+
+    let length = length + NICE_LEN;
+    let distance = distance + NICE_LEN - 1;
+
+    let Some(start) = out.len().checked_sub(distance) else {
+        return Err(Error::InvalidCompressedText {
+            description: format!(
+                "Repeated sequence starts {distance} bytes back, but only {} bytes were decoded",
+                out.len()
+            ),
+        });
+    };
+
+    for i in start..start + length {
+        out.push(out[i]);
+    }
+
+    Ok(())
+}
+
+/// Decodes a template, a hexadecimal sequence, a UUID, or a binary sequence, returning `None` if
+/// the data ends within the sequence.
+fn decode_template_or_hex(reader: &mut BitReader, out: &mut Vec<u8>) -> Option<()> {
+    let index = reader.read_step_code(5)?;
+
+    match index {
+        0 => {
+            let template = TEMPLATES.get(reader.read_step_code(4)?)?;
+            let omitted = reader.read_count()?;
+            let length = template.len().checked_sub(omitted)?;
+
+            for c in template.bytes().take(length) {
+                let bits = match c {
+                    b'f' => 4,
+                    b'r' => 3,
+                    b't' => 2,
+                    b'o' => 1,
+                    _ => {
+                        out.push(c);
+                        continue;
+                    }
+                };
+
+                out.push(hex_char(reader.read_number(bits)?, false));
+            }
+        }
+        5 => {
+            let count = reader.read_count()?;
+
+            if count == 0 {
+                return None;
+            }
+
+            for _ in 0..count {
+                out.push(reader.read_number(8)? as u8);
+            }
+        }
+        _ => {
+            let is_uuid = index == 2 || index == 4;
+            let count = match is_uuid {
+                true => 32,
+                false => reader.read_count()?,
+            };
+
+            if count == 0 {
+                return None;
+            }
+
+            for remaining in (1..=count).rev() {
+                out.push(hex_char(reader.read_number(4)?, index >= 3));
+
+                if is_uuid && matches!(remaining, 25 | 21 | 17 | 13) {
+                    out.push(b'-');
+                }
+            }
+        }
+    }
+
+    Some(())
+}
+
+fn hex_char(nibble: u32, is_upper: bool) -> u8 {
+    match (nibble as u8, is_upper) {
+        (n @ 0..=9, _) => b'0' + n,
+        (n, false) => b'a' + n - 10,
+        (n, true) => b'A' + n - 10,
+    }
+}
+
+fn push_code_point(out: &mut Vec<u8>, code_point: i32) -> Result<(), Error> {
+    let c = u32::try_from(code_point)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| Error::InvalidCompressedText {
+            description: format!("Invalid unicode code point {code_point}"),
+        })?;
+
+    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs a string of '0' and '1' characters into bytes, padding the last byte with the last bit.
+    ///
+    /// The bit strings of the tests are assembled by hand from the code tables of the Unishox2 default
+    /// preset, rather than taken from the output of the reference C implementation. They verify that
+    /// the tables are applied as specified, but not that every encoder choice matches the firmware.
+    fn pack_bits(bits: &str) -> Vec<u8> {
+        let mut bits: Vec<u8> = bits
+            .bytes()
+            .filter(|b| *b != b' ')
+            .map(|b| b - b'0')
+            .collect();
+        let last_bit = bits.last().copied().unwrap_or(0);
+
+        while !bits.len().is_multiple_of(8) {
+            bits.push(last_bit);
+        }
+
+        bits.chunks(8)
+            .map(|chunk| chunk.iter().fold(0, |byte, bit| (byte << 1) | bit))
+            .collect()
+    }
+
+    #[test]
+    fn decompresses_lower_case_text() {
+        // h e l l o, followed by a switch to the number set and the terminator
+        let compressed = pack_bits("1110110 011 111000 111000 1010 00 10 11111111");
+
+        assert_eq!(decompress_text(&compressed).unwrap(), "hello");
+    }
+
+    #[test]
+    fn decompresses_mixed_case_text_and_numbers() {
+        // Upper case h, i, space, a switch to the number set for 4 and 2, and the terminator
+        let compressed = pack_bits("00 00 1110110 1011 010 00 10 111001 1011 11111111");
+
+        assert_eq!(decompress_text(&compressed).unwrap(), "Hi 42");
+    }
+
+    #[test]
+    fn compresses_known_answer_vectors() {
+        // The same codes as decoded above, where the last byte of "hello" is padded with unset bits
+        assert_eq!(
+            compress_text("hello"),
+            pack_bits("1110110 011 111000 111000 1010 00 10 11111111 00")
+        );
+        assert_eq!(
+            compress_text("Hi 42"),
+            pack_bits("00 00 1110110 1011 010 00 10 111001 1011 11111111")
+        );
+    }
+
+    #[test]
+    fn round_trips_compressed_text() {
+        for text in [
+            "",
+            "hello",
+            "Hi 42, see you at 10:30!",
+            "Temp=21.5C\r\nHumidity: 40%",
+            "Grüße aus Köln, 東京 and back",
+            "\u{b}\u{c} {\"key\": [1, 2]}",
+        ] {
+            assert_eq!(decompress_text(&compress_text(text)).unwrap(), text);
+        }
+
+        assert!(compress_text("the quick brown fox").len() < "the quick brown fox".len());
+    }
+
+    #[test]
+    fn compresses_payloads() {
+        let compressed = compress_payload(protobufs::PortNum::PrivateApp, b"status ok").unwrap();
+
+        assert_eq!(compressed.portnum(), protobufs::PortNum::PrivateApp);
+        assert_eq!(decompress_payload(&compressed).unwrap(), b"status ok");
+        assert!(matches!(
+            compress_payload(protobufs::PortNum::PrivateApp, &[0xff, 0xfe]),
+            Err(Error::InvalidCompressionInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::protobufs;
+
+impl protobufs::Waypoint {
+    /// Returns the icon of the waypoint as a `char`.
+    ///
+    /// The `icon` field holds the Unicode code point of the emoji, e.g. `0x1f3e0` for 🏠, rather than
+    /// its UTF-8 encoding. Icons that are not valid code points, e.g. UTF-8 bytes packed into the field
+    /// by a misbehaving client, are rejected rather than being misinterpreted.
+    ///
+    /// # Arguments
+    ///
+    /// None
+    ///
+    /// # Returns
+    ///
+    /// The icon of the waypoint, or `None` if no icon is set or the icon is not a valid code point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let icon = waypoint.icon_char().unwrap_or('📍');
+    /// println!("{icon} {}", waypoint.name);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// None
+    ///
+    /// # Panics
+    ///
+    /// None
+    ///
+    pub fn icon_char(&self) -> Option<char> {
+        match self.icon {
+            0 => None,
+            icon => char::from_u32(icon),
+        }
+    }
+
+    /// Sets the icon of the waypoint to the Unicode code point of the specified emoji.
+    pub fn set_icon_char(&mut self, icon: char) {
+        self.icon = u32::from(icon);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_code_points_for_waypoint_icons() {
+        // Icon as sent by the Android app for a waypoint marked with 🏠
+        let mut waypoint = protobufs::Waypoint {
+            icon: 0x0001_f3e0,
+            ..Default::default()
+        };
+        assert_eq!(waypoint.icon_char(), Some('🏠'));
+
+        waypoint.set_icon_char('📍');
+        assert_eq!(waypoint.icon, 0x0001_f4cd);
+
+        // The UTF-8 encoding of 🏠 packed into the field is not a valid code point
+        waypoint.icon = u32::from_be_bytes([0xf0, 0x9f, 0x8f, 0xa0]);
+        assert_eq!(waypoint.icon_char(), None);
+
+        waypoint.icon = 0;
+        assert_eq!(waypoint.icon_char(), None);
+    }
+}
@@ -1,7 +1,12 @@
 pub(crate) mod connections;
+pub(crate) mod crypto_internal;
 pub(crate) mod errors_internal;
+pub(crate) mod helpers_internal;
 pub(crate) mod utils_internal;
 
+#[cfg(feature = "ts-gen")]
+pub(crate) mod ts_internal;
+
 /// A re-export of the `prost::Message` trait, which is required to call the `encode`
 /// and `decode` methods on protocol buffer messages.
 pub use prost::Message;
@@ -25,12 +30,139 @@ pub use prost::Message;
 /// instance of the `ConnectedStreamApi` struct. This resulting instance will then have access
 /// to the full set of API sender methods.
 ///
-/// To disconnect from the radio, the user can call the `disconnect` method at any time.
+/// To disconnect from the radio, the user can call the `disconnect` method at any time. The `drain`
+/// method collects the packets still queued within the `PacketReceiver` beforehand, so that they
+/// are not lost.
+///
+/// The `StreamApi::connect_readonly` method connects in a passive mode for observing a radio
+/// without disturbing it. It writes at most the `WantConfigId` packet of the handshake and no
+/// heartbeats, and returns a `ReadOnlyStreamApi` struct that exposes no methods that send packets.
+///
+/// The `StreamApi::connect_with_retry` method returns a `ReconnectingStreamApi` struct, which
+/// transparently re-establishes the connection with an exponential backoff, configured by a
+/// `ReconnectConfig` struct, whenever the stream to the radio is lost. Each new connection repeats
+/// the `configure` handshake, after which a `ConnectionEvent::Reconnected` event is emitted.
+///
+/// The `StreamApi::with_max_buffer_size` method bounds the memory used to buffer incomplete packets
+/// received from the radio, which defaults to `DEFAULT_MAX_BUFFER_SIZE` bytes. The
+/// `StreamApi::with_log_buffer_size` method sets how many of the log records reported by the radio
+/// are retained for the `recent_logs` method, which defaults to `DEFAULT_LOG_BUFFER_SIZE` records.
+///
+/// The `ConfigSync` struct can be fed the packets received during the `configure` handshake to
+/// track which configuration sections are still outstanding, e.g. to display sync progress. The
+/// `ConfigHandle` returned by the `config_handle` method waits for the handshake to complete, and
+/// can cancel a stalled handshake, which can then be retried without reconnecting using the
+/// `reconfigure` method. The `refresh_config` method re-requests the configuration and node
+/// database, and returns a `DeviceSnapshot` of them. The `snapshot` method returns a
+/// `DeviceSnapshot` of the current state without contacting the radio, which can be serialized if
+/// the `serde` feature is enabled. The `StreamApi::connect_with_snapshot` method seeds a new
+/// connection with a saved snapshot, so that known nodes are available before the handshake
+/// completes. The `wait_for_config` method waits for the handshake with a timeout, and fails with
+/// `Error::NotAMeshtasticDevice` if the device never sent a valid Meshtastic packet, e.g. because
+/// the wrong serial port was selected.
+///
+/// The `position_stream`, `telemetry_stream`, `paxcount_stream`, `map_report_stream`,
+/// `text_message_stream`, and `waypoint_stream` methods of the `ConnectedStreamApi` struct return
+/// typed streams of decoded packets, which are independent of the decoded packet channel returned
+/// by the `connect` method. The `events` method returns a single stream of all received packets as
+/// `MeshEvent` enums.
+///
+/// The `QueueFullBehavior` enum configures whether sending mesh packets waits for, or fails on, a
+/// full outgoing packet queue on the radio, as reported by the radio's `QueueStatus` packets. The
+/// `set_rate_limit` method of the `ConnectedStreamApi` struct limits the number of mesh packets
+/// sent per minute, to avoid accidentally flooding the mesh.
+///
+/// The `health` method of the `ConnectedStreamApi` struct returns a watch channel of
+/// `ConnectionHealth` structs, which report when data was last received from the radio and the
+/// current `ConnectionState`. The `connection_events` method returns a separate channel of
+/// `ConnectionEvent` enums, which report lifecycle events such as the completion of the
+/// configuration handshake, reboots of the radio, and disconnection. A `ConfigChanged` event is
+/// emitted when the radio reports a changed configuration after the handshake, which is reflected
+/// by the `local_config`, `local_module_config`, and `channels` methods. The `is_managed` method
+/// reports whether the device configuration marks the radio as managed by a mesh administrator. A
+/// `ClockSkewDetected` event is emitted when the packet timestamps of the radio differ from the
+/// host time by more than `MAX_CLOCK_SKEW_SECS` seconds.
+///
+/// The `begin_edit` method of the `ConnectedStreamApi` struct returns an `EditTransaction` guard,
+/// which buffers settings edits and applies them within a single
+/// `BeginEditSettings`/`CommitEditSettings` transaction when committed. Dropping the guard without
+/// committing discards the edits without sending anything to the radio. The edits are sent to the
+/// local node on the channel returned by the `admin_channel` method, which is the channel named
+/// `admin` if one exists.
+///
+/// The `StreamApi::with_drop_diagnostics` method opts into reporting the data discarded by the
+/// receive pipeline, e.g. packets that fail to decode or are skipped by a packet stream. The
+/// `dropped_packets` method then returns a channel of `DroppedPacket` structs, each with a
+/// `DropReason` describing why the data was discarded.
+///
+/// The `on_packet` method of the `ConnectedStreamApi` struct registers a callback that is called
+/// with every received packet, for users who prefer callbacks to the `PacketReceiver` channel.
+///
+/// The `my_node_id` method of the `ConnectedStreamApi` struct returns the node id of the connected
+/// radio, once the radio has reported it during the `configure` handshake. The `resolve_user_id`
+/// method looks up the node id of a `User.id` string within the node database, while
+/// `NodeId::from_user_id` and `NodeId::to_user_id` convert between both formats. The `device_time`
+/// method estimates the current time of the radio's clock, and the `set_time` method syncs the
+/// clock of the radio to the host.
+///
+/// The `BlockingStreamApi` struct is a thin, blocking facade over the `StreamApi` struct for simple
+/// scripts that do not want to set up an async runtime. Its `iter` method returns a blocking
+/// iterator over received packets. This struct is only available if the `blocking` feature is
+/// enabled.
+///
+/// The `MeshtasticCodec` struct implements the `tokio_util::codec::Decoder` and `Encoder` traits,
+/// which allows advanced users to wrap a transport in a `Framed` to receive decoded `FromRadio`
+/// packets as a stream and send `ToRadio` packets as a sink, without the `StreamApi` struct. The
+/// `START1`, `START2` and `MAX_PACKET_SIZE` constants define the framing of packets, which can be
+/// overridden for the codec with a `FramingConfig` struct.
 pub mod api {
+    #[cfg(feature = "blocking")]
+    pub use crate::connections::blocking::BlockingStreamApi;
+    pub use crate::connections::codec::MeshtasticCodec;
+    pub use crate::connections::config_sync::ConfigHandle;
+    pub use crate::connections::config_sync::ConfigSync;
+    pub use crate::connections::config_sync::MAX_NUM_CHANNELS;
+    pub use crate::connections::device_snapshot::DeviceSnapshot;
+    pub use crate::connections::diagnostics::DropReason;
+    pub use crate::connections::diagnostics::DroppedPacket;
+    pub use crate::connections::diagnostics::DROPPED_PACKET_CAPACITY;
+    pub use crate::connections::edit_transaction::EditTransaction;
+    pub use crate::connections::health::ConnectionEvent;
+    pub use crate::connections::health::ConnectionHealth;
+    pub use crate::connections::health::ConnectionState;
+    pub use crate::connections::health::CONNECTION_EVENT_CAPACITY;
+    pub use crate::connections::health::MAX_CLOCK_SKEW_SECS;
+    pub use crate::connections::radio_state::QueueFullBehavior;
+    pub use crate::connections::radio_state::DEFAULT_LOG_BUFFER_SIZE;
+    pub use crate::connections::readonly::ReadOnlyStreamApi;
+    pub use crate::connections::reconnecting::ReconnectConfig;
+    pub use crate::connections::reconnecting::ReconnectingStreamApi;
     pub use crate::connections::stream_api::state;
     pub use crate::connections::stream_api::ConnectedStreamApi;
     pub use crate::connections::stream_api::StreamApi;
     pub use crate::connections::stream_api::StreamHandle;
+    pub use crate::connections::stream_buffer::FramingConfig;
+    pub use crate::connections::stream_buffer::DEFAULT_MAX_BUFFER_SIZE;
+    pub use crate::connections::stream_buffer::MAX_PACKET_SIZE;
+    pub use crate::connections::stream_buffer::START1;
+    pub use crate::connections::stream_buffer::START2;
+}
+
+/// This module contains the functions that implement the channel encryption of Meshtastic.
+///
+/// The `decrypt_mesh_packet` function decrypts the payload of a mesh packet that the radio passed
+/// on encrypted, e.g. a packet received on a channel that is not within the channel table of the
+/// radio, using the PSK of the channel the packet was sent on. Packets are encrypted with AES-CTR,
+/// using the expanded PSK as the key and a nonce derived from the sender and id of the packet, as
+/// implemented by the firmware.
+///
+/// The `encrypt_data` function is the inverse of `decrypt_mesh_packet`, and produces the encrypted
+/// payload the firmware expects for a `Data` message. The `build_encrypted` method of the
+/// `MeshPacketBuilder` struct builds packets with an encrypted payload, which the radio sends
+/// without re-encrypting them.
+pub mod crypto {
+    pub use crate::crypto_internal::decrypt_mesh_packet;
+    pub use crate::crypto_internal::encrypt_data;
 }
 
 /// This module contains the global `Error` type of the library. This enum implements
@@ -55,13 +187,93 @@ pub mod errors {
 ///     node in the mesh. The `u32` value is the node id of the node that the packet should be sent to. This is commonly
 ///     used for direct text messages.
 ///
+/// Destinations can also be parsed from strings using `str::parse`, which accepts the `^local` and
+/// `^all` aliases used by other Meshtastic tooling, as well as node ids such as `!a1b2c3d4`.
+///
 /// The `PacketRouter` trait defines the behavior of a struct that is able to route mesh packets. This trait is used
 /// to allow for the echoing of mesh packets within the `send_mesh_packet` method of the `ConnectedStreamApi` struct.
 ///
 /// The `PacketReceiver` type defines the type of the tokio channel that is used to receive decoded packets from the radio.
 /// This is intended to simplify the complexity of the underlying channel type.
+///
+/// The `AckTracker` struct classifies routing acknowledgements for packets sent with `want_ack`
+/// set, distinguishing implicit acknowledgements (a rebroadcast was overheard) from explicit
+/// acknowledgements and negative acknowledgements. The `ReliableSendConfig` struct configures the
+/// retransmission behavior of the `send_mesh_packet_reliable` method of the `ConnectedStreamApi`
+/// struct. The number of reliable sends awaiting an acknowledgement at once is limited by the
+/// `set_max_in_flight_reliable_sends` method, and defaults to the queue length reported by the
+/// radio, or `DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS`. As that method borrows the connection until
+/// the packet is acknowledged, the `ReliableSender` struct returned by the `reliable_sender` method
+/// allows several tasks to await acknowledgements concurrently, sharing the same limit.
+///
+/// The `MeshPacketBuilder` struct builds outgoing mesh packets, including their priority within the
+/// client-side write queue and the transmit queue of the radio. The
+/// `MeshPacketBuilder::new_private` method builds packets on the private application ports (256 to
+/// 511), as sent by the `send_private` method of the `ConnectedStreamApi` struct.
+///
+/// The `TextMessage` struct represents a text message received from the mesh, as returned by the
+/// `text_message_stream` method of the `ConnectedStreamApi` struct. The `WaypointEvent` enum
+/// distinguishes new or updated waypoints from deleted waypoints, as returned by the
+/// `waypoint_stream` method. The `DetectionEvent` struct represents an alert sent by the detection
+/// sensor module of a node, as returned by the `detection_stream` method.
+///
+/// The `MeshEvent` enum represents a high-level event received from the radio, as returned by the
+/// `events` method of the `ConnectedStreamApi` struct. The `DecodePolicy` enum defines whether
+/// packets that fail to decode are passed through as `MeshEvent::Raw` or skipped. The
+/// `events_with_raw` method additionally pairs each event with the raw bytes of the packet it was
+/// decoded from, for auditing and later re-decoding. The `packets_with_timestamps` and
+/// `events_with_timestamps` methods pair each packet or event with the host time at which it was
+/// received, as the `rx_time` reported by the radio may be missing or inaccurate. The
+/// `events_with_channels` method wraps each event in a `ChannelEvent` along with the
+/// `ReceivedChannel` it arrived on, resolved against the channel table of the radio, so that
+/// packets on unknown channels are not attributed to the primary channel.
+///
+/// The `decoded_payload` function decodes the payload of a single `MeshPacket` into a
+/// `DecodedPayload` enum, based on the port number of the packet. Payloads on unknown ports, e.g.
+/// private application ports, are returned as `DecodedPayload::Raw` rather than being dropped.
+///
+/// The `AdminSession` struct correlates admin responses with outstanding admin requests, resolving
+/// a `PendingAdminResponse` future for each request, as returned by the `send_admin_request` method
+/// of the `ConnectedStreamApi` struct. Results that span several response packets, e.g. a full
+/// channel table, are requested with the `send_admin_requests` method, which returns a
+/// `PendingAdminResponses` future that only resolves once every request of the sequence has been
+/// answered. Admin messages are sent to remote nodes on a shared admin channel with the
+/// `send_admin_to` method, or with the `send_admin_request_to` method to await the node's response.
+/// The `AdminMessage::is_get_request` method reports whether an admin message is a request that
+/// must be sent with `want_response` set.
+///
+/// The `ping` method of the `ConnectedStreamApi` struct measures the round-trip time to a node,
+/// using a packet on the `ReplyApp` port that the node answers with a reply. The `send_traceroute`
+/// method starts a traceroute to a node, returning the packet id that the `request_id` of the
+/// `RouteDiscovery` reply refers to.
+///
+/// The `pending_requests` and `cancel_request` methods of the `ConnectedStreamApi` struct list and
+/// cancel the requests whose responses or acknowledgements are still being awaited. The
+/// `RequestRegistry` struct returned by the `request_registry` method allows requests to be
+/// cancelled while another method awaits them.
 pub mod packet {
+    pub use crate::connections::ack_tracker::AckEvent;
+    pub use crate::connections::ack_tracker::AckStatus;
+    pub use crate::connections::ack_tracker::AckTracker;
+    pub use crate::connections::ack_tracker::ReliableSendConfig;
+    pub use crate::connections::ack_tracker::DEFAULT_MAX_IN_FLIGHT_RELIABLE_SENDS;
+    pub use crate::connections::admin_session::AdminSession;
+    pub use crate::connections::admin_session::PendingAdminResponse;
+    pub use crate::connections::admin_session::PendingAdminResponses;
+    pub use crate::connections::decoded_payload::decoded_payload;
+    pub use crate::connections::decoded_payload::DecodedPayload;
+    pub use crate::connections::events::ChannelEvent;
+    pub use crate::connections::events::DecodePolicy;
+    pub use crate::connections::events::DetectionEvent;
+    pub use crate::connections::events::MeshEvent;
+    pub use crate::connections::events::ReceivedChannel;
+    pub use crate::connections::events::TextMessage;
+    pub use crate::connections::events::WaypointEvent;
     pub use crate::connections::handlers::CLIENT_HEARTBEAT_INTERVAL;
+    pub use crate::connections::mesh_packet_builder::MeshPacketBuilder;
+    pub use crate::connections::reliable_sender::ReliableSender;
+    pub use crate::connections::request_registry::PendingRequest;
+    pub use crate::connections::request_registry::RequestRegistry;
     pub use crate::connections::PacketDestination;
     pub use crate::connections::PacketRouter;
 
@@ -85,6 +297,10 @@ pub mod protobufs {
 /// TypeScript type definition file containing TypeScript types for all members of the
 /// `protobufs` module. This allows for complete type safety when interfacing with a TypeScript
 /// application.
+///
+/// The `export_ts_types` method wraps the `specta` export, and accepts an `EnumExportBehavior`
+/// that controls whether protobuf enums are additionally rendered as string-literal unions
+/// matching their `as_str_name()` values, which is the form used by the Meshtastic web/JS clients.
 #[cfg(feature = "ts-gen")]
 pub mod ts {
     #![allow(non_snake_case)]
@@ -92,28 +308,42 @@ pub mod ts {
     /// A re-export of the `specta` crate, which is used to generate TypeScript type definitions
     /// from the protobuf definitions of the `meshtastic/protobufs` Git submodule.
     pub use specta;
+
+    pub use crate::ts_internal::enum_string_unions;
+    pub use crate::ts_internal::export_ts_types;
+    pub use crate::ts_internal::EnumExportBehavior;
+    pub use crate::ts_internal::ENUM_NAMESPACE;
 }
 
 /// This module exposes utility functions that aren't fundamental to the operation of the
 /// library, but simplify the configuration and usage of member methods.
 ///
-/// The `DEFAULT_DTR_PIN_STATE` and `DEFAULT_RTS_PIN_STATE` constants are used to define the
-/// default pin states of the DTR and RTS pins of the serial connection. The `DEFAULT_SERIAL_BAUD`
-/// constant is used to define the default baud rate of incoming serial connections created by the
-/// `build_serial_stream` method.
+/// The `DEFAULT_DTR_PIN_STATE` and `DEFAULT_RTS_PIN_STATE` constants are used to define the default
+/// pin states of the DTR and RTS pins of the serial connection. The `DEFAULT_SERIAL_BAUD` constant
+/// is used to define the default baud rate of incoming serial connections created by the
+/// `build_serial_stream` method. The `DEFAULT_CONNECT_TIMEOUT` constant defines how long the
+/// `build_tcp_stream` method waits for a connection to open before failing with
+/// `Error::ConnectTimeout`. BLE streams use the `BLE_CONNECT_TIMEOUT` constant instead.
 ///
-/// Additionally, this module exposes helper methods that are used internally to format data packets.
-/// These methods are intended for use by more advanced users.
+/// Additionally, this module exposes helper methods that are used internally to format data
+/// packets. These methods are intended for use by more advanced users. The `split_text` method
+/// splits text into chunks of at most `DATA_PAYLOAD_LEN` bytes, the maximum payload of a single
+/// mesh packet, without splitting characters.
 ///
 /// The `stream` module contains helper methods that are used to build connection stream instances.
 pub mod utils {
+    pub use crate::utils_internal::DEFAULT_CONNECT_TIMEOUT;
     pub use crate::utils_internal::DEFAULT_DTR_PIN_STATE;
     pub use crate::utils_internal::DEFAULT_RTS_PIN_STATE;
     pub use crate::utils_internal::DEFAULT_SERIAL_BAUD;
+    pub use crate::utils_internal::DEFAULT_SIMULATOR_BASE_PORT;
+
+    pub use crate::utils_internal::DATA_PAYLOAD_LEN;
 
     pub use crate::utils_internal::current_epoch_secs_u32;
     pub use crate::utils_internal::format_data_packet;
     pub use crate::utils_internal::generate_rand_id;
+    pub use crate::utils_internal::split_text;
     pub use crate::utils_internal::strip_data_packet_header;
 
     /// This module contains utility functions that are used to build the `Stream` instances
@@ -121,17 +351,157 @@ pub mod utils {
     /// requires that streams implement the `tokio::io::AsyncReadExt` and `tokio::io::AsyncWriteExt`
     /// methods, there are countless ways a user could theoretically connect to a radio.
     ///
-    /// This module exposes the `build_serial_stream` and `build_tcp_stream` methods, which
-    /// simplify the process of initializing a connection stream. The vast majority of users will
-    /// only need to use these two methods to connect to a radio. The `available_serial_ports` method
-    /// can also be used to list all available serial ports on the host machine.
+    /// This module exposes the `build_serial_stream` and `build_tcp_stream` methods, which simplify
+    /// the process of initializing a connection stream. The vast majority of users will only need
+    /// to use these two methods to connect to a radio. The `build_tcp_stream_with_timeout` method
+    /// allows the connection timeout to be tuned independently of the configuration handshake. The
+    /// `available_serial_ports` method can also be used to list all available serial ports on the
+    /// host machine. The `build_simulator_stream` method connects to a node of a Meshtasticator
+    /// simulation. If the `bluetooth-le` feature is enabled, the `ble_discovery_stream` method
+    /// returns a stream of `BleDiscoveryEvent`s reporting the BLE radios that come into and go out
+    /// of range, and the `build_ble_stream` method connects to one of them within
+    /// `BLE_CONNECT_TIMEOUT`, restoring the connection if it stalls for `BLE_STALL_TIMEOUT`. The
+    /// `build_ble_stream_with_timeout` method allows the connection timeout to be tuned.
     pub mod stream {
         pub use crate::utils_internal::available_serial_ports;
         pub use crate::utils_internal::build_serial_stream;
+        pub use crate::utils_internal::build_simulator_stream;
         pub use crate::utils_internal::build_tcp_stream;
+        pub use crate::utils_internal::build_tcp_stream_with_timeout;
+
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::ble_discovery_stream;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::build_ble_stream;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::build_ble_stream_with_timeout;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::BleDevice;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::BleDiscoveryEvent;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::BLE_CONNECT_TIMEOUT;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::BLE_DEVICE_LOST_TIMEOUT;
+        #[cfg(feature = "bluetooth-le")]
+        pub use crate::connections::ble_handler::BLE_STALL_TIMEOUT;
     }
 }
 
+/// This module exposes helpers that simplify working with the protocol buffer messages defined
+/// within the `protobufs` module. Most of these helpers are implemented as methods directly on
+/// the generated protobuf structs, and as such do not need to be imported.
+///
+/// The `ModuleConfigCollector` struct reassembles a `LocalModuleConfig` from the individual
+/// `GetModuleConfigResponse` admin messages sent by the radio. The inverse operation is provided by
+/// the `LocalModuleConfig::into_module_configs` and `LocalModuleConfig::into_admin_messages`
+/// methods. The `LocalConfig::sections` and `LocalModuleConfig::sections` methods iterate over the
+/// populated configuration sections as `ConfigSection` enums, which can be named and converted into
+/// admin messages without matching every variant.
+///
+/// The `routing::Error::description` and `routing::Error::suggestion` methods translate routing
+/// errors, e.g. those reported within a negative acknowledgement, into user-facing messages. The
+/// `QueueStatus::result` method decodes the error code reported by the radio for the last packet it
+/// was asked to enqueue into a `QueueError`.
+///
+/// The `HardwareModel::display_name` and `HardwareModel::capabilities` methods provide
+/// human-friendly hardware model names and a `HwCaps` summary of the hardware, which can be refined
+/// using the `DeviceMetadata` reported by the device. The `NodeInfo::display_name` method returns
+/// the name a node should be displayed with, falling back to its hexadecimal node id. The
+/// `DeviceMetadata::firmware_semver` method parses the firmware version reported by a device, and
+/// the `DeviceCapabilities` struct combines the firmware version with the reported metadata, so
+/// that applications can gate features such as PKI-encrypted direct messages, which are supported
+/// from `PKI_MIN_FIRMWARE_VERSION` onwards. The capabilities are only exposed, and don't change how
+/// the library sends or decodes packets. The `device_capabilities` method of the
+/// `ConnectedStreamApi` struct returns the capabilities of the connected radio. The
+/// `supported_config_types` and `supported_module_config_types` functions return the configuration
+/// sections a device answers requests for, so that configuration syncs don't stall waiting for
+/// sections older firmware doesn't know. The `ConfigSync` struct uses them to narrow down the
+/// expected sections once the radio reports its metadata.
+///
+/// The `MeshPacket::from_lora_header` method parses packets captured directly from the LoRa radio
+/// layer, which begin with a packed header of `LORA_HEADER_LEN` bytes rather than being wrapped in
+/// a protobuf. The `MeshPacket::to_lora_bytes` method performs the inverse operation.
+///
+/// The `MeshPacket::to_simulator_packet` and `MeshPacket::from_simulator_packet` methods wrap and
+/// unwrap the `SimulatorApp` packets used to exchange packets with simulated nodes, e.g. those of a
+/// Meshtasticator simulation.
+///
+/// The `decode_admin_response` function decodes the `AdminMessage` within an `AdminApp` packet, and
+/// the `AdminResponse` enum classifies the responses to admin requests, e.g. to correlate remote
+/// admin responses with the requests they answer.
+///
+/// The `ChannelSet::from_channels` method builds a shareable `ChannelSet` from the channel table of
+/// a radio, containing the primary channel followed by the enabled secondary channels. The
+/// `ChannelSet::default_public` method builds the `ChannelSet` of the public default mesh within a
+/// region, combining the `ChannelSettings::default_primary` channel and the
+/// `LoRaConfig::default_for_region` configuration, and the `ChannelSet::into_channels` method
+/// converts a `ChannelSet` back into the channel table entries that apply it to a radio. The
+/// `ChannelSet::to_url` and `ChannelSet::from_url` methods encode and decode the
+/// `https://meshtastic.org/e/#...` URLs used by the Meshtastic apps to share channels.
+///
+/// The `MapReport::from_mesh_packet` method decodes the unencrypted map reports published to MQTT,
+/// and the `MapReport::position` method returns the position within a map report as a `Position`.
+///
+/// The `ModemPreset::characteristics` method returns a `PresetInfo` struct describing the LoRa
+/// modulation parameters of a modem preset, along with its approximate `PresetRange` and data rate.
+///
+/// The `Position::from_nmea_gga` method parses a GGA sentence reported by an external NMEA GPS
+/// receiver into a `Position`, as sent by the `send_position_from_nmea` method of the
+/// `ConnectedStreamApi` struct. The `Position::effective_precision_m` method estimates the
+/// uncertainty of a received position from its precision bits. The `Position::degrees` and
+/// `Position::degrees_opt` methods convert the fixed-point coordinate of a position into degrees,
+/// the latter returning `None` for positions without a fix, and `Position::from_degrees` is their
+/// inverse. The `LatLng` struct holds a coordinate in degrees, and converts from and into a
+/// `Position`.
+///
+/// The `EnvironmentMetrics::is_valid` method detects telemetry containing sentinel or implausible
+/// readings from misbehaving sensors, and the `EnvironmentMetrics::temperature`,
+/// `relative_humidity`, `barometric_pressure`, `gas_resistance`, `voltage`, `current` and `iaq`
+/// methods return each reading as an `Option`, treating unset or implausible values as `None`.
+///
+/// The `MeshPacket::clock_skew` method compares the receive timestamp of a packet with the host
+/// time, to detect nodes whose clock should not be trusted. The `MeshPacket::is_delayed` method
+/// detects packets replayed by a store and forward server, as marked by the deprecated `delayed`
+/// field, and the `MeshPacket::is_broadcast` method classifies replayed packets by their original
+/// destination.
+///
+/// The `ipv4_from_fixed32` and `ipv4_to_fixed32` functions convert between `Ipv4Addr` and the
+/// `fixed32` fields that hold IPv4 addresses, which the firmware stores in little-endian byte
+/// order. The `IpV4Config` and `NetworkConnectionStatus` structs provide accessors returning these
+/// addresses as `Ipv4Addr`. The `Waypoint::icon_char` and `Waypoint::set_icon_char` methods read
+/// and write the emoji icon of a waypoint. The `RouteDiscovery::hops` method lists the nodes that
+/// relayed a traceroute, in the order they were passed.
+///
+/// The `decompress_text` function decompresses the Unishox2-compressed payload of a
+/// `TextMessageCompressedApp` packet. Compressed text messages are decompressed transparently by
+/// `TextMessage::from_mesh_packet`. The `compress_text` function is its inverse. The
+/// `compress_payload` and `decompress_payload` functions wrap compressed payloads in `Compressed`
+/// messages, for applications that exchange compressed payloads on a private port.
+pub mod helpers {
+    pub use crate::helpers_internal::admin::decode_admin_response;
+    pub use crate::helpers_internal::admin::AdminResponse;
+    pub use crate::helpers_internal::config::ConfigSection;
+    pub use crate::helpers_internal::config::ModuleConfigCollector;
+    pub use crate::helpers_internal::firmware::supported_config_types;
+    pub use crate::helpers_internal::firmware::supported_module_config_types;
+    pub use crate::helpers_internal::firmware::DeviceCapabilities;
+    pub use crate::helpers_internal::firmware::CONFIG_SECTIONS_MIN_FIRMWARE_VERSION;
+    pub use crate::helpers_internal::firmware::PKI_MIN_FIRMWARE_VERSION;
+    pub use crate::helpers_internal::hardware::HwCaps;
+    pub use crate::helpers_internal::lora::LORA_HEADER_LEN;
+    pub use crate::helpers_internal::modem_preset::PresetInfo;
+    pub use crate::helpers_internal::modem_preset::PresetRange;
+    pub use crate::helpers_internal::network::ipv4_from_fixed32;
+    pub use crate::helpers_internal::network::ipv4_to_fixed32;
+    pub use crate::helpers_internal::position::LatLng;
+    pub use crate::helpers_internal::queue_status::QueueError;
+    pub use crate::helpers_internal::text_compression::compress_payload;
+    pub use crate::helpers_internal::text_compression::compress_text;
+    pub use crate::helpers_internal::text_compression::decompress_payload;
+    pub use crate::helpers_internal::text_compression::decompress_text;
+}
+
 /// This module exposes wrappers around common types that are used throughout the library.
 /// These wrappers are used to simplify the API of the library, and to provide additional
 /// type safety.
@@ -140,10 +510,18 @@ pub mod utils {
 /// in the mesh. This struct is used to provide additional type safety when specifying
 /// node IDs.
 ///
+/// The `PacketId` struct is a wrapper around a `u32` value that represents the ID of a mesh packet.
+///
 /// The `MeshChannel` enum is a wrapper around a `u32` value that represents the channel
 /// of the mesh. This struct is used to provide additional type safety when specifying
 /// mesh channels, as it will only allow channels with indices between 0 and 7, inclusive.
 ///
+/// The `ChannelPsk` struct is a wrapper around the pre-shared key of a channel, as stored within
+/// the `psk` field of `ChannelSettings`. This struct only allows valid PSKs to be constructed, and
+/// expands shorthand PSKs into the AES keys derived from `DEFAULT_PSK`. The `PskBits` enum selects
+/// the size of random PSKs. The `ChannelSettings::channel_psk` and
+/// `ChannelSettings::set_channel_psk` methods read and write the PSK of a channel.
+///
 /// The `EncodedMeshPacketData` struct is a wrapper around a `Vec<u8>` value that represents
 /// the payload data of a mesh packet (e.g., a text message).
 ///
@@ -157,9 +535,14 @@ pub mod utils {
 /// struct includes the required packet header, and can be sent to the radio.
 pub mod types {
     pub use crate::connections::wrappers::NodeId;
+    pub use crate::connections::wrappers::PacketId;
 
     pub use crate::connections::wrappers::mesh_channel::MeshChannel;
 
+    pub use crate::connections::wrappers::channel_psk::ChannelPsk;
+    pub use crate::connections::wrappers::channel_psk::PskBits;
+    pub use crate::connections::wrappers::channel_psk::DEFAULT_PSK;
+
     pub use crate::connections::wrappers::encoded_data::EncodedMeshPacketData;
     pub use crate::connections::wrappers::encoded_data::EncodedToRadioPacket;
     pub use crate::connections::wrappers::encoded_data::EncodedToRadioPacketWithHeader;
@@ -0,0 +1,265 @@
+use std::io::Write;
+
+use specta::{
+    export::ts_with_cfg,
+    ts::{ExportConfiguration, TsExportError},
+};
+
+use crate::protobufs;
+
+/// The largest enum value that is checked when enumerating the variants of a protobuf enum.
+/// All enums defined by the Meshtastic protobufs currently fall well within this range.
+const MAX_ENUM_VALUE: i32 = u16::MAX as i32;
+
+/// The name of the TypeScript namespace that string-literal enum unions are emitted into.
+/// A namespace is used to avoid colliding with the type names exported by `specta`.
+pub const ENUM_NAMESPACE: &str = "ProtoEnums";
+
+/// An enum that defines how protocol buffer enums are rendered when exporting
+/// TypeScript type definitions.
+///
+/// # Variants
+///
+/// * `Numeric` - Only the `specta` export is written. Enum-valued fields remain numeric,
+///     as they are stored as `i32` values within the generated protobuf structs.
+/// * `StringUnion` - In addition to the `specta` export, every protobuf enum is rendered as a
+///     string-literal union of its `as_str_name()` values (e.g. `"TEXT_MESSAGE_APP"`). This matches
+///     the string forms used by the firmware and the Meshtastic web/JS clients. The unions are only
+///     emitted within the `ProtoEnums` namespace, so the enum-valued fields of the `specta` export
+///     remain numeric.
+///
+/// # Default
+///
+/// The default value for this enum is `Numeric`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EnumExportBehavior {
+    #[default]
+    Numeric,
+    StringUnion,
+}
+
+/// Renders a single protobuf enum as a TypeScript string-literal union, using the
+/// `as_str_name()` values of every valid variant in ascending numeric order.
+macro_rules! string_union {
+    ($ts_name:literal, $enum_type:ty) => {{
+        let variants = (0..=MAX_ENUM_VALUE)
+            .filter_map(|value| <$enum_type>::try_from(value).ok())
+            .map(|variant| format!("\"{}\"", variant.as_str_name()))
+            .collect::<Vec<String>>();
+
+        format!("  export type {} = {};\n", $ts_name, variants.join(" | "))
+    }};
+}
+
+/// A helper function that renders all protobuf enums as TypeScript string-literal unions.
+///
+/// The unions are wrapped in an exported `ProtoEnums` namespace. Enums nested within protobuf
+/// messages are named using the path of their parent messages, e.g. `channel::Role` is rendered
+/// as `ProtoEnums.ChannelRole`.
+///
+/// # Arguments
+///
+/// None
+///
+/// # Returns
+///
+/// A `String` containing the TypeScript declarations of all enum string unions.
+///
+/// # Examples
+///
+/// ```
+/// let unions = ts::enum_string_unions();
+/// assert!(unions.contains("\"TEXT_MESSAGE_APP\""));
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// None
+///
+pub fn enum_string_unions() -> String {
+    let unions = [
+        string_union!("ChannelRole", protobufs::channel::Role),
+        string_union!("DeviceConfigRole", protobufs::config::device_config::Role),
+        string_union!(
+            "DeviceConfigRebroadcastMode",
+            protobufs::config::device_config::RebroadcastMode
+        ),
+        string_union!(
+            "PositionConfigPositionFlags",
+            protobufs::config::position_config::PositionFlags
+        ),
+        string_union!(
+            "PositionConfigGpsMode",
+            protobufs::config::position_config::GpsMode
+        ),
+        string_union!(
+            "NetworkConfigAddressMode",
+            protobufs::config::network_config::AddressMode
+        ),
+        string_union!(
+            "DisplayConfigGpsCoordinateFormat",
+            protobufs::config::display_config::GpsCoordinateFormat
+        ),
+        string_union!(
+            "DisplayConfigDisplayUnits",
+            protobufs::config::display_config::DisplayUnits
+        ),
+        string_union!(
+            "DisplayConfigOledType",
+            protobufs::config::display_config::OledType
+        ),
+        string_union!(
+            "DisplayConfigDisplayMode",
+            protobufs::config::display_config::DisplayMode
+        ),
+        string_union!(
+            "LoRaConfigRegionCode",
+            protobufs::config::lo_ra_config::RegionCode
+        ),
+        string_union!(
+            "LoRaConfigModemPreset",
+            protobufs::config::lo_ra_config::ModemPreset
+        ),
+        string_union!(
+            "BluetoothConfigPairingMode",
+            protobufs::config::bluetooth_config::PairingMode
+        ),
+        string_union!(
+            "AudioConfigAudioBaud",
+            protobufs::module_config::audio_config::AudioBaud
+        ),
+        string_union!(
+            "SerialConfigSerialBaud",
+            protobufs::module_config::serial_config::SerialBaud
+        ),
+        string_union!(
+            "SerialConfigSerialMode",
+            protobufs::module_config::serial_config::SerialMode
+        ),
+        string_union!(
+            "CannedMessageConfigInputEventChar",
+            protobufs::module_config::canned_message_config::InputEventChar
+        ),
+        string_union!("RemoteHardwarePinType", protobufs::RemoteHardwarePinType),
+        string_union!("PortNum", protobufs::PortNum),
+        string_union!("TelemetrySensorType", protobufs::TelemetrySensorType),
+        string_union!("XModemControl", protobufs::x_modem::Control),
+        string_union!("PositionLocSource", protobufs::position::LocSource),
+        string_union!("PositionAltSource", protobufs::position::AltSource),
+        string_union!("RoutingError", protobufs::routing::Error),
+        string_union!("MeshPacketPriority", protobufs::mesh_packet::Priority),
+        string_union!("MeshPacketDelayed", protobufs::mesh_packet::Delayed),
+        string_union!("LogRecordLevel", protobufs::log_record::Level),
+        string_union!("HardwareModel", protobufs::HardwareModel),
+        string_union!("Constants", protobufs::Constants),
+        string_union!("CriticalErrorCode", protobufs::CriticalErrorCode),
+        string_union!(
+            "AdminMessageConfigType",
+            protobufs::admin_message::ConfigType
+        ),
+        string_union!(
+            "AdminMessageModuleConfigType",
+            protobufs::admin_message::ModuleConfigType
+        ),
+        string_union!("Team", protobufs::Team),
+        string_union!("MemberRole", protobufs::MemberRole),
+        string_union!("ScreenFonts", protobufs::ScreenFonts),
+        string_union!("HardwareMessageType", protobufs::hardware_message::Type),
+        string_union!(
+            "StoreAndForwardRequestResponse",
+            protobufs::store_and_forward::RequestResponse
+        ),
+    ];
+
+    format!(
+        "export namespace {} {{\n{}}}\n",
+        ENUM_NAMESPACE,
+        unions.concat()
+    )
+}
+
+/// A helper method that exports TypeScript type definitions for all types registered with
+/// `specta`, and optionally renders protobuf enums as string-literal unions.
+///
+/// This method is intended to replace direct calls to `specta::export::ts_with_cfg` when the
+/// generated TypeScript types need to line up with the `as_str_name()` enum representations
+/// used by the firmware and the Meshtastic web/JS clients.
+///
+/// # Arguments
+///
+/// * `file_path` - The path of the TypeScript file to write.
+/// * `config` - The `specta` export configuration to use.
+/// * `enum_behavior` - An `EnumExportBehavior` enum that specifies how protobuf enums are rendered.
+///
+/// # Returns
+///
+/// A result indicating whether the TypeScript types were successfully written.
+///
+/// # Examples
+///
+/// ```
+/// let ts_export_config = ExportConfiguration::default()
+///     .bigint(BigIntExportBehavior::String)
+///     .modules(ModuleExportBehavior::Enabled);
+///
+/// ts::export_ts_types("./bindings.ts", &ts_export_config, ts::EnumExportBehavior::StringUnion)?;
+/// ```
+///
+/// # Errors
+///
+/// Fails if the `specta` export fails, or if the enum unions cannot be appended to the file.
+///
+/// # Panics
+///
+/// None
+///
+pub fn export_ts_types(
+    file_path: &str,
+    config: &ExportConfiguration,
+    enum_behavior: EnumExportBehavior,
+) -> Result<(), TsExportError> {
+    ts_with_cfg(file_path, config)?;
+
+    if enum_behavior == EnumExportBehavior::StringUnion {
+        let mut file = std::fs::OpenOptions::new().append(true).open(file_path)?;
+        file.write_all(format!("\n{}", enum_string_unions()).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_enums_within_namespace() {
+        let unions = enum_string_unions();
+        let lines: Vec<&str> = unions.lines().collect();
+
+        assert_eq!(lines.first(), Some(&"export namespace ProtoEnums {"));
+        assert_eq!(lines.last(), Some(&"}"));
+        assert_eq!(lines.len(), 39);
+        assert!(lines[1..lines.len() - 1]
+            .iter()
+            .all(|line| line.starts_with("  export type ")));
+    }
+
+    #[test]
+    fn renders_variants_in_numeric_order() {
+        let unions = enum_string_unions();
+
+        assert!(unions
+            .contains("  export type ChannelRole = \"DISABLED\" | \"PRIMARY\" | \"SECONDARY\";\n"));
+
+        // Values that are not contiguous are found as well
+        assert!(unions.contains(concat!(
+            "  export type MeshPacketPriority = \"UNSET\" | \"MIN\" | \"BACKGROUND\" | ",
+            "\"DEFAULT\" | \"RELIABLE\" | \"ACK\" | \"MAX\";\n"
+        )));
+    }
+}
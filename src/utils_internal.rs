@@ -6,6 +6,7 @@ use rand::{distr::StandardUniform, prelude::Distribution, Rng};
 use tokio_serial::{available_ports, SerialPort, SerialStream};
 
 use crate::connections::stream_api::StreamHandle;
+use crate::connections::stream_buffer::{START1, START2};
 use crate::connections::wrappers::encoded_data::{
     EncodedToRadioPacket, EncodedToRadioPacketWithHeader,
 };
@@ -21,6 +22,18 @@ pub const DEFAULT_DTR_PIN_STATE: bool = true;
 /// The default pin state of the RTS pin of incoming serial connections created by the `build_serial_stream` method.
 pub const DEFAULT_RTS_PIN_STATE: bool = false;
 
+/// The default time allowed for opening a TCP connection in the `build_tcp_stream` method. The timeout
+/// only applies to TCP streams; serial ports open immediately, and BLE streams use the
+/// `BLE_CONNECT_TIMEOUT` constant.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(3000);
+
+/// The TCP port of the first node of a Meshtasticator simulation. Each simulated node listens on
+/// this port plus its node index.
+pub const DEFAULT_SIMULATOR_BASE_PORT: u16 = 4403;
+
+/// The maximum number of payload bytes within the `Data` message of a single mesh packet.
+pub const DATA_PAYLOAD_LEN: usize = crate::protobufs::Constants::DataPayloadLen as usize;
+
 /// A helper method that uses the `tokio_serial` crate to list the names of all
 /// available serial ports on the system. This method is intended to be used
 /// to select a valid serial port, then to pass that port name to the `connect`
@@ -140,7 +153,13 @@ pub fn build_serial_stream(
 /// implements `AsyncReadExt + AsyncWriteExt` traits.
 ///
 /// This method is intended to be used to create a `TcpStream` instance, which is
-/// then passed into the `StreamApi::connect` method.
+/// then passed into the `StreamApi::connect` method. The connection attempt times out
+/// after `DEFAULT_CONNECT_TIMEOUT`, i.e. the same 3 seconds this method has always waited;
+/// use the `build_tcp_stream_with_timeout` method to specify a different timeout.
+///
+/// **Note:** A timeout was previously reported as `Error::StreamBuildError`, and is now
+/// reported as `Error::ConnectTimeout`. Code that detects unreachable radios by matching
+/// on the `StreamBuildError` variant must match on `ConnectTimeout` instead.
 ///
 /// # Arguments
 ///
@@ -166,7 +185,8 @@ pub fn build_serial_stream(
 /// # Errors
 ///
 /// Will return a `String` error message in the event that the radio refuses the connection,
-/// or if the specified address is invalid.
+/// or if the specified address is invalid. Will return `Error::ConnectTimeout` if the connection
+/// could not be opened within `DEFAULT_CONNECT_TIMEOUT`.
 ///
 /// # Panics
 ///
@@ -174,26 +194,120 @@ pub fn build_serial_stream(
 ///
 pub async fn build_tcp_stream(
     address: String,
+) -> Result<StreamHandle<tokio::net::TcpStream>, Error> {
+    build_tcp_stream_with_timeout(address, DEFAULT_CONNECT_TIMEOUT).await
+}
+
+/// A helper method that uses the `tokio` crate to build a TCP stream, failing if the
+/// connection cannot be opened within the specified timeout.
+///
+/// This timeout only applies to opening the connection, and is independent of the time
+/// taken by the radio to respond to the `configure` handshake. BLE streams are built with a
+/// timeout by the `build_ble_stream_with_timeout` method.
+///
+/// # Arguments
+///
+/// * `address` - The full TCP address of the device, including the port.
+/// * `timeout` - The maximum time allowed for opening the connection.
+///
+/// # Returns
+///
+/// Returns a result that resolves to a `tokio::net::TcpStream` instance, or
+/// an error if the stream could not be created.
+///
+/// # Examples
+///
+/// ```
+/// // Fail fast when connecting to an unreachable host
+/// let tcp_stream = utils::build_tcp_stream_with_timeout(
+///     "192.168.0.1:4403".to_string(),
+///     Duration::from_millis(500),
+/// )
+/// .await?;
+/// let decoded_listener = stream_api.connect(tcp_stream).await;
+/// ```
+///
+/// # Errors
+///
+/// Will return `Error::StreamBuildError` in the event that the radio refuses the connection,
+/// or if the specified address is invalid. Will return `Error::ConnectTimeout` if the connection
+/// could not be opened within the specified timeout.
+///
+/// # Panics
+///
+/// None
+///
+pub async fn build_tcp_stream_with_timeout(
+    address: String,
+    timeout: Duration,
 ) -> Result<StreamHandle<tokio::net::TcpStream>, Error> {
     let connection_future = tokio::net::TcpStream::connect(address.clone());
-    let timeout_duration = Duration::from_millis(3000);
 
-    let stream = match tokio::time::timeout(timeout_duration, connection_future).await {
-        Ok(stream) => stream.map_err(|e| Error::StreamBuildError {
+    let stream = connect_within(&address, timeout, connection_future)
+        .await?
+        .map_err(|e| Error::StreamBuildError {
             source: Box::new(e),
             description: format!("Failed to connect to {}", address).to_string(),
-        })?,
-        Err(e) => {
-            return Err(Error::StreamBuildError{source:Box::new(e),description:format!(
-                    "Timed out connecting to {}. Check that the radio is on, network is enabled, and the address is correct.",
-                    address,
-                )});
-        }
-    };
+        })?;
 
     Ok(StreamHandle::from_stream(stream))
 }
 
+/// Waits for a future that opens a connection to the specified address, failing with
+/// `Error::ConnectTimeout` if the future does not complete within the specified timeout.
+pub(crate) async fn connect_within<F: std::future::Future>(
+    address: &str,
+    timeout: Duration,
+    connection_future: F,
+) -> Result<F::Output, Error> {
+    tokio::time::timeout(timeout, connection_future)
+        .await
+        .map_err(|_| Error::ConnectTimeout {
+            address: address.to_owned(),
+            timeout,
+        })
+}
+
+/// A helper method to build a TCP stream to a node of a Meshtasticator simulation running on the
+/// local machine. Each simulated node runs a native firmware instance that listens on the
+/// `DEFAULT_SIMULATOR_BASE_PORT` plus its node index.
+///
+/// Packets exchanged between simulated nodes are reported to the client as `SimulatorApp` packets,
+/// which can be unwrapped using the `MeshPacket::from_simulator_packet` method. Packets can be
+/// injected into the simulated node as if they were received over LoRa using the
+/// `send_simulated_packet` method of the `ConnectedStreamApi` struct.
+///
+/// # Arguments
+///
+/// * `node_index` - The index of the simulated node to connect to.
+///
+/// # Returns
+///
+/// Returns a result that resolves to a `tokio::net::TcpStream` instance, or
+/// an error if the stream could not be created.
+///
+/// # Examples
+///
+/// ```
+/// let tcp_stream = utils::build_simulator_stream(0).await?;
+/// let decoded_listener = stream_api.connect(tcp_stream).await;
+/// ```
+///
+/// # Errors
+///
+/// Fails in the same cases as the `build_tcp_stream` method.
+///
+/// # Panics
+///
+/// None
+///
+pub async fn build_simulator_stream(
+    node_index: u16,
+) -> Result<StreamHandle<tokio::net::TcpStream>, Error> {
+    let port = DEFAULT_SIMULATOR_BASE_PORT.saturating_add(node_index);
+    build_tcp_stream(format!("localhost:{port}")).await
+}
+
 /// A helper method to generate random numbers using the `rand` crate.
 ///
 /// This method is intended to be used to generate random id values. This method
@@ -281,7 +395,7 @@ pub fn format_data_packet(
         });
     }
     let [lsb, msb, ..] = data.len().to_le_bytes();
-    let magic_buffer = [0x94, 0xc3, msb, lsb];
+    let magic_buffer = [START1, START2, msb, lsb];
 
     Ok([&magic_buffer, data].concat().into())
 }
@@ -381,10 +495,92 @@ pub fn current_epoch_secs_u32() -> u32 {
         .expect("Could not convert u128 to u32")
 }
 
+/// A helper function that splits a text into chunks of at most `max_len` bytes, e.g. to send a text
+/// that exceeds the `DATA_PAYLOAD_LEN` limit of a single mesh packet within several packets.
+///
+/// Chunks are only split at character boundaries, so that each chunk is valid UTF-8 and no multibyte
+/// character is split between two chunks. Each chunk is filled with as many characters as fit.
+///
+/// # Arguments
+///
+/// * `text` - The text to split.
+/// * `max_len` - The maximum length of each chunk in bytes.
+///
+/// # Returns
+///
+/// A vector of the chunks in order, which contains a single empty chunk if the text is empty.
+///
+/// # Examples
+///
+/// ```
+/// let chunks = utils::split_text("Hello world!", 6);
+/// assert_eq!(chunks, ["Hello ", "world!"]);
+/// ```
+///
+/// # Errors
+///
+/// None
+///
+/// # Panics
+///
+/// Panics if `max_len` is less than 4, the maximum length of a character in UTF-8.
+///
+pub fn split_text(text: &str, max_len: usize) -> Vec<&str> {
+    assert!(
+        max_len >= 4,
+        "Chunks must be able to hold a character of up to 4 bytes"
+    );
+
+    let mut chunks = vec![];
+    let mut remaining = text;
+
+    while remaining.len() > max_len {
+        let mut split_index = max_len;
+        while !remaining.is_char_boundary(split_index) {
+            split_index -= 1;
+        }
+
+        let (chunk, rest) = remaining.split_at(split_index);
+        chunks.push(chunk);
+        remaining = rest;
+    }
+
+    chunks.push(remaining);
+    chunks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test(start_paused = true)]
+    async fn times_out_connecting_to_unresponsive_radios() {
+        let timeout = Duration::from_secs(3);
+        let started_at = tokio::time::Instant::now();
+
+        // A connection attempt to an unresponsive radio never completes
+        let result =
+            connect_within("192.168.0.1:4403", timeout, std::future::pending::<()>()).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::ConnectTimeout { address, timeout: t })
+                if address == "192.168.0.1:4403" && t == timeout
+        ));
+        assert_eq!(started_at.elapsed(), timeout);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connects_within_the_timeout() {
+        let result = connect_within("localhost:4403", Duration::from_secs(3), async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            42
+        })
+        .await;
+
+        assert!(matches!(result, Ok(42)));
+    }
+
     #[test]
     fn valid_empty_packet() {
         let data = vec![];
@@ -422,4 +618,31 @@ mod tests {
 
         assert!(serial_data.is_err());
     }
+
+    #[test]
+    fn splits_text_at_character_boundaries() {
+        assert_eq!(split_text("", DATA_PAYLOAD_LEN), [""]);
+        assert_eq!(split_text("Hello world!", 6), ["Hello ", "world!"]);
+
+        // 59 four-byte emoji fill 236 bytes, so the 60th doesn't fit into the first chunk
+        let emoji = "\u{1f600}".repeat(60);
+        let chunks = split_text(&emoji, DATA_PAYLOAD_LEN);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), 236);
+        assert_eq!(chunks[1], "\u{1f600}");
+
+        // A three-byte CJK character straddles the limit after 236 ASCII bytes
+        let cjk = format!("{}你好", "a".repeat(236));
+        let chunks = split_text(&cjk, DATA_PAYLOAD_LEN);
+        assert_eq!(chunks, ["a".repeat(236).as_str(), "你好"]);
+
+        // Exactly 237 bytes fit into a single chunk
+        let exact = format!("{}世", "a".repeat(234));
+        assert_eq!(split_text(&exact, DATA_PAYLOAD_LEN), [exact.as_str()]);
+
+        let long = "日本語のテキスト".repeat(40);
+        let chunks = split_text(&long, DATA_PAYLOAD_LEN);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= DATA_PAYLOAD_LEN));
+        assert_eq!(chunks.concat(), long);
+    }
 }